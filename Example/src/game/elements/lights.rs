@@ -0,0 +1,37 @@
+use orbital::{
+    cgmath::{InnerSpace, Vector3},
+    game::{Element, ElementRegistration, WorldChange},
+    resources::descriptors::{LightDescriptor, LightType, ShadowDescriptor},
+    ulid::Ulid,
+};
+
+pub struct Lights;
+
+impl Element for Lights {
+    fn on_registration(&mut self, _ulid: &Ulid) -> ElementRegistration {
+        ElementRegistration {
+            world_changes: Some(vec![
+                WorldChange::SpawnLightOwned(LightDescriptor {
+                    identifier: "Sun".into(),
+                    light_type: LightType::Directional {
+                        direction: Vector3::new(-0.5, -1.0, -0.3).normalize(),
+                    },
+                    color: Vector3::new(1.0, 1.0, 1.0),
+                    intensity: 3.0,
+                    shadow: Some(ShadowDescriptor::default()),
+                }),
+                WorldChange::SpawnLightOwned(LightDescriptor {
+                    identifier: "Fill".into(),
+                    light_type: LightType::Point {
+                        position: Vector3::new(2.0, 1.0, 2.0),
+                        range: 10.0,
+                    },
+                    color: Vector3::new(1.0, 0.6, 0.3),
+                    intensity: 5.0,
+                    shadow: None,
+                }),
+            ]),
+            ..Default::default()
+        }
+    }
+}
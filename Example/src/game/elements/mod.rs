@@ -1,4 +1,5 @@
 pub mod camera;
 pub mod cubes;
 pub mod damaged_helmet;
+pub mod lights;
 pub mod ping_pong;
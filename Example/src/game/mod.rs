@@ -1,4 +1,7 @@
-use elements::{camera::Camera, damaged_helmet::ChessCube, cubes::Cubes, ping_pong::PingPongElement};
+use elements::{
+    camera::Camera, cubes::Cubes, damaged_helmet::ChessCube, lights::Lights,
+    ping_pong::PingPongElement,
+};
 use orbital::{
     game::{Game, World, WorldChange},
     log::info,
@@ -23,6 +26,18 @@ impl Game for ExampleGame {
         info!("Queuing Camera spawn");
         world.process_world_change(WorldChange::SpawnElement(Box::new(Camera::new())));
 
+        // An environment can be used for ambient lighting and as a skybox.
+        // world.process_world_change(WorldChange::ChangeEnvironment(Some(
+        //     EnvironmentDescriptor {
+        //         texture: TextureDescriptor::EquirectangularHDR("Assets/HDRs/Environment.hdr"),
+        //         intensity: 1.0,
+        //         draw_skybox: true,
+        //     },
+        // )));
+
+        info!("Queuing Lights spawn");
+        world.process_world_change(WorldChange::SpawnElement(Box::new(Lights {})));
+
         info!("Queuing Ping & Pong spawn");
         world.process_world_change(WorldChange::SpawnElement(Box::new(PingPongElement::new(
             true,
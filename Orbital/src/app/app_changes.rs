@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use winit::{dpi::Position, window::Cursor};
 
 #[derive(Debug)]
@@ -16,4 +18,17 @@ pub enum AppChange {
     /// A grabbed mouse cursor **cannot** escape the current window.  
     /// Gets send directly to [winit], issues may appear in log.
     ChangeCursorGrabbed(bool),
+    /// Captures the next rendered frame into a PNG at the given path.  
+    /// Encoding and writing happen in the background, issues may appear
+    /// in log.
+    CaptureScreenshot(PathBuf),
+    /// Captures the next `count` rendered frames as PNGs into `directory`.  
+    /// Frames are named `frame_00000.png`, `frame_00001.png`, ...  
+    /// Encoding and writing happen in the background, issues may appear
+    /// in log.
+    CaptureFrames { count: u32, directory: PathBuf },
+    /// Changes the samples per pixel for MSAA, `1` disables it.  
+    /// Validated like [AppSettings::sample_count](super::AppSettings::sample_count),
+    /// issues may appear in log.
+    ChangeSampleCount(u32),
 }
@@ -0,0 +1,306 @@
+use std::{
+    path::PathBuf,
+    sync::mpsc::{channel, Receiver, TryRecvError},
+    thread::JoinHandle,
+};
+
+use image::RgbaImage;
+use log::{debug, error, warn};
+use wgpu::{
+    BufferAsyncError, BufferDescriptor, BufferUsages, CommandEncoderDescriptor, Device,
+    ImageCopyBuffer, ImageDataLayout, Maintain, MapMode, Queue, Texture, TextureFormat,
+    TextureUsages, COPY_BYTES_PER_ROW_ALIGNMENT,
+};
+
+/// Captures rendered frames into PNG files.
+/// Requested via [AppChange::CaptureScreenshot](super::AppChange::CaptureScreenshot)
+/// and [AppChange::CaptureFrames](super::AppChange::CaptureFrames).
+///
+/// After rendering, the frame is copied into a buffer, which gets mapped
+/// asynchronously.
+/// Once mapped, the frame is encoded and written on a separate thread.
+/// Thus, the runtime doesn't wait for either.
+#[derive(Debug, Default)]
+pub struct FrameCapture {
+    /// Paths to write the next frame to
+    screenshots: Vec<PathBuf>,
+    sequences: Vec<FrameSequence>,
+    /// Frames copied, but not yet mapped
+    in_flight: Vec<CapturedFrame>,
+    /// Frames being encoded and written
+    writers: Vec<JoinHandle<()>>,
+}
+
+#[derive(Debug)]
+struct FrameSequence {
+    directory: PathBuf,
+    next_index: u32,
+    remaining: u32,
+}
+
+#[derive(Debug)]
+struct CapturedFrame {
+    buffer: wgpu::Buffer,
+    mapped: Receiver<Result<(), BufferAsyncError>>,
+    width: u32,
+    height: u32,
+    bytes_per_row: u32,
+    format: TextureFormat,
+    paths: Vec<PathBuf>,
+}
+
+impl FrameCapture {
+    /// Captures the next frame into `path`.
+    pub fn capture_screenshot(&mut self, path: PathBuf) {
+        self.screenshots.push(path);
+    }
+
+    /// Captures the next `count` frames into `directory`.
+    /// Frames are named `frame_00000.png`, `frame_00001.png`, ...
+    pub fn capture_frames(&mut self, count: u32, directory: PathBuf) {
+        if count == 0 {
+            return;
+        }
+
+        if let Err(e) = std::fs::create_dir_all(&directory) {
+            error!(
+                "Failed creating frame capture directory {:?}: {}",
+                directory, e
+            );
+            return;
+        }
+
+        self.sequences.push(FrameSequence {
+            directory,
+            next_index: 0,
+            remaining: count,
+        });
+    }
+
+    /// Checks if any frames are requested or still processed.
+    pub fn is_capturing(&self) -> bool {
+        !self.screenshots.is_empty()
+            || !self.sequences.is_empty()
+            || !self.in_flight.is_empty()
+            || !self.writers.is_empty()
+    }
+
+    /// Returns the paths the current frame should be written to.
+    fn take_frame_paths(&mut self) -> Vec<PathBuf> {
+        let mut paths = std::mem::take(&mut self.screenshots);
+
+        for sequence in &mut self.sequences {
+            paths.push(
+                sequence
+                    .directory
+                    .join(format!("frame_{:05}.png", sequence.next_index)),
+            );
+
+            sequence.next_index += 1;
+            sequence.remaining -= 1;
+        }
+        self.sequences.retain(|x| x.remaining > 0);
+
+        paths
+    }
+
+    /// Copies the given, rendered, texture into a buffer, if a capture
+    /// was requested.
+    /// Must be called after rendering, but before presenting.
+    ///
+    /// ⚠️ The texture must have [TextureUsages::COPY_SRC].
+    pub fn capture(&mut self, texture: &Texture, device: &Device, queue: &Queue) {
+        if self.screenshots.is_empty() && self.sequences.is_empty() {
+            return;
+        }
+
+        let paths = self.take_frame_paths();
+
+        if !texture.usage().contains(TextureUsages::COPY_SRC) {
+            error!(
+                "Frame capture requested, but the target texture can't be copied from! Skipping {} capture(s).",
+                paths.len()
+            );
+            return;
+        }
+
+        let format = texture.format();
+        if !Self::is_supported_format(format) {
+            error!(
+                "Frame capture requested, but the target texture format {:?} isn't supported! Skipping {} capture(s).",
+                format,
+                paths.len()
+            );
+            return;
+        }
+
+        let width = texture.width();
+        let height = texture.height();
+        let bytes_per_row = Self::padded_bytes_per_row(width);
+
+        let buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Frame Capture Buffer"),
+            size: (bytes_per_row * height) as u64,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("Frame Capture"),
+        });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            ImageCopyBuffer {
+                buffer: &buffer,
+                layout: ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            texture.size(),
+        );
+        queue.submit(Some(encoder.finish()));
+
+        let (sender, mapped) = channel();
+        buffer.slice(..).map_async(MapMode::Read, move |result| {
+            // The receiver might be gone already, if the capture was dropped
+            let _ = sender.send(result);
+        });
+
+        self.in_flight.push(CapturedFrame {
+            buffer,
+            mapped,
+            width,
+            height,
+            bytes_per_row,
+            format,
+            paths,
+        });
+    }
+
+    /// Hands any mapped frames over to be written.
+    /// Doesn't block.
+    pub fn poll(&mut self, device: &Device) {
+        if self.in_flight.is_empty() && self.writers.is_empty() {
+            return;
+        }
+
+        device.poll(Maintain::Poll);
+
+        let mut i = 0;
+        while i < self.in_flight.len() {
+            match self.in_flight[i].mapped.try_recv() {
+                Ok(result) => {
+                    let frame = self.in_flight.remove(i);
+                    self.write_frame(frame, result);
+                }
+                Err(TryRecvError::Empty) => i += 1,
+                Err(TryRecvError::Disconnected) => {
+                    let frame = self.in_flight.remove(i);
+                    error!("Frame capture for {:?} got lost!", frame.paths);
+                }
+            }
+        }
+
+        self.writers.retain(|x| !x.is_finished());
+    }
+
+    /// Waits until all captured frames are written.
+    /// Frames requested, but not yet rendered, are dropped.
+    pub fn flush(&mut self, device: &Device) {
+        if !self.screenshots.is_empty() || !self.sequences.is_empty() {
+            warn!("Dropping frame capture requests, which weren't rendered yet!");
+            self.screenshots.clear();
+            self.sequences.clear();
+        }
+
+        if !self.in_flight.is_empty() {
+            device.poll(Maintain::Wait);
+            self.poll(device);
+        }
+
+        for writer in self.writers.drain(..) {
+            if writer.join().is_err() {
+                error!("Frame capture writer panicked!");
+            }
+        }
+    }
+
+    fn write_frame(&mut self, frame: CapturedFrame, result: Result<(), BufferAsyncError>) {
+        if let Err(e) = result {
+            error!("Failed mapping frame capture for {:?}: {}", frame.paths, e);
+            return;
+        }
+
+        // Only copy while mapped, everything else happens on the writer
+        let data = frame.buffer.slice(..).get_mapped_range().to_vec();
+        frame.buffer.unmap();
+
+        let CapturedFrame {
+            width,
+            height,
+            bytes_per_row,
+            format,
+            paths,
+            ..
+        } = frame;
+
+        self.writers.push(std::thread::spawn(move || {
+            let Some(image) = Self::frame_to_image(&data, width, height, bytes_per_row, format)
+            else {
+                error!("Frame capture for {:?} has an invalid size!", paths);
+                return;
+            };
+
+            for path in paths {
+                match image.save(&path) {
+                    Ok(_) => debug!("Captured frame into {:?}", path),
+                    Err(e) => error!("Failed writing frame capture {:?}: {}", path, e),
+                }
+            }
+        }));
+    }
+
+    /// Checks if frames in the given [TextureFormat] can be captured.
+    pub fn is_supported_format(format: TextureFormat) -> bool {
+        matches!(
+            format,
+            TextureFormat::Rgba8Unorm
+                | TextureFormat::Rgba8UnormSrgb
+                | TextureFormat::Bgra8Unorm
+                | TextureFormat::Bgra8UnormSrgb
+        )
+    }
+
+    /// Rows have to be aligned when copying a texture into a buffer.
+    pub fn padded_bytes_per_row(width: u32) -> u32 {
+        (width * 4).div_ceil(COPY_BYTES_PER_ROW_ALIGNMENT) * COPY_BYTES_PER_ROW_ALIGNMENT
+    }
+
+    /// Converts a frame copied from a texture into an image.
+    /// The row padding is dropped and BGRA frames are converted into RGBA.
+    pub fn frame_to_image(
+        data: &[u8],
+        width: u32,
+        height: u32,
+        bytes_per_row: u32,
+        format: TextureFormat,
+    ) -> Option<RgbaImage> {
+        let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+        for row in data.chunks(bytes_per_row as usize) {
+            pixels.extend_from_slice(row.get(..(width * 4) as usize)?);
+        }
+
+        if matches!(
+            format,
+            TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb
+        ) {
+            for pixel in pixels.chunks_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+
+        RgbaImage::from_raw(width, height, pixels)
+    }
+}
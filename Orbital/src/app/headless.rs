@@ -0,0 +1,231 @@
+use std::sync::{Mutex, PoisonError};
+
+use image::RgbaImage;
+use log::{debug, info, warn};
+use wgpu::{
+    Adapter, BufferDescriptor, BufferUsages, CommandEncoderDescriptor, CompositeAlphaMode, Device,
+    DeviceDescriptor, Extent3d, ImageCopyBuffer, ImageDataLayout, Instance, Limits, Maintain,
+    MapMode, PowerPreference, PresentMode, Queue, RequestAdapterOptions, SurfaceConfiguration,
+    Texture, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+    TextureViewDescriptor,
+};
+
+use crate::{
+    error::Error,
+    resources::realizations::{Material, Pipeline},
+};
+
+use super::{App, AppChange, AppRuntime, FrameCapture, HeadlessSettings};
+
+/// Held for the whole of a headless run.
+/// The [Pipeline] and [Material] caches are process-global, but each
+/// headless run has its own [Device], so runs can't overlap.
+static HEADLESS_RUN: Mutex<()> = Mutex::new(());
+
+impl<AppImpl: App> AppRuntime<AppImpl> {
+    /// Runs the [App] without a window, e.g. in CI.
+    ///
+    /// Instead of a surface, the [App] renders into an offscreen texture
+    /// of [HeadlessSettings::size].
+    /// After [HeadlessSettings::frame_count] frames, the runtime stops.
+    ///
+    /// # Returns
+    /// Each rendered frame, unless [HeadlessSettings::output_directory] is
+    /// set, in which case the frames are written to disk as PNGs instead
+    /// and nothing is returned.
+    ///
+    /// ⚠️ Only one headless run happens at a time per process.  
+    /// ⚠️ Concurrent runs, e.g. tests on parallel threads, wait for each
+    /// other, as they share the global [Pipeline] and [Material] caches.
+    pub fn liftoff_headless(settings: HeadlessSettings) -> Result<Vec<RgbaImage>, Error> {
+        info!("Akimo-Project: App Runtime (Headless)");
+        info!(" --- @SakulFlee --- ");
+
+        Self::__liftoff_headless(settings, AppImpl::init)
+    }
+
+    /// Same as [AppRuntime::liftoff_headless], but the [App] is made by
+    /// `init` instead of [App::init].
+    pub(crate) fn __liftoff_headless<F>(
+        settings: HeadlessSettings,
+        init: F,
+    ) -> Result<Vec<RgbaImage>, Error>
+    where
+        F: FnOnce(&SurfaceConfiguration, &Device, &Queue) -> AppImpl,
+    {
+        // A panicked run is harmless, the caches get cleared below anyways
+        let _run = HEADLESS_RUN.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if !FrameCapture::is_supported_format(settings.format) {
+            return Err(Error::UnsupportedTextureFormat(settings.format));
+        }
+
+        let instance = Self::make_instance();
+        let adapter = Self::make_headless_adapter(&instance)?;
+
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &DeviceDescriptor {
+                label: None,
+                required_features: Self::device_features(&adapter),
+                required_limits: Limits::default(),
+            },
+            None,
+        ))
+        .map_err(|_| Error::RequestDeviceError)?;
+
+        // Cached resources of a previous headless run belong to a
+        // different device and can't be reused
+        Pipeline::prepare_cache_access(None, None, &device, &queue).clear();
+        Material::prepare_cache_access().clear();
+
+        // There is no surface, but Apps expect a configuration anyways
+        let configuration = SurfaceConfiguration {
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            format: settings.format,
+            width: settings.size.x,
+            height: settings.size.y,
+            present_mode: PresentMode::AutoNoVsync,
+            desired_maximum_frame_latency: 2,
+            alpha_mode: CompositeAlphaMode::Auto,
+            view_formats: vec![settings.format],
+        };
+
+        let target = device.create_texture(&TextureDescriptor {
+            label: Some("Headless Target"),
+            size: Extent3d {
+                width: settings.size.x,
+                height: settings.size.y,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: settings.format,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = target.create_view(&TextureViewDescriptor::default());
+
+        if let Some(output_directory) = &settings.output_directory {
+            std::fs::create_dir_all(output_directory).map_err(Error::IOError)?;
+        }
+
+        info!("Bootstrapping app ...");
+        let mut app = init(&configuration, &device, &queue);
+        let color_format = app.color_format().unwrap_or(settings.format);
+        app.on_sample_count_change(
+            Self::validate_sample_count(settings.sample_count, color_format, &adapter, &device),
+            &device,
+            &queue,
+        );
+
+        let mut frame_capture = FrameCapture::default();
+        let mut frames = Vec::new();
+        for i in 0..settings.frame_count {
+            for app_change in app.on_update().unwrap_or_default() {
+                match app_change {
+                    AppChange::CaptureScreenshot(path) => frame_capture.capture_screenshot(path),
+                    AppChange::CaptureFrames { count, directory } => {
+                        frame_capture.capture_frames(count, directory)
+                    }
+                    AppChange::ChangeSampleCount(sample_count) => app.on_sample_count_change(
+                        Self::validate_sample_count(sample_count, color_format, &adapter, &device),
+                        &device,
+                        &queue,
+                    ),
+                    _ => debug!(
+                        "Ignoring {:?}, there is no window in headless mode!",
+                        app_change
+                    ),
+                }
+            }
+
+            app.on_render(&view, &device, &queue);
+            frame_capture.capture(&target, &device, &queue);
+
+            let frame = Self::read_frame(&target, settings.format, &device, &queue);
+            match &settings.output_directory {
+                Some(output_directory) => frame
+                    .save(output_directory.join(format!("frame_{:05}.png", i)))
+                    .map_err(Error::ImageError)?,
+                None => frames.push(frame),
+            }
+        }
+        frame_capture.flush(&device);
+
+        Ok(frames)
+    }
+
+    /// Requests an adapter without any compatible surface.
+    /// If there is no hardware adapter, the fallback (i.e. software)
+    /// adapter is tried.
+    fn make_headless_adapter(instance: &Instance) -> Result<Adapter, Error> {
+        let request_adapter = |force_fallback_adapter| {
+            pollster::block_on(instance.request_adapter(&RequestAdapterOptions {
+                power_preference: PowerPreference::HighPerformance,
+                force_fallback_adapter,
+                compatible_surface: None,
+            }))
+        };
+
+        let adapter = match request_adapter(false) {
+            Some(adapter) => adapter,
+            None => {
+                warn!("No hardware adapter found! Trying fallback adapter ...");
+                request_adapter(true).ok_or(Error::NoAdapters)?
+            }
+        };
+
+        let adapter_info = adapter.get_info();
+        debug!("Adapter: {} ({:#?})", adapter_info.name, adapter_info);
+
+        Ok(adapter)
+    }
+
+    /// Copies the given texture back from the GPU.
+    /// BGRA textures are converted into RGBA.
+    fn read_frame(
+        texture: &Texture,
+        format: TextureFormat,
+        device: &Device,
+        queue: &Queue,
+    ) -> RgbaImage {
+        let width = texture.width();
+        let height = texture.height();
+
+        let bytes_per_row = FrameCapture::padded_bytes_per_row(width);
+
+        let buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Headless Readback Buffer"),
+            size: (bytes_per_row * height) as u64,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("Headless Readback"),
+        });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            ImageCopyBuffer {
+                buffer: &buffer,
+                layout: ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            texture.size(),
+        );
+        queue.submit(Some(encoder.finish()));
+
+        buffer.slice(..).map_async(MapMode::Read, |_| ());
+        device.poll(Maintain::Wait);
+
+        let data = buffer.slice(..).get_mapped_range().to_vec();
+        buffer.unmap();
+
+        FrameCapture::frame_to_image(&data, width, height, bytes_per_row, format)
+            .expect("Frame size mismatch!")
+    }
+}
@@ -1,6 +1,6 @@
 //! ⚠️ You are most likely looking for the [App] description!
 
-use wgpu::{Device, Queue, SurfaceConfiguration, TextureView};
+use wgpu::{Device, Queue, SurfaceConfiguration, TextureFormat, TextureView};
 
 pub mod settings;
 pub use settings::*;
@@ -8,6 +8,11 @@ pub use settings::*;
 pub mod runtime;
 pub use runtime::*;
 
+pub mod headless;
+
+pub mod capture;
+pub use capture::*;
+
 pub mod input_event;
 pub use input_event::*;
 
@@ -366,6 +371,27 @@ pub trait App {
     {
     }
 
+    /// Gets called each time the samples per pixel (MSAA) change, as well
+    /// as once right after [App::init].
+    /// Check [AppSettings::sample_count].  
+    /// Any multisampled resources (e.g. render targets, pipelines) should
+    /// be remade inside here.
+    fn on_sample_count_change(&mut self, _sample_count: u32, _device: &Device, _queue: &Queue)
+    where
+        Self: Sized,
+    {
+    }
+
+    /// Format of the color targets which get multisampled, used to
+    /// validate [AppSettings::sample_count].
+    /// [None] if the app renders onto the surface directly.
+    fn color_format(&self) -> Option<TextureFormat>
+    where
+        Self: Sized,
+    {
+        None
+    }
+
     fn on_focus_change(&mut self, _focused: bool)
     where
         Self: Sized,
@@ -7,7 +7,8 @@ use wgpu::{
     util::{backend_bits_from_env, dx12_shader_compiler_from_env, gles_minor_version_from_env},
     Adapter, Device, DeviceDescriptor, Features, Instance, InstanceDescriptor, InstanceFlags,
     Limits, PowerPreference, PresentMode, Queue, RequestAdapterOptions, Surface,
-    SurfaceConfiguration, SurfaceTexture, TextureViewDescriptor,
+    SurfaceConfiguration, SurfaceTexture, TextureFormat, TextureFormatFeatureFlags, TextureUsages,
+    TextureViewDescriptor,
 };
 use winit::{
     application::ApplicationHandler,
@@ -19,13 +20,17 @@ use winit::{
 
 use crate::error::Error;
 
-use super::{App, AppChange, AppSettings, InputEvent};
+use super::{App, AppChange, AppSettings, FrameCapture, InputEvent};
 
 pub struct AppRuntime<AppImpl: App> {
     // App related
     app: Option<AppImpl>,
+    /// Makes the [App] once the first [Device] exists.
+    /// If unset, [App::init] is used.
+    app_init: Option<AppInit<AppImpl>>,
     runtime_settings: AppSettings,
     gil: Gilrs,
+    frame_capture: FrameCapture,
     // Window related
     window: Option<Arc<Window>>,
     surface: Option<Surface<'static>>,
@@ -37,6 +42,8 @@ pub struct AppRuntime<AppImpl: App> {
     queue: Option<Queue>,
 }
 
+type AppInit<AppImpl> = Box<dyn FnOnce(&SurfaceConfiguration, &Device, &Queue) -> AppImpl>;
+
 pub static mut WINDOW_HALF_SIZE: (i32, i32) = (0, 0);
 
 impl<AppImpl: App> AppRuntime<AppImpl> {
@@ -44,17 +51,33 @@ impl<AppImpl: App> AppRuntime<AppImpl> {
         info!("Akimo-Project: App Runtime");
         info!(" --- @SakulFlee --- ");
 
-        Self::__liftoff(event_loop, settings)
+        Self::run(event_loop, settings, None)
+    }
+
+    /// Same as [AppRuntime::liftoff], but the [App] is made by `init`
+    /// instead of [App::init].
+    pub(crate) fn __liftoff<F>(
+        event_loop: EventLoop<()>,
+        runtime_settings: AppSettings,
+        init: F,
+    ) -> Result<(), Error>
+    where
+        F: FnOnce(&SurfaceConfiguration, &Device, &Queue) -> AppImpl + 'static,
+    {
+        Self::run(event_loop, runtime_settings, Some(Box::new(init)))
     }
 
-    pub(crate) fn __liftoff(
+    fn run(
         event_loop: EventLoop<()>,
         runtime_settings: AppSettings,
+        app_init: Option<AppInit<AppImpl>>,
     ) -> Result<(), Error> {
         let mut runtime = Self {
             app: None,
+            app_init,
             runtime_settings,
             gil: Gilrs::new().unwrap(),
+            frame_capture: FrameCapture::default(),
             window: None,
             surface: None,
             surface_configuration: None,
@@ -69,7 +92,7 @@ impl<AppImpl: App> AppRuntime<AppImpl> {
             .map_err(Error::EventLoopError)
     }
 
-    fn make_instance() -> Instance {
+    pub(crate) fn make_instance() -> Instance {
         let instance = Instance::new(InstanceDescriptor {
             backends: backend_bits_from_env().unwrap_or_default(),
             flags: InstanceFlags::from_build_config().with_env(),
@@ -96,6 +119,76 @@ impl<AppImpl: App> AppRuntime<AppImpl> {
         adapter
     }
 
+    /// Features requested for each [Device].
+    /// Adapter specific format features allow more sample counts than the
+    /// ones guaranteed everywhere.
+    pub(crate) fn device_features(adapter: &Adapter) -> Features {
+        adapter.features() & Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+    }
+
+    /// Returns the highest sample count, up to `requested`, which a color
+    /// target of the given format and the depth target support.
+    /// Check [App::color_format].
+    /// Falls back to `1`, i.e. no MSAA.
+    pub(crate) fn validate_sample_count(
+        requested: u32,
+        color_format: TextureFormat,
+        adapter: &Adapter,
+        device: &Device,
+    ) -> u32 {
+        let features = device.features();
+        let format_flags = |format: TextureFormat| {
+            if features.contains(Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES) {
+                adapter.get_texture_format_features(format).flags
+            } else {
+                format.guaranteed_format_features(features).flags
+            }
+        };
+        let color_flags = format_flags(color_format);
+        let depth_flags = format_flags(TextureFormat::Depth32Float);
+
+        let sample_count = [16, 8, 4, 2]
+            .into_iter()
+            .filter(|x| *x <= requested)
+            .find(|x| {
+                color_flags.sample_count_supported(*x)
+                    && color_flags.contains(TextureFormatFeatureFlags::MULTISAMPLE_RESOLVE)
+                    && depth_flags.sample_count_supported(*x)
+            })
+            .unwrap_or(1);
+
+        if sample_count != requested {
+            warn!("Sample count {requested} isn't supported, falling back to {sample_count}!");
+        }
+
+        sample_count
+    }
+
+    /// Validates the sample count and passes it on to the [App].
+    fn change_sample_count(&mut self, sample_count: u32) {
+        let (Some(adapter), Some(device), Some(queue), Some(surface_configuration)) = (
+            &self.adapter,
+            &self.device,
+            &self.queue,
+            &self.surface_configuration,
+        ) else {
+            error!("Sample count change requested, but Device does not exist yet!");
+            return;
+        };
+
+        let color_format = self
+            .app
+            .as_ref()
+            .and_then(|x| x.color_format())
+            .unwrap_or(surface_configuration.format);
+        let sample_count = Self::validate_sample_count(sample_count, color_format, adapter, device);
+        self.runtime_settings.sample_count = sample_count;
+
+        if let Some(app) = &mut self.app {
+            app.on_sample_count_change(sample_count, device, queue);
+        }
+    }
+
     fn make_surface_configuration(
         surface: &Surface,
         adapter: &Adapter,
@@ -111,6 +204,15 @@ impl<AppImpl: App> AppRuntime<AppImpl> {
             false => PresentMode::AutoNoVsync,
         };
 
+        // Allow frame captures, if possible
+        if surface
+            .get_capabilities(adapter)
+            .usages
+            .contains(TextureUsages::COPY_SRC)
+        {
+            surface_configuration.usage |= TextureUsages::COPY_SRC;
+        }
+
         // Add SRGB view format
         surface_configuration
             .view_formats
@@ -195,6 +297,13 @@ impl<AppImpl: App> AppRuntime<AppImpl> {
                         self.queue.as_ref().unwrap(),
                     );
 
+                // Copy the frame, if requested, before it's gone
+                self.frame_capture.capture(
+                    &frame.texture,
+                    self.device.as_ref().unwrap(),
+                    self.queue.as_ref().unwrap(),
+                );
+
                 // Present the frame after rendering and inform the window about a redraw being needed
                 frame.present();
             } else {
@@ -203,6 +312,9 @@ impl<AppImpl: App> AppRuntime<AppImpl> {
         } else {
             warn!("No surface yet, but redraw was requested!");
         }
+
+        // Write any previously captured frames
+        self.frame_capture.poll(self.device.as_ref().unwrap());
     }
 
     fn gamepad_inputs(&mut self) {
@@ -273,6 +385,15 @@ impl<AppImpl: App> AppRuntime<AppImpl> {
                         error!("AppChange::ChangeCursorVisible proposed, but Window does not exist yet!");
                     }
                 }
+                AppChange::CaptureScreenshot(path) => {
+                    self.frame_capture.capture_screenshot(path);
+                }
+                AppChange::CaptureFrames { count, directory } => {
+                    self.frame_capture.capture_frames(count, directory);
+                }
+                AppChange::ChangeSampleCount(sample_count) => {
+                    self.change_sample_count(sample_count);
+                }
             }
         }
     }
@@ -318,7 +439,7 @@ impl<AppImpl: App> ApplicationHandler for AppRuntime<AppImpl> {
         let (device, queue) = pollster::block_on(self.adapter.as_ref().unwrap().request_device(
             &DeviceDescriptor {
                 label: None,
-                required_features: Features::default(),
+                required_features: Self::device_features(self.adapter.as_ref().unwrap()),
                 required_limits: Limits::default(),
             },
             None,
@@ -333,15 +454,26 @@ impl<AppImpl: App> ApplicationHandler for AppRuntime<AppImpl> {
         if self.app.is_none() {
             info!("Bootstrapping app ...");
 
-            self.app = Some(AppImpl::init(
-                self.surface_configuration.as_ref().unwrap(),
-                self.device.as_ref().unwrap(),
-                self.queue.as_ref().unwrap(),
-            ));
+            let configuration = self.surface_configuration.as_ref().unwrap();
+            let device = self.device.as_ref().unwrap();
+            let queue = self.queue.as_ref().unwrap();
+
+            self.app = Some(match self.app_init.take() {
+                Some(init) => init(configuration, device, queue),
+                None => AppImpl::init(configuration, device, queue),
+            });
         }
+
+        // The adapter may have changed, thus validate again
+        self.change_sample_count(self.runtime_settings.sample_count);
     }
 
     fn suspended(&mut self, _event_loop: &ActiveEventLoop) {
+        // Captured frames belong to the device
+        if let Some(device) = &self.device {
+            self.frame_capture.flush(device);
+        }
+
         // Invalidate everything related to the window, surface and device.
         // (Except for the app!)
         self.window = None;
@@ -372,6 +504,11 @@ impl<AppImpl: App> ApplicationHandler for AppRuntime<AppImpl> {
             }
             WindowEvent::CloseRequested => {
                 info!("Close requested!");
+
+                if let Some(device) = &self.device {
+                    self.frame_capture.flush(device);
+                }
+
                 event_loop.exit();
             }
             WindowEvent::RedrawRequested => {
@@ -503,8 +640,10 @@ impl<AppImpl: App> Default for AppRuntime<AppImpl> {
     fn default() -> Self {
         Self {
             app: Default::default(),
+            app_init: Default::default(),
             runtime_settings: Default::default(),
             gil: Gilrs::new().unwrap(),
+            frame_capture: FrameCapture::default(),
             window: Default::default(),
             surface: Default::default(),
             surface_configuration: Default::default(),
@@ -1,3 +1,7 @@
+use std::path::PathBuf;
+
+use cgmath::Vector2;
+use wgpu::TextureFormat;
 use winit::dpi::{PhysicalSize, Size};
 
 #[derive(Debug, Clone)]
@@ -5,6 +9,14 @@ pub struct AppSettings {
     pub name: String,
     pub size: Size,
     pub vsync_enabled: bool,
+    /// Samples per pixel for MSAA, `1` disables it.
+    /// Usually `1`, `2`, `4` or `8`.
+    ///
+    /// If the adapter doesn't support the sample count, the next lower
+    /// supported one is used instead.
+    /// Can be changed later via
+    /// [AppChange::ChangeSampleCount](super::AppChange::ChangeSampleCount).
+    pub sample_count: u32,
 }
 
 impl Default for AppSettings {
@@ -13,6 +25,42 @@ impl Default for AppSettings {
             name: "Default App".into(),
             size: PhysicalSize::new(1280, 720).into(),
             vsync_enabled: true,
+            sample_count: 1,
+        }
+    }
+}
+
+/// Settings for headless runs, i.e. without a window.
+/// Check [AppRuntime::liftoff_headless](super::AppRuntime::liftoff_headless).
+#[derive(Debug, Clone)]
+pub struct HeadlessSettings {
+    /// Resolution of each frame
+    pub size: Vector2<u32>,
+    /// Amount of frames to render before returning
+    pub frame_count: u32,
+    /// Time each frame takes in seconds.
+    /// Passed on as the delta time of [Games](crate::game::Game).
+    pub timestep: f64,
+    /// Format of the offscreen texture.  
+    /// ⚠️ Must be one of the 8-bit RGBA or BGRA formats!
+    pub format: TextureFormat,
+    /// If set, each frame is written into this directory as
+    /// `frame_<index>.png` instead of being returned.
+    pub output_directory: Option<PathBuf>,
+    /// Samples per pixel for MSAA.
+    /// Check [AppSettings::sample_count].
+    pub sample_count: u32,
+}
+
+impl Default for HeadlessSettings {
+    fn default() -> Self {
+        Self {
+            size: Vector2::new(1280, 720),
+            frame_count: 1,
+            timestep: 1.0 / 60.0,
+            format: TextureFormat::Rgba8UnormSrgb,
+            output_directory: None,
+            sample_count: 1,
         }
     }
 }
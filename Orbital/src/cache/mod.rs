@@ -150,6 +150,21 @@ where
         self.map = new_map;
     }
 
+    /// Drops all values, regardless of when they were last accessed.
+    ///
+    /// This is needed if the values can't be used anymore, e.g. if they
+    /// were made with a different [Device](crate::wgpu::Device).
+    pub fn clear(&mut self) -> CacheChange {
+        let before = self.size();
+
+        self.map.clear();
+
+        CacheChange {
+            before,
+            after: self.size(),
+        }
+    }
+
     pub fn size(&self) -> usize {
         self.map.len()
     }
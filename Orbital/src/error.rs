@@ -1,4 +1,6 @@
-use wgpu::CreateSurfaceError;
+use std::path::PathBuf;
+
+use wgpu::{CreateSurfaceError, TextureFormat};
 use winit::error::EventLoopError;
 
 #[derive(Debug)]
@@ -17,4 +19,9 @@ pub enum Error {
     ModelNotFound,
     ImageError(image::ImageError),
     CannotRealizeTag(String),
+    UnsupportedTextureFormat(TextureFormat),
+    NoFrames,
+    GoldenImageMissing(PathBuf),
+    GoldenImageMismatch(PathBuf, usize),
+    GoldenImageSizeMismatch((u32, u32), (u32, u32)),
 }
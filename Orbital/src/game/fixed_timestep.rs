@@ -0,0 +1,112 @@
+use log::{debug, warn};
+
+use super::FixedUpdateSettings;
+
+/// Accumulates frame times and turns them into fixed-size steps.
+/// Check [FixedUpdateSettings].
+#[derive(Debug, Clone)]
+pub struct FixedTimestep {
+    settings: FixedUpdateSettings,
+    accumulator: f64,
+}
+
+impl FixedTimestep {
+    /// A [FixedUpdateSettings::rate] that isn't positive and finite falls
+    /// back to the default rate.
+    pub fn new(mut settings: FixedUpdateSettings) -> Self {
+        if !(settings.rate.is_finite() && settings.rate > 0.0) {
+            let default_rate = FixedUpdateSettings::default().rate;
+            warn!(
+                "Fixed update rate must be positive, but is {}! Falling back to {}.",
+                settings.rate, default_rate
+            );
+            settings.rate = default_rate;
+        }
+
+        Self {
+            settings,
+            accumulator: 0.0,
+        }
+    }
+
+    /// Adds the time passed since the last frame and returns how many
+    /// fixed steps need to be simulated.
+    ///
+    /// Never returns more than [FixedUpdateSettings::max_catch_up_steps].
+    /// Any time past that is dropped.
+    pub fn advance(&mut self, delta_time: f64) -> u32 {
+        let fixed_delta_time = self.fixed_delta_time();
+        self.accumulator += delta_time;
+
+        let steps = (self.accumulator / fixed_delta_time).floor() as u32;
+        if steps > self.settings.max_catch_up_steps {
+            debug!(
+                "Fixed update is falling behind! Skipping {} steps.",
+                steps - self.settings.max_catch_up_steps
+            );
+
+            self.accumulator %= fixed_delta_time;
+            return self.settings.max_catch_up_steps;
+        }
+
+        self.accumulator -= steps as f64 * fixed_delta_time;
+        steps
+    }
+
+    /// How far (`0.0` to `1.0`) the current frame is in between the last
+    /// and the next fixed step.
+    /// Used to interpolate between fixed step states when rendering.
+    pub fn alpha(&self) -> f64 {
+        (self.accumulator / self.fixed_delta_time()).clamp(0.0, 1.0)
+    }
+
+    pub fn fixed_delta_time(&self) -> f64 {
+        self.settings.fixed_delta_time()
+    }
+
+    pub fn settings(&self) -> &FixedUpdateSettings {
+        &self.settings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixed_timestep(rate: f64) -> FixedTimestep {
+        FixedTimestep::new(FixedUpdateSettings {
+            rate,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn accumulates_into_steps() {
+        let mut fixed_timestep = fixed_timestep(10.0);
+
+        assert_eq!(fixed_timestep.advance(0.05), 0);
+        assert!((fixed_timestep.alpha() - 0.5).abs() < 1e-9);
+        assert_eq!(fixed_timestep.advance(0.2), 2);
+        assert!((fixed_timestep.alpha() - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn drops_time_past_max_catch_up_steps() {
+        let mut fixed_timestep = fixed_timestep(10.0);
+
+        assert_eq!(fixed_timestep.advance(10.05), 5);
+        assert!((fixed_timestep.alpha() - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn invalid_rates_fall_back_to_default() {
+        let default_rate = FixedUpdateSettings::default().rate;
+
+        for rate in [0.0, -30.0, f64::NAN, f64::INFINITY] {
+            let mut fixed_timestep = fixed_timestep(rate);
+
+            assert_eq!(fixed_timestep.settings().rate, default_rate);
+            assert_eq!(fixed_timestep.advance(1.0 / default_rate), 1);
+        }
+    }
+}
@@ -9,6 +9,9 @@ pub use settings::*;
 pub mod world;
 pub use world::*;
 
+pub mod fixed_timestep;
+pub use fixed_timestep::*;
+
 /// Implement this trait to make a [Game].  
 /// A [Game] effectively builds upon an [App], but automates everything
 /// much further.
@@ -103,6 +106,8 @@ pub use world::*;
 /// Support here is limited as there is a virtual infinite amount of
 /// possibilities.
 /// In most cases, the [StandardRenderer] should be more than enough!
+/// Scenes with many small lights may benefit from the [DeferredRenderer]
+/// instead.
 ///
 /// # Examples
 ///
@@ -230,6 +235,7 @@ pub use world::*;
 /// [AppSettings]: crate::app::AppSettings
 /// [Renderer]: crate::renderer::Renderer
 /// [StandardRenderer]: crate::renderer::StandardRenderer
+/// [DeferredRenderer]: crate::renderer::DeferredRenderer
 /// [Pipelines]: crate::resources::realizations::Pipeline
 /// [EventLoop]: crate::winit::event_loop::EventLoop
 /// [resources]: crate::resources
@@ -1,25 +1,27 @@
 use std::{sync::OnceLock, time::Instant};
 
 use cgmath::Vector2;
+use image::RgbaImage;
 use log::{debug, info};
-use wgpu::{Device, Queue, SurfaceConfiguration, TextureView};
+use wgpu::{Device, Queue, SurfaceConfiguration, TextureFormat, TextureView};
 use winit::event_loop::EventLoop;
 
 use crate::{
-    app::{App, AppChange, AppRuntime, InputEvent},
+    app::{App, AppChange, AppRuntime, HeadlessSettings, InputEvent},
     error::Error,
     renderer::Renderer,
     resources::realizations::{Material, Pipeline},
     timer::Timer,
 };
 
-use super::{CacheSettings, Game, GameSettings, World};
+use super::{CacheSettings, FixedTimestep, Game, GameSettings, World};
 
 pub struct GameRuntime<GameImpl: Game, RendererImpl: Renderer> {
     game: GameImpl,
     game_startup_complete: bool,
     world: World,
     timer: Timer,
+    fixed_timestep: Option<FixedTimestep>,
     renderer: RendererImpl,
     pipeline_cleanup_timer: Instant,
     material_cleanup_timer: Instant,
@@ -33,17 +35,76 @@ impl<GameImpl: Game, RendererImpl: Renderer> GameRuntime<GameImpl, RendererImpl>
         info!("Akimo-Project: Game Runtime");
         info!(" --- @SakulFlee --- ");
 
-        unsafe {
-            PIPELINE_CACHE_SETTINGS.get_or_init(|| settings.pipeline_cache);
-            MATERIAL_CACHE_SETTINGS.get_or_init(|| settings.material_cache);
-        }
+        Self::init_static_settings(&settings);
 
         AppRuntime::<GameRuntime<GameImpl, RendererImpl>>::__liftoff(
             event_loop,
-            settings.app_settings,
+            settings.app_settings.clone(),
+            move |config, device, queue| Self::new(&settings, config, device, queue),
         )
     }
 
+    /// Runs the [Game] without a window, e.g. in CI.
+    /// The [World] is advanced by [HeadlessSettings::timestep] each frame.
+    ///
+    /// Check [AppRuntime::liftoff_headless] for more.
+    pub fn liftoff_headless(
+        settings: GameSettings,
+        headless_settings: HeadlessSettings,
+    ) -> Result<Vec<RgbaImage>, Error> {
+        info!("Akimo-Project: Game Runtime (Headless)");
+        info!(" --- @SakulFlee --- ");
+
+        Self::init_static_settings(&settings);
+
+        let timestep = headless_settings.timestep;
+        AppRuntime::<GameRuntime<GameImpl, RendererImpl>>::__liftoff_headless(
+            headless_settings,
+            |config, device, queue| Self {
+                timer: Timer::fixed(timestep),
+                ..Self::new(&settings, config, device, queue)
+            },
+        )
+    }
+
+    fn init_static_settings(settings: &GameSettings) {
+        unsafe {
+            PIPELINE_CACHE_SETTINGS.get_or_init(|| settings.pipeline_cache.clone());
+            MATERIAL_CACHE_SETTINGS.get_or_init(|| settings.material_cache.clone());
+        }
+    }
+
+    /// Applies the per-runtime parts of the [GameSettings].
+    fn new(
+        settings: &GameSettings,
+        config: &SurfaceConfiguration,
+        device: &Device,
+        queue: &Queue,
+    ) -> Self {
+        let mut renderer = RendererImpl::new(
+            config.format,
+            (config.width, config.height).into(),
+            device,
+            queue,
+        );
+        renderer.change_settings(&settings.renderer, device, queue);
+
+        let mut world = World::default();
+        world.set_viewport_size((config.width, config.height).into());
+        world.set_gpu_culling(renderer.is_gpu_culling());
+
+        Self {
+            game: GameImpl::init(),
+            game_startup_complete: false,
+            world,
+            timer: Timer::new(),
+            fixed_timestep: settings.fixed_update.clone().map(FixedTimestep::new),
+            renderer,
+            pipeline_cleanup_timer: Instant::now(),
+            material_cleanup_timer: Instant::now(),
+        }
+    }
+
     fn do_cleanup(&mut self, device: &Device, queue: &Queue) {
         self.do_pipeline_cache_cleanup(device, queue);
         self.do_material_cache_cleanup();
@@ -59,7 +120,7 @@ impl<GameImpl: Game, RendererImpl: Renderer> GameRuntime<GameImpl, RendererImpl>
             self.pipeline_cleanup_timer = Instant::now();
 
             // Cache access
-            let cache = Pipeline::prepare_cache_access(None, device, queue);
+            let cache = Pipeline::prepare_cache_access(None, None, device, queue);
 
             // Run cleanup
             let change = cache.cleanup(pipeline_cache_settings.retain_period);
@@ -109,30 +170,33 @@ impl<GameImpl: Game, RendererImpl: Renderer> App for GameRuntime<GameImpl, Rende
     where
         Self: Sized,
     {
-        Self {
-            game: GameImpl::init(),
-            game_startup_complete: false,
-            world: World::default(),
-            timer: Timer::new(),
-            renderer: RendererImpl::new(
-                config.format,
-                (config.width, config.height).into(),
-                device,
-                queue,
-            ),
-            pipeline_cleanup_timer: Instant::now(),
-            material_cleanup_timer: Instant::now(),
-        }
+        Self::new(&GameSettings::default(), config, device, queue)
     }
 
     fn on_resize(&mut self, new_resolution: Vector2<u32>, device: &Device, queue: &Queue)
     where
         Self: Sized,
     {
+        self.world.set_viewport_size(new_resolution);
         self.renderer
             .change_resolution(new_resolution, device, queue);
     }
 
+    fn on_sample_count_change(&mut self, sample_count: u32, device: &Device, queue: &Queue)
+    where
+        Self: Sized,
+    {
+        self.renderer
+            .change_sample_count(sample_count, device, queue);
+    }
+
+    fn color_format(&self) -> Option<TextureFormat>
+    where
+        Self: Sized,
+    {
+        Some(self.renderer.color_format())
+    }
+
     fn on_focus_change(&mut self, focused: bool)
     where
         Self: Sized,
@@ -158,6 +222,18 @@ impl<GameImpl: Game, RendererImpl: Renderer> App for GameRuntime<GameImpl, Rende
             self.game_startup_complete = true;
         }
 
+        if let Some(fixed_timestep) = &mut self.fixed_timestep {
+            let fixed_delta_time = fixed_timestep.fixed_delta_time();
+            for _ in 0..fixed_timestep.advance(delta_time) {
+                self.world.fixed_update(fixed_delta_time);
+            }
+
+            self.world.set_interpolation_alpha(fixed_timestep.alpha());
+        } else {
+            // Without fixed updates, physics follows the frame rate
+            self.world.step_physics(delta_time);
+        }
+
         let app_changes = self.world.update(delta_time);
 
         // TODO: Needed?
@@ -176,14 +252,24 @@ impl<GameImpl: Game, RendererImpl: Renderer> App for GameRuntime<GameImpl, Rende
     {
         self.world.prepare_render(device, queue);
 
-        let (camera, models) = self.world.gather_render_resources();
+        let (camera, models, lights, environment) = self.world.gather_render_resources();
 
         self.renderer
-            .render(target_view, device, queue, &models, camera);
+            .set_interpolation_alpha(self.world.interpolation_alpha());
+        self.renderer.render(
+            target_view,
+            device,
+            queue,
+            &models,
+            &lights,
+            environment,
+            camera,
+        );
 
         if let Some((delta_time, fps)) = self.timer.tick() {
             debug!("FPS: {fps}");
             debug!("Tick  Delta: {} ms", delta_time);
+            debug!("Render Stats: {:?}", self.renderer.stats());
 
             self.do_cleanup(device, queue);
         }
@@ -1,12 +1,20 @@
 use std::time::Duration;
 
-use crate::app::AppSettings;
+use crate::{app::AppSettings, renderer::RendererSettings};
 
 #[derive(Default, Debug, Clone)]
 pub struct GameSettings {
     pub app_settings: AppSettings,
     pub pipeline_cache: CacheSettings,
     pub material_cache: CacheSettings,
+    /// If set, [Elements](super::Element) additionally get updated at a
+    /// fixed rate, independent of the frame rate.
+    /// Check [Element::on_fixed_update](super::Element::on_fixed_update).
+    pub fixed_update: Option<FixedUpdateSettings>,
+    /// Applied to the [Renderer](crate::renderer::Renderer) once it's
+    /// made.
+    /// Check [Renderer::change_settings](crate::renderer::Renderer::change_settings).
+    pub renderer: RendererSettings,
 }
 
 #[derive(Debug, Clone)]
@@ -23,3 +31,30 @@ impl Default for CacheSettings {
         }
     }
 }
+
+#[derive(Debug, Clone)]
+pub struct FixedUpdateSettings {
+    /// Fixed updates per second.
+    /// Must be positive, otherwise the default is used.
+    pub rate: f64,
+    /// Maximum amount of fixed updates per frame.
+    /// If a frame takes longer, the simulation slows down instead of
+    /// spiraling into ever longer frames.
+    pub max_catch_up_steps: u32,
+}
+
+impl FixedUpdateSettings {
+    /// Time between two fixed updates in seconds.
+    pub fn fixed_delta_time(&self) -> f64 {
+        1.0 / self.rate
+    }
+}
+
+impl Default for FixedUpdateSettings {
+    fn default() -> Self {
+        Self {
+            rate: 60.0,
+            max_catch_up_steps: 5,
+        }
+    }
+}
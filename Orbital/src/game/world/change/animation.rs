@@ -0,0 +1,40 @@
+use crate::resources::descriptors::ImportDescriptor;
+
+/// Controls the [AnimationPlayer] of a [Model].
+/// Used by [WorldChange::UpdateModelAnimation](super::WorldChange::UpdateModelAnimation).
+///
+/// [AnimationClip]s are selected with an [ImportDescriptor], i.e. by
+/// their index or their name.
+///
+/// [AnimationPlayer]: crate::resources::realizations::AnimationPlayer
+/// [AnimationClip]: crate::resources::realizations::AnimationClip
+/// [Model]: crate::resources::realizations::Model
+#[derive(Debug, Clone)]
+pub enum AnimationChange {
+    /// Plays an [AnimationClip](crate::resources::realizations::AnimationClip)
+    /// from the start, replacing whatever is currently playing.
+    Play(ImportDescriptor),
+    /// Blends from whatever is currently playing to an
+    /// [AnimationClip](crate::resources::realizations::AnimationClip)
+    /// over the given duration in seconds.
+    BlendTo(ImportDescriptor, f32),
+    /// Pauses playback, keeping the current pose.
+    Pause,
+    /// Resumes a paused playback.
+    Resume,
+    /// Stops playback and returns to the rest pose.
+    Stop,
+    /// Enables or disables looping.
+    /// If disabled, playback stops on the last keyframe.
+    SetLooping(bool),
+    /// Changes the playback speed.
+    /// `1.0` is normal speed, negative values play in reverse.
+    SetSpeed(f32),
+    /// Jumps to the given time in seconds.
+    Seek(f32),
+    /// Sets the weight of each
+    /// [MorphTarget](crate::resources::realizations::MorphTarget),
+    /// used whenever the weights aren't animated.
+    /// Missing weights are zero.
+    SetMorphWeights(Vec<f32>),
+}
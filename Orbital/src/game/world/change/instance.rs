@@ -0,0 +1,29 @@
+use crate::resources::descriptors::InstanceDescriptor;
+
+use super::TransformChange;
+
+/// A single operation on the [Instance]s of a [Model].
+/// Used by [WorldChange::UpdateModelInstances](super::WorldChange::UpdateModelInstances).
+///
+/// Operations are applied in order.
+/// Indices always refer to the state **after** all previous operations
+/// have been applied.
+///
+/// [Instance]: crate::resources::realizations::Instance
+/// [Model]: crate::resources::realizations::Model
+#[derive(Debug, Clone)]
+pub enum InstanceChange {
+    /// Changes the [Instance](crate::resources::realizations::Instance)
+    /// at the given index.
+    Update(usize, TransformChange),
+    /// Changes **all** [Instance](crate::resources::realizations::Instance)s
+    /// the same way.
+    UpdateAll(TransformChange),
+    /// Appends a new [Instance](crate::resources::realizations::Instance).
+    Add(InstanceDescriptor),
+    /// Removes the [Instance](crate::resources::realizations::Instance) at
+    /// the given index.
+    /// Any following [Instance](crate::resources::realizations::Instance)s
+    /// shift down by one.
+    Remove(usize),
+}
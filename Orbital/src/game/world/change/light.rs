@@ -0,0 +1,27 @@
+use cgmath::Vector3;
+
+use crate::resources::descriptors::ShadowDescriptor;
+
+use super::Mode;
+
+#[derive(Debug, Default)]
+pub struct LightChange {
+    pub target: String,
+    pub position: Option<Mode<Vector3<f32>>>,
+    pub direction: Option<Mode<Vector3<f32>>>,
+    pub color: Option<Vector3<f32>>,
+    pub intensity: Option<Mode<f32>>,
+    /// `Some(None)` disables shadows, `Some(Some(...))` enables or changes
+    /// them.
+    pub shadow: Option<Option<ShadowDescriptor>>,
+}
+
+impl LightChange {
+    pub fn does_change_something(&self) -> bool {
+        self.position.is_some()
+            || self.direction.is_some()
+            || self.color.is_some()
+            || self.intensity.is_some()
+            || self.shadow.is_some()
+    }
+}
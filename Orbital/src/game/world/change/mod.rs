@@ -2,14 +2,20 @@ use std::{any::Any, fmt};
 
 use hashbrown::HashMap;
 
+use cgmath::Vector3;
+
 use crate::{
     app::AppChange,
     game::Element,
-    resources::descriptors::{CameraDescriptor, ModelDescriptor},
+    physics::{RigidBodyChange, RigidBodyDescriptor},
+    resources::descriptors::{
+        CameraDescriptor, CompositionDescriptor, EnvironmentDescriptor, LightDescriptor,
+        ModelDescriptor,
+    },
     variant::Variant,
 };
 
-use super::{ElementUlid, Identifier, ModelUlid};
+use super::{ElementUlid, Identifier, ModelIdentifier};
 
 pub mod mode;
 pub use mode::*;
@@ -17,6 +23,18 @@ pub use mode::*;
 pub mod camera;
 pub use camera::*;
 
+pub mod light;
+pub use light::*;
+
+pub mod instance;
+pub use instance::*;
+
+pub mod transform;
+pub use transform::*;
+
+pub mod animation;
+pub use animation::*;
+
 /// A [WorldChange] is a _proposed change to the [World]_.  
 ///
 /// [World]: super::World
@@ -33,20 +51,98 @@ pub enum WorldChange {
     /// an [ElementUlid].
     /// The [ElementUlid] of the current [Element] will be used.
     ///
+    /// Optionally, a _label_ can be given to the [Model].
+    /// Check [WorldChange::SpawnModel] for more.
+    ///
     /// [Model]: crate::resources::realizations::Model
-    SpawnModelOwned(ModelDescriptor),
+    SpawnModelOwned(ModelDescriptor, Option<String>),
     /// Queues a [Model] to be spawned.
     ///
     /// Same as [WorldChange::SpawnModelOwned], but with needing to supply
     /// an [ElementUlid].
     ///
+    /// Once spawned, the owning [Element] will be informed about the
+    /// [ModelUlid](super::ModelUlid) via [Element::on_model_spawned].  
+    /// Optionally, a _label_ can be given to the [Model].
+    /// The _label_ can be used to identify the [Model] later on.
+    /// Check [ModelIdentifier] for more.
+    ///
+    /// [Model]: crate::resources::realizations::Model
+    SpawnModel(ModelDescriptor, Option<String>, ElementUlid),
+    /// Queues one or many [Model(s)](crate::resources::realizations::Model)
+    /// to be despawned.  
+    /// Use a [ModelIdentifier] to select what to despawn!
+    DespawnModel(ModelIdentifier),
+    /// Queues a [Composition] to be spawned.
+    ///
+    /// Same as [WorldChange::SpawnComposition], but without needing to
+    /// supply an [ElementUlid].
+    /// The [ElementUlid] of the current [Element] will be used.
+    ///
+    /// [Composition]: crate::resources::realizations::Composition
+    SpawnCompositionOwned(CompositionDescriptor),
+    /// Queues a [Composition] to be spawned.
+    ///
+    /// Every [Model], [Camera] and [Light] of the [Composition] will be
+    /// owned by the given [ElementUlid].
+    /// If said [Element] despawns, all of them will be despawned as well.
+    ///
+    /// [Camera]s and [Light]s follow the same rules as
+    /// [WorldChange::SpawnCamera] and [WorldChange::SpawnLight].
+    /// I.e. if an `identifier` is already taken, that [Camera] or [Light]
+    /// will be rejected.
+    ///
+    /// [Composition]: crate::resources::realizations::Composition
+    /// [Model]: crate::resources::realizations::Model
+    /// [Camera]: crate::resources::realizations::Camera
+    /// [Light]: crate::resources::realizations::Light
+    SpawnComposition(CompositionDescriptor, ElementUlid),
+    /// Applies [InstanceChange]s to the target [Model], in order.
+    ///
+    /// Use this to move, rotate or scale [Instance]s at runtime, or to
+    /// add and remove [Instance]s.  
+    /// If multiple [Model]s match the [ModelIdentifier], all will be changed.  
+    /// If no [Model] matches the [ModelIdentifier], this change
+    /// will be rejected and a warning will be printed to console.
+    ///
+    /// [Model]: crate::resources::realizations::Model
+    /// [Instance]: crate::resources::realizations::Instance
+    UpdateModelInstances(ModelIdentifier, Vec<InstanceChange>),
+    /// Applies an [AnimationChange] to the target [Model], e.g. to play,
+    /// pause or blend between [AnimationClip]s.
+    ///
+    /// Usually, a [ModelIdentifier::Ulid] is used, as reported by
+    /// [Element::on_model_spawned].  
+    /// If multiple [Model]s match the [ModelIdentifier], all will be changed.  
+    /// If no [Model] matches the [ModelIdentifier], this change
+    /// will be rejected and a warning will be printed to console.
+    /// Same goes for [Model]s without a [Skeleton].
+    ///
     /// [Model]: crate::resources::realizations::Model
-    SpawnModel(ModelDescriptor, ElementUlid),
-    /// Queues a [Model] to be despawned.  
-    /// Use a [ModelUlid] to specify which is being despawned.
+    /// [AnimationClip]: crate::resources::realizations::AnimationClip
+    /// [Skeleton]: crate::resources::realizations::Skeleton
+    UpdateModelAnimation(ModelIdentifier, AnimationChange),
+    /// Applies a [TransformChange] to the local [Transform] of one or
+    /// many [Element]s.
+    ///
+    /// Child [Element]s and any linked [Model]s will be moved along.  
+    /// World matrices are recalculated at the end of the current
+    /// [World::update](super::World::update).
     ///
+    /// [Transform]: super::Transform
     /// [Model]: crate::resources::realizations::Model
-    DespawnModel(ModelUlid),
+    UpdateElementTransform(Identifier, TransformChange),
+    /// Changes the parent of one or many [Element]s.  
+    /// `None` detaches the [Element]s from their parent, making their
+    /// [Transform](super::Transform) relative to the [World](super::World)
+    /// origin.
+    ///
+    /// If the parent [Identifier] matches multiple [Element]s, the first
+    /// one is used.  
+    /// If the parent can't be found, or the change would result in a
+    /// cycle, this change will be rejected and a warning will be printed
+    /// to console.
+    ChangeElementParent(Identifier, Option<Identifier>),
     /// Sends a message to one or many [Elements](Element).  
     /// The message must be a [HashMap<String, Variant>].
     SendMessage(Identifier, HashMap<String, Variant>),
@@ -90,6 +186,90 @@ pub enum WorldChange {
     /// [Camera]: crate::resources::realizations::Camera
     /// [Buffer]: wgpu::Buffer
     UpdateCamera(CameraChange),
+    /// Queues a [Light] to be spawned.
+    ///
+    /// Same as [WorldChange::SpawnLight], but without needing to supply
+    /// an [ElementUlid].
+    /// The [ElementUlid] of the current [Element] will be used.
+    ///
+    /// [Light]: crate::resources::realizations::Light
+    SpawnLightOwned(LightDescriptor),
+    /// Queues a [Light] to be spawned.
+    ///
+    /// The [Light] will be owned by the given [ElementUlid].
+    /// If said [Element] despawns, the [Light] will be despawned as well.
+    ///
+    /// If the chosen `identifier` of the [Light] is already taken, this change
+    /// will be rejected.
+    ///
+    /// [Light]: crate::resources::realizations::Light
+    SpawnLight(LightDescriptor, ElementUlid),
+    /// Despawns a [Light] given a `identifier` ([String]).
+    ///
+    /// If a [Light] with the given `identifier` exists, it will be removed.  
+    /// If a [Light] with the given `identifier` does not exist, nothing will happen.
+    ///
+    /// [Light]: crate::resources::realizations::Light
+    DespawnLight(String),
+    /// Applies changes to the target [Light].
+    ///
+    /// If a [Light] exists with the specified [LightDescriptor::identifier],
+    /// any property that is set to `Some(...)` will be applied and will
+    /// trigger a [Buffer] update.  
+    /// If a [Light] does not exists with the [LightDescriptor::identifier],
+    /// this world change will be rejected and a warning will be printed to
+    /// console.
+    ///
+    /// [Light]: crate::resources::realizations::Light
+    /// [Buffer]: wgpu::Buffer
+    UpdateLight(LightChange),
+    /// Changes the [Environment] of the [World].
+    ///
+    /// The [Environment] is used as ambient light (IBL) and, optionally,
+    /// drawn as a skybox.
+    /// `None` removes the current [Environment], resulting in a dim
+    /// uniform ambient light and a black background.
+    ///
+    /// ⚠️ Realizing an [Environment] is expensive, as multiple cube maps
+    /// ⚠️ have to be computed!
+    ///
+    /// If the [Environment] fails to realize, the current one will be kept.
+    ///
+    /// [Environment]: crate::resources::realizations::Environment
+    /// [World]: super::World
+    ChangeEnvironment(Option<EnvironmentDescriptor>),
+    /// Gives the current [Element] a [RigidBody].
+    ///
+    /// Same as [WorldChange::SpawnRigidBody], but without needing to
+    /// supply an [ElementUlid].
+    /// The [ElementUlid] of the current [Element] will be used.
+    ///
+    /// [RigidBody]: crate::physics::RigidBody
+    SpawnRigidBodyOwned(RigidBodyDescriptor),
+    /// Gives an [Element] a [RigidBody], starting at the current
+    /// [Transform](super::Transform) of the [Element].
+    /// An existing [RigidBody] of the [Element] will be replaced.
+    ///
+    /// Usually, [ElementRegistration::rigid_body](super::ElementRegistration::rigid_body)
+    /// is used instead.
+    ///
+    /// [RigidBody]: crate::physics::RigidBody
+    SpawnRigidBody(RigidBodyDescriptor, ElementUlid),
+    /// Removes the [RigidBody] of one or many [Element]s.
+    /// The [Element]s stay where they are.
+    ///
+    /// [RigidBody]: crate::physics::RigidBody
+    DespawnRigidBody(Identifier),
+    /// Applies a [RigidBodyChange] to the [RigidBody] of one or many
+    /// [Element]s, e.g. to set velocities or apply an impulse.
+    ///
+    /// To teleport a [RigidBody], use [WorldChange::UpdateElementTransform].
+    ///
+    /// [RigidBody]: crate::physics::RigidBody
+    UpdateRigidBody(Identifier, RigidBodyChange),
+    /// Changes the gravity of the [PhysicsWorld](crate::physics::PhysicsWorld),
+    /// in meters per second squared.
+    ChangeGravity(Vector3<f32>),
     /// Any [AppChange]s that need to be processed need to use this variant!
     AppChange(AppChange),
 }
@@ -99,11 +279,46 @@ impl fmt::Debug for WorldChange {
         match self {
             Self::SpawnElement(arg0) => write!(f, "SpawnElement@{:?}", arg0.type_id()),
             Self::DespawnElement(arg0) => f.debug_tuple("DespawnElement").field(arg0).finish(),
-            Self::SpawnModelOwned(arg0) => f.debug_tuple("SpawnModelOwned").field(arg0).finish(),
-            Self::SpawnModel(arg0, arg1) => {
-                f.debug_tuple("SpawnModel").field(arg0).field(arg1).finish()
-            }
+            Self::SpawnModelOwned(arg0, arg1) => f
+                .debug_tuple("SpawnModelOwned")
+                .field(arg0)
+                .field(arg1)
+                .finish(),
+            Self::SpawnModel(arg0, arg1, arg2) => f
+                .debug_tuple("SpawnModel")
+                .field(arg0)
+                .field(arg1)
+                .field(arg2)
+                .finish(),
             Self::DespawnModel(arg0) => f.debug_tuple("DespawnModel").field(arg0).finish(),
+            Self::UpdateModelInstances(arg0, arg1) => f
+                .debug_tuple("UpdateModelInstances")
+                .field(arg0)
+                .field(arg1)
+                .finish(),
+            Self::UpdateModelAnimation(arg0, arg1) => f
+                .debug_tuple("UpdateModelAnimation")
+                .field(arg0)
+                .field(arg1)
+                .finish(),
+            Self::SpawnCompositionOwned(arg0) => {
+                f.debug_tuple("SpawnCompositionOwned").field(arg0).finish()
+            }
+            Self::SpawnComposition(arg0, arg1) => f
+                .debug_tuple("SpawnComposition")
+                .field(arg0)
+                .field(arg1)
+                .finish(),
+            Self::UpdateElementTransform(arg0, arg1) => f
+                .debug_tuple("UpdateElementTransform")
+                .field(arg0)
+                .field(arg1)
+                .finish(),
+            Self::ChangeElementParent(arg0, arg1) => f
+                .debug_tuple("ChangeElementParent")
+                .field(arg0)
+                .field(arg1)
+                .finish(),
             Self::SendMessage(arg0, arg1) => f
                 .debug_tuple("SendMessage")
                 .field(arg0)
@@ -119,6 +334,30 @@ impl fmt::Debug for WorldChange {
                 f.debug_tuple("ChangeActiveCamera").field(arg0).finish()
             }
             Self::UpdateCamera(arg0) => f.debug_tuple("UpdateCamera").field(arg0).finish(),
+            Self::SpawnLightOwned(arg0) => f.debug_tuple("SpawnLightOwned").field(arg0).finish(),
+            Self::SpawnLight(arg0, arg1) => {
+                f.debug_tuple("SpawnLight").field(arg0).field(arg1).finish()
+            }
+            Self::DespawnLight(arg0) => f.debug_tuple("DespawnLight").field(arg0).finish(),
+            Self::UpdateLight(arg0) => f.debug_tuple("UpdateLight").field(arg0).finish(),
+            Self::ChangeEnvironment(arg0) => {
+                f.debug_tuple("ChangeEnvironment").field(arg0).finish()
+            }
+            Self::SpawnRigidBodyOwned(arg0) => {
+                f.debug_tuple("SpawnRigidBodyOwned").field(arg0).finish()
+            }
+            Self::SpawnRigidBody(arg0, arg1) => f
+                .debug_tuple("SpawnRigidBody")
+                .field(arg0)
+                .field(arg1)
+                .finish(),
+            Self::DespawnRigidBody(arg0) => f.debug_tuple("DespawnRigidBody").field(arg0).finish(),
+            Self::UpdateRigidBody(arg0, arg1) => f
+                .debug_tuple("UpdateRigidBody")
+                .field(arg0)
+                .field(arg1)
+                .finish(),
+            Self::ChangeGravity(arg0) => f.debug_tuple("ChangeGravity").field(arg0).finish(),
             Self::AppChange(app_change) => f.debug_tuple("AppChange").field(app_change).finish(),
         }
     }
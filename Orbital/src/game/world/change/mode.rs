@@ -34,7 +34,7 @@
 ///
 /// [Camera]: crate::resources::realizations::Camera
 /// [Vector3<f32>]: crate::cgmath::Vector3
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Mode<T> {
     Overwrite(T),
     Offset(T),
@@ -0,0 +1,60 @@
+use cgmath::{InnerSpace, Quaternion, Vector3, Zero};
+
+use super::Mode;
+
+/// Changes a transform (i.e. position, rotation and scale).
+/// Used for [Element](crate::game::Element)s and
+/// [Instance](crate::resources::realizations::Instance)s.
+///
+/// Rotations are combined for [Mode::Offset], meaning the offset rotation
+/// is applied **after** the current rotation.
+/// Positions and scales are added.
+/// [Mode::OffsetViewAligned] falls back to [Mode::Offset], as a transform
+/// has no view angle.
+#[derive(Debug, Default, Clone)]
+pub struct TransformChange {
+    pub position: Option<Mode<Vector3<f32>>>,
+    pub rotation: Option<Mode<Quaternion<f32>>>,
+    pub scale: Option<Mode<Vector3<f32>>>,
+}
+
+impl TransformChange {
+    pub fn does_change_something(&self) -> bool {
+        self.position.is_some() || self.rotation.is_some() || self.scale.is_some()
+    }
+
+    /// Applies this change to the given position, rotation and scale.
+    pub fn apply_to(
+        self,
+        position: &mut Vector3<f32>,
+        rotation: &mut Quaternion<f32>,
+        scale: &mut Vector3<f32>,
+    ) {
+        if let Some(mode) = self.position {
+            match mode {
+                Mode::Overwrite(new_position) => *position = new_position,
+                Mode::Offset(offset) | Mode::OffsetViewAligned(offset) => *position += offset,
+            }
+        }
+
+        if let Some(mode) = self.rotation {
+            match mode {
+                Mode::Overwrite(new_rotation) => *rotation = new_rotation,
+                Mode::Offset(offset) | Mode::OffsetViewAligned(offset) => {
+                    *rotation = offset * *rotation
+                }
+            }
+
+            if !rotation.is_zero() {
+                *rotation = rotation.normalize();
+            }
+        }
+
+        if let Some(mode) = self.scale {
+            match mode {
+                Mode::Overwrite(new_scale) => *scale = new_scale,
+                Mode::Offset(offset) | Mode::OffsetViewAligned(offset) => *scale += offset,
+            }
+        }
+    }
+}
@@ -2,7 +2,12 @@ use hashbrown::HashMap;
 use log::warn;
 use ulid::Ulid;
 
-use crate::{app::InputEvent, game::WorldChange, variant::Variant};
+use crate::{
+    app::InputEvent,
+    game::{ModelUlid, PickEvent, WorldChange},
+    physics::CollisionEvent,
+    variant::Variant,
+};
 
 pub mod registration;
 pub use registration::*;
@@ -113,6 +118,58 @@ pub trait Element {
         None
     }
 
+    /// Called at a fixed rate, independent of the frame rate.
+    /// Thus, this may be called multiple times per frame, or not at all.
+    /// Use this for anything that has to be frame rate independent,
+    /// like physics or gameplay logic.
+    ///
+    /// ⚠️ Only called if
+    /// [GameSettings::fixed_update](crate::game::GameSettings::fixed_update)
+    /// is set.
+    fn on_fixed_update(&mut self, _fixed_delta_time: f64) -> Option<Vec<WorldChange>> {
+        None
+    }
+
+    /// Called once a [Model] owned by this [Element] got spawned.
+    ///
+    /// `label` is the _label_ given when spawning the [Model], if any.
+    /// Keep the [ModelUlid] around to e.g. later despawn or update the
+    /// [Model]. Alternatively, a _label_ can be used via
+    /// [ModelIdentifier](crate::game::ModelIdentifier).
+    ///
+    /// [Model]: crate::resources::realizations::Model
+    fn on_model_spawned(
+        &mut self,
+        _model_ulid: ModelUlid,
+        _label: Option<&str>,
+    ) -> Option<Vec<WorldChange>> {
+        None
+    }
+
+    /// Called once a [Model] owned by this [Element] got clicked, i.e.
+    /// it is the closest [Model] under the cursor when a mouse button
+    /// is pressed.
+    ///
+    /// Check [World::pick](crate::game::World::pick) for how [Model]s
+    /// are picked.
+    ///
+    /// [Model]: crate::resources::realizations::Model
+    fn on_pick(&mut self, _pick_event: &PickEvent) -> Option<Vec<WorldChange>> {
+        None
+    }
+
+    /// Called once the [RigidBody] of this [Element] starts or stops
+    /// touching another [RigidBody].
+    /// Both [Element]s involved get informed.
+    ///
+    /// ⚠️ Only called for [Element]s with a [RigidBody], check
+    /// [ElementRegistration::rigid_body].
+    ///
+    /// [RigidBody]: crate::physics::RigidBody
+    fn on_collision(&mut self, _event: &CollisionEvent) -> Option<Vec<WorldChange>> {
+        None
+    }
+
     fn on_message(&mut self, message: HashMap<String, Variant>) -> Option<Vec<WorldChange>> {
         warn!("Unhandled message received: {:#?}", message);
 
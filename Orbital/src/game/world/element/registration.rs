@@ -1,4 +1,8 @@
-use crate::{game::WorldChange, resources::descriptors::ModelDescriptor};
+use crate::{
+    game::{Identifier, Transform, WorldChange},
+    physics::RigidBodyDescriptor,
+    resources::descriptors::ModelDescriptor,
+};
 
 /// Used when registering an [Element](super::Element).
 #[derive(Default, Debug)]
@@ -29,7 +33,8 @@ pub struct ElementRegistration {
     ///
     /// Each [Model] will be linked with the registering [Element].
     /// If an [Element] is removed, any linked [Model] will also be removed.
-    /// If an [Element] is moved, any linked [Model] will also be offset.
+    /// If an [Element] is moved (see [ElementRegistration::transform]),
+    /// any linked [Model] will be moved along with it.
     ///
     /// If there is a need to remove or add [Model]s at some later point in time
     /// (i.e. **after registration**), it is possible to directly interact with
@@ -40,6 +45,51 @@ pub struct ElementRegistration {
     /// [Element]: super::Element
     /// [World]: crate::game::world::World
     pub models: Option<Vec<ModelDescriptor>>,
+    /// Each [Element] can **optionally** define it's initial [Transform].
+    /// If not set, [Transform::default] will be used.
+    ///
+    /// The [Transform] is relative to the parent (see
+    /// [ElementRegistration::parent]), or the [World] origin if there is
+    /// no parent.
+    /// Any linked [Model] will be transformed by it.
+    ///
+    /// Use [WorldChange::UpdateElementTransform] to move the [Element]
+    /// later on.
+    ///
+    /// [Element]: super::Element
+    /// [Model]: crate::resources::realizations::Model
+    /// [World]: crate::game::world::World
+    pub transform: Option<Transform>,
+    /// Each [Element] can **optionally** have a parent [Element].
+    /// If the parent is moved, this [Element] (and it's [Model]s) will be
+    /// moved along with it.
+    /// If the parent is despawned, this [Element] will be despawned too.
+    ///
+    /// If the [Identifier] matches multiple [Element]s, the first one
+    /// is used.
+    /// If the parent can't be found, the [Element] will have no parent
+    /// and a warning will be printed to console.
+    ///
+    /// Use [WorldChange::ChangeElementParent] to change the parent
+    /// later on.
+    ///
+    /// [Element]: super::Element
+    /// [Model]: crate::resources::realizations::Model
+    pub parent: Option<Identifier>,
+    /// Each [Element] can **optionally** have a [RigidBody].
+    /// The [RigidBody] starts at the [Transform] of the [Element] and
+    /// moves the [Element] (and it's [Model]s) while simulating.
+    ///
+    /// Collisions are reported via [Element::on_collision].
+    ///
+    /// Use [WorldChange::UpdateRigidBody] to e.g. change the velocity
+    /// later on.
+    ///
+    /// [Element]: super::Element
+    /// [Element::on_collision]: super::Element::on_collision
+    /// [Model]: crate::resources::realizations::Model
+    /// [RigidBody]: crate::physics::RigidBody
+    pub rigid_body: Option<RigidBodyDescriptor>,
     /// Each [Element] can **optionally** define one or more [WorldChange]s.
     /// These [WorldChange]s will be queued and realized lazily.
     ///
@@ -1,7 +1,21 @@
 use ulid::Ulid;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Identifier {
     Ulid(Ulid),
     Tag(String),
 }
+
+/// Similar to [Identifier], but for [Model]s.
+///
+/// A _label_ can optionally be given to a [Model] when spawning it.
+/// Like _tags_, _labels_ don't need to be unique.
+/// If multiple [Model]s share the same _label_, **all** will be
+/// interacted with.
+///
+/// [Model]: crate::resources::realizations::Model
+#[derive(Debug, Clone)]
+pub enum ModelIdentifier {
+    Ulid(Ulid),
+    Label(String),
+}
@@ -1,16 +1,32 @@
-use std::{any::Any, mem::replace};
+use std::{
+    any::Any,
+    mem::{replace, take},
+    sync::OnceLock,
+};
 
-use hashbrown::HashMap;
+use cgmath::{
+    EuclideanSpace, Matrix4, Point3, Quaternion, SquareMatrix, Transform as _, Vector2, Vector3,
+    Zero,
+};
+use hashbrown::{HashMap, HashSet};
 use log::{info, warn};
 use ulid::Ulid;
 use wgpu::{Device, Queue};
+use winit::{dpi::PhysicalPosition, event::ElementState};
 
 use crate::{
     app::{AppChange, InputEvent},
     log::error,
+    physics::{
+        ColliderShape, CollisionEvent, PhysicsWorld, Ray, RigidBody, RigidBodyDescriptor,
+        RigidBodyType,
+    },
     resources::{
-        descriptors::{CameraDescriptor, ModelDescriptor},
-        realizations::{Camera, Model},
+        descriptors::{
+            CameraDescriptor, CompositionDescriptor, EnvironmentDescriptor, InstanceDescriptor,
+            LightDescriptor, ModelDescriptor,
+        },
+        realizations::{Camera, Composition, Environment, Light, Model},
     },
     variant::Variant,
 };
@@ -24,6 +40,12 @@ pub use element::*;
 pub mod identifier;
 pub use identifier::*;
 
+pub mod transform;
+pub use transform::*;
+
+pub mod query;
+pub use query::*;
+
 pub type ElementUlid = Ulid;
 pub type ModelUlid = Ulid;
 
@@ -72,8 +94,22 @@ pub struct World {
     /// Translation map to determine ownership over [Model]s
     /// based on [Element] [Ulid]s
     model_owner: HashMap<ModelUlid, ElementUlid>,
+    /// Translation map to determine _label_ association between [Model]s
+    model_labels: HashMap<String, Vec<ModelUlid>>,
     /// Translation map to determine _tag_ association between [Element]s
     tags: HashMap<String, Vec<ElementUlid>>,
+    // --- Transforms ---
+    /// Local [Transform]s of [Element]s
+    element_transforms: HashMap<ElementUlid, Transform>,
+    /// Parent of each [Element], if any
+    element_parents: HashMap<ElementUlid, ElementUlid>,
+    /// World matrices of [Element]s, calculated from the hierarchy
+    element_world_matrices: HashMap<ElementUlid, Matrix4<f32>>,
+    /// Set if any [Transform] or parent changed since the last propagation
+    transforms_dirty: bool,
+    /// Queue for [Element]s whose world matrix changed.
+    /// Owned [Model]s need to be updated.
+    queue_element_transform_update: Vec<ElementUlid>,
     // --- Queues ---
     /// Queue for [WorldChange]s before being processed into other queues
     queue_world_changes: Vec<WorldChange>,
@@ -82,9 +118,17 @@ pub struct World {
     /// Queue for despawning [Element]s
     queue_element_despawn: Vec<ElementUlid>,
     /// Queue for spawning [Model]s
-    queue_model_spawn: Vec<(ElementUlid, ModelDescriptor)>,
+    queue_model_spawn: Vec<(ElementUlid, ModelDescriptor, Option<String>)>,
     /// Queue for despawning [Model]s
     queue_model_despawn: Vec<ModelUlid>,
+    /// Queue for changes to [Model] [Instance]s
+    ///
+    /// [Instance]: crate::resources::realizations::Instance
+    queue_model_instances_change: Vec<(ModelIdentifier, Vec<InstanceChange>)>,
+    /// Queue for changes to the animation playback of [Model]s
+    queue_model_animation_change: Vec<(ModelIdentifier, AnimationChange)>,
+    /// Queue for spawning [Composition]s
+    queue_composition_spawn: Vec<(ElementUlid, CompositionDescriptor)>,
     /// Queue for messages being send to a target [Ulid]
     queue_messages: HashMap<ElementUlid, Vec<HashMap<String, Variant>>>,
     // --- Camera ---
@@ -95,6 +139,10 @@ pub struct World {
     active_camera_change: Option<CameraChange>,
     /// Cameras
     camera_descriptors: Vec<CameraDescriptor>,
+    /// Translation map to determine ownership over [Camera]s
+    /// based on [Element] [Ulid]s.
+    /// Only [Camera]s spawned as part of a [Composition] are owned.
+    camera_owner: HashMap<String, ElementUlid>,
     /// Next camera to be changed to upon next cycle.
     /// Must be set to `Some` if we do change.
     /// Must be set to `None` if we don't change.
@@ -103,6 +151,60 @@ pub struct World {
     /// ⚠️ Only the most recent `WorldChange` request will be applied as we can
     /// only ever have one single camera active!
     next_camera: Option<String>,
+    // --- Lights ---
+    /// **Active** [Light]s and their identifiers
+    lights: HashMap<String, Light>,
+    /// Translation map to determine ownership over [Light]s
+    /// based on [Element] [Ulid]s
+    light_owner: HashMap<String, ElementUlid>,
+    /// Queue for spawning [Light]s
+    queue_light_spawn: Vec<(ElementUlid, LightDescriptor)>,
+    /// Queue for despawning [Light]s
+    queue_light_despawn: Vec<String>,
+    /// Queue for changes to [Light]s
+    queue_light_change: Vec<LightChange>,
+    // --- Environment ---
+    /// The **active** [Environment], if any
+    environment: Option<Environment>,
+    /// The next [Environment] to be realized.
+    /// `Some(None)` removes the active [Environment].
+    ///
+    /// ⚠️ Only the most recent `WorldChange` request will be applied!
+    next_environment: Option<Option<EnvironmentDescriptor>>,
+    // --- Physics ---
+    /// [RigidBody]s of [Element]s, simulated in world space
+    physics: PhysicsWorld,
+    /// [Element]s moved by their [RigidBody] since the last propagation.
+    /// Their [RigidBody] is already where the [Element] is.
+    physics_moved_elements: HashSet<ElementUlid>,
+    /// [AppChange]s proposed during [World::fixed_update].
+    /// Returned with the next [World::update].
+    pending_app_changes: Vec<AppChange>,
+    // --- Spatial Queries ---
+    /// Top-level structure over all [Model] [Instance]s.
+    /// Cleared once any [Model] or [Instance] changes and rebuilt on the
+    /// next query.
+    ///
+    /// [Instance]: crate::resources::realizations::Instance
+    instance_bvh: OnceLock<InstanceBvh>,
+    /// If set, [Model]s without [Lod]s are culled by the [Renderer]
+    /// instead.
+    /// Check [World::set_gpu_culling].
+    ///
+    /// [Lod]: crate::resources::realizations::Lod
+    /// [Renderer]: crate::renderer::Renderer
+    gpu_culling: bool,
+    // --- Picking ---
+    /// Size of the viewport in pixels.
+    /// Check [World::set_viewport_size].
+    viewport_size: (u32, u32),
+    /// Last known cursor position inside the viewport
+    cursor_position: PhysicalPosition<f64>,
+    // --- Timing ---
+    /// How far the current frame is in between the last and the next
+    /// fixed update.
+    /// Check [World::interpolation_alpha].
+    interpolation_alpha: f64,
 }
 
 impl World {
@@ -162,7 +264,9 @@ impl World {
     }
 
     fn process_queue_spawn_element(&mut self) {
-        for mut element in self.queue_element_spawn.drain(..) {
+        let drain = self.queue_element_spawn.drain(..).collect::<Vec<_>>();
+
+        for mut element in drain {
             // Generate new ULID
             let element_ulid = Ulid::new();
             info!("New element: {}@{:?}", element_ulid, element.type_id());
@@ -173,6 +277,19 @@ impl World {
             // Store boxed element
             self.elements.insert(element_ulid, element);
 
+            // Place element in hierarchy
+            self.element_transforms
+                .insert(element_ulid, registration.transform.unwrap_or_default());
+            if let Some(parent_identifier) = registration.parent {
+                self.change_element_parent(element_ulid, Some(parent_identifier));
+            }
+            self.transforms_dirty = true;
+
+            // Process rigid body
+            if let Some(rigid_body) = registration.rigid_body {
+                self.spawn_rigid_body(element_ulid, rigid_body);
+            }
+
             // Process any tags
             if let Some(tags) = registration.tags {
                 for tag in tags {
@@ -187,7 +304,7 @@ impl World {
             if let Some(models) = registration.models {
                 for model in models {
                     // model_spawns_to_queue.push((element_ulid, model));
-                    self.queue_model_spawn.push((element_ulid, model));
+                    self.queue_model_spawn.push((element_ulid, model, None));
                 }
             }
 
@@ -195,19 +312,44 @@ impl World {
             if let Some(element_world_changes) = registration.world_changes {
                 for world_change in element_world_changes {
                     // world_changes_to_queue.push(world_change);
-                    self.queue_world_changes.push(world_change);
+                    self.queue_world_changes
+                        .push(Self::own_world_change(world_change, element_ulid));
                 }
             }
         }
     }
 
     fn process_queue_despawn_element(&mut self) {
-        let drain = self.queue_element_despawn.drain(..).collect::<Vec<_>>();
+        let mut drain = self.queue_element_despawn.drain(..).collect::<Vec<_>>();
+
+        // Children are despawned together with their parent
+        let mut i = 0;
+        while i < drain.len() {
+            let parent_ulid = drain[i];
+            for (child_ulid, _) in self
+                .element_parents
+                .iter()
+                .filter(|(_, v)| **v == parent_ulid)
+            {
+                if !drain.contains(child_ulid) {
+                    drain.push(*child_ulid);
+                }
+            }
+            i += 1;
+        }
 
         drain.iter().for_each(|element_ulid| {
             // Remove the element
             self.elements.remove(element_ulid);
 
+            // Remove the element from the hierarchy
+            self.element_transforms.remove(element_ulid);
+            self.element_parents.remove(element_ulid);
+            self.element_world_matrices.remove(element_ulid);
+
+            // Remove the rigid body
+            self.physics.remove(element_ulid);
+
             // Find any ModelUlid and queue those for removal
             self.model_owner
                 .iter()
@@ -216,17 +358,84 @@ impl World {
                 .for_each(|x| {
                     self.queue_model_despawn.push(*x);
                 });
+
+            // Find any Light identifiers and queue those for removal
+            self.light_owner
+                .iter()
+                .filter(|(_, v)| *v == element_ulid)
+                .map(|(k, _)| k)
+                .for_each(|x| {
+                    self.queue_light_despawn.push(x.clone());
+                });
+
+            // Find any Camera identifiers and remove those
+            let owned_cameras = self
+                .camera_owner
+                .iter()
+                .filter(|(_, v)| *v == element_ulid)
+                .map(|(k, _)| k.clone())
+                .collect::<Vec<_>>();
+            for identifier in owned_cameras {
+                self.despawn_camera(&identifier);
+            }
         });
     }
 
     fn process_queue_model_despawn(&mut self) {
         for model_ulid in self.queue_model_despawn.drain(..) {
+            self.instance_bvh.take();
+
             self.models.remove(&model_ulid);
+            self.model_owner.remove(&model_ulid);
+
+            self.model_labels.retain(|_, model_ulids| {
+                model_ulids.retain(|x| *x != model_ulid);
+                !model_ulids.is_empty()
+            });
+        }
+    }
+
+    /// Inserts an already realized [Model] into the [World] and informs
+    /// the owning [Element] about it.
+    fn insert_model(
+        &mut self,
+        mut model: Model,
+        label: Option<String>,
+        element_id: ElementUlid,
+        device: &Device,
+        queue: &Queue,
+    ) {
+        if let Some(world_matrix) = self.element_world_matrices.get(&element_id) {
+            model.set_transform(*world_matrix, device, queue);
+        }
+
+        let model_id = Ulid::new();
+        self.models.insert(model_id, model);
+        self.instance_bvh.take();
+        self.model_owner.insert(model_id, element_id);
+
+        if let Some(label) = &label {
+            self.model_labels
+                .entry(label.clone())
+                .or_default()
+                .push(model_id);
+        }
+
+        if let Some(element) = self.elements.get_mut(&element_id) {
+            if let Some(world_changes) = element.on_model_spawned(model_id, label.as_deref()) {
+                self.queue_world_changes.extend(
+                    world_changes
+                        .into_iter()
+                        .map(|x| Self::own_world_change(x, element_id)),
+                );
+            }
         }
     }
 
     fn process_queue_model_spawn(&mut self, device: &Device, queue: &Queue) {
-        for (element_id, model_descriptor) in self.queue_model_spawn.drain(..) {
+        let drain = self.queue_model_spawn.drain(..).collect::<Vec<_>>();
+
+        for (element_id, model_descriptor, label) in drain {
             let model = match Model::from_descriptor(&model_descriptor, device, queue) {
                 Ok(model) => model,
                 Err(e) => {
@@ -238,9 +447,186 @@ impl World {
                 }
             };
 
-            let model_id = Ulid::new();
-            self.models.insert(model_id, model);
-            self.model_owner.insert(model_id, element_id);
+            self.insert_model(model, label, element_id, device, queue);
+        }
+    }
+
+    fn process_queue_element_transform_update(&mut self, device: &Device, queue: &Queue) {
+        if self.queue_element_transform_update.is_empty() {
+            return;
+        }
+
+        let element_ulids = self
+            .queue_element_transform_update
+            .drain(..)
+            .collect::<Vec<_>>();
+        self.instance_bvh.take();
+
+        for (model_ulid, model) in &mut self.models {
+            let Some(element_ulid) = self.model_owner.get(model_ulid) else {
+                continue;
+            };
+
+            if element_ulids.contains(element_ulid) {
+                let world_matrix = self
+                    .element_world_matrices
+                    .get(element_ulid)
+                    .copied()
+                    .unwrap_or(Matrix4::identity());
+
+                model.set_transform(world_matrix, device, queue);
+            }
+        }
+    }
+
+    fn process_queue_model_instances_change(&mut self, device: &Device, queue: &Queue) {
+        let drain = self
+            .queue_model_instances_change
+            .drain(..)
+            .collect::<Vec<_>>();
+
+        for (model_identifier, changes) in drain {
+            self.instance_bvh.take();
+
+            let model_ulids = self.resolve_model_identifier(model_identifier.clone());
+            if model_ulids.is_empty() {
+                warn!(
+                    "Trying to update instances of Model '{:?}', but no such model exists!",
+                    model_identifier
+                );
+                continue;
+            }
+
+            for model_ulid in model_ulids {
+                if let Some(model) = self.models.get_mut(&model_ulid) {
+                    model.update_instances_from_changes(changes.clone(), device, queue);
+                }
+            }
+        }
+    }
+
+    fn process_queue_model_animation_change(&mut self) {
+        let drain = self
+            .queue_model_animation_change
+            .drain(..)
+            .collect::<Vec<_>>();
+
+        for (model_identifier, change) in drain {
+            let model_ulids = self.resolve_model_identifier(model_identifier.clone());
+            if model_ulids.is_empty() {
+                warn!(
+                    "Trying to change animation of Model '{:?}', but no such model exists!",
+                    model_identifier
+                );
+                continue;
+            }
+
+            for model_ulid in model_ulids {
+                if let Some(model) = self.models.get_mut(&model_ulid) {
+                    model.apply_animation_change(change.clone());
+                }
+            }
+        }
+    }
+
+    /// Advances the animations of all [Model]s.
+    fn update_model_animations(&mut self, delta_time: f64) {
+        for model in self.models.values_mut() {
+            model.update_animation(delta_time);
+        }
+    }
+
+    fn process_model_animation_buffers(&mut self, queue: &Queue) {
+        for model in self.models.values_mut() {
+            model.update_animation_buffers(queue);
+        }
+    }
+
+    fn process_queue_composition_spawn(&mut self, device: &Device, queue: &Queue) {
+        let drain = self.queue_composition_spawn.drain(..).collect::<Vec<_>>();
+
+        for (element_id, composition_descriptor) in drain {
+            let composition =
+                match Composition::from_descriptor(&composition_descriptor, device, queue) {
+                    Ok(composition) => composition,
+                    Err(e) => {
+                        error!(
+                            "Failure realizing composition for element '{}': {:#?}",
+                            element_id, e
+                        );
+                        continue;
+                    }
+                };
+
+            let (models, cameras, lights) = composition.into_parts();
+
+            for (model, label) in models {
+                self.insert_model(model, label, element_id, device, queue);
+            }
+
+            for camera_descriptor in cameras {
+                let identifier = camera_descriptor.identifier.clone();
+                if self.spawn_camera(camera_descriptor) {
+                    self.camera_owner.insert(identifier, element_id);
+                }
+            }
+
+            // Lights are realized by the light spawn queue
+            for light_descriptor in lights {
+                self.queue_light_spawn.push((element_id, light_descriptor));
+            }
+        }
+    }
+
+    fn process_queue_light_despawn(&mut self) {
+        for identifier in self.queue_light_despawn.drain(..) {
+            self.lights.remove(&identifier);
+            self.light_owner.remove(&identifier);
+        }
+    }
+
+    fn process_queue_light_spawn(&mut self, device: &Device, queue: &Queue) {
+        for (element_id, light_descriptor) in self.queue_light_spawn.drain(..) {
+            if self.lights.contains_key(&light_descriptor.identifier) {
+                warn!(
+                    "Trying to spawn Light with identifier '{}', which already exists. Rejecting change!",
+                    light_descriptor.identifier
+                );
+                continue;
+            }
+
+            let identifier = light_descriptor.identifier.clone();
+            let light = Light::from_descriptor(light_descriptor, device, queue);
+
+            self.lights.insert(identifier.clone(), light);
+            self.light_owner.insert(identifier, element_id);
+        }
+    }
+
+    fn process_queue_light_change(&mut self, device: &Device, queue: &Queue) {
+        for change in self.queue_light_change.drain(..) {
+            match self.lights.get_mut(&change.target) {
+                Some(light) => light.update_from_change(change, device, queue),
+                None => warn!(
+                    "Trying to update Light '{}', but no such light exists!",
+                    change.target
+                ),
+            }
+        }
+    }
+
+    fn process_next_environment(&mut self, device: &Device, queue: &Queue) {
+        match self.next_environment.take() {
+            Some(Some(environment_descriptor)) => {
+                match Environment::from_descriptor(environment_descriptor, device, queue) {
+                    Ok(environment) => self.environment = Some(environment),
+                    Err(e) => {
+                        error!("Environment realization failed, keeping the current one: {e:?}")
+                    }
+                }
+            }
+            Some(None) => self.environment = None,
+            None => (),
         }
     }
 
@@ -253,7 +639,11 @@ impl World {
                     let result = element.on_message(message);
 
                     if let Some(result_world_changes) = result {
-                        world_changes.extend(result_world_changes);
+                        world_changes.extend(
+                            result_world_changes
+                                .into_iter()
+                                .map(|x| Self::own_world_change(x, element_id)),
+                        );
                     }
                 }
             }
@@ -277,11 +667,137 @@ impl World {
         self.process_queue_spawn_element();
         self.process_queue_despawn_element();
         self.process_queue_model_despawn();
+        self.process_queue_light_despawn();
         self.process_queue_messages();
+        self.propagate_transforms();
 
         app_changes
     }
 
+    /// Recalculates the world matrices of all [Element]s, if any
+    /// [Transform] or parent changed.
+    /// [Element]s whose world matrix changed are queued up, so that their
+    /// [Model]s can be updated.
+    fn propagate_transforms(&mut self) {
+        if !self.transforms_dirty {
+            return;
+        }
+        self.transforms_dirty = false;
+
+        let mut world_matrices = HashMap::new();
+        for element_ulid in self.elements.keys() {
+            self.calculate_world_matrix(*element_ulid, &mut world_matrices);
+        }
+
+        for (element_ulid, world_matrix) in &world_matrices {
+            if self.element_world_matrices.get(element_ulid) != Some(world_matrix) {
+                self.queue_element_transform_update.push(*element_ulid);
+
+                // Rigid bodies follow their Element, e.g. when it or any
+                // parent got moved
+                if !self.physics_moved_elements.contains(element_ulid) {
+                    let pose = InstanceDescriptor::from_matrix(*world_matrix);
+                    self.physics
+                        .set_pose(element_ulid, pose.position, pose.rotation);
+                }
+            }
+        }
+
+        self.element_world_matrices = world_matrices;
+        self.physics_moved_elements.clear();
+    }
+
+    /// Calculates the world matrix of an [Element] by walking up the
+    /// hierarchy.
+    /// Already calculated world matrices are reused.
+    fn calculate_world_matrix(
+        &self,
+        element_ulid: ElementUlid,
+        world_matrices: &mut HashMap<ElementUlid, Matrix4<f32>>,
+    ) -> Matrix4<f32> {
+        if let Some(world_matrix) = world_matrices.get(&element_ulid) {
+            return *world_matrix;
+        }
+
+        let local_matrix = self
+            .element_transforms
+            .get(&element_ulid)
+            .map(Transform::to_matrix)
+            .unwrap_or(Matrix4::identity());
+
+        let world_matrix = match self.element_parents.get(&element_ulid) {
+            Some(parent_ulid) => {
+                self.calculate_world_matrix(*parent_ulid, world_matrices) * local_matrix
+            }
+            None => local_matrix,
+        };
+
+        world_matrices.insert(element_ulid, world_matrix);
+        world_matrix
+    }
+
+    /// Changes the parent of an [Element].
+    /// `None` detaches the [Element] from it's parent.
+    ///
+    /// Rejected, if the parent can't be found or if the change would
+    /// result in a cycle.
+    fn change_element_parent(
+        &mut self,
+        element_ulid: ElementUlid,
+        parent_identifier: Option<Identifier>,
+    ) {
+        let parent_ulid = match parent_identifier {
+            Some(parent_identifier) => {
+                let parent_ulids = self.resolve_identifier(parent_identifier);
+                if parent_ulids.len() > 1 {
+                    warn!(
+                        "Parent identifier of element '{}' resolves to multiple elements, using the first one!",
+                        element_ulid
+                    );
+                }
+
+                match parent_ulids.first() {
+                    Some(parent_ulid) if self.elements.contains_key(parent_ulid) => {
+                        Some(*parent_ulid)
+                    }
+                    _ => {
+                        warn!(
+                            "Parent of element '{}' does not exist. Rejecting change!",
+                            element_ulid
+                        );
+                        return;
+                    }
+                }
+            }
+            None => None,
+        };
+
+        match parent_ulid {
+            Some(parent_ulid) => {
+                // Walk up from the new parent, we must never reach ourself
+                let mut ancestor = Some(parent_ulid);
+                while let Some(ancestor_ulid) = ancestor {
+                    if ancestor_ulid == element_ulid {
+                        warn!(
+                            "Making '{}' the parent of element '{}' would result in a cycle. Rejecting change!",
+                            parent_ulid, element_ulid
+                        );
+                        return;
+                    }
+
+                    ancestor = self.element_parents.get(&ancestor_ulid).copied();
+                }
+
+                self.element_parents.insert(element_ulid, parent_ulid);
+            }
+            None => {
+                self.element_parents.remove(&element_ulid);
+            }
+        }
+
+        self.transforms_dirty = true;
+    }
+
     /// Call this function to queue a given [WorldChange].  
     /// The [WorldChange] will be processed during the next possible
     /// cycle.
@@ -293,13 +809,52 @@ impl World {
                     self.queue_element_despawn.push(element_ulid)
                 }
             }
-            WorldChange::SpawnModel(model_descriptor, element_ulid) => self
+            WorldChange::SpawnModel(model_descriptor, label, element_ulid) => self
                 .queue_model_spawn
-                .push((element_ulid, model_descriptor)),
-            WorldChange::SpawnModelOwned(_) => {
+                .push((element_ulid, model_descriptor, label)),
+            WorldChange::SpawnModelOwned(_, _) => {
                 error!("SpawnModelOwned cannot be used directly. Use SpawnModel instead!");
             }
-            WorldChange::DespawnModel(model_ulid) => self.queue_model_despawn.push(model_ulid),
+            WorldChange::DespawnModel(model_identifier) => {
+                for model_ulid in self.resolve_model_identifier(model_identifier) {
+                    self.queue_model_despawn.push(model_ulid);
+                }
+            }
+            WorldChange::UpdateElementTransform(identifier, change) => {
+                for element_ulid in self.resolve_identifier(identifier) {
+                    match self.element_transforms.get_mut(&element_ulid) {
+                        Some(transform) => {
+                            // The rigid body gets teleported along once
+                            // transforms are propagated
+                            transform.apply_change(change.clone());
+                            self.transforms_dirty = true;
+                        }
+                        None => warn!(
+                            "Trying to update transform of element '{}', but no such element exists!",
+                            element_ulid
+                        ),
+                    }
+                }
+            }
+            WorldChange::ChangeElementParent(identifier, parent_identifier) => {
+                for element_ulid in self.resolve_identifier(identifier) {
+                    self.change_element_parent(element_ulid, parent_identifier.clone());
+                }
+            }
+            WorldChange::UpdateModelInstances(model_identifier, changes) => self
+                .queue_model_instances_change
+                .push((model_identifier, changes)),
+            WorldChange::UpdateModelAnimation(model_identifier, change) => self
+                .queue_model_animation_change
+                .push((model_identifier, change)),
+            WorldChange::SpawnCompositionOwned(_) => {
+                error!(
+                    "SpawnCompositionOwned cannot be used directly. Use SpawnComposition instead!"
+                );
+            }
+            WorldChange::SpawnComposition(composition_descriptor, element_ulid) => self
+                .queue_composition_spawn
+                .push((element_ulid, composition_descriptor)),
             WorldChange::SendMessage(identifier, message) => {
                 for element_ulid in self.resolve_identifier(identifier) {
                     self.queue_messages
@@ -308,24 +863,15 @@ impl World {
                         .push(message.clone());
                 }
             }
-            WorldChange::SpawnCamera(descriptor) => self.spawn_camera(descriptor),
+            WorldChange::SpawnCamera(descriptor) => {
+                self.spawn_camera(descriptor);
+            }
             WorldChange::SpawnCameraAndMakeActive(descriptor) => {
                 let identifier = descriptor.identifier.clone();
                 self.spawn_camera(descriptor);
                 self.next_camera = Some(identifier);
             }
-            WorldChange::DespawnCamera(identifier) => {
-                if let Some(camera) = &self.active_camera {
-                    if camera.descriptor().identifier == identifier {
-                        self.active_camera = None;
-
-                        warn!("Despawned Camera was active!");
-                    }
-                }
-
-                self.camera_descriptors
-                    .retain(|x| x.identifier != identifier);
-            }
+            WorldChange::DespawnCamera(identifier) => self.despawn_camera(&identifier),
             WorldChange::ChangeActiveCamera(identifier) => {
                 if let Some(camera) = &self.active_camera {
                     if camera.descriptor().identifier == identifier {
@@ -352,23 +898,111 @@ impl World {
                     }
                 }
             }
+            WorldChange::SpawnLightOwned(_) => {
+                error!("SpawnLightOwned cannot be used directly. Use SpawnLight instead!");
+            }
+            WorldChange::SpawnLight(light_descriptor, element_ulid) => self
+                .queue_light_spawn
+                .push((element_ulid, light_descriptor)),
+            WorldChange::DespawnLight(identifier) => self.queue_light_despawn.push(identifier),
+            WorldChange::UpdateLight(change) => {
+                // Lights which are not yet realized can be changed directly
+                if let Some((_, queued_descriptor)) = self
+                    .queue_light_spawn
+                    .iter_mut()
+                    .find(|(_, x)| x.identifier == change.target)
+                {
+                    queued_descriptor.apply_change(change);
+                } else {
+                    self.queue_light_change.push(change);
+                }
+            }
+            WorldChange::ChangeEnvironment(environment_descriptor) => {
+                self.next_environment = Some(environment_descriptor)
+            }
+            WorldChange::SpawnRigidBodyOwned(_) => {
+                error!("SpawnRigidBodyOwned cannot be used directly. Use SpawnRigidBody instead!");
+            }
+            WorldChange::SpawnRigidBody(descriptor, element_ulid) => {
+                self.spawn_rigid_body(element_ulid, descriptor)
+            }
+            WorldChange::DespawnRigidBody(identifier) => {
+                for element_ulid in self.resolve_identifier(identifier) {
+                    self.physics.remove(&element_ulid);
+                }
+            }
+            WorldChange::UpdateRigidBody(identifier, change) => {
+                for element_ulid in self.resolve_identifier(identifier) {
+                    match self.physics.get_mut(&element_ulid) {
+                        Some(rigid_body) => rigid_body.apply_change(change.clone()),
+                        None => warn!(
+                            "Trying to update rigid body of element '{}', but it has no rigid body!",
+                            element_ulid
+                        ),
+                    }
+                }
+            }
+            WorldChange::ChangeGravity(gravity) => self.physics.set_gravity(gravity),
             WorldChange::AppChange(app_change) => return Some(app_change),
         }
 
         None
     }
 
-    fn spawn_camera(&mut self, descriptor: CameraDescriptor) {
+    /// Gives an [Element] a [RigidBody], starting at it's current
+    /// position and rotation in world space.
+    fn spawn_rigid_body(&mut self, element_ulid: ElementUlid, descriptor: RigidBodyDescriptor) {
+        if !self.element_transforms.contains_key(&element_ulid) {
+            warn!(
+                "Trying to spawn rigid body for element '{}', but no such element exists!",
+                element_ulid
+            );
+            return;
+        }
+
+        let world_pose = InstanceDescriptor::from_matrix(
+            self.calculate_world_matrix(element_ulid, &mut HashMap::new()),
+        );
+        self.physics.insert(
+            element_ulid,
+            RigidBody::from_descriptor(
+                descriptor,
+                &Transform {
+                    position: world_pose.position,
+                    rotation: world_pose.rotation,
+                    scale: world_pose.scale,
+                },
+            ),
+        );
+    }
+
+    /// Returns `true` if the [Camera] got spawned.
+    fn spawn_camera(&mut self, descriptor: CameraDescriptor) -> bool {
         if self
             .camera_descriptors
             .iter()
             .any(|x| x.identifier == descriptor.identifier)
         {
             warn!("Trying to spawn Camera with identifier '{}', which already exists. Rejecting change!", descriptor.identifier);
-            return;
+            return false;
         }
 
         self.camera_descriptors.push(descriptor);
+        true
+    }
+
+    fn despawn_camera(&mut self, identifier: &str) {
+        if let Some(camera) = &self.active_camera {
+            if camera.descriptor().identifier == identifier {
+                self.active_camera = None;
+
+                warn!("Despawned Camera was active!");
+            }
+        }
+
+        self.camera_descriptors
+            .retain(|x| x.identifier != identifier);
+        self.camera_owner.remove(identifier);
     }
 
     pub fn on_focus_change(&mut self, focused: bool) {
@@ -381,6 +1015,79 @@ impl World {
         for (_element_ulid, element) in &mut self.elements {
             element.on_input_event(input_event)
         }
+
+        match input_event {
+            InputEvent::MouseMoved { position, .. } => self.cursor_position = *position,
+            InputEvent::MouseButton {
+                state: ElementState::Pressed,
+                button,
+                ..
+            } => {
+                if let Some(hit) = self.pick(self.cursor_position) {
+                    self.deliver_pick_event(PickEvent {
+                        button: *button,
+                        hit,
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn deliver_pick_event(&mut self, pick_event: PickEvent) {
+        let element_ulid = pick_event.hit.element_ulid;
+        let Some(element) = self.elements.get_mut(&element_ulid) else {
+            return;
+        };
+
+        if let Some(element_world_changes) = element.on_pick(&pick_event) {
+            for element_world_change in element_world_changes {
+                self.queue_world_changes
+                    .push(Self::own_world_change(element_world_change, element_ulid));
+            }
+        }
+    }
+
+    /// Sets the size of the viewport in pixels.
+    /// Needed to convert pixel positions into [Ray]s.
+    ///
+    /// ⚠️ This is already called automatically by the [GameRuntime].  
+    /// ⚠️ You will only need to call this if you are making your own thing.
+    ///
+    /// [GameRuntime]: crate::game::GameRuntime
+    pub fn set_viewport_size(&mut self, viewport_size: Vector2<u32>) {
+        self.viewport_size = viewport_size.into();
+    }
+
+    pub fn viewport_size(&self) -> Vector2<u32> {
+        self.viewport_size.into()
+    }
+
+    /// Converts a pixel position inside the viewport into a world space
+    /// [Ray] through the active [Camera], together with the distance to
+    /// it's far plane.
+    /// Check [Camera::screen_to_ray].
+    ///
+    /// Returns [None] if there is no active [Camera] yet or the viewport
+    /// is empty.
+    pub fn screen_to_ray(&self, position: PhysicalPosition<f64>) -> Option<(Ray, f32)> {
+        self.active_camera
+            .as_ref()?
+            .screen_to_ray(position, self.viewport_size.into())
+    }
+
+    /// Returns the closest [Model] under a pixel position, e.g. the
+    /// cursor position, up to the far plane of the active [Camera].
+    ///
+    /// On each mouse button press, this is done automatically for the
+    /// last known cursor position and the owning [Element] is informed
+    /// via [Element::on_pick].
+    ///
+    /// ⚠️ Same limitations as [World::raycast] apply.
+    pub fn pick(&self, position: PhysicalPosition<f64>) -> Option<RaycastHit> {
+        let (ray, far_distance) = self.screen_to_ray(position)?;
+
+        self.raycast(ray.origin, ray.direction, far_distance)
     }
 
     /// Processes queued up [WorldChanges]
@@ -393,18 +1100,178 @@ impl World {
         for (element_ulid, element) in &mut self.elements {
             if let Some(element_world_changes) = element.on_update(delta_time) {
                 for element_world_change in element_world_changes {
-                    // Convert owned model spawning to auto-include the [ElementUlid]
-                    if let WorldChange::SpawnModelOwned(x) = element_world_change {
-                        self.queue_world_changes
-                            .push(WorldChange::SpawnModel(x, *element_ulid));
-                    } else {
-                        self.queue_world_changes.push(element_world_change);
-                    }
+                    self.queue_world_changes
+                        .push(Self::own_world_change(element_world_change, *element_ulid));
+                }
+            }
+        }
+
+        self.update_model_animations(delta_time);
+
+        let mut app_changes = take(&mut self.pending_app_changes);
+        app_changes.extend(self.process_world_changes());
+        app_changes
+    }
+
+    /// Calls [Element::on_fixed_update] on each [Element], processes
+    /// any proposed [WorldChanges] and steps the physics simulation.
+    /// Any resulting [AppChange]s are returned with the next
+    /// [World::update].
+    ///
+    /// ⚠️ This is already called automatically by the [GameRuntime], if
+    /// [GameSettings::fixed_update](crate::game::GameSettings::fixed_update)
+    /// is set.  
+    /// ⚠️ You will only need to call this if you are making your own thing.
+    ///
+    /// [GameRuntime]: crate::game::GameRuntime
+    /// [WorldChanges]: WorldChange
+    pub fn fixed_update(&mut self, fixed_delta_time: f64) {
+        for (element_ulid, element) in &mut self.elements {
+            if let Some(element_world_changes) = element.on_fixed_update(fixed_delta_time) {
+                for element_world_change in element_world_changes {
+                    self.queue_world_changes
+                        .push(Self::own_world_change(element_world_change, *element_ulid));
                 }
             }
         }
 
-        self.process_world_changes()
+        let app_changes = self.process_world_changes();
+        self.pending_app_changes.extend(app_changes);
+
+        self.step_physics(fixed_delta_time);
+    }
+
+    /// Advances the physics simulation by `delta_time` seconds.
+    ///
+    /// Each simulated [RigidBody] moves it's [Element] (and thus it's
+    /// [Model]s).
+    /// Any [CollisionEvent] is delivered via [Element::on_collision] and
+    /// proposed [WorldChanges] are processed with the next
+    /// [World::update].
+    ///
+    /// ⚠️ This is already called automatically by [World::fixed_update]
+    /// or, if fixed updates are disabled, by the [GameRuntime].  
+    /// ⚠️ You will only need to call this if you are making your own thing.
+    ///
+    /// [GameRuntime]: crate::game::GameRuntime
+    /// [WorldChanges]: WorldChange
+    pub fn step_physics(&mut self, delta_time: f64) {
+        // Rigid bodies must be where their Elements are before simulating
+        self.propagate_transforms();
+
+        let events = self.physics.step(delta_time as f32);
+
+        // Parents first, so that children are placed relative to where
+        // their parent got moved to
+        let mut moved = self
+            .physics
+            .bodies()
+            .filter(|(_, rigid_body)| rigid_body.body_type() != RigidBodyType::Static)
+            .map(|(element_ulid, rigid_body)| {
+                (*element_ulid, rigid_body.position, rigid_body.rotation)
+            })
+            .collect::<Vec<_>>();
+        moved.sort_by_cached_key(|(element_ulid, _, _)| self.element_depth(element_ulid));
+
+        for (element_ulid, position, rotation) in moved {
+            let local_pose = match self.element_parents.get(&element_ulid) {
+                Some(parent_ulid) => Self::world_to_local_pose(
+                    self.calculate_world_matrix(*parent_ulid, &mut HashMap::new()),
+                    position,
+                    rotation,
+                ),
+                None => Some((position, rotation)),
+            };
+
+            if let (Some((position, rotation)), Some(transform)) =
+                (local_pose, self.element_transforms.get_mut(&element_ulid))
+            {
+                transform.position = position;
+                transform.rotation = rotation;
+                self.physics_moved_elements.insert(element_ulid);
+                self.transforms_dirty = true;
+            }
+        }
+
+        for (element_ulid, event) in events {
+            self.deliver_collision_event(element_ulid, &event);
+        }
+        self.propagate_transforms();
+    }
+
+    /// Converts a position and rotation in world space into the space of
+    /// the given parent world matrix.
+    /// Returns `None` if the parent matrix can't be inverted, e.g. if it's
+    /// scaled to zero.
+    fn world_to_local_pose(
+        parent_world_matrix: Matrix4<f32>,
+        position: Vector3<f32>,
+        rotation: Quaternion<f32>,
+    ) -> Option<(Vector3<f32>, Quaternion<f32>)> {
+        let inverse_parent_matrix = parent_world_matrix.invert()?;
+        let parent_rotation = InstanceDescriptor::from_matrix(parent_world_matrix).rotation;
+
+        Some((
+            inverse_parent_matrix
+                .transform_point(Point3::from_vec(position))
+                .to_vec(),
+            parent_rotation.conjugate() * rotation,
+        ))
+    }
+
+    /// Amount of parents above an [Element].
+    fn element_depth(&self, element_ulid: &ElementUlid) -> usize {
+        let mut depth = 0;
+        let mut current = element_ulid;
+        while let Some(parent_ulid) = self.element_parents.get(current) {
+            depth += 1;
+            current = parent_ulid;
+        }
+
+        depth
+    }
+
+    fn deliver_collision_event(&mut self, element_ulid: ElementUlid, event: &CollisionEvent) {
+        let Some(element) = self.elements.get_mut(&element_ulid) else {
+            return;
+        };
+
+        if let Some(element_world_changes) = element.on_collision(event) {
+            for element_world_change in element_world_changes {
+                self.queue_world_changes
+                    .push(Self::own_world_change(element_world_change, element_ulid));
+            }
+        }
+    }
+
+    /// How far (`0.0` to `1.0`) the current frame is in between the last
+    /// and the next fixed update.
+    /// Use this to interpolate between the states of the last two fixed
+    /// updates, to render smoothly regardless of the fixed update rate.
+    ///
+    /// Always `0.0` if fixed updates are disabled.
+    pub fn interpolation_alpha(&self) -> f64 {
+        self.interpolation_alpha
+    }
+
+    pub fn set_interpolation_alpha(&mut self, interpolation_alpha: f64) {
+        self.interpolation_alpha = interpolation_alpha;
+    }
+
+    /// Converts any _owned_ [WorldChange] (e.g. [WorldChange::SpawnModelOwned])
+    /// into it's non-owned counterpart, by including the [ElementUlid] of
+    /// the [Element] proposing the change.
+    /// Any other [WorldChange] is returned as-is.
+    fn own_world_change(world_change: WorldChange, element_ulid: ElementUlid) -> WorldChange {
+        match world_change {
+            WorldChange::SpawnModelOwned(x, label) => {
+                WorldChange::SpawnModel(x, label, element_ulid)
+            }
+            WorldChange::SpawnLightOwned(x) => WorldChange::SpawnLight(x, element_ulid),
+            WorldChange::SpawnCompositionOwned(x) => WorldChange::SpawnComposition(x, element_ulid),
+            WorldChange::SpawnRigidBodyOwned(x) => WorldChange::SpawnRigidBody(x, element_ulid),
+            x => x,
+        }
     }
 
     /// Similar to [World::update], but for [WorldChanges]
@@ -417,20 +1284,134 @@ impl World {
     /// [WorldChanges]: WorldChange
     pub fn prepare_render(&mut self, device: &Device, queue: &Queue) {
         self.process_queue_model_spawn(device, queue);
+        self.process_queue_composition_spawn(device, queue);
+        self.process_queue_element_transform_update(device, queue);
+        self.process_queue_model_instances_change(device, queue);
+        self.process_queue_model_animation_change();
+        self.process_model_animation_buffers(queue);
         self.process_active_camera_change(device, queue);
         self.process_next_camera(device, queue);
+        self.process_queue_light_spawn(device, queue);
+        self.process_queue_light_change(device, queue);
+        self.process_next_environment(device, queue);
+        self.cull_models(device, queue);
+    }
+
+    /// Culls the [Instance]s of every [Model] against the [Frustum] of
+    /// the active [Camera] and selects their level of detail.
+    /// Check [Model::cull].
+    ///
+    /// [Instance]: crate::resources::realizations::Instance
+    /// [Frustum]: crate::physics::Frustum
+    fn cull_models(&mut self, device: &Device, queue: &Queue) {
+        let Some(camera) = &self.active_camera else {
+            return;
+        };
+
+        for model in self.models.values_mut() {
+            // Levels of detail are still selected on the CPU
+            if self.gpu_culling && model.lods().is_empty() {
+                model.reset_culling();
+            } else {
+                model.cull(camera, device, queue);
+            }
+        }
+    }
+
+    /// Skips culling [Model]s without [Lod]s, as the [Renderer] culls
+    /// them on the GPU.
+    /// Set by the [GameRuntime](super::GameRuntime), check
+    /// [Renderer::is_gpu_culling].
+    ///
+    /// [Lod]: crate::resources::realizations::Lod
+    /// [Renderer]: crate::renderer::Renderer
+    /// [Renderer::is_gpu_culling]: crate::renderer::Renderer::is_gpu_culling
+    pub fn set_gpu_culling(&mut self, gpu_culling: bool) {
+        self.gpu_culling = gpu_culling;
+    }
+
+    pub fn gpu_culling(&self) -> bool {
+        self.gpu_culling
+    }
+
+    /// Returns the [InstanceBvh], rebuilding it if anything changed since
+    /// the last query.
+    fn instance_bvh(&self) -> &InstanceBvh {
+        self.instance_bvh
+            .get_or_init(|| InstanceBvh::build(&self.models, &self.model_owner))
     }
 
-    /// This function returns a [Vec<&Model>] of all [Models] that
-    /// need to be rendered.
+    /// Casts a [Ray] against the [Mesh] of every [Model] under each of
+    /// it's [Instance]s and returns the closest hit within
+    /// `max_distance`, if any.
+    /// `direction` doesn't need to be normalized.
+    ///
+    /// ⚠️ [Model]s are tested as of the last [World::prepare_render].  
+    /// ⚠️ Skinning and morph targets are ignored, [Model]s are tested in
+    /// their rest pose.
+    ///
+    /// [Mesh]: crate::resources::realizations::Mesh
+    /// [Instance]: crate::resources::realizations::Instance
+    pub fn raycast(
+        &self,
+        origin: Vector3<f32>,
+        direction: Vector3<f32>,
+        max_distance: f32,
+    ) -> Option<RaycastHit> {
+        if direction.is_zero() {
+            return None;
+        }
+
+        self.instance_bvh()
+            .raycast(&self.models, &Ray::new(origin, direction), max_distance)
+    }
+
+    /// Returns each [Model] [Instance] whose [Mesh] overlaps with a
+    /// convex [ColliderShape] at the given position and rotation.
+    ///
+    /// ⚠️ [ColliderShape::TriMesh] isn't supported and never overlaps.  
+    /// ⚠️ Same limitations as [World::raycast] apply.
+    ///
+    /// [Mesh]: crate::resources::realizations::Mesh
+    /// [Instance]: crate::resources::realizations::Instance
+    pub fn overlap(
+        &self,
+        shape: &ColliderShape,
+        position: Vector3<f32>,
+        rotation: Quaternion<f32>,
+    ) -> Vec<OverlapHit> {
+        if !shape.is_convex() {
+            warn!("Overlap queries don't support triangle mesh shapes!");
+            return Vec::new();
+        }
+
+        self.instance_bvh()
+            .overlap(&self.models, shape, position, rotation)
+    }
+
+    /// This function returns the active [Camera], a [Vec<&Model>] of all
+    /// [Models] that need to be rendered, a [Vec<&Light>] of all active
+    /// [Lights] and the active [Environment], if any.
     /// This information is intended to be send to a [Renderer].
     ///
+    /// Every [Model] is returned, even if not visible, as it may still
+    /// cast shadows.
+    /// Use [Model::visible_instance_buffer] and
+    /// [Model::visible_instance_count] to only draw [Instance]s visible to
+    /// the [Camera], as culled in [World::prepare_render].
+    ///
+    /// [Instance]: crate::resources::realizations::Instance
     /// [Models]: Model
+    /// [Lights]: Light
     /// [Renderer]: crate::renderer::Renderer
-    pub fn gather_render_resources(&self) -> (&Camera, Vec<&Model>) {
+    pub fn gather_render_resources(
+        &self,
+    ) -> (&Camera, Vec<&Model>, Vec<&Light>, Option<&Environment>) {
         (
             self.active_camera.as_ref().unwrap(),
             self.models.values().collect::<Vec<_>>(),
+            self.lights.values().collect::<Vec<_>>(),
+            self.environment.as_ref(),
         )
     }
 
@@ -464,6 +1445,51 @@ impl World {
         ulids
     }
 
+    /// Returns the local [Transform] of an [Element], if it exists.
+    pub fn element_transform(&self, element_ulid: &ElementUlid) -> Option<&Transform> {
+        self.element_transforms.get(element_ulid)
+    }
+
+    /// Returns the [RigidBody] of an [Element], if it has one.
+    pub fn rigid_body(&self, element_ulid: &ElementUlid) -> Option<&RigidBody> {
+        self.physics.get(element_ulid)
+    }
+
+    /// Returns the [PhysicsWorld] simulating all [RigidBody]s.
+    pub fn physics(&self) -> &PhysicsWorld {
+        &self.physics
+    }
+
+    /// Returns the parent of an [Element], if it has one.
+    pub fn element_parent(&self, element_ulid: &ElementUlid) -> Option<ElementUlid> {
+        self.element_parents.get(element_ulid).copied()
+    }
+
+    /// Returns the world matrix of an [Element], if it exists.
+    ///
+    /// ⚠️ World matrices are only updated once per [World::update]!
+    pub fn element_world_matrix(&self, element_ulid: &ElementUlid) -> Option<Matrix4<f32>> {
+        self.element_world_matrices.get(element_ulid).copied()
+    }
+
+    /// Converts a given _label_ into [ModelUlid]s if found.
+    pub fn label_to_model_ulids(&self, label: &str) -> Option<&Vec<ModelUlid>> {
+        self.model_labels.get(label)
+    }
+
+    /// Converts a given [ModelIdentifier] into a [Vec<ModelUlid>].
+    ///
+    /// Similar to [World::resolve_identifier], but for [Model]s.
+    pub fn resolve_model_identifier(&self, model_identifier: ModelIdentifier) -> Vec<ModelUlid> {
+        match model_identifier {
+            ModelIdentifier::Ulid(ulid) => vec![ulid],
+            ModelIdentifier::Label(label) => self
+                .label_to_model_ulids(&label)
+                .cloned()
+                .unwrap_or_default(),
+        }
+    }
+
     // pub fn handle_input_event(&mut self, input_event: InputEvent) -> Result<(), Error> {
     //     // self.input_manager.handle_input_event(input_event)
     // }
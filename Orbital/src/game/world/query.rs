@@ -0,0 +1,209 @@
+use cgmath::{InnerSpace, Matrix, Matrix4, Quaternion, Rotation, SquareMatrix, Vector3};
+use hashbrown::HashMap;
+use winit::event::MouseButton;
+
+use crate::{
+    physics::{narrowphase::convex_overlap, Aabb, Bvh, ColliderShape, Ray},
+    resources::realizations::Model,
+};
+
+use super::{ElementUlid, ModelUlid};
+
+/// A hit of a [World::raycast](super::World::raycast).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RaycastHit {
+    pub model_ulid: ModelUlid,
+    /// The [Element](super::Element) owning the [Model]
+    pub element_ulid: ElementUlid,
+    /// Index of the hit [Instance](crate::resources::realizations::Instance)
+    pub instance_index: usize,
+    /// Distance along the [Ray]
+    pub distance: f32,
+    /// Hit point in world space
+    pub point: Vector3<f32>,
+    /// Normal of the hit triangle in world space, following it's
+    /// winding order.
+    pub normal: Vector3<f32>,
+}
+
+/// Informs an [Element](super::Element) that one of it's [Model]s was
+/// clicked.
+/// Received via [Element::on_pick](super::Element::on_pick).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PickEvent {
+    /// The pressed [MouseButton]
+    pub button: MouseButton,
+    /// Where the [Model] was hit
+    pub hit: RaycastHit,
+}
+
+/// A hit of a [World::overlap](super::World::overlap).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OverlapHit {
+    pub model_ulid: ModelUlid,
+    /// The [Element](super::Element) owning the [Model]
+    pub element_ulid: ElementUlid,
+    /// Index of the overlapping
+    /// [Instance](crate::resources::realizations::Instance)
+    pub instance_index: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct InstanceEntry {
+    model_ulid: ModelUlid,
+    element_ulid: ElementUlid,
+    instance_index: usize,
+    world_matrix: Matrix4<f32>,
+    inverse_matrix: Matrix4<f32>,
+}
+
+/// Top-level [Bvh] over every [Instance](crate::resources::realizations::Instance)
+/// of every [Model] in a [World](super::World).
+/// Each [Instance] refers to the [MeshBvh](crate::physics::MeshBvh) of
+/// it's [Mesh](crate::resources::realizations::Mesh).
+#[derive(Debug, Default)]
+pub(crate) struct InstanceBvh {
+    entries: Vec<InstanceEntry>,
+    bvh: Bvh,
+}
+
+impl InstanceBvh {
+    /// [Model]s without a [MeshBvh](crate::physics::MeshBvh) or owner
+    /// are skipped.
+    pub fn build(
+        models: &HashMap<ModelUlid, Model>,
+        model_owner: &HashMap<ModelUlid, ElementUlid>,
+    ) -> Self {
+        let mut entries = Vec::new();
+        let mut aabbs = Vec::new();
+
+        for (model_ulid, model) in models {
+            // The bounds of the mesh avoid building it's MeshBvh already
+            let (Some(_), Some(mesh_bounds), Some(element_ulid)) = (
+                model.mesh().bvh(),
+                model.mesh().bounds(),
+                model_owner.get(model_ulid),
+            ) else {
+                continue;
+            };
+
+            for (instance_index, instance) in model.instances().iter().enumerate() {
+                let world_matrix = model.transform() * instance.make_model_space_matrix();
+                let Some(inverse_matrix) = world_matrix.invert() else {
+                    // Scaled to zero, nothing to hit
+                    continue;
+                };
+
+                entries.push(InstanceEntry {
+                    model_ulid: *model_ulid,
+                    element_ulid: *element_ulid,
+                    instance_index,
+                    world_matrix,
+                    inverse_matrix,
+                });
+                aabbs.push(mesh_bounds.transform(&world_matrix));
+            }
+        }
+
+        Self {
+            bvh: Bvh::build(&aabbs),
+            entries,
+        }
+    }
+
+    pub fn raycast(
+        &self,
+        models: &HashMap<ModelUlid, Model>,
+        ray: &Ray,
+        max_distance: f32,
+    ) -> Option<RaycastHit> {
+        let mut closest = None;
+
+        self.bvh.raycast(ray, max_distance, |index| {
+            let entry = &self.entries[index as usize];
+            let mesh_bvh = models.get(&entry.model_ulid)?.mesh().bvh()?;
+
+            // The direction isn't normalized again, thus the distance
+            // stays in world space
+            let local_ray = ray.transform(&entry.inverse_matrix);
+            let hit = mesh_bvh.raycast(&local_ray, max_distance)?;
+
+            let normal = (entry.inverse_matrix.transpose() * hit.normal.extend(0.0))
+                .truncate()
+                .normalize();
+            let is_closest = match &closest {
+                Some(RaycastHit { distance, .. }) => hit.distance < *distance,
+                None => true,
+            };
+            if is_closest {
+                closest = Some(RaycastHit {
+                    model_ulid: entry.model_ulid,
+                    element_ulid: entry.element_ulid,
+                    instance_index: entry.instance_index,
+                    distance: hit.distance,
+                    point: ray.at(hit.distance),
+                    normal,
+                });
+            }
+
+            Some(hit.distance)
+        });
+
+        closest
+    }
+
+    pub fn overlap(
+        &self,
+        models: &HashMap<ModelUlid, Model>,
+        shape: &ColliderShape,
+        position: Vector3<f32>,
+        rotation: Quaternion<f32>,
+    ) -> Vec<OverlapHit> {
+        let shape_matrix = Matrix4::from_translation(position) * Matrix4::from(rotation);
+        let shape_aabb = shape.local_aabb().transform(&shape_matrix);
+        let inverse_rotation = rotation.invert();
+        let shape_support = |direction: Vector3<f32>| {
+            position
+                + rotation.rotate_vector(shape.support(inverse_rotation.rotate_vector(direction)))
+        };
+
+        self.bvh
+            .query_aabb(&shape_aabb)
+            .into_iter()
+            .map(|index| &self.entries[index as usize])
+            .filter(|entry| {
+                let Some(mesh_bvh) = models.get(&entry.model_ulid).and_then(|x| x.mesh().bvh())
+                else {
+                    return false;
+                };
+
+                let local_aabb = shape_aabb.transform(&entry.inverse_matrix);
+                mesh_bvh.query_aabb(&local_aabb).into_iter().any(|x| {
+                    let triangle = mesh_bvh
+                        .triangle(x)
+                        .map(|x| (entry.world_matrix * x.extend(1.0)).truncate());
+                    if !Aabb::from_points(triangle).is_some_and(|x| x.intersects(&shape_aabb)) {
+                        return false;
+                    }
+
+                    let center = (triangle[0] + triangle[1] + triangle[2]) / 3.0;
+                    convex_overlap(
+                        |direction| {
+                            triangle
+                                .into_iter()
+                                .max_by(|a, b| a.dot(direction).total_cmp(&b.dot(direction)))
+                                .unwrap()
+                        },
+                        shape_support,
+                        position - center,
+                    )
+                })
+            })
+            .map(|entry| OverlapHit {
+                model_ulid: entry.model_ulid,
+                element_ulid: entry.element_ulid,
+                instance_index: entry.instance_index,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,39 @@
+use cgmath::{Matrix4, One, Quaternion, Vector3, Zero};
+
+use super::TransformChange;
+
+/// The local transform of an [Element](super::Element).
+///
+/// If the [Element](super::Element) has a parent, the [Transform] is
+/// relative to said parent.
+/// Otherwise, it's relative to the [World](super::World) origin.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub position: Vector3<f32>,
+    pub rotation: Quaternion<f32>,
+    pub scale: Vector3<f32>,
+}
+
+impl Transform {
+    pub fn apply_change(&mut self, change: TransformChange) {
+        change.apply_to(&mut self.position, &mut self.rotation, &mut self.scale);
+    }
+
+    /// Converts the [Transform] into a matrix.
+    /// Scale is applied first, then rotation, then translation.
+    pub fn to_matrix(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(self.position)
+            * Matrix4::from(self.rotation)
+            * Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z)
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            position: Vector3::zero(),
+            rotation: Quaternion::one(),
+            scale: Vector3::new(1.0, 1.0, 1.0),
+        }
+    }
+}
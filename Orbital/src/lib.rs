@@ -310,6 +310,7 @@ pub mod cache;
 pub mod error;
 pub mod game;
 pub mod logging;
+pub mod physics;
 pub mod renderer;
 pub mod resources;
 pub mod timer;
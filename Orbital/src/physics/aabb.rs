@@ -0,0 +1,102 @@
+use cgmath::{Matrix4, Vector3, Vector4};
+
+/// An axis-aligned bounding box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vector3<f32>,
+    pub max: Vector3<f32>,
+}
+
+impl Aabb {
+    pub fn new(min: Vector3<f32>, max: Vector3<f32>) -> Self {
+        Self { min, max }
+    }
+
+    /// Makes the smallest [Aabb] containing all given points.
+    /// Returns [None], if there are no points.
+    pub fn from_points<I: IntoIterator<Item = Vector3<f32>>>(points: I) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+
+        Some(points.fold(Self::new(first, first), |aabb, point| aabb.grow_to(point)))
+    }
+
+    /// Returns an [Aabb] also containing `point`.
+    pub fn grow_to(self, point: Vector3<f32>) -> Self {
+        Self {
+            min: Vector3::new(
+                self.min.x.min(point.x),
+                self.min.y.min(point.y),
+                self.min.z.min(point.z),
+            ),
+            max: Vector3::new(
+                self.max.x.max(point.x),
+                self.max.y.max(point.y),
+                self.max.z.max(point.z),
+            ),
+        }
+    }
+
+    /// Returns an [Aabb] containing both [Aabb]s.
+    pub fn merge(self, other: Self) -> Self {
+        self.grow_to(other.min).grow_to(other.max)
+    }
+
+    /// Returns the [Aabb] enlarged by `margin` on each side.
+    pub fn expand(self, margin: f32) -> Self {
+        let margin = Vector3::new(margin, margin, margin);
+
+        Self {
+            min: self.min - margin,
+            max: self.max + margin,
+        }
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.x <= other.max.x
+            && self.max.x >= other.min.x
+            && self.min.y <= other.max.y
+            && self.max.y >= other.min.y
+            && self.min.z <= other.max.z
+            && self.max.z >= other.min.z
+    }
+
+    pub fn contains(&self, point: Vector3<f32>) -> bool {
+        point.x >= self.min.x
+            && point.x <= self.max.x
+            && point.y >= self.min.y
+            && point.y <= self.max.y
+            && point.z >= self.min.z
+            && point.z <= self.max.z
+    }
+
+    pub fn center(&self) -> Vector3<f32> {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn half_extents(&self) -> Vector3<f32> {
+        (self.max - self.min) * 0.5
+    }
+
+    pub fn corners(&self) -> [Vector3<f32>; 8] {
+        [
+            Vector3::new(self.min.x, self.min.y, self.min.z),
+            Vector3::new(self.max.x, self.min.y, self.min.z),
+            Vector3::new(self.min.x, self.max.y, self.min.z),
+            Vector3::new(self.max.x, self.max.y, self.min.z),
+            Vector3::new(self.min.x, self.min.y, self.max.z),
+            Vector3::new(self.max.x, self.min.y, self.max.z),
+            Vector3::new(self.min.x, self.max.y, self.max.z),
+            Vector3::new(self.max.x, self.max.y, self.max.z),
+        ]
+    }
+
+    /// Transforms all corners and returns the [Aabb] containing them.
+    pub fn transform(&self, matrix: &Matrix4<f32>) -> Self {
+        Self::from_points(self.corners().map(|corner| {
+            let transformed = matrix * Vector4::new(corner.x, corner.y, corner.z, 1.0);
+            transformed.truncate()
+        }))
+        .unwrap()
+    }
+}
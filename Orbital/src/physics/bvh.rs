@@ -0,0 +1,270 @@
+use std::sync::OnceLock;
+
+use cgmath::Vector3;
+
+use crate::resources::realizations::Vertex;
+
+use super::{Aabb, Ray};
+
+/// Maximum amount of primitives in a leaf of a [Bvh].
+const LEAF_SIZE: usize = 4;
+
+#[derive(Debug, Clone, Copy)]
+struct BvhNode {
+    aabb: Aabb,
+    /// Leaf: index of the first primitive in [Bvh::primitives].
+    /// Otherwise: index of the left child, the right child follows.
+    start: u32,
+    /// Amount of primitives, `0` if not a leaf.
+    count: u32,
+}
+
+/// A bounding volume hierarchy over any primitives with an [Aabb].
+///
+/// The [Bvh] only knows about the [Aabb]s of the primitives.
+/// Primitives are referred to by their index in the slice given to
+/// [Bvh::build].
+#[derive(Debug, Clone, Default)]
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    primitives: Vec<u32>,
+}
+
+impl Bvh {
+    /// Builds a [Bvh] by recursively splitting along the longest axis.
+    pub fn build(aabbs: &[Aabb]) -> Self {
+        let mut bvh = Self {
+            nodes: Vec::with_capacity(aabbs.len() * 2),
+            primitives: (0..aabbs.len() as u32).collect(),
+        };
+
+        if !aabbs.is_empty() {
+            bvh.nodes.push(BvhNode {
+                aabb: aabbs[0],
+                start: 0,
+                count: aabbs.len() as u32,
+            });
+            bvh.subdivide(0, aabbs);
+        }
+
+        bvh
+    }
+
+    fn subdivide(&mut self, node_index: usize, aabbs: &[Aabb]) {
+        let start = self.nodes[node_index].start as usize;
+        let count = self.nodes[node_index].count as usize;
+        let primitives = &mut self.primitives[start..start + count];
+
+        self.nodes[node_index].aabb = primitives
+            .iter()
+            .map(|x| aabbs[*x as usize])
+            .reduce(Aabb::merge)
+            .unwrap();
+        if count <= LEAF_SIZE {
+            return;
+        }
+
+        // Split at the median along the longest axis of the centers
+        let center_bounds =
+            Aabb::from_points(primitives.iter().map(|x| aabbs[*x as usize].center())).unwrap();
+        let extent = center_bounds.max - center_bounds.min;
+        let axis = if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        };
+        if extent[axis] <= 0.0 {
+            // All centers are the same, splitting doesn't help
+            return;
+        }
+
+        let middle = count / 2;
+        primitives.select_nth_unstable_by(middle, |a, b| {
+            aabbs[*a as usize].center()[axis].total_cmp(&aabbs[*b as usize].center()[axis])
+        });
+
+        let left = self.nodes.len();
+        self.nodes.push(BvhNode {
+            aabb: self.nodes[node_index].aabb,
+            start: start as u32,
+            count: middle as u32,
+        });
+        self.nodes.push(BvhNode {
+            aabb: self.nodes[node_index].aabb,
+            start: (start + middle) as u32,
+            count: (count - middle) as u32,
+        });
+        self.nodes[node_index].start = left as u32;
+        self.nodes[node_index].count = 0;
+
+        self.subdivide(left, aabbs);
+        self.subdivide(left + 1, aabbs);
+    }
+
+    /// The [Aabb] around all primitives, if there are any.
+    pub fn bounds(&self) -> Option<Aabb> {
+        self.nodes.first().map(|x| x.aabb)
+    }
+
+    /// Finds the closest primitive hit by the [Ray].
+    ///
+    /// `test` is called for each primitive whose [Aabb] is hit closer
+    /// than the closest hit so far and returns the distance of a hit, if
+    /// any.
+    /// Returns the index of the closest primitive and the distance.
+    pub fn raycast<F>(&self, ray: &Ray, max_distance: f32, mut test: F) -> Option<(u32, f32)>
+    where
+        F: FnMut(u32) -> Option<f32>,
+    {
+        let mut closest: Option<(u32, f32)> = None;
+        let mut stack = Vec::new();
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+
+        while let Some(node_index) = stack.pop() {
+            let node: &BvhNode = &self.nodes[node_index];
+            let max_distance = closest.map_or(max_distance, |(_, x)| x);
+            if ray.intersect_aabb(&node.aabb, max_distance).is_none() {
+                continue;
+            }
+
+            if node.count == 0 {
+                stack.push(node.start as usize);
+                stack.push(node.start as usize + 1);
+                continue;
+            }
+
+            for primitive in
+                &self.primitives[node.start as usize..(node.start + node.count) as usize]
+            {
+                if let Some(distance) = test(*primitive) {
+                    if distance <= closest.map_or(max_distance, |(_, x)| x) {
+                        closest = Some((*primitive, distance));
+                    }
+                }
+            }
+        }
+
+        closest
+    }
+
+    /// Returns all primitives whose [Aabb] might intersect with `aabb`.
+    pub fn query_aabb(&self, aabb: &Aabb) -> Vec<u32> {
+        let mut result = Vec::new();
+        let mut stack = Vec::new();
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+
+        while let Some(node_index) = stack.pop() {
+            let node: &BvhNode = &self.nodes[node_index];
+            if !node.aabb.intersects(aabb) {
+                continue;
+            }
+
+            if node.count == 0 {
+                stack.push(node.start as usize);
+                stack.push(node.start as usize + 1);
+            } else {
+                result.extend_from_slice(
+                    &self.primitives[node.start as usize..(node.start + node.count) as usize],
+                );
+            }
+        }
+
+        result
+    }
+}
+
+/// A hit of a [Ray] on a [MeshBvh].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeshHit {
+    pub distance: f32,
+    /// Index of the hit triangle
+    pub triangle: u32,
+    /// Normal of the hit triangle, following it's winding order.
+    /// Not normalized.
+    pub normal: Vector3<f32>,
+}
+
+/// A [Bvh] over the triangles of a [Mesh](crate::resources::realizations::Mesh),
+/// used for spatial queries like raycasts.
+///
+/// The [Bvh] itself is only built on the first query, so that [Mesh]es
+/// which are never queried don't pay for it.
+///
+/// ⚠️ Always in the bind (rest) pose, skinning and morph targets are
+/// ignored.
+///
+/// [Mesh]: crate::resources::realizations::Mesh
+#[derive(Debug, Clone, Default)]
+pub struct MeshBvh {
+    positions: Vec<Vector3<f32>>,
+    triangles: Vec<[u32; 3]>,
+    bvh: OnceLock<Bvh>,
+}
+
+impl MeshBvh {
+    /// Incomplete triangles or out of bounds indices are skipped.
+    pub fn from_data(vertices: &[Vertex], indices: &[u32]) -> Self {
+        let positions = vertices.iter().map(|x| x.position).collect::<Vec<_>>();
+        let triangles = indices
+            .chunks_exact(3)
+            .filter(|x| x.iter().all(|i| (*i as usize) < positions.len()))
+            .map(|x| [x[0], x[1], x[2]])
+            .collect::<Vec<_>>();
+
+        Self {
+            positions,
+            triangles,
+            bvh: OnceLock::new(),
+        }
+    }
+
+    /// Returns the [Bvh] over all triangles, building it if needed.
+    fn bvh(&self) -> &Bvh {
+        self.bvh.get_or_init(|| {
+            let aabbs = (0..self.triangles.len())
+                .map(|x| Aabb::from_points(self.triangle(x as u32)).unwrap())
+                .collect::<Vec<_>>();
+
+            Bvh::build(&aabbs)
+        })
+    }
+
+    /// Returns the positions of a triangle.
+    pub fn triangle(&self, index: u32) -> [Vector3<f32>; 3] {
+        self.triangles[index as usize].map(|x| self.positions[x as usize])
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    /// The [Aabb] around all triangles, if there are any.
+    pub fn bounds(&self) -> Option<Aabb> {
+        self.bvh().bounds()
+    }
+
+    /// Finds the closest triangle hit by the [Ray].
+    pub fn raycast(&self, ray: &Ray, max_distance: f32) -> Option<MeshHit> {
+        let (triangle, distance) = self.bvh().raycast(ray, max_distance, |x| {
+            ray.intersect_triangle(&self.triangle(x))
+        })?;
+
+        let [a, b, c] = self.triangle(triangle);
+        Some(MeshHit {
+            distance,
+            triangle,
+            normal: (b - a).cross(c - a),
+        })
+    }
+
+    /// Returns all triangles whose [Aabb] might intersect with `aabb`.
+    pub fn query_aabb(&self, aabb: &Aabb) -> Vec<u32> {
+        self.bvh().query_aabb(aabb)
+    }
+}
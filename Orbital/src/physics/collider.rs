@@ -0,0 +1,378 @@
+use cgmath::{InnerSpace, Vector3, Zero};
+use hashbrown::{HashMap, HashSet};
+
+use crate::resources::descriptors::MeshDescriptor;
+
+use super::Aabb;
+
+/// The shape of a [RigidBody](super::RigidBody), in it's local space.
+///
+/// Any shape, except [ColliderShape::TriMesh], is convex and can collide
+/// with any other shape.
+/// [ColliderShape::TriMesh] is meant for static level geometry and can
+/// only collide with convex shapes.
+#[derive(Debug, Clone)]
+pub enum ColliderShape {
+    Sphere {
+        radius: f32,
+    },
+    Box {
+        half_extents: Vector3<f32>,
+    },
+    /// A capsule along the Y-axis.
+    /// `half_height` is the distance from the center to either of the
+    /// hemisphere centers.
+    Capsule {
+        radius: f32,
+        half_height: f32,
+    },
+    /// The convex hull around all points.
+    /// Points inside the hull are allowed, but wasteful.
+    ConvexHull {
+        points: Vec<Vector3<f32>>,
+    },
+    /// A triangle mesh, e.g. terrain.
+    ///
+    /// ⚠️ Dynamic [RigidBodies](super::RigidBody) can't use a
+    /// [ColliderShape::TriMesh], they will be made static.
+    TriMesh {
+        vertices: Vec<Vector3<f32>>,
+        /// Three indices per triangle
+        indices: Vec<u32>,
+    },
+}
+
+impl ColliderShape {
+    /// Makes a [ColliderShape::ConvexHull] around all vertices of a mesh.
+    /// Only the corners of the hull are kept, vertices inside of it or on
+    /// it's faces are dropped.
+    pub fn convex_hull_from_mesh(mesh: &MeshDescriptor) -> Self {
+        Self::ConvexHull {
+            points: convex_hull(mesh.vertices.iter().map(|x| x.position)),
+        }
+    }
+
+    /// Makes a [ColliderShape::TriMesh] from the triangles of a mesh.
+    pub fn trimesh_from_mesh(mesh: &MeshDescriptor) -> Self {
+        Self::TriMesh {
+            vertices: mesh.vertices.iter().map(|x| x.position).collect(),
+            indices: mesh.indices.clone(),
+        }
+    }
+
+    pub fn is_convex(&self) -> bool {
+        !matches!(self, Self::TriMesh { .. })
+    }
+
+    /// Returns the point of the shape furthest into `direction`.
+    /// Used to find collisions between convex shapes.
+    ///
+    /// ⚠️ [ColliderShape::TriMesh] isn't convex, the support point of
+    /// all vertices is returned.
+    pub fn support(&self, direction: Vector3<f32>) -> Vector3<f32> {
+        match self {
+            Self::Sphere { radius } => safe_normalize(direction) * *radius,
+            Self::Box { half_extents } => Vector3::new(
+                half_extents.x.copysign(direction.x),
+                half_extents.y.copysign(direction.y),
+                half_extents.z.copysign(direction.z),
+            ),
+            Self::Capsule {
+                radius,
+                half_height,
+            } => {
+                Vector3::new(0.0, half_height.copysign(direction.y), 0.0)
+                    + safe_normalize(direction) * *radius
+            }
+            Self::ConvexHull { points } => support_of_points(points, direction),
+            Self::TriMesh { vertices, .. } => support_of_points(vertices, direction),
+        }
+    }
+
+    /// Returns the [Aabb] of the shape in it's local space.
+    pub fn local_aabb(&self) -> Aabb {
+        match self {
+            Self::Sphere { radius } => {
+                let extent = Vector3::new(*radius, *radius, *radius);
+                Aabb::new(-extent, extent)
+            }
+            Self::Box { half_extents } => Aabb::new(-*half_extents, *half_extents),
+            Self::Capsule {
+                radius,
+                half_height,
+            } => {
+                let extent = Vector3::new(*radius, half_height + radius, *radius);
+                Aabb::new(-extent, extent)
+            }
+            Self::ConvexHull { points } => Aabb::from_points(points.iter().copied())
+                .unwrap_or(Aabb::new(Vector3::zero(), Vector3::zero())),
+            Self::TriMesh { vertices, .. } => Aabb::from_points(vertices.iter().copied())
+                .unwrap_or(Aabb::new(Vector3::zero(), Vector3::zero())),
+        }
+    }
+
+    /// Returns each triangle, if this is a [ColliderShape::TriMesh].
+    /// Incomplete triangles or out of bounds indices are skipped.
+    pub fn triangles(&self) -> Vec<[Vector3<f32>; 3]> {
+        let Self::TriMesh { vertices, indices } = self else {
+            return Vec::new();
+        };
+
+        indices
+            .chunks_exact(3)
+            .filter_map(|x| {
+                Some([
+                    *vertices.get(x[0] as usize)?,
+                    *vertices.get(x[1] as usize)?,
+                    *vertices.get(x[2] as usize)?,
+                ])
+            })
+            .collect()
+    }
+}
+
+/// Computes the corners of the convex hull around all points, by
+/// incrementally growing a tetrahedron.
+///
+/// Flat or degenerate point clouds have no volume to grow, their unique
+/// points are returned as is.
+fn convex_hull<I: IntoIterator<Item = Vector3<f32>>>(points: I) -> Vec<Vector3<f32>> {
+    let mut unique = HashSet::new();
+    let points: Vec<Vector3<f32>> = points
+        .into_iter()
+        .filter(|x| unique.insert([x.x.to_bits(), x.y.to_bits(), x.z.to_bits()]))
+        .collect();
+
+    let Some(aabb) = Aabb::from_points(points.iter().copied()) else {
+        return points;
+    };
+    // Relative to the size, so that points on faces aren't kept due to
+    // rounding
+    let epsilon = (aabb.max - aabb.min).magnitude() * 1e-5;
+
+    let Some(tetrahedron) = initial_tetrahedron(&points, epsilon) else {
+        return points;
+    };
+
+    // Faces are wound counter-clockwise, seen from outside
+    let plane = |face: &[usize; 3]| {
+        let [a, b, c] = face.map(|x| points[x]);
+        let normal = (b - a).cross(c - a).normalize();
+        (normal, normal.dot(a))
+    };
+    let center = tetrahedron.iter().map(|x| points[*x]).sum::<Vector3<f32>>() / 4.0;
+    let [a, b, c, d] = tetrahedron;
+    let mut faces: Vec<[usize; 3]> = [[a, b, c], [a, c, d], [a, d, b], [b, d, c]]
+        .into_iter()
+        .map(|face| {
+            let (normal, distance) = plane(&face);
+            if normal.dot(center) > distance {
+                [face[0], face[2], face[1]]
+            } else {
+                face
+            }
+        })
+        .collect();
+
+    for (i, point) in points.iter().enumerate() {
+        if tetrahedron.contains(&i) {
+            continue;
+        }
+
+        let (visible, hidden): (Vec<_>, Vec<_>) = faces.into_iter().partition(|face| {
+            let (normal, distance) = plane(face);
+            normal.dot(*point) - distance > epsilon
+        });
+        faces = hidden;
+        if visible.is_empty() {
+            // Inside the hull
+            continue;
+        }
+
+        // Edges only used by a single visible face border the hole
+        let mut edges = HashSet::new();
+        for face in &visible {
+            for edge in [(face[0], face[1]), (face[1], face[2]), (face[2], face[0])] {
+                if !edges.remove(&(edge.1, edge.0)) {
+                    edges.insert(edge);
+                }
+            }
+        }
+        faces.extend(edges.into_iter().map(|(a, b)| [a, b, i]));
+    }
+
+    // Points on faces or edges may have been added before the corners
+    // around them, but only corners touch at least three distinct planes
+    let mut corner_normals: HashMap<usize, Vec<Vector3<f32>>> = HashMap::new();
+    for face in &faces {
+        let (normal, _) = plane(face);
+        for corner in face {
+            let normals = corner_normals.entry(*corner).or_default();
+            if normals.iter().all(|x| x.dot(normal) < 1.0 - 1e-5) {
+                normals.push(normal);
+            }
+        }
+    }
+
+    let mut corners: Vec<usize> = corner_normals
+        .into_iter()
+        .filter(|(_, normals)| normals.len() >= 3)
+        .map(|(corner, _)| corner)
+        .collect();
+    corners.sort_unstable();
+    corners.into_iter().map(|x| points[x]).collect()
+}
+
+/// Finds four points spanning a tetrahedron with volume, if there are
+/// any.
+fn initial_tetrahedron(points: &[Vector3<f32>], epsilon: f32) -> Option<[usize; 4]> {
+    let furthest_from = |distance: &dyn Fn(Vector3<f32>) -> f32| {
+        points
+            .iter()
+            .enumerate()
+            .map(|(i, x)| (i, distance(*x)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .filter(|(_, distance)| *distance > epsilon)
+            .map(|(i, _)| i)
+    };
+
+    let a = 0;
+    let b = furthest_from(&|x| (x - points[a]).magnitude())?;
+    let line = (points[b] - points[a]).normalize();
+    let c = furthest_from(&|x| (x - points[a]).cross(line).magnitude())?;
+    let normal = line.cross(points[c] - points[a]).normalize();
+    let d = furthest_from(&|x| (x - points[a]).dot(normal).abs())?;
+
+    Some([a, b, c, d])
+}
+
+fn support_of_points(points: &[Vector3<f32>], direction: Vector3<f32>) -> Vector3<f32> {
+    points
+        .iter()
+        .copied()
+        .max_by(|a, b| a.dot(direction).total_cmp(&b.dot(direction)))
+        .unwrap_or(Vector3::zero())
+}
+
+/// Normalizes a vector, unless it's (nearly) zero.
+pub(crate) fn safe_normalize(vector: Vector3<f32>) -> Vector3<f32> {
+    let length = vector.magnitude();
+    if length > f32::EPSILON {
+        vector / length
+    } else {
+        Vector3::unit_y()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Vector2;
+
+    use crate::resources::realizations::Vertex;
+
+    use super::*;
+
+    fn cube_corners() -> Vec<Vector3<f32>> {
+        (0..8)
+            .map(|i| {
+                Vector3::new(
+                    if i & 1 == 0 { -1.0 } else { 1.0 },
+                    if i & 2 == 0 { -1.0 } else { 1.0 },
+                    if i & 4 == 0 { -1.0 } else { 1.0 },
+                )
+            })
+            .collect()
+    }
+
+    fn assert_same_points(mut actual: Vec<Vector3<f32>>, mut expected: Vec<Vector3<f32>>) {
+        let key = |x: &Vector3<f32>| [x.x, x.y, x.z];
+        actual.sort_by(|a, b| key(a).partial_cmp(&key(b)).unwrap());
+        expected.sort_by(|a, b| key(a).partial_cmp(&key(b)).unwrap());
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn hull_drops_duplicates_and_inner_points() {
+        let mut points = cube_corners();
+        points.extend(cube_corners());
+        points.push(Vector3::zero());
+        points.push(Vector3::new(0.5, -0.25, 0.75));
+
+        assert_same_points(convex_hull(points), cube_corners());
+    }
+
+    #[test]
+    fn hull_drops_points_on_faces_and_edges() {
+        let grid = [-1.0, 0.0, 1.0];
+        let points = grid.iter().flat_map(|x| {
+            grid.iter()
+                .flat_map(move |y| grid.map(|z| Vector3::new(*x, *y, z)))
+        });
+
+        assert_same_points(convex_hull(points), cube_corners());
+    }
+
+    #[test]
+    fn hull_keeps_all_points_on_a_sphere() {
+        // Fibonacci sphere, evenly spread points
+        let sphere: Vec<Vector3<f32>> = (0..100)
+            .map(|i| {
+                let y = 1.0 - (i as f32 + 0.5) / 50.0;
+                let radius = (1.0 - y * y).sqrt();
+                let angle = i as f32 * 2.399_963;
+                Vector3::new(angle.cos() * radius, y, angle.sin() * radius)
+            })
+            .collect();
+        let inner = sphere.iter().map(|x| x * 0.5);
+
+        let hull = convex_hull(sphere.iter().copied().chain(inner));
+
+        assert_same_points(hull, sphere);
+    }
+
+    #[test]
+    fn flat_hull_keeps_unique_points() {
+        let quad = vec![
+            Vector3::new(-1.0, 0.0, -1.0),
+            Vector3::new(1.0, 0.0, -1.0),
+            Vector3::new(1.0, 0.0, 1.0),
+            Vector3::new(-1.0, 0.0, 1.0),
+        ];
+        let mut points = quad.clone();
+        points.extend(quad.clone());
+
+        assert_same_points(convex_hull(points), quad);
+    }
+
+    #[test]
+    fn hull_from_mesh_supports_like_the_mesh() {
+        let vertex = |position| Vertex {
+            position,
+            normal: Vector3::unit_y(),
+            tangent: Vector3::unit_x(),
+            bitangent: Vector3::unit_z(),
+            uv: Vector2::zero(),
+        };
+        let mesh = MeshDescriptor {
+            vertices: cube_corners()
+                .into_iter()
+                .chain(cube_corners())
+                .chain([Vector3::new(0.2, 0.3, -0.4)])
+                .map(vertex)
+                .collect(),
+            indices: Vec::new(),
+        };
+
+        let shape = ColliderShape::convex_hull_from_mesh(&mesh);
+        let ColliderShape::ConvexHull { points } = &shape else {
+            panic!("Expected a convex hull");
+        };
+
+        assert_eq!(points.len(), 8);
+        assert_eq!(
+            shape.support(Vector3::new(1.0, 2.0, -3.0)),
+            Vector3::new(1.0, 1.0, -1.0)
+        );
+    }
+}
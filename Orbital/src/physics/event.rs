@@ -0,0 +1,22 @@
+use crate::game::ElementUlid;
+
+use super::Contact;
+
+/// Informs an [Element](crate::game::Element) about collisions of it's
+/// [RigidBody](super::RigidBody).
+/// Received via [Element::on_collision](crate::game::Element::on_collision).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CollisionEvent {
+    /// Started touching the [RigidBody](super::RigidBody) of another
+    /// [Element](crate::game::Element).
+    ///
+    /// The [Contact] normal points away from the receiving
+    /// [Element](crate::game::Element).
+    Started {
+        other: ElementUlid,
+        contact: Contact,
+    },
+    /// Stopped touching the [RigidBody](super::RigidBody) of another
+    /// [Element](crate::game::Element).
+    Ended { other: ElementUlid },
+}
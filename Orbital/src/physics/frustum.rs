@@ -0,0 +1,84 @@
+use cgmath::{InnerSpace, Matrix, Matrix4, Vector3, Vector4};
+
+use super::Aabb;
+
+/// A view frustum, made of six planes pointing inwards.
+///
+/// Used to check if something is visible to a
+/// [Camera](crate::resources::realizations::Camera).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    /// Left, right, bottom, top, near and far planes.
+    /// `xyz` is the normal, `w` the distance.
+    planes: [Vector4<f32>; 6],
+}
+
+impl Frustum {
+    /// Extracts the [Frustum] from a view projection matrix.
+    ///
+    /// ⚠️ Expects the WGPU depth range of `0.0` to `1.0`, check
+    /// [Camera::OPEN_GL_MATRIX](crate::resources::realizations::Camera::OPEN_GL_MATRIX).
+    pub fn from_matrix(view_projection_matrix: &Matrix4<f32>) -> Self {
+        let row = |i: usize| view_projection_matrix.row(i);
+
+        let planes = [
+            row(3) + row(0),
+            row(3) - row(0),
+            row(3) + row(1),
+            row(3) - row(1),
+            row(2),
+            row(3) - row(2),
+        ]
+        .map(|plane| {
+            let length = plane.truncate().magnitude();
+            if length > f32::EPSILON {
+                plane / length
+            } else {
+                plane
+            }
+        });
+
+        Self { planes }
+    }
+
+    /// Checks if the [Aabb] is at least partially inside.
+    ///
+    /// ⚠️ Conservative, some [Aabb]s close to the corners of the
+    /// [Frustum] are reported as inside, even if they aren't.
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            // The corner furthest along the plane normal
+            let corner = Vector3::new(
+                if plane.x >= 0.0 {
+                    aabb.max.x
+                } else {
+                    aabb.min.x
+                },
+                if plane.y >= 0.0 {
+                    aabb.max.y
+                } else {
+                    aabb.min.y
+                },
+                if plane.z >= 0.0 {
+                    aabb.max.z
+                } else {
+                    aabb.min.z
+                },
+            );
+
+            plane.truncate().dot(corner) + plane.w >= 0.0
+        })
+    }
+
+    /// Left, right, bottom, top, near and far planes, pointing inwards.
+    /// `xyz` is the normal, `w` the distance.
+    pub fn planes(&self) -> &[Vector4<f32>; 6] {
+        &self.planes
+    }
+
+    pub fn contains(&self, point: Vector3<f32>) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.truncate().dot(point) + plane.w >= 0.0)
+    }
+}
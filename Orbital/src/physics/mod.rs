@@ -0,0 +1,40 @@
+//! Rigid-body physics for [Element](crate::game::Element)s and spatial
+//! queries, like [Ray]casts.
+//!
+//! Give an [Element](crate::game::Element) a [RigidBodyDescriptor] via
+//! [ElementRegistration::rigid_body](crate::game::ElementRegistration::rigid_body)
+//! and the [World](crate::game::World) will move it (and it's
+//! [Model](crate::resources::realizations::Model)s) by simulating it.
+//! Collisions are reported via
+//! [Element::on_collision](crate::game::Element::on_collision).
+//!
+//! The simulation is stepped on each fixed update, if
+//! [GameSettings::fixed_update](crate::game::GameSettings::fixed_update)
+//! is set, otherwise once per frame.
+
+pub mod aabb;
+pub use aabb::*;
+
+pub mod collider;
+pub use collider::*;
+
+pub mod rigid_body;
+pub use rigid_body::*;
+
+pub mod narrowphase;
+pub use narrowphase::*;
+
+pub mod event;
+pub use event::*;
+
+pub mod world;
+pub use world::*;
+
+pub mod ray;
+pub use ray::*;
+
+pub mod bvh;
+pub use bvh::*;
+
+pub mod frustum;
+pub use frustum::*;
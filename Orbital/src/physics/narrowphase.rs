@@ -0,0 +1,543 @@
+use cgmath::{InnerSpace, Vector3};
+
+use super::{collider::safe_normalize, Aabb, ColliderShape, RigidBody};
+
+/// A single point of contact between two [RigidBody]s.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contact {
+    /// Direction from the first into the second [RigidBody].
+    /// Moving the second [RigidBody] by `normal * depth` separates both.
+    pub normal: Vector3<f32>,
+    /// Penetration depth
+    pub depth: f32,
+    /// Point in between both surfaces, in world space
+    pub point: Vector3<f32>,
+}
+
+impl Contact {
+    /// The same contact, seen from the other [RigidBody].
+    pub fn flipped(self) -> Self {
+        Self {
+            normal: -self.normal,
+            ..self
+        }
+    }
+}
+
+const GJK_MAX_ITERATIONS: usize = 64;
+const EPA_MAX_ITERATIONS: usize = 64;
+const EPA_TOLERANCE: f32 = 0.0001;
+
+/// Point on the Minkowski difference `A - B`.
+/// The point on `A` is kept to later find the contact point.
+#[derive(Debug, Clone, Copy)]
+struct SupportPoint {
+    point: Vector3<f32>,
+    a: Vector3<f32>,
+}
+
+impl PartialEq for SupportPoint {
+    fn eq(&self, other: &Self) -> bool {
+        self.point == other.point
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Face {
+    vertices: [SupportPoint; 3],
+    normal: Vector3<f32>,
+}
+
+impl Face {
+    /// Makes a face with the normal pointing away from the origin.
+    fn new(a: SupportPoint, b: SupportPoint, c: SupportPoint) -> Self {
+        let normal = safe_normalize((b.point - a.point).cross(c.point - a.point));
+
+        if normal.dot(a.point) < 0.0 {
+            Self {
+                vertices: [b, a, c],
+                normal: -normal,
+            }
+        } else {
+            Self {
+                vertices: [a, b, c],
+                normal,
+            }
+        }
+    }
+
+    fn distance(&self) -> f32 {
+        self.normal.dot(self.vertices[0].point)
+    }
+}
+
+/// Finds all contacts between two [RigidBody]s.
+///
+/// Convex shapes have at most a single contact.
+/// Against a [ColliderShape::TriMesh], there is up to one contact per
+/// overlapping triangle.
+/// Two [ColliderShape::TriMesh]s never collide.
+pub fn collide(a: &RigidBody, b: &RigidBody) -> Vec<Contact> {
+    match (a.shape().is_convex(), b.shape().is_convex()) {
+        (true, true) => collide_convex(a, b).into_iter().collect(),
+        (false, true) => collide_trimesh(a, b),
+        (true, false) => collide_trimesh(b, a)
+            .into_iter()
+            .map(Contact::flipped)
+            .collect(),
+        (false, false) => Vec::new(),
+    }
+}
+
+fn collide_convex(a: &RigidBody, b: &RigidBody) -> Option<Contact> {
+    // Spheres are common and cheap to check directly
+    if let (
+        ColliderShape::Sphere { radius: radius_a },
+        ColliderShape::Sphere { radius: radius_b },
+    ) = (a.shape(), b.shape())
+    {
+        let offset = b.position - a.position;
+        let distance = offset.magnitude();
+        let depth = radius_a + radius_b - distance;
+        if depth <= 0.0 {
+            return None;
+        }
+
+        let normal = safe_normalize(offset);
+        return Some(Contact {
+            normal,
+            depth,
+            point: a.position + normal * (radius_a - depth * 0.5),
+        });
+    }
+
+    gjk_epa(|x| a.support(x), |x| b.support(x), b.position - a.position)
+}
+
+/// Collides each triangle of `trimesh` against the convex `other`.
+fn collide_trimesh(trimesh: &RigidBody, other: &RigidBody) -> Vec<Contact> {
+    let other_aabb = other.world_aabb();
+
+    trimesh
+        .shape()
+        .triangles()
+        .into_iter()
+        .filter_map(|triangle| {
+            let triangle = triangle.map(|x| trimesh.to_world(x));
+
+            let triangle_aabb = Aabb::from_points(triangle)?;
+            if !triangle_aabb.intersects(&other_aabb) {
+                return None;
+            }
+
+            let center = (triangle[0] + triangle[1] + triangle[2]) / 3.0;
+            gjk_epa(
+                |direction| {
+                    triangle
+                        .into_iter()
+                        .max_by(|x, y| x.dot(direction).total_cmp(&y.dot(direction)))
+                        .unwrap()
+                },
+                |x| other.support(x),
+                other.position - center,
+            )
+        })
+        .collect()
+}
+
+/// Runs GJK to check if two convex shapes, given by their support
+/// functions, overlap.
+/// If so, EPA is used to find the penetration.
+///
+/// `initial_direction` should roughly point from `A` to `B`.
+fn gjk_epa<A, B>(support_a: A, support_b: B, initial_direction: Vector3<f32>) -> Option<Contact>
+where
+    A: Fn(Vector3<f32>) -> Vector3<f32>,
+    B: Fn(Vector3<f32>) -> Vector3<f32>,
+{
+    let support = |direction: Vector3<f32>| {
+        let a = support_a(direction);
+        SupportPoint {
+            point: a - support_b(-direction),
+            a,
+        }
+    };
+
+    let simplex = gjk(&support, initial_direction)?;
+    epa(&support, simplex)
+}
+
+/// Checks if two convex shapes, given by their support functions,
+/// overlap.
+/// Cheaper than [gjk_epa], as the penetration isn't needed.
+pub(crate) fn convex_overlap<A, B>(
+    support_a: A,
+    support_b: B,
+    initial_direction: Vector3<f32>,
+) -> bool
+where
+    A: Fn(Vector3<f32>) -> Vector3<f32>,
+    B: Fn(Vector3<f32>) -> Vector3<f32>,
+{
+    let support = |direction: Vector3<f32>| {
+        let a = support_a(direction);
+        SupportPoint {
+            point: a - support_b(-direction),
+            a,
+        }
+    };
+
+    gjk(&support, initial_direction).is_some()
+}
+
+/// Returns an enclosing tetrahedron, if the origin is inside the
+/// Minkowski difference.
+fn gjk<S>(support: &S, initial_direction: Vector3<f32>) -> Option<[SupportPoint; 4]>
+where
+    S: Fn(Vector3<f32>) -> SupportPoint,
+{
+    let mut c = support(safe_normalize(initial_direction));
+    let mut direction = -c.point;
+    let mut b = support(direction);
+    if b.point.dot(direction) < 0.0 {
+        return None;
+    }
+
+    direction = perpendicular_towards_origin(c.point - b.point, -b.point);
+    let mut d = b;
+    let mut dimension = 2;
+
+    for _ in 0..GJK_MAX_ITERATIONS {
+        let a = support(direction);
+        if a.point.dot(direction) < 0.0 {
+            return None;
+        }
+
+        dimension += 1;
+        if dimension == 3 {
+            // Triangle case
+            let ab = b.point - a.point;
+            let ac = c.point - a.point;
+            let ao = -a.point;
+            let normal = ab.cross(ac);
+
+            dimension = 2;
+            if ab.cross(normal).dot(ao) > 0.0 {
+                c = a;
+                direction = perpendicular_towards_origin(ab, ao);
+                continue;
+            }
+            if normal.cross(ac).dot(ao) > 0.0 {
+                b = a;
+                direction = perpendicular_towards_origin(ac, ao);
+                continue;
+            }
+
+            dimension = 3;
+            if normal.dot(ao) > 0.0 {
+                d = c;
+                c = b;
+                b = a;
+                direction = normal;
+            } else {
+                d = b;
+                b = a;
+                direction = -normal;
+            }
+        } else {
+            // Tetrahedron case
+            let ab = b.point - a.point;
+            let ac = c.point - a.point;
+            let ad = d.point - a.point;
+            let ao = -a.point;
+            let abc = ab.cross(ac);
+            let acd = ac.cross(ad);
+            let adb = ad.cross(ab);
+
+            dimension = 3;
+            if abc.dot(ao) > 0.0 {
+                d = c;
+                c = b;
+                b = a;
+                direction = abc;
+            } else if acd.dot(ao) > 0.0 {
+                b = a;
+                direction = acd;
+            } else if adb.dot(ao) > 0.0 {
+                c = d;
+                d = b;
+                b = a;
+                direction = adb;
+            } else {
+                return Some([a, b, c, d]);
+            }
+        }
+    }
+
+    None
+}
+
+/// Direction perpendicular to `edge`, pointing towards `to_origin`.
+fn perpendicular_towards_origin(edge: Vector3<f32>, to_origin: Vector3<f32>) -> Vector3<f32> {
+    let direction = edge.cross(to_origin).cross(edge);
+    if direction.magnitude2() > f32::EPSILON {
+        return direction;
+    }
+
+    // The origin is on the edge, any perpendicular direction works
+    let direction = edge.cross(Vector3::unit_x());
+    if direction.magnitude2() > f32::EPSILON {
+        direction
+    } else {
+        edge.cross(Vector3::unit_z())
+    }
+}
+
+/// Expands the tetrahedron from [gjk] until the face closest to the
+/// origin is found, which gives the penetration normal and depth.
+fn epa<S>(support: &S, simplex: [SupportPoint; 4]) -> Option<Contact>
+where
+    S: Fn(Vector3<f32>) -> SupportPoint,
+{
+    let [a, b, c, d] = simplex;
+    let mut faces = vec![
+        Face::new(a, b, c),
+        Face::new(a, c, d),
+        Face::new(a, d, b),
+        Face::new(b, d, c),
+    ];
+
+    for _ in 0..EPA_MAX_ITERATIONS {
+        let closest = closest_face(&faces)?;
+
+        let point = support(closest.normal);
+        if point.point.dot(closest.normal) - closest.distance() < EPA_TOLERANCE {
+            return Some(contact_from_face(&closest));
+        }
+
+        // Remove all faces seeing the new point, keeping their outline
+        let mut loose_edges: Vec<[SupportPoint; 2]> = Vec::new();
+        faces.retain(|face| {
+            if face.normal.dot(point.point - face.vertices[0].point) <= 0.0 {
+                return true;
+            }
+
+            for i in 0..3 {
+                let edge = [face.vertices[i], face.vertices[(i + 1) % 3]];
+                match loose_edges
+                    .iter()
+                    .position(|x| x[0] == edge[1] && x[1] == edge[0])
+                {
+                    Some(shared) => {
+                        loose_edges.swap_remove(shared);
+                    }
+                    None => loose_edges.push(edge),
+                }
+            }
+
+            false
+        });
+
+        // Close the hole with the new point
+        for [from, to] in loose_edges {
+            faces.push(Face::new(from, to, point));
+        }
+    }
+
+    // Didn't converge, but the closest face is still a decent guess
+    closest_face(&faces).map(|x| contact_from_face(&x))
+}
+
+fn closest_face(faces: &[Face]) -> Option<Face> {
+    faces
+        .iter()
+        .min_by(|x, y| x.distance().total_cmp(&y.distance()))
+        .copied()
+}
+
+fn contact_from_face(face: &Face) -> Contact {
+    let depth = face.distance().max(0.0);
+
+    // Project the origin onto the face and find the matching point on A
+    let [u, v, w] = barycentric(face.normal * depth, face.vertices.map(|x| x.point));
+    let point_a = face.vertices[0].a * u + face.vertices[1].a * v + face.vertices[2].a * w;
+
+    Contact {
+        normal: face.normal,
+        depth,
+        point: point_a - face.normal * (depth * 0.5),
+    }
+}
+
+fn barycentric(point: Vector3<f32>, triangle: [Vector3<f32>; 3]) -> [f32; 3] {
+    let v0 = triangle[1] - triangle[0];
+    let v1 = triangle[2] - triangle[0];
+    let v2 = point - triangle[0];
+
+    let d00 = v0.dot(v0);
+    let d01 = v0.dot(v1);
+    let d11 = v1.dot(v1);
+    let d20 = v2.dot(v0);
+    let d21 = v2.dot(v1);
+
+    let denominator = d00 * d11 - d01 * d01;
+    if denominator.abs() <= f32::EPSILON {
+        return [1.0, 0.0, 0.0];
+    }
+
+    let v = (d11 * d20 - d01 * d21) / denominator;
+    let w = (d00 * d21 - d01 * d20) / denominator;
+    [1.0 - v - w, v, w]
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Zero;
+
+    use crate::{
+        game::Transform,
+        physics::{RigidBodyDescriptor, RigidBodyType},
+    };
+
+    use super::*;
+
+    /// Tolerance for EPA, which only approximates curved shapes
+    const TOLERANCE: f32 = 0.01;
+
+    fn body(shape: ColliderShape, position: Vector3<f32>) -> RigidBody {
+        RigidBody::from_descriptor(
+            RigidBodyDescriptor {
+                body_type: RigidBodyType::Static,
+                shape,
+                ..Default::default()
+            },
+            &Transform {
+                position,
+                ..Default::default()
+            },
+        )
+    }
+
+    fn single_contact(a: &RigidBody, b: &RigidBody) -> Contact {
+        let contacts = collide(a, b);
+        assert_eq!(contacts.len(), 1, "Expected a single contact");
+
+        contacts[0]
+    }
+
+    fn assert_contact(contact: Contact, normal: Vector3<f32>, depth: f32) {
+        assert!(
+            (contact.normal - normal).magnitude() < TOLERANCE,
+            "Normal {:?} isn't {:?}",
+            contact.normal,
+            normal
+        );
+        assert!(
+            (contact.depth - depth).abs() < TOLERANCE,
+            "Depth {} isn't {}",
+            contact.depth,
+            depth
+        );
+    }
+
+    #[test]
+    fn sphere_sphere() {
+        let a = body(ColliderShape::Sphere { radius: 1.0 }, Vector3::zero());
+        let b = body(
+            ColliderShape::Sphere { radius: 1.0 },
+            Vector3::new(1.5, 0.0, 0.0),
+        );
+
+        let contact = single_contact(&a, &b);
+        assert_contact(contact, Vector3::unit_x(), 0.5);
+        assert!((contact.point - Vector3::new(0.75, 0.0, 0.0)).magnitude() < TOLERANCE);
+
+        assert_contact(single_contact(&b, &a), -Vector3::unit_x(), 0.5);
+    }
+
+    #[test]
+    fn sphere_sphere_through_gjk_epa() {
+        let a = body(ColliderShape::Sphere { radius: 1.0 }, Vector3::zero());
+        let b = body(
+            ColliderShape::Sphere { radius: 0.5 },
+            Vector3::new(0.0, 0.0, 1.2),
+        );
+
+        let contact = gjk_epa(|x| a.support(x), |x| b.support(x), b.position - a.position)
+            .expect("Spheres overlap");
+        assert_contact(contact, Vector3::unit_z(), 0.3);
+    }
+
+    #[test]
+    fn box_box() {
+        let a = body(
+            ColliderShape::Box {
+                half_extents: Vector3::new(1.0, 1.0, 1.0),
+            },
+            Vector3::zero(),
+        );
+        let b = body(
+            ColliderShape::Box {
+                half_extents: Vector3::new(1.0, 0.5, 1.0),
+            },
+            Vector3::new(0.3, 1.3, -0.2),
+        );
+
+        assert_contact(single_contact(&a, &b), Vector3::unit_y(), 0.2);
+    }
+
+    #[test]
+    fn capsule_box() {
+        let ground = body(
+            ColliderShape::Box {
+                half_extents: Vector3::new(5.0, 0.5, 5.0),
+            },
+            Vector3::new(0.0, -0.5, 0.0),
+        );
+        let capsule = body(
+            ColliderShape::Capsule {
+                radius: 0.5,
+                half_height: 1.0,
+            },
+            Vector3::new(1.0, 1.4, 0.0),
+        );
+
+        assert_contact(single_contact(&ground, &capsule), Vector3::unit_y(), 0.1);
+    }
+
+    #[test]
+    fn capsule_sphere() {
+        let capsule = body(
+            ColliderShape::Capsule {
+                radius: 0.5,
+                half_height: 1.0,
+            },
+            Vector3::zero(),
+        );
+        let sphere = body(
+            ColliderShape::Sphere { radius: 0.5 },
+            Vector3::new(0.8, 0.5, 0.0),
+        );
+
+        assert_contact(single_contact(&capsule, &sphere), Vector3::unit_x(), 0.2);
+    }
+
+    #[test]
+    fn separated_shapes_dont_collide() {
+        let a = body(
+            ColliderShape::Box {
+                half_extents: Vector3::new(1.0, 1.0, 1.0),
+            },
+            Vector3::zero(),
+        );
+        let b = body(
+            ColliderShape::Capsule {
+                radius: 0.5,
+                half_height: 1.0,
+            },
+            Vector3::new(1.6, 0.0, 0.0),
+        );
+
+        assert!(collide(&a, &b).is_empty());
+    }
+}
@@ -0,0 +1,99 @@
+use cgmath::{InnerSpace, Matrix4, Vector3};
+
+use super::Aabb;
+
+/// A ray, starting at `origin` and going into `direction`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    pub origin: Vector3<f32>,
+    /// Distances along the [Ray] are measured in lengths of this
+    /// direction.
+    /// Thus, it should usually be normalized.
+    pub direction: Vector3<f32>,
+}
+
+impl Ray {
+    /// Makes a new [Ray] with a normalized direction.
+    pub fn new(origin: Vector3<f32>, direction: Vector3<f32>) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    /// Returns the point at `distance` along the [Ray].
+    pub fn at(&self, distance: f32) -> Vector3<f32> {
+        self.origin + self.direction * distance
+    }
+
+    /// Transforms the [Ray] by a matrix.
+    ///
+    /// The direction is **not** normalized again, so that distances
+    /// along the transformed [Ray] match distances along this [Ray].
+    pub fn transform(&self, matrix: &Matrix4<f32>) -> Self {
+        Self {
+            origin: (matrix * self.origin.extend(1.0)).truncate(),
+            direction: (matrix * self.direction.extend(0.0)).truncate(),
+        }
+    }
+
+    /// Returns the distance at which the [Ray] enters the [Aabb], if
+    /// within `max_distance`.
+    /// Starting inside the [Aabb] counts as entering at `0.0`.
+    pub fn intersect_aabb(&self, aabb: &Aabb, max_distance: f32) -> Option<f32> {
+        let mut near = 0.0f32;
+        let mut far = max_distance;
+
+        for axis in 0..3 {
+            let inverse = 1.0 / self.direction[axis];
+            let mut t0 = (aabb.min[axis] - self.origin[axis]) * inverse;
+            let mut t1 = (aabb.max[axis] - self.origin[axis]) * inverse;
+            if inverse < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            // NaN (ray parallel to and on a slab) is ignored by min/max
+            near = near.max(t0);
+            far = far.min(t1);
+            if near > far {
+                return None;
+            }
+        }
+
+        Some(near)
+    }
+
+    /// Returns the distance at which the [Ray] hits the triangle.
+    /// Both sides of the triangle can be hit.
+    pub fn intersect_triangle(&self, triangle: &[Vector3<f32>; 3]) -> Option<f32> {
+        let edge_a = triangle[1] - triangle[0];
+        let edge_b = triangle[2] - triangle[0];
+
+        let p = self.direction.cross(edge_b);
+        let determinant = edge_a.dot(p);
+        if determinant.abs() <= f32::EPSILON {
+            // Parallel to the triangle
+            return None;
+        }
+        let inverse_determinant = 1.0 / determinant;
+
+        let to_origin = self.origin - triangle[0];
+        let u = to_origin.dot(p) * inverse_determinant;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = to_origin.cross(edge_a);
+        let v = self.direction.dot(q) * inverse_determinant;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let distance = edge_b.dot(q) * inverse_determinant;
+        if distance < 0.0 {
+            return None;
+        }
+
+        Some(distance)
+    }
+}
@@ -0,0 +1,277 @@
+use cgmath::{InnerSpace, Matrix4, Quaternion, Rotation, Vector3, Zero};
+use log::warn;
+
+use crate::game::{Mode, Transform};
+
+use super::{Aabb, ColliderShape};
+
+/// How a [RigidBody] is simulated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RigidBodyType {
+    /// Affected by gravity and collisions.
+    #[default]
+    Dynamic,
+    /// Only moved by it's velocity.
+    /// Pushes dynamic [RigidBody]s, but isn't pushed back.
+    Kinematic,
+    /// Never moves, e.g. level geometry.
+    Static,
+}
+
+/// Describes a [RigidBody] attached to an [Element](crate::game::Element).
+///
+/// The [RigidBody] starts at the [Transform] of the
+/// [Element](crate::game::Element) and moves it (and thus any owned
+/// [Model](crate::resources::realizations::Model)) while simulating.
+#[derive(Debug, Clone)]
+pub struct RigidBodyDescriptor {
+    pub body_type: RigidBodyType,
+    pub shape: ColliderShape,
+    /// Mass in kilograms.
+    /// Ignored for non-dynamic [RigidBody]s.
+    pub mass: f32,
+    pub linear_velocity: Vector3<f32>,
+    /// Axis-angle rotation per second
+    pub angular_velocity: Vector3<f32>,
+    /// Multiplier of the gravity of the [PhysicsWorld](super::PhysicsWorld)
+    pub gravity_scale: f32,
+    /// Fraction of velocity lost per second
+    pub linear_damping: f32,
+    /// Fraction of angular velocity lost per second
+    pub angular_damping: f32,
+    /// Bounciness, `0.0` to `1.0`.
+    /// The bigger value of both colliding [RigidBody]s is used.
+    pub restitution: f32,
+    /// Coulomb friction coefficient.
+    /// The geometric mean of both colliding [RigidBody]s is used.
+    pub friction: f32,
+    /// Sensors report collisions, but don't collide.
+    /// E.g. for trigger volumes.
+    pub is_sensor: bool,
+}
+
+impl Default for RigidBodyDescriptor {
+    fn default() -> Self {
+        Self {
+            body_type: RigidBodyType::Dynamic,
+            shape: ColliderShape::Sphere { radius: 0.5 },
+            mass: 1.0,
+            linear_velocity: Vector3::zero(),
+            angular_velocity: Vector3::zero(),
+            gravity_scale: 1.0,
+            linear_damping: 0.0,
+            angular_damping: 0.05,
+            restitution: 0.0,
+            friction: 0.5,
+            is_sensor: false,
+        }
+    }
+}
+
+/// Changes a [RigidBody].
+/// Used with [WorldChange::UpdateRigidBody](crate::game::WorldChange::UpdateRigidBody).
+///
+/// [Mode::OffsetViewAligned] falls back to [Mode::Offset].
+#[derive(Debug, Default, Clone)]
+pub struct RigidBodyChange {
+    pub body_type: Option<RigidBodyType>,
+    pub linear_velocity: Option<Mode<Vector3<f32>>>,
+    pub angular_velocity: Option<Mode<Vector3<f32>>>,
+    /// Instant change in momentum, e.g. for a jump or explosion.
+    /// Only affects dynamic [RigidBody]s.
+    pub impulse: Option<Vector3<f32>>,
+    pub gravity_scale: Option<f32>,
+}
+
+/// A simulated body with a [ColliderShape].
+/// Check [RigidBodyDescriptor].
+///
+/// ⚠️ Collisions only change the linear velocity.
+/// ⚠️ Rotation is only driven by the angular velocity.
+///
+/// ⚠️ The [RigidBody] is simulated in world space.
+/// If the [Element](crate::game::Element) or any of it's parents gets
+/// moved, the [RigidBody] is teleported along.
+/// ⚠️ The scale of the [Element](crate::game::Element) doesn't affect the
+/// [ColliderShape].
+#[derive(Debug, Clone)]
+pub struct RigidBody {
+    body_type: RigidBodyType,
+    shape: ColliderShape,
+    local_aabb: Aabb,
+    mass: f32,
+    inverse_mass: f32,
+    pub position: Vector3<f32>,
+    pub rotation: Quaternion<f32>,
+    pub linear_velocity: Vector3<f32>,
+    pub angular_velocity: Vector3<f32>,
+    pub gravity_scale: f32,
+    pub linear_damping: f32,
+    pub angular_damping: f32,
+    pub restitution: f32,
+    pub friction: f32,
+    pub is_sensor: bool,
+}
+
+impl RigidBody {
+    pub fn from_descriptor(descriptor: RigidBodyDescriptor, transform: &Transform) -> Self {
+        let body_type = match descriptor.body_type {
+            RigidBodyType::Dynamic if !descriptor.shape.is_convex() => {
+                warn!("Dynamic rigid bodies can't use a triangle mesh collider! Making it static.");
+                RigidBodyType::Static
+            }
+            x => x,
+        };
+
+        let mut rigid_body = Self {
+            body_type,
+            local_aabb: descriptor.shape.local_aabb(),
+            shape: descriptor.shape,
+            mass: descriptor.mass,
+            inverse_mass: 0.0,
+            position: transform.position,
+            rotation: transform.rotation,
+            linear_velocity: descriptor.linear_velocity,
+            angular_velocity: descriptor.angular_velocity,
+            gravity_scale: descriptor.gravity_scale,
+            linear_damping: descriptor.linear_damping,
+            angular_damping: descriptor.angular_damping,
+            restitution: descriptor.restitution,
+            friction: descriptor.friction,
+            is_sensor: descriptor.is_sensor,
+        };
+        rigid_body.update_inverse_mass();
+
+        rigid_body
+    }
+
+    pub fn apply_change(&mut self, change: RigidBodyChange) {
+        if let Some(body_type) = change.body_type {
+            if body_type == RigidBodyType::Dynamic && !self.shape.is_convex() {
+                warn!("Dynamic rigid bodies can't use a triangle mesh collider! Ignoring body type change.");
+            } else {
+                self.body_type = body_type;
+                self.update_inverse_mass();
+            }
+        }
+
+        if let Some(mode) = change.linear_velocity {
+            match mode {
+                Mode::Overwrite(x) => self.linear_velocity = x,
+                Mode::Offset(x) | Mode::OffsetViewAligned(x) => self.linear_velocity += x,
+            }
+        }
+
+        if let Some(mode) = change.angular_velocity {
+            match mode {
+                Mode::Overwrite(x) => self.angular_velocity = x,
+                Mode::Offset(x) | Mode::OffsetViewAligned(x) => self.angular_velocity += x,
+            }
+        }
+
+        if let Some(impulse) = change.impulse {
+            self.apply_impulse(impulse);
+        }
+
+        if let Some(gravity_scale) = change.gravity_scale {
+            self.gravity_scale = gravity_scale;
+        }
+    }
+
+    /// Changes the momentum of a dynamic [RigidBody].
+    pub fn apply_impulse(&mut self, impulse: Vector3<f32>) {
+        self.linear_velocity += impulse * self.inverse_mass;
+    }
+
+    /// Advances position and rotation by the current velocities.
+    /// `gravity` is only applied to dynamic [RigidBody]s.
+    pub fn integrate(&mut self, gravity: Vector3<f32>, delta_time: f32) {
+        match self.body_type {
+            RigidBodyType::Static => return,
+            RigidBodyType::Dynamic => {
+                self.linear_velocity += gravity * self.gravity_scale * delta_time;
+                self.linear_velocity /= 1.0 + self.linear_damping * delta_time;
+                self.angular_velocity /= 1.0 + self.angular_damping * delta_time;
+            }
+            RigidBodyType::Kinematic => (),
+        }
+
+        self.position += self.linear_velocity * delta_time;
+
+        if !self.angular_velocity.is_zero() {
+            let spin = Quaternion::from_sv(0.0, self.angular_velocity * (0.5 * delta_time));
+            self.rotation = (self.rotation + spin * self.rotation).normalize();
+        }
+    }
+
+    /// Returns the point of the [ColliderShape] furthest into `direction`,
+    /// in world space.
+    pub fn support(&self, direction: Vector3<f32>) -> Vector3<f32> {
+        let local_direction = self.rotation.invert().rotate_vector(direction);
+
+        self.position
+            + self
+                .rotation
+                .rotate_vector(self.shape.support(local_direction))
+    }
+
+    /// Transforms a point from the local space of the [RigidBody] into
+    /// world space.
+    pub fn to_world(&self, point: Vector3<f32>) -> Vector3<f32> {
+        self.position + self.rotation.rotate_vector(point)
+    }
+
+    pub fn world_aabb(&self) -> Aabb {
+        match self.shape {
+            // Spheres look the same from any angle
+            ColliderShape::Sphere { .. } => Aabb::new(
+                self.local_aabb.min + self.position,
+                self.local_aabb.max + self.position,
+            ),
+            _ => self.local_aabb.transform(&self.matrix()),
+        }
+    }
+
+    pub fn matrix(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(self.position) * Matrix4::from(self.rotation)
+    }
+
+    pub fn set_mass(&mut self, mass: f32) {
+        self.mass = mass;
+        self.update_inverse_mass();
+    }
+
+    /// Non-dynamic [RigidBody]s have an infinite mass.
+    fn update_inverse_mass(&mut self) {
+        self.inverse_mass = if self.body_type == RigidBodyType::Dynamic && self.mass > 0.0 {
+            1.0 / self.mass
+        } else {
+            0.0
+        };
+    }
+
+    /// Mass in kilograms, infinite for non-dynamic [RigidBody]s.
+    pub fn mass(&self) -> f32 {
+        if self.inverse_mass > 0.0 {
+            1.0 / self.inverse_mass
+        } else {
+            f32::INFINITY
+        }
+    }
+
+    pub fn inverse_mass(&self) -> f32 {
+        self.inverse_mass
+    }
+
+    pub fn body_type(&self) -> RigidBodyType {
+        self.body_type
+    }
+
+    pub fn shape(&self) -> &ColliderShape {
+        &self.shape
+    }
+
+    pub fn is_dynamic(&self) -> bool {
+        self.body_type == RigidBodyType::Dynamic
+    }
+}
@@ -0,0 +1,480 @@
+use cgmath::{InnerSpace, Quaternion, Vector3};
+use hashbrown::{HashMap, HashSet};
+
+use crate::game::ElementUlid;
+
+use super::{collide, Aabb, CollisionEvent, Contact, RigidBody};
+
+/// Penetration that is tolerated before positions get corrected.
+/// Prevents jitter of resting [RigidBody]s.
+const PENETRATION_SLOP: f32 = 0.001;
+/// Fraction of the penetration corrected per step.
+const POSITION_CORRECTION: f32 = 0.8;
+/// Below this approaching speed, collisions don't bounce.
+/// Prevents resting [RigidBody]s from bouncing forever.
+const RESTITUTION_THRESHOLD: f32 = 1.0;
+
+/// Simulates all [RigidBody]s of a [World](crate::game::World).
+///
+/// Each [RigidBody] is owned by an [Element](crate::game::Element)
+/// and identified by it's [ElementUlid].
+#[derive(Debug)]
+pub struct PhysicsWorld {
+    bodies: HashMap<ElementUlid, RigidBody>,
+    gravity: Vector3<f32>,
+    solver_iterations: u32,
+    /// Pairs touching in the last step, in [ordered](ordered_pair) form
+    active_pairs: HashSet<(ElementUlid, ElementUlid)>,
+}
+
+impl Default for PhysicsWorld {
+    fn default() -> Self {
+        Self {
+            bodies: HashMap::new(),
+            gravity: Vector3::new(0.0, -9.81, 0.0),
+            solver_iterations: 4,
+            active_pairs: HashSet::new(),
+        }
+    }
+}
+
+impl PhysicsWorld {
+    pub fn insert(&mut self, element_ulid: ElementUlid, rigid_body: RigidBody) {
+        self.bodies.insert(element_ulid, rigid_body);
+    }
+
+    /// Removes a [RigidBody].
+    /// No [CollisionEvent::Ended] will be reported for it.
+    pub fn remove(&mut self, element_ulid: &ElementUlid) -> Option<RigidBody> {
+        self.active_pairs
+            .retain(|(a, b)| a != element_ulid && b != element_ulid);
+
+        self.bodies.remove(element_ulid)
+    }
+
+    pub fn get(&self, element_ulid: &ElementUlid) -> Option<&RigidBody> {
+        self.bodies.get(element_ulid)
+    }
+
+    pub fn get_mut(&mut self, element_ulid: &ElementUlid) -> Option<&mut RigidBody> {
+        self.bodies.get_mut(element_ulid)
+    }
+
+    pub fn bodies(&self) -> impl Iterator<Item = (&ElementUlid, &RigidBody)> {
+        self.bodies.iter()
+    }
+
+    /// Teleports a [RigidBody], keeping it's velocities.
+    pub fn set_pose(
+        &mut self,
+        element_ulid: &ElementUlid,
+        position: Vector3<f32>,
+        rotation: Quaternion<f32>,
+    ) {
+        if let Some(rigid_body) = self.bodies.get_mut(element_ulid) {
+            rigid_body.position = position;
+            rigid_body.rotation = rotation;
+        }
+    }
+
+    pub fn gravity(&self) -> Vector3<f32> {
+        self.gravity
+    }
+
+    pub fn set_gravity(&mut self, gravity: Vector3<f32>) {
+        self.gravity = gravity;
+    }
+
+    /// Number of velocity iterations per step.
+    /// More iterations are more stable for stacked [RigidBody]s, but
+    /// slower.
+    pub fn set_solver_iterations(&mut self, solver_iterations: u32) {
+        self.solver_iterations = solver_iterations.max(1);
+    }
+
+    /// Advances the simulation by `delta_time` seconds.
+    ///
+    /// Returns each [CollisionEvent] together with the [ElementUlid] it
+    /// should be delivered to.
+    pub fn step(&mut self, delta_time: f32) -> Vec<(ElementUlid, CollisionEvent)> {
+        if delta_time <= 0.0 {
+            return Vec::new();
+        }
+
+        for rigid_body in self.bodies.values_mut() {
+            rigid_body.integrate(self.gravity, delta_time);
+        }
+
+        let mut contacts = Vec::new();
+        let mut touching = HashSet::new();
+        for (a, b) in self.broadphase() {
+            let (body_a, body_b) = (&self.bodies[&a], &self.bodies[&b]);
+
+            let pair_contacts = collide(body_a, body_b);
+            if pair_contacts.is_empty() {
+                continue;
+            }
+
+            touching.insert(ordered_pair(a, b));
+            if !body_a.is_sensor && !body_b.is_sensor {
+                contacts.extend(pair_contacts.into_iter().map(|x| (a, b, x)));
+            } else if !self.active_pairs.contains(&ordered_pair(a, b)) {
+                // Sensors still need a contact for the event
+                contacts.push((a, b, pair_contacts[0]));
+            }
+        }
+
+        let events = self.collision_events(&contacts, touching);
+
+        contacts.retain(|(a, b, _)| !self.bodies[a].is_sensor && !self.bodies[b].is_sensor);
+        for (a, b, contact) in &contacts {
+            self.correct_position(a, b, contact);
+        }
+        for _ in 0..self.solver_iterations {
+            for (a, b, contact) in &contacts {
+                self.resolve_velocity(a, b, contact);
+            }
+        }
+
+        events
+    }
+
+    /// Sweep and prune along the X-axis.
+    /// Returns all pairs with overlapping [Aabb]s, where at least one
+    /// [RigidBody] is dynamic or a sensor.
+    fn broadphase(&self) -> Vec<(ElementUlid, ElementUlid)> {
+        let mut entries: Vec<(ElementUlid, Aabb, bool)> = self
+            .bodies
+            .iter()
+            .map(|(ulid, rigid_body)| {
+                (
+                    *ulid,
+                    rigid_body.world_aabb(),
+                    rigid_body.is_dynamic() || rigid_body.is_sensor,
+                )
+            })
+            .collect();
+        entries.sort_by(|a, b| a.1.min.x.total_cmp(&b.1.min.x));
+
+        let mut pairs = Vec::new();
+        for (i, (ulid_a, aabb_a, active_a)) in entries.iter().enumerate() {
+            for (ulid_b, aabb_b, active_b) in &entries[i + 1..] {
+                if aabb_b.min.x > aabb_a.max.x {
+                    break;
+                }
+
+                if (*active_a || *active_b) && aabb_a.intersects(aabb_b) {
+                    pairs.push((*ulid_a, *ulid_b));
+                }
+            }
+        }
+
+        pairs
+    }
+
+    /// Compares the touching pairs with the last step.
+    fn collision_events(
+        &mut self,
+        contacts: &[(ElementUlid, ElementUlid, Contact)],
+        touching: HashSet<(ElementUlid, ElementUlid)>,
+    ) -> Vec<(ElementUlid, CollisionEvent)> {
+        let mut events = Vec::new();
+        let mut started = HashSet::new();
+
+        for (a, b, contact) in contacts {
+            // Only the first contact of each pair is reported
+            let pair = ordered_pair(*a, *b);
+            if self.active_pairs.contains(&pair) || !started.insert(pair) {
+                continue;
+            }
+
+            events.push((
+                *a,
+                CollisionEvent::Started {
+                    other: *b,
+                    contact: *contact,
+                },
+            ));
+            events.push((
+                *b,
+                CollisionEvent::Started {
+                    other: *a,
+                    contact: contact.flipped(),
+                },
+            ));
+        }
+
+        for (a, b) in self.active_pairs.difference(&touching) {
+            events.push((*a, CollisionEvent::Ended { other: *b }));
+            events.push((*b, CollisionEvent::Ended { other: *a }));
+        }
+
+        self.active_pairs = touching;
+        events
+    }
+
+    fn correct_position(&mut self, a: &ElementUlid, b: &ElementUlid, contact: &Contact) {
+        let inverse_mass_a = self.bodies[a].inverse_mass();
+        let inverse_mass_b = self.bodies[b].inverse_mass();
+        let inverse_mass_sum = inverse_mass_a + inverse_mass_b;
+        if inverse_mass_sum <= 0.0 {
+            return;
+        }
+
+        let correction = (contact.depth - PENETRATION_SLOP).max(0.0) * POSITION_CORRECTION
+            / inverse_mass_sum
+            * contact.normal;
+
+        self.bodies.get_mut(a).unwrap().position -= correction * inverse_mass_a;
+        self.bodies.get_mut(b).unwrap().position += correction * inverse_mass_b;
+    }
+
+    fn resolve_velocity(&mut self, a: &ElementUlid, b: &ElementUlid, contact: &Contact) {
+        let (body_a, body_b) = (&self.bodies[a], &self.bodies[b]);
+        let inverse_mass_a = body_a.inverse_mass();
+        let inverse_mass_b = body_b.inverse_mass();
+        let inverse_mass_sum = inverse_mass_a + inverse_mass_b;
+        if inverse_mass_sum <= 0.0 {
+            return;
+        }
+
+        let relative_velocity = body_b.linear_velocity - body_a.linear_velocity;
+        let normal_velocity = relative_velocity.dot(contact.normal);
+        if normal_velocity >= 0.0 {
+            // Already separating
+            return;
+        }
+
+        let restitution = if -normal_velocity > RESTITUTION_THRESHOLD {
+            body_a.restitution.max(body_b.restitution)
+        } else {
+            0.0
+        };
+        let normal_impulse = -(1.0 + restitution) * normal_velocity / inverse_mass_sum;
+        let mut impulse = contact.normal * normal_impulse;
+
+        // Coulomb friction along the sliding direction
+        let tangent_velocity = relative_velocity - contact.normal * normal_velocity;
+        let tangent_speed = tangent_velocity.magnitude();
+        if tangent_speed > f32::EPSILON {
+            let friction = (body_a.friction * body_b.friction).sqrt();
+            let tangent_impulse = (tangent_speed / inverse_mass_sum).min(normal_impulse * friction);
+            impulse -= tangent_velocity / tangent_speed * tangent_impulse;
+        }
+
+        self.bodies.get_mut(a).unwrap().linear_velocity -= impulse * inverse_mass_a;
+        self.bodies.get_mut(b).unwrap().linear_velocity += impulse * inverse_mass_b;
+    }
+}
+
+/// Orders a pair, so `(a, b)` and `(b, a)` are the same.
+fn ordered_pair(a: ElementUlid, b: ElementUlid) -> (ElementUlid, ElementUlid) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Zero;
+
+    use crate::{
+        game::Transform,
+        physics::{ColliderShape, RigidBodyDescriptor, RigidBodyType},
+    };
+
+    use super::*;
+
+    const DELTA_TIME: f32 = 0.01;
+
+    fn sphere(
+        descriptor: RigidBodyDescriptor,
+        position: Vector3<f32>,
+        linear_velocity: Vector3<f32>,
+    ) -> RigidBody {
+        RigidBody::from_descriptor(
+            RigidBodyDescriptor {
+                shape: ColliderShape::Sphere { radius: 1.0 },
+                linear_velocity,
+                ..descriptor
+            },
+            &Transform {
+                position,
+                ..Default::default()
+            },
+        )
+    }
+
+    fn world_without_gravity(bodies: [RigidBody; 2]) -> (PhysicsWorld, [ElementUlid; 2]) {
+        let mut world = PhysicsWorld::default();
+        world.set_gravity(Vector3::zero());
+
+        let ulids = [ElementUlid::from(1), ElementUlid::from(2)];
+        for (ulid, rigid_body) in ulids.into_iter().zip(bodies) {
+            world.insert(ulid, rigid_body);
+        }
+
+        (world, ulids)
+    }
+
+    fn assert_velocity(world: &PhysicsWorld, ulid: &ElementUlid, velocity: Vector3<f32>) {
+        let actual = world.get(ulid).unwrap().linear_velocity;
+        assert!(
+            (actual - velocity).magnitude() < 0.0001,
+            "Velocity {:?} isn't {:?}",
+            actual,
+            velocity
+        );
+    }
+
+    #[test]
+    fn elastic_collision_swaps_velocities() {
+        let descriptor = RigidBodyDescriptor {
+            restitution: 1.0,
+            ..Default::default()
+        };
+        let (mut world, [a, b]) = world_without_gravity([
+            sphere(
+                descriptor.clone(),
+                Vector3::zero(),
+                Vector3::new(2.0, 0.0, 0.0),
+            ),
+            sphere(
+                descriptor,
+                Vector3::new(1.9, 0.0, 0.0),
+                Vector3::new(-2.0, 0.0, 0.0),
+            ),
+        ]);
+
+        world.step(DELTA_TIME);
+
+        assert_velocity(&world, &a, Vector3::new(-2.0, 0.0, 0.0));
+        assert_velocity(&world, &b, Vector3::new(2.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn inelastic_collision_conserves_momentum() {
+        let (mut world, [a, b]) = world_without_gravity([
+            sphere(
+                RigidBodyDescriptor::default(),
+                Vector3::zero(),
+                Vector3::new(3.0, 0.0, 0.0),
+            ),
+            sphere(
+                RigidBodyDescriptor {
+                    mass: 2.0,
+                    ..Default::default()
+                },
+                Vector3::new(1.9, 0.0, 0.0),
+                Vector3::zero(),
+            ),
+        ]);
+
+        world.step(DELTA_TIME);
+
+        assert_velocity(&world, &a, Vector3::new(1.0, 0.0, 0.0));
+        assert_velocity(&world, &b, Vector3::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn static_bodies_stop_slow_impacts_without_moving() {
+        let (mut world, [ground, ball]) = world_without_gravity([
+            sphere(
+                RigidBodyDescriptor {
+                    body_type: RigidBodyType::Static,
+                    ..Default::default()
+                },
+                Vector3::zero(),
+                Vector3::zero(),
+            ),
+            sphere(
+                RigidBodyDescriptor {
+                    restitution: 1.0,
+                    ..Default::default()
+                },
+                Vector3::new(0.0, 1.95, 0.0),
+                Vector3::new(0.0, -0.5, 0.0),
+            ),
+        ]);
+
+        world.step(DELTA_TIME);
+
+        // Below the restitution threshold, thus no bounce
+        assert_velocity(&world, &ball, Vector3::zero());
+        assert_velocity(&world, &ground, Vector3::zero());
+        assert_eq!(world.get(&ground).unwrap().position, Vector3::zero());
+        assert!(world.get(&ball).unwrap().position.y > 1.945);
+    }
+
+    #[test]
+    fn friction_slows_sliding() {
+        let (mut world, [ground, ball]) = world_without_gravity([
+            RigidBody::from_descriptor(
+                RigidBodyDescriptor {
+                    body_type: RigidBodyType::Static,
+                    shape: ColliderShape::Box {
+                        half_extents: Vector3::new(10.0, 1.0, 10.0),
+                    },
+                    friction: 1.0,
+                    ..Default::default()
+                },
+                &Transform::default(),
+            ),
+            sphere(
+                RigidBodyDescriptor {
+                    friction: 1.0,
+                    ..Default::default()
+                },
+                Vector3::new(0.0, 1.95, 0.0),
+                Vector3::new(2.0, -0.5, 0.0),
+            ),
+        ]);
+
+        world.step(DELTA_TIME);
+
+        // The normal impulse of 0.5 limits the friction impulse
+        assert_velocity(&world, &ball, Vector3::new(1.5, 0.0, 0.0));
+        assert_velocity(&world, &ground, Vector3::zero());
+    }
+
+    #[test]
+    fn collisions_start_and_end_once() {
+        let (mut world, [a, b]) = world_without_gravity([
+            sphere(
+                RigidBodyDescriptor::default(),
+                Vector3::zero(),
+                Vector3::zero(),
+            ),
+            sphere(
+                RigidBodyDescriptor {
+                    is_sensor: true,
+                    ..Default::default()
+                },
+                Vector3::new(1.5, 0.0, 0.0),
+                Vector3::zero(),
+            ),
+        ]);
+
+        let events = world.step(DELTA_TIME);
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().any(|(ulid, event)| *ulid == a
+            && matches!(event, CollisionEvent::Started { other, .. } if *other == b)));
+
+        // Sensors don't push
+        assert!(world.step(DELTA_TIME).is_empty());
+        assert_velocity(&world, &a, Vector3::zero());
+
+        world.set_pose(
+            &b,
+            Vector3::new(5.0, 0.0, 0.0),
+            Quaternion::new(1.0, 0.0, 0.0, 0.0),
+        );
+        let events = world.step(DELTA_TIME);
+        assert_eq!(events.len(), 2);
+        assert!(events
+            .iter()
+            .all(|(_, event)| matches!(event, CollisionEvent::Ended { .. })));
+    }
+}
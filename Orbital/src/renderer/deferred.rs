@@ -0,0 +1,504 @@
+use cgmath::Vector2;
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingResource, BindingType, BlendState, Buffer, BufferBindingType,
+    Color, ColorTargetState, ColorWrites, CommandEncoderDescriptor, Device, FragmentState, LoadOp,
+    MultisampleState, Operations, PipelineCompilationOptions, PipelineLayoutDescriptor,
+    PrimitiveState, Queue, RenderPassColorAttachment, RenderPassDepthStencilAttachment,
+    RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor, ShaderStages, StoreOp,
+    TextureFormat, TextureSampleType, TextureView, TextureViewDimension, VertexState,
+};
+
+use crate::{
+    log::warn,
+    resources::{
+        descriptors::{AlphaMode, EnvironmentDescriptor, PipelineDescriptor},
+        realizations::{Camera, Environment, Light, Model, Shader},
+    },
+};
+
+use super::{
+    draw::Draw, GBuffer, GpuCulling, LightClusters, LightStorage, PostProcessing, RenderStats,
+    Renderer, RendererSettings, Skybox,
+};
+
+/// A deferred alternative to the [StandardRenderer](super::StandardRenderer).
+///
+/// Rendering happens in multiple passes:
+/// 1. All opaque and masked [Model]s are drawn into the [GBuffer], using
+///    the G-buffer variant of their
+///    [Pipeline](crate::resources::realizations::Pipeline) (check
+///    [PipelineDescriptor::deferred]).
+/// 2. [LightClusters] assigns all [Light]s to the clusters they reach.
+/// 3. Each covered pixel is shaded once, only considering the [Light]s of
+///    it's cluster.
+///    This makes many [Light]s with a limited range cheap.  
+///    ⚠️ Only up to [LightClusters::MAX_LIGHTS_PER_CLUSTER] [Light]s
+///    ⚠️ reach into a single cluster, others are ignored!
+/// 4. The skybox is drawn behind everything, using the depth of the
+///    [GBuffer].
+/// 5. Blended [Model]s are drawn on top in a forward pass, sorted back to
+///    front like in the [StandardRenderer](super::StandardRenderer)
+///    (check [AlphaMode::Blend]).
+///    They are shaded with all [Light]s, not just the ones of their
+///    cluster.
+/// 6. The result is post processed onto the target, check
+///    [PostProcessing].
+///
+/// ⚠️ Custom shaders need to provide the G-buffer variant entrypoint.  
+/// ⚠️ MSAA isn't supported, use
+/// ⚠️ [PostProcessingSettings::fxaa](super::PostProcessingSettings::fxaa)
+/// ⚠️ instead.
+pub struct DeferredRenderer {
+    g_buffer: GBuffer,
+    light_storage: LightStorage,
+    light_clusters: LightClusters,
+    /// Used in case the [World](crate::game::World) has no [Environment].
+    fallback_environment: Environment,
+    skybox: Skybox,
+    post_processing: PostProcessing,
+    lighting_pipeline: RenderPipeline,
+    lighting_bind_group_layout: BindGroupLayout,
+    lighting_bind_group: BindGroup,
+    /// Instances of merged draws, copied one after another.
+    /// Made once draws get merged.
+    batch_instance_buffer: Option<Buffer>,
+    /// Only set if enabled via [RendererSettings::gpu_culling] and
+    /// supported
+    gpu_culling: Option<GpuCulling>,
+    stats: RenderStats,
+}
+
+impl Renderer for DeferredRenderer {
+    fn new(
+        surface_texture_format: TextureFormat,
+        resolution: Vector2<u32>,
+        device: &Device,
+        queue: &Queue,
+    ) -> Self {
+        let g_buffer = GBuffer::new(resolution, device, queue);
+        let light_clusters = LightClusters::new(device, queue);
+
+        let lighting_bind_group_layout =
+            device.create_bind_group_layout(&Self::lighting_bind_group_layout_descriptor());
+        let lighting_pipeline =
+            Self::make_lighting_pipeline(&lighting_bind_group_layout, device, queue);
+        let lighting_bind_group = Self::make_lighting_bind_group(
+            &lighting_bind_group_layout,
+            &g_buffer,
+            &light_clusters,
+            device,
+        );
+
+        Self {
+            g_buffer,
+            light_storage: LightStorage::new(device, queue),
+            light_clusters,
+            fallback_environment: Environment::from_descriptor(
+                EnvironmentDescriptor::default(),
+                device,
+                queue,
+            )
+            .expect("Fallback environment realization failed!"),
+            skybox: Skybox::new(PostProcessing::HDR_FORMAT, 1, device, queue),
+            post_processing: PostProcessing::new(surface_texture_format, resolution, device, queue),
+            lighting_pipeline,
+            lighting_bind_group_layout,
+            lighting_bind_group,
+            batch_instance_buffer: None,
+            gpu_culling: None,
+            stats: RenderStats::default(),
+        }
+    }
+
+    fn change_surface_texture_format(
+        &mut self,
+        surface_texture_format: TextureFormat,
+        device: &Device,
+        _queue: &Queue,
+    ) {
+        // Everything else renders into the HDR texture, which keeps its
+        // format
+        self.post_processing
+            .change_surface_texture_format(surface_texture_format, device);
+    }
+
+    fn change_resolution(&mut self, resolution: Vector2<u32>, device: &Device, queue: &Queue) {
+        // All render targets need to match the new size
+        self.g_buffer = GBuffer::new(resolution, device, queue);
+        self.post_processing
+            .change_resolution(resolution, device, queue);
+
+        self.lighting_bind_group = Self::make_lighting_bind_group(
+            &self.lighting_bind_group_layout,
+            &self.g_buffer,
+            &self.light_clusters,
+            device,
+        );
+    }
+
+    fn change_sample_count(&mut self, sample_count: u32, _device: &Device, _queue: &Queue) {
+        if sample_count > 1 {
+            warn!("MSAA isn't supported by the deferred renderer, use FXAA instead!");
+        }
+    }
+
+    fn change_settings(&mut self, settings: &RendererSettings, device: &Device, queue: &Queue) {
+        if !settings.gpu_culling {
+            self.gpu_culling = None;
+        } else if self.gpu_culling.is_none() {
+            if GpuCulling::is_supported(device) {
+                self.gpu_culling = Some(GpuCulling::new(device, queue));
+            } else {
+                warn!("GPU culling isn't supported by this device, falling back to CPU culling!");
+            }
+        }
+
+        self.post_processing
+            .change_settings(&settings.post_processing, device, queue);
+    }
+
+    fn is_gpu_culling(&self) -> bool {
+        self.gpu_culling.is_some()
+    }
+
+    fn update(&mut self, _delta_time: f64) {}
+
+    fn render(
+        &mut self,
+        target_view: &TextureView,
+        device: &Device,
+        queue: &Queue,
+        models: &[&Model],
+        lights: &[&Light],
+        environment: Option<&Environment>,
+        camera: &Camera,
+    ) {
+        let draw_skybox = environment.is_some_and(|x| x.descriptor().draw_skybox);
+        let environment = environment.unwrap_or(&self.fallback_environment);
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor { label: None });
+
+        self.light_storage.update(
+            lights,
+            models,
+            camera,
+            &PostProcessing::HDR_FORMAT,
+            1,
+            &mut encoder,
+            device,
+            queue,
+        );
+        self.light_clusters.update(
+            camera,
+            self.g_buffer.resolution(),
+            &self.light_storage,
+            &mut encoder,
+            device,
+            queue,
+        );
+
+        if draw_skybox {
+            self.skybox.update(camera, environment, device, queue);
+        }
+
+        // Blended models can't be stored in the G-buffer, thus they keep
+        // their forward variant and are drawn after the lighting pass
+        let draws = Draw::from_models(
+            models,
+            camera,
+            &PostProcessing::HDR_FORMAT,
+            1,
+            |pipeline_descriptor: &PipelineDescriptor| {
+                if pipeline_descriptor.alpha_mode == AlphaMode::Blend {
+                    pipeline_descriptor.clone()
+                } else {
+                    pipeline_descriptor.deferred_variant()
+                }
+            },
+            self.gpu_culling.is_some(),
+            device,
+            queue,
+        );
+
+        self.stats = RenderStats::default();
+        let mut draws = Draw::sort_and_merge(draws, &mut self.stats);
+        Draw::copy_batched_instances(
+            &mut draws,
+            &mut self.batch_instance_buffer,
+            &mut encoder,
+            device,
+        );
+        if let Some(gpu_culling) = &mut self.gpu_culling {
+            Draw::cull_on_gpu(&mut draws, gpu_culling, camera, &mut encoder, device, queue);
+        }
+        let (opaque_draws, transparent_draws) = draws.split_at(Draw::first_transparent(&draws));
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("G-Buffer Pass"),
+                color_attachments: &self.g_buffer.color_attachments(),
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                    view: self.g_buffer.depth().view(),
+                    depth_ops: Some(Operations {
+                        load: LoadOp::Clear(1.0),
+                        store: StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            Draw::record(
+                opaque_draws,
+                &mut render_pass,
+                &[camera.bind_group()],
+                self.batch_instance_buffer.as_ref(),
+                self.gpu_culling.as_ref(),
+                &mut self.stats,
+            );
+        }
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Lighting Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: self.post_processing.hdr_texture().view(),
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::BLACK),
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            render_pass.set_pipeline(&self.lighting_pipeline);
+            render_pass.set_bind_group(0, &self.lighting_bind_group, &[]);
+            render_pass.set_bind_group(1, camera.bind_group(), &[]);
+            render_pass.set_bind_group(2, self.light_storage.bind_group(), &[]);
+            render_pass.set_bind_group(3, environment.bind_group(), &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        // Only reads the depth, so that the skybox stays behind everything
+        if draw_skybox {
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Skybox Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: self.post_processing.hdr_texture().view(),
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Load,
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                    view: self.g_buffer.depth().view(),
+                    depth_ops: Some(Operations {
+                        load: LoadOp::Load,
+                        store: StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            self.skybox.draw(&mut render_pass);
+        }
+
+        // Transparent draws don't write depth, but are hidden behind
+        // whatever is in the G-buffer
+        if !transparent_draws.is_empty() {
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Transparent Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: self.post_processing.hdr_texture().view(),
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Load,
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                    view: self.g_buffer.depth().view(),
+                    depth_ops: Some(Operations {
+                        load: LoadOp::Load,
+                        store: StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            Draw::record(
+                transparent_draws,
+                &mut render_pass,
+                &[
+                    camera.bind_group(),
+                    self.light_storage.bind_group(),
+                    environment.bind_group(),
+                ],
+                self.batch_instance_buffer.as_ref(),
+                self.gpu_culling.as_ref(),
+                &mut self.stats,
+            );
+        }
+
+        self.post_processing.render(target_view, &mut encoder);
+
+        queue.submit(Some(encoder.finish()));
+    }
+
+    fn stats(&self) -> RenderStats {
+        self.stats
+    }
+}
+
+impl DeferredRenderer {
+    /// The [GBuffer] and [LightClusters], bound as group 0 of the
+    /// lighting pass.
+    fn lighting_bind_group_layout_descriptor() -> BindGroupLayoutDescriptor<'static> {
+        const fn texture_entry(
+            binding: u32,
+            sample_type: TextureSampleType,
+        ) -> BindGroupLayoutEntry {
+            BindGroupLayoutEntry {
+                binding,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture {
+                    sample_type,
+                    view_dimension: TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }
+        }
+        const COLOR: TextureSampleType = TextureSampleType::Float { filterable: false };
+        const ENTRIES: &[BindGroupLayoutEntry] = &[
+            texture_entry(0, COLOR),
+            texture_entry(1, COLOR),
+            texture_entry(2, COLOR),
+            texture_entry(3, COLOR),
+            // Bound as plain float, as loading depth textures isn't
+            // supported everywhere
+            texture_entry(4, COLOR),
+            // Cluster uniform
+            BindGroupLayoutEntry {
+                binding: 5,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            // Clusters
+            BindGroupLayoutEntry {
+                binding: 6,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+        ];
+
+        BindGroupLayoutDescriptor {
+            label: Some("Deferred Lighting"),
+            entries: ENTRIES,
+        }
+    }
+
+    fn make_lighting_bind_group(
+        layout: &BindGroupLayout,
+        g_buffer: &GBuffer,
+        light_clusters: &LightClusters,
+        device: &Device,
+    ) -> BindGroup {
+        let mut entries = g_buffer
+            .color_targets()
+            .into_iter()
+            .chain([g_buffer.depth()])
+            .enumerate()
+            .map(|(i, texture)| BindGroupEntry {
+                binding: i as u32,
+                resource: BindingResource::TextureView(texture.view()),
+            })
+            .collect::<Vec<_>>();
+        entries.push(BindGroupEntry {
+            binding: 5,
+            resource: light_clusters.uniform_buffer().as_entire_binding(),
+        });
+        entries.push(BindGroupEntry {
+            binding: 6,
+            resource: light_clusters.cluster_buffer().as_entire_binding(),
+        });
+
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Deferred Lighting Bind Group"),
+            layout,
+            entries: &entries,
+        })
+    }
+
+    fn make_lighting_pipeline(
+        bind_group_layout: &BindGroupLayout,
+        device: &Device,
+        queue: &Queue,
+    ) -> RenderPipeline {
+        let shader = Shader::from_descriptor(
+            concat!(
+                include_str!("../resources/descriptors/shader/lighting.wgsl"),
+                include_str!("../resources/descriptors/shader/deferred_lighting.wgsl")
+            ),
+            device,
+            queue,
+        )
+        .expect("Deferred lighting shader realization failed!");
+
+        let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Deferred Lighting Pipeline Layout"),
+            bind_group_layouts: &[
+                bind_group_layout,
+                &device.create_bind_group_layout(&Camera::bind_group_layout_descriptor()),
+                &device.create_bind_group_layout(&Light::bind_group_layout_descriptor()),
+                &device.create_bind_group_layout(&Environment::bind_group_layout_descriptor()),
+            ],
+            push_constant_ranges: &[],
+        });
+
+        device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Deferred Lighting Pipeline"),
+            layout: Some(&layout),
+            vertex: VertexState {
+                module: shader.shader_module(),
+                entry_point: "entrypoint_vertex",
+                buffers: &[],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: Some(FragmentState {
+                module: shader.shader_module(),
+                entry_point: "entrypoint_fragment",
+                targets: &[Some(ColorTargetState {
+                    format: PostProcessing::HDR_FORMAT,
+                    blend: Some(BlendState::REPLACE),
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: PipelineCompilationOptions::default(),
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+        })
+    }
+}
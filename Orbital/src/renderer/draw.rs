@@ -0,0 +1,440 @@
+use std::{cmp::Ordering, ptr};
+
+use wgpu::{
+    BindGroup, Buffer, BufferDescriptor, BufferUsages, CommandEncoder, Device, IndexFormat, Queue,
+    RenderPass, TextureFormat,
+};
+
+use crate::{
+    log::error,
+    resources::{
+        descriptors::PipelineDescriptor,
+        realizations::{Camera, Material, Mesh, Model, Pipeline},
+    },
+};
+
+use super::{GpuCulledDraw, GpuCulling, RenderStats};
+
+/// A single instanced draw call of a [Renderer](super::Renderer).
+///
+/// Shared by the [StandardRenderer](super::StandardRenderer) and the
+/// [DeferredRenderer](super::DeferredRenderer).
+pub(crate) struct Draw<'a> {
+    pub pipeline: &'a Pipeline,
+    /// Usually the [Material::bind_group], check
+    /// [Model::material_bind_group]
+    pub bind_group: &'a BindGroup,
+    pub mesh: &'a Mesh,
+    /// Only set for skinned [Model]s
+    pub skin_buffer: Option<&'a Buffer>,
+    /// Instance buffers and how many instances to draw from each.
+    /// Multiple if [Draw]s got merged.
+    pub instances: Vec<(&'a Buffer, u32)>,
+    /// Offset into the batch instance buffer or, if GPU culled, the
+    /// output of [GpuCulling], in instances.
+    /// Only set if [Draw]s got merged or GPU culled.
+    pub batch_offset: Option<u64>,
+    /// Instances are culled by [GpuCulling] instead of the
+    /// [World](crate::game::World)
+    pub gpu_culled: bool,
+    /// Index of the indirect arguments written by [GpuCulling]
+    pub indirect_index: Option<u64>,
+    /// Only set for blended [Model]s, check
+    /// [PipelineDescriptor::is_blended].
+    /// Squared distance to the [Camera], check
+    /// [Model::camera_distance_squared].
+    pub transparent_distance: Option<f32>,
+}
+
+impl<'a> Draw<'a> {
+    /// Makes a [Draw] for each visible level of detail of each [Model].
+    ///
+    /// `variant` turns the [PipelineDescriptor] of a [Model] into the
+    /// one to draw with, e.g. [PipelineDescriptor::deferred_variant].
+    /// Pipelines are realized for `sample_count` samples per pixel.
+    /// If `gpu_culling` is set, [Model]s which can be culled on the GPU
+    /// are drawn with all their instances, check [Draw::gpu_culled].
+    /// [Model]s failing to realize their [Material] or [Pipeline] are
+    /// skipped.
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_models(
+        models: &[&'a Model],
+        camera: &Camera,
+        surface_texture_format: &TextureFormat,
+        sample_count: u32,
+        variant: impl Fn(&PipelineDescriptor) -> PipelineDescriptor,
+        gpu_culling: bool,
+        device: &Device,
+        queue: &Queue,
+    ) -> Vec<Self> {
+        // Adding to a cache may move it's entries, thus everything is
+        // realized first and only referenced afterwards
+        let models = models
+            .iter()
+            .filter(|model| !model.visible_lods().is_empty())
+            .filter(|model| {
+                Self::model_material_and_pipeline(
+                    model,
+                    surface_texture_format,
+                    sample_count,
+                    &variant,
+                    device,
+                    queue,
+                )
+                .is_some()
+            })
+            .collect::<Vec<_>>();
+
+        let mut draws = Vec::new();
+        for model in models {
+            let Some((material, pipeline, blended)) = Self::model_material_and_pipeline(
+                model,
+                surface_texture_format,
+                sample_count,
+                &variant,
+                device,
+                queue,
+            ) else {
+                continue;
+            };
+            let bind_group = model.material_bind_group(material, pipeline, device);
+            let skin_buffer = model.mesh().skin_buffer().filter(|_| model.is_skinned());
+            let transparent_distance = blended.then(|| model.camera_distance_squared(camera));
+
+            if gpu_culling && Self::is_gpu_cullable(model) {
+                draws.push(Self {
+                    pipeline,
+                    bind_group,
+                    mesh: model.mesh(),
+                    skin_buffer,
+                    instances: vec![(model.instance_buffer(), model.instances().len() as u32)],
+                    batch_offset: None,
+                    gpu_culled: true,
+                    indirect_index: None,
+                    transparent_distance,
+                });
+                continue;
+            }
+
+            // Deformed models never have levels of detail
+            for (mesh, instance_buffer, instance_count) in model.visible_lods() {
+                draws.push(Self {
+                    pipeline,
+                    bind_group,
+                    mesh,
+                    skin_buffer,
+                    instances: vec![(instance_buffer, instance_count)],
+                    batch_offset: None,
+                    gpu_culled: false,
+                    indirect_index: None,
+                    transparent_distance,
+                });
+            }
+        }
+
+        draws
+    }
+
+    /// Returns the [Material] and [Pipeline] to draw the [Model] with and
+    /// if the [Pipeline] blends, check [PipelineDescriptor::is_blended].
+    /// Errors are logged and [None] is returned.
+    fn model_material_and_pipeline(
+        model: &Model,
+        surface_texture_format: &TextureFormat,
+        sample_count: u32,
+        variant: impl Fn(&PipelineDescriptor) -> PipelineDescriptor,
+        device: &Device,
+        queue: &Queue,
+    ) -> Option<(&'static Material, &'static Pipeline, bool)> {
+        let material = match model.material(surface_texture_format, sample_count, device, queue) {
+            Ok(material) => material,
+            Err(e) => {
+                error!("Material failure: {:#?}", e);
+                error!("Skipping model render!");
+                return None;
+            }
+        };
+
+        // Deformed models need the matching variant of the pipeline
+        let pipeline_descriptor =
+            variant(&model.pipeline_descriptor(material.pipeline_descriptor()));
+
+        match Pipeline::from_descriptor(
+            &pipeline_descriptor,
+            surface_texture_format,
+            sample_count,
+            device,
+            queue,
+        ) {
+            Ok(pipeline) => Some((material, pipeline, pipeline_descriptor.is_blended())),
+            Err(e) => {
+                error!("Pipeline in invalid state! Error: {:?}", e);
+                None
+            }
+        }
+    }
+
+    /// Checks if the instances of the [Model] can be culled by
+    /// [GpuCulling].
+    /// [Lod]s are selected on the CPU, deformations can move vertices
+    /// outside of the [Mesh::bounds].
+    ///
+    /// [Lod]: crate::resources::realizations::Lod
+    fn is_gpu_cullable(model: &Model) -> bool {
+        model.lods().is_empty()
+            && !model.is_skinned()
+            && !model.is_morphed()
+            && model.mesh().bounds().is_some()
+    }
+
+    fn sort_key(&self) -> (usize, usize, usize) {
+        (
+            self.pipeline as *const Pipeline as usize,
+            self.bind_group as *const BindGroup as usize,
+            // Shared by all meshes sharing their buffers
+            self.mesh.vertex_buffer() as *const Buffer as usize,
+        )
+    }
+
+    /// Sorts [Draw]s by pipeline, then material and then mesh to avoid
+    /// state changes and merges [Draw]s which can be drawn at once.
+    ///
+    /// Transparent [Draw]s come last, sorted back to front, check
+    /// [Draw::transparent_distance] and [Draw::first_transparent].
+    /// Blending depends on their order, thus they are never merged.
+    pub fn sort_and_merge(draws: Vec<Self>, stats: &mut RenderStats) -> Vec<Self> {
+        let (mut transparent_draws, mut draws): (Vec<_>, Vec<_>) = draws
+            .into_iter()
+            .partition(|x| x.transparent_distance.is_some());
+
+        draws.sort_by_key(Self::sort_key);
+
+        let mut merged_draws: Vec<Self> = Vec::with_capacity(draws.len());
+        for draw in draws {
+            match merged_draws.last_mut() {
+                Some(last) if last.can_merge(&draw) => {
+                    last.instances.extend(draw.instances);
+                    stats.merged_draws += 1;
+                }
+                _ => merged_draws.push(draw),
+            }
+        }
+
+        transparent_draws.sort_by(|a, b| {
+            b.transparent_distance
+                .partial_cmp(&a.transparent_distance)
+                .unwrap_or(Ordering::Equal)
+        });
+        merged_draws.extend(transparent_draws);
+
+        merged_draws
+    }
+
+    /// Index of the first transparent [Draw] after [Draw::sort_and_merge].
+    /// All [Draw]s before are opaque.
+    pub fn first_transparent(draws: &[Self]) -> usize {
+        draws.partition_point(|x| x.transparent_distance.is_none())
+    }
+
+    /// Checks if the other [Draw] only differs in it's instances.
+    fn can_merge(&self, other: &Self) -> bool {
+        ptr::eq(self.pipeline, other.pipeline)
+            && ptr::eq(self.bind_group, other.bind_group)
+            && self.gpu_culled == other.gpu_culled
+            && self.skin_buffer.is_none()
+            && other.skin_buffer.is_none()
+            && self.mesh.shares_buffers(other.mesh)
+    }
+
+    fn instance_count(&self) -> u32 {
+        self.instances.iter().map(|(_, x)| x).sum()
+    }
+
+    /// Culls the instances of all GPU culled [Draw]s and sets their
+    /// [Draw::indirect_index] and [Draw::batch_offset].
+    pub fn cull_on_gpu(
+        draws: &mut [Self],
+        gpu_culling: &mut GpuCulling,
+        camera: &Camera,
+        encoder: &mut CommandEncoder,
+        device: &Device,
+        queue: &Queue,
+    ) {
+        let (indices, gpu_culled_draws): (Vec<_>, Vec<_>) = draws
+            .iter()
+            .enumerate()
+            .filter(|(_, draw)| draw.gpu_culled)
+            .filter_map(|(index, draw)| {
+                Some((
+                    index,
+                    GpuCulledDraw {
+                        bounds: draw.mesh.bounds()?,
+                        index_count: draw.mesh.index_count(),
+                        instances: &draw.instances,
+                    },
+                ))
+            })
+            .unzip();
+
+        let first_outputs =
+            gpu_culling.cull(&gpu_culled_draws, &camera.frustum(), encoder, device, queue);
+
+        for (indirect_index, (index, first_output)) in
+            indices.into_iter().zip(first_outputs).enumerate()
+        {
+            draws[index].indirect_index = Some(indirect_index as u64);
+            draws[index].batch_offset = Some(first_output);
+        }
+    }
+
+    /// Copies the instances of all merged [Draw]s one after another into
+    /// the batch instance buffer and sets their [Draw::batch_offset].
+    pub fn copy_batched_instances(
+        draws: &mut [Self],
+        batch_instance_buffer: &mut Option<Buffer>,
+        encoder: &mut CommandEncoder,
+        device: &Device,
+    ) {
+        let required_size = Model::INSTANCE_SIZE
+            * draws
+                .iter()
+                .filter(|x| !x.gpu_culled && x.instances.len() > 1)
+                .map(|x| x.instance_count() as u64)
+                .sum::<u64>();
+        if required_size == 0 {
+            return;
+        }
+
+        let is_big_enough = batch_instance_buffer
+            .as_ref()
+            .is_some_and(|x| x.size() >= required_size);
+        if !is_big_enough {
+            *batch_instance_buffer = Some(device.create_buffer(&BufferDescriptor {
+                label: Some("Batch Instance Buffer"),
+                size: required_size.next_power_of_two(),
+                usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+        }
+        let Some(batch_instance_buffer) = batch_instance_buffer.as_ref() else {
+            return;
+        };
+
+        let mut offset = 0;
+        for draw in draws
+            .iter_mut()
+            .filter(|x| !x.gpu_culled && x.instances.len() > 1)
+        {
+            draw.batch_offset = Some(offset);
+
+            for (instance_buffer, instance_count) in &draw.instances {
+                encoder.copy_buffer_to_buffer(
+                    instance_buffer,
+                    0,
+                    batch_instance_buffer,
+                    offset * Model::INSTANCE_SIZE,
+                    *instance_count as u64 * Model::INSTANCE_SIZE,
+                );
+                offset += *instance_count as u64;
+            }
+        }
+    }
+
+    /// Records all [Draw]s into the [RenderPass], skipping redundant
+    /// state changes.
+    ///
+    /// `bind_groups` are bound right after the material bind group,
+    /// i.e. starting at index 1, whenever the pipeline changes.
+    pub fn record<'p>(
+        draws: &'p [Draw<'p>],
+        render_pass: &mut RenderPass<'p>,
+        bind_groups: &[&'p BindGroup],
+        batch_instance_buffer: Option<&'p Buffer>,
+        gpu_culling: Option<&'p GpuCulling>,
+        stats: &mut RenderStats,
+    ) {
+        let mut current_pipeline: Option<&Pipeline> = None;
+        let mut current_bind_group: Option<&BindGroup> = None;
+        let mut current_skin_buffer: Option<&Buffer> = None;
+        let mut current_mesh: Option<&Mesh> = None;
+        for draw in draws {
+            if !current_pipeline.is_some_and(|x| ptr::eq(x, draw.pipeline)) {
+                render_pass.set_pipeline(draw.pipeline.render_pipeline());
+                current_pipeline = Some(draw.pipeline);
+                stats.pipeline_changes += 1;
+
+                // Bind groups aren't guaranteed to stay bound across
+                // pipelines
+                for (index, bind_group) in bind_groups.iter().enumerate() {
+                    render_pass.set_bind_group(index as u32 + 1, bind_group, &[]);
+                }
+                current_bind_group = None;
+                stats.bind_group_changes += bind_groups.len() as u32;
+            }
+
+            if !current_bind_group.is_some_and(|x| ptr::eq(x, draw.bind_group)) {
+                render_pass.set_bind_group(0, draw.bind_group, &[]);
+                current_bind_group = Some(draw.bind_group);
+                stats.bind_group_changes += 1;
+            }
+
+            if let Some(skin_buffer) = draw.skin_buffer {
+                if !current_skin_buffer.is_some_and(|x| ptr::eq(x, skin_buffer)) {
+                    render_pass.set_vertex_buffer(2, skin_buffer.slice(..));
+                    current_skin_buffer = Some(skin_buffer);
+                    stats.buffer_changes += 1;
+                }
+            }
+
+            if !current_mesh.is_some_and(|x| x.shares_buffers(draw.mesh)) {
+                render_pass.set_vertex_buffer(0, draw.mesh.vertex_buffer().slice(..));
+                render_pass
+                    .set_index_buffer(draw.mesh.index_buffer().slice(..), IndexFormat::Uint32);
+                current_mesh = Some(draw.mesh);
+                stats.buffer_changes += 2;
+            }
+
+            let instance_count = draw.instance_count();
+            if let (Some(indirect_index), Some(offset), Some(gpu_culling)) =
+                (draw.indirect_index, draw.batch_offset, gpu_culling)
+            {
+                if let (Some(output_slice), Some(indirect_buffer)) = (
+                    gpu_culling.output_slice(offset, instance_count),
+                    gpu_culling.indirect_buffer(),
+                ) {
+                    render_pass.set_vertex_buffer(1, output_slice);
+                    stats.buffer_changes += 1;
+
+                    render_pass.draw_indexed_indirect(
+                        indirect_buffer,
+                        indirect_index * GpuCulling::INDIRECT_SIZE,
+                    );
+                    stats.draw_calls += 1;
+                    stats.indirect_draws += 1;
+                    stats.instances += instance_count;
+                    continue;
+                }
+            }
+
+            match (draw.batch_offset, batch_instance_buffer) {
+                (Some(offset), Some(batch_instance_buffer)) => {
+                    render_pass.set_vertex_buffer(
+                        1,
+                        batch_instance_buffer.slice(
+                            offset * Model::INSTANCE_SIZE
+                                ..(offset + instance_count as u64) * Model::INSTANCE_SIZE,
+                        ),
+                    );
+                }
+                _ => {
+                    render_pass.set_vertex_buffer(1, draw.instances[0].0.slice(..));
+                }
+            }
+            stats.buffer_changes += 1;
+
+            render_pass.draw_indexed(0..draw.mesh.index_count(), 0, 0..instance_count);
+            stats.draw_calls += 1;
+            stats.instances += instance_count;
+        }
+    }
+}
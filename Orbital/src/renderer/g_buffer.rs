@@ -0,0 +1,122 @@
+use cgmath::Vector2;
+use wgpu::{
+    AddressMode, Color, Device, Extent3d, FilterMode, LoadOp, Operations, Queue,
+    RenderPassColorAttachment, SamplerDescriptor, StoreOp, TextureDescriptor, TextureDimension,
+    TextureFormat, TextureUsages, TextureViewDescriptor,
+};
+
+use crate::resources::realizations::{Pipeline, Texture};
+
+/// Render targets of a deferred renderer, holding the surface properties
+/// of every pixel.
+/// Written by the G-buffer variant of a [Pipeline], check
+/// [PipelineDescriptor::deferred](crate::resources::descriptors::PipelineDescriptor::deferred).
+///
+/// The layout is defined by [Pipeline::G_BUFFER_FORMATS].
+pub struct GBuffer {
+    albedo: Texture,
+    normal: Texture,
+    material: Texture,
+    emissive: Texture,
+    depth: Texture,
+    resolution: Vector2<u32>,
+}
+
+impl GBuffer {
+    pub fn new(resolution: Vector2<u32>, device: &Device, queue: &Queue) -> Self {
+        let [albedo, normal, material, emissive] = Pipeline::G_BUFFER_FORMATS
+            .map(|format| Self::make_target(format, &resolution, device, queue));
+
+        Self {
+            albedo,
+            normal,
+            material,
+            emissive,
+            depth: Texture::depth_texture(&resolution, device, queue),
+            resolution,
+        }
+    }
+
+    fn make_target(
+        format: TextureFormat,
+        resolution: &Vector2<u32>,
+        device: &Device,
+        queue: &Queue,
+    ) -> Texture {
+        Texture::from_descriptors(
+            &TextureDescriptor {
+                label: Some("G-Buffer Texture"),
+                size: Extent3d {
+                    width: resolution.x,
+                    height: resolution.y,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format,
+                usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            &TextureViewDescriptor::default(),
+            &SamplerDescriptor {
+                address_mode_u: AddressMode::ClampToEdge,
+                address_mode_v: AddressMode::ClampToEdge,
+                address_mode_w: AddressMode::ClampToEdge,
+                mag_filter: FilterMode::Nearest,
+                min_filter: FilterMode::Nearest,
+                mipmap_filter: FilterMode::Nearest,
+                ..Default::default()
+            },
+            device,
+            queue,
+        )
+    }
+
+    /// Color attachments of the G-buffer, cleared to zero.
+    /// In the order of [Pipeline::G_BUFFER_FORMATS].
+    pub fn color_attachments(&self) -> Vec<Option<RenderPassColorAttachment<'_>>> {
+        self.color_targets()
+            .into_iter()
+            .map(|target| {
+                Some(RenderPassColorAttachment {
+                    view: target.view(),
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::TRANSPARENT),
+                        store: StoreOp::Store,
+                    },
+                })
+            })
+            .collect()
+    }
+
+    /// All color targets in the order of [Pipeline::G_BUFFER_FORMATS].
+    pub fn color_targets(&self) -> [&Texture; 4] {
+        [&self.albedo, &self.normal, &self.material, &self.emissive]
+    }
+
+    pub fn albedo(&self) -> &Texture {
+        &self.albedo
+    }
+
+    pub fn normal(&self) -> &Texture {
+        &self.normal
+    }
+
+    pub fn material(&self) -> &Texture {
+        &self.material
+    }
+
+    pub fn emissive(&self) -> &Texture {
+        &self.emissive
+    }
+
+    pub fn depth(&self) -> &Texture {
+        &self.depth
+    }
+
+    pub fn resolution(&self) -> Vector2<u32> {
+        self.resolution
+    }
+}
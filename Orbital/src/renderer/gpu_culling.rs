@@ -0,0 +1,440 @@
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingResource, BindingType, Buffer, BufferBinding, BufferBindingType,
+    BufferDescriptor, BufferSize, BufferSlice, BufferUsages, CommandEncoder, ComputePassDescriptor,
+    ComputePipeline, ComputePipelineDescriptor, Device, PipelineCompilationOptions,
+    PipelineLayoutDescriptor, Queue, ShaderStages,
+};
+
+use crate::{
+    physics::{Aabb, Frustum},
+    resources::realizations::{Model, Shader},
+};
+
+/// A draw to cull on the GPU.
+/// Check [GpuCulling::cull].
+#[derive(Debug, Clone, Copy)]
+pub struct GpuCulledDraw<'a> {
+    /// Model space bounds of the mesh
+    pub bounds: Aabb,
+    pub index_count: u32,
+    /// Instance buffers and how many instances to cull from each.
+    /// All end up in the same draw.
+    pub instances: &'a [(&'a Buffer, u32)],
+}
+
+/// A range of instances culled by a single dispatch.
+/// Check `CullJob` in the shader.
+#[derive(Debug, Clone, Copy)]
+struct CullJob {
+    bounds: Aabb,
+    first_input: u32,
+    instance_count: u32,
+    first_output: u32,
+    draw_index: u32,
+}
+
+impl CullJob {
+    /// Bounds (2x vec4 f32) + Offsets and counts (4x u32)
+    const SIZE: u64 = 2 * 4 * 4 + 4 * 4;
+
+    /// Makes the job data, padded to `stride`.
+    fn to_bytes(self, stride: u64) -> Vec<u8> {
+        let mut data = [
+            self.bounds.min.x,
+            self.bounds.min.y,
+            self.bounds.min.z,
+            1.0,
+            self.bounds.max.x,
+            self.bounds.max.y,
+            self.bounds.max.z,
+            1.0,
+        ]
+        .iter()
+        .flat_map(|x| x.to_le_bytes())
+        .chain(
+            [
+                self.first_input,
+                self.instance_count,
+                self.first_output,
+                self.draw_index,
+            ]
+            .iter()
+            .flat_map(|x| x.to_le_bytes()),
+        )
+        .collect::<Vec<u8>>();
+        data.resize(stride as usize, 0);
+
+        data
+    }
+}
+
+/// Culls instances against a [Frustum] in a compute pass and writes the
+/// arguments of indirect draws.
+///
+/// All instances are copied into a single input buffer first.
+/// Visible instances of each draw are then compacted into it's range of
+/// [GpuCulling::output_slice], while the instance count of it's
+/// [GpuCulling::indirect_buffer] arguments is counted up.
+pub struct GpuCulling {
+    pipeline: ComputePipeline,
+    bind_group_layout: BindGroupLayout,
+    frustum_buffer: Buffer,
+    job_buffer: Option<Buffer>,
+    input_buffer: Option<Buffer>,
+    output_buffer: Option<Buffer>,
+    indirect_buffer: Option<Buffer>,
+    /// Remade if any [Buffer] is remade
+    bind_group: Option<BindGroup>,
+    /// Stride of the jobs inside the job buffer, matching the dynamic
+    /// offset alignment
+    job_stride: u64,
+    /// Maximum amount of instances a single dispatch can cull
+    max_job_instances: u32,
+}
+
+impl GpuCulling {
+    pub const WORKGROUP_SIZE: u32 = 64;
+    /// Size of the arguments of a single indirect draw in bytes.
+    /// Check [wgpu::util::DrawIndexedIndirectArgs].
+    pub const INDIRECT_SIZE: u64 = 5 * 4;
+    /// Six planes (6x vec4 f32)
+    const FRUSTUM_SIZE: u64 = 6 * 4 * 4;
+
+    /// Checks if the [Device] supports compute shaders with enough
+    /// storage buffers.
+    pub fn is_supported(device: &Device) -> bool {
+        let limits = device.limits();
+
+        limits.max_compute_workgroups_per_dimension > 0
+            && limits.max_compute_invocations_per_workgroup >= Self::WORKGROUP_SIZE
+            && limits.max_storage_buffers_per_shader_stage >= 3
+    }
+
+    pub fn new(device: &Device, queue: &Queue) -> Self {
+        let uniform_entry =
+            |binding: u32, has_dynamic_offset: bool, size: u64| BindGroupLayoutEntry {
+                binding,
+                visibility: ShaderStages::COMPUTE,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset,
+                    min_binding_size: BufferSize::new(size),
+                },
+                count: None,
+            };
+        let storage_entry = |binding: u32, read_only: bool| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("GPU Culling"),
+            entries: &[
+                uniform_entry(0, false, Self::FRUSTUM_SIZE),
+                uniform_entry(1, true, CullJob::SIZE),
+                storage_entry(2, true),
+                storage_entry(3, false),
+                storage_entry(4, false),
+            ],
+        });
+
+        let shader = Shader::from_descriptor(
+            include_str!("../resources/descriptors/shader/gpu_culling.wgsl"),
+            device,
+            queue,
+        )
+        .expect("GPU culling shader realization failed!");
+
+        let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("GPU Culling Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("GPU Culling Pipeline"),
+            layout: Some(&layout),
+            module: shader.shader_module(),
+            entry_point: "entrypoint_compute",
+            compilation_options: PipelineCompilationOptions::default(),
+        });
+
+        let frustum_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("GPU Culling Frustum Buffer"),
+            size: Self::FRUSTUM_SIZE,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let limits = device.limits();
+        let alignment = limits.min_uniform_buffer_offset_alignment as u64;
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            frustum_buffer,
+            job_buffer: None,
+            input_buffer: None,
+            output_buffer: None,
+            indirect_buffer: None,
+            bind_group: None,
+            job_stride: CullJob::SIZE.div_ceil(alignment) * alignment,
+            max_job_instances: limits
+                .max_compute_workgroups_per_dimension
+                .saturating_mul(Self::WORKGROUP_SIZE),
+        }
+    }
+
+    /// Records copying all instances and culling them into the
+    /// [CommandEncoder].
+    ///
+    /// Returns the first instance of each draw inside
+    /// [GpuCulling::output_slice].
+    /// The arguments of each draw are at `index * INDIRECT_SIZE` inside
+    /// [GpuCulling::indirect_buffer].
+    ///
+    /// ⚠️ The instance buffers must be copyable, i.e. have
+    /// [BufferUsages::COPY_SRC].
+    pub fn cull(
+        &mut self,
+        draws: &[GpuCulledDraw],
+        frustum: &Frustum,
+        encoder: &mut CommandEncoder,
+        device: &Device,
+        queue: &Queue,
+    ) -> Vec<u64> {
+        let mut first_outputs = Vec::with_capacity(draws.len());
+        let mut indirect_data = Vec::with_capacity(draws.len() * Self::INDIRECT_SIZE as usize);
+        let mut jobs = Vec::new();
+        let mut instance_count = 0u64;
+
+        for (draw_index, draw) in draws.iter().enumerate() {
+            first_outputs.push(instance_count);
+
+            // Instance count starts at zero and is counted up while culling
+            for x in [draw.index_count, 0, 0, 0, 0] {
+                indirect_data.extend_from_slice(&x.to_le_bytes());
+            }
+
+            for (_, count) in draw.instances {
+                let mut first = 0;
+                while first < *count {
+                    let job_count = (*count - first).min(self.max_job_instances);
+                    jobs.push(CullJob {
+                        bounds: draw.bounds,
+                        first_input: (instance_count + first as u64) as u32,
+                        instance_count: job_count,
+                        first_output: first_outputs[draw_index] as u32,
+                        draw_index: draw_index as u32,
+                    });
+                    first += job_count;
+                }
+                instance_count += *count as u64;
+            }
+        }
+
+        if draws.is_empty() {
+            return first_outputs;
+        }
+
+        self.prepare_buffers(
+            jobs.len() as u64,
+            instance_count,
+            draws.len() as u64,
+            device,
+        );
+        let (Some(job_buffer), Some(input_buffer), Some(indirect_buffer), Some(bind_group)) = (
+            &self.job_buffer,
+            &self.input_buffer,
+            &self.indirect_buffer,
+            &self.bind_group,
+        ) else {
+            return first_outputs;
+        };
+
+        queue.write_buffer(
+            &self.frustum_buffer,
+            0,
+            &frustum
+                .planes()
+                .iter()
+                .flat_map(|x| [x.x, x.y, x.z, x.w])
+                .flat_map(|x| x.to_le_bytes())
+                .collect::<Vec<u8>>(),
+        );
+        queue.write_buffer(indirect_buffer, 0, &indirect_data);
+        if !jobs.is_empty() {
+            queue.write_buffer(
+                job_buffer,
+                0,
+                &jobs
+                    .iter()
+                    .flat_map(|x| x.to_bytes(self.job_stride))
+                    .collect::<Vec<u8>>(),
+            );
+        }
+
+        let mut offset = 0;
+        for draw in draws {
+            for (instance_buffer, count) in draw.instances {
+                if *count > 0 {
+                    encoder.copy_buffer_to_buffer(
+                        instance_buffer,
+                        0,
+                        input_buffer,
+                        offset * Model::INSTANCE_SIZE,
+                        *count as u64 * Model::INSTANCE_SIZE,
+                    );
+                }
+                offset += *count as u64;
+            }
+        }
+
+        let mut compute_pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: Some("GPU Culling"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(&self.pipeline);
+        for (job_index, job) in jobs.iter().enumerate() {
+            if job.instance_count == 0 {
+                continue;
+            }
+
+            compute_pass.set_bind_group(
+                0,
+                bind_group,
+                &[(job_index as u64 * self.job_stride) as u32],
+            );
+            compute_pass.dispatch_workgroups(
+                job.instance_count.div_ceil(Self::WORKGROUP_SIZE),
+                1,
+                1,
+            );
+        }
+
+        first_outputs
+    }
+
+    /// Makes sure all buffers are big enough.
+    /// If any buffer is remade, the [BindGroup] is remade too.
+    fn prepare_buffers(
+        &mut self,
+        job_count: u64,
+        instance_count: u64,
+        draw_count: u64,
+        device: &Device,
+    ) {
+        let mut remade = self.bind_group.is_none();
+        let mut prepare = |buffer: &mut Option<Buffer>, size: u64, usage, label| {
+            let size = size.max(1).next_power_of_two().max(4);
+            if buffer.as_ref().is_some_and(|x| x.size() >= size) {
+                return;
+            }
+
+            *buffer = Some(device.create_buffer(&BufferDescriptor {
+                label: Some(label),
+                size,
+                usage,
+                mapped_at_creation: false,
+            }));
+            remade = true;
+        };
+
+        prepare(
+            &mut self.job_buffer,
+            job_count.max(1) * self.job_stride,
+            BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            "GPU Culling Job Buffer",
+        );
+        prepare(
+            &mut self.input_buffer,
+            instance_count.max(1) * Model::INSTANCE_SIZE,
+            BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            "GPU Culling Input Buffer",
+        );
+        prepare(
+            &mut self.output_buffer,
+            instance_count.max(1) * Model::INSTANCE_SIZE,
+            BufferUsages::STORAGE | BufferUsages::VERTEX,
+            "GPU Culling Output Buffer",
+        );
+        prepare(
+            &mut self.indirect_buffer,
+            draw_count.max(1) * Self::INDIRECT_SIZE,
+            BufferUsages::STORAGE | BufferUsages::INDIRECT | BufferUsages::COPY_DST,
+            "GPU Culling Indirect Buffer",
+        );
+
+        if !remade {
+            return;
+        }
+        let (Some(job_buffer), Some(input_buffer), Some(output_buffer), Some(indirect_buffer)) = (
+            &self.job_buffer,
+            &self.input_buffer,
+            &self.output_buffer,
+            &self.indirect_buffer,
+        ) else {
+            return;
+        };
+
+        self.bind_group = Some(device.create_bind_group(&BindGroupDescriptor {
+            label: Some("GPU Culling"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: self.frustum_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Buffer(BufferBinding {
+                        buffer: job_buffer,
+                        offset: 0,
+                        size: BufferSize::new(CullJob::SIZE),
+                    }),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: input_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: output_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 4,
+                    resource: indirect_buffer.as_entire_binding(),
+                },
+            ],
+        }));
+    }
+
+    /// The visible instances of the draw starting at `first_instance`,
+    /// with room for `instance_count` instances.
+    /// Check [GpuCulling::cull].
+    pub fn output_slice(
+        &self,
+        first_instance: u64,
+        instance_count: u32,
+    ) -> Option<BufferSlice<'_>> {
+        self.output_buffer.as_ref().map(|x| {
+            x.slice(
+                first_instance * Model::INSTANCE_SIZE
+                    ..(first_instance + instance_count as u64) * Model::INSTANCE_SIZE,
+            )
+        })
+    }
+
+    /// The arguments of all indirect draws.
+    /// Check [GpuCulling::cull].
+    pub fn indirect_buffer(&self) -> Option<&Buffer> {
+        self.indirect_buffer.as_ref()
+    }
+}
@@ -0,0 +1,224 @@
+use cgmath::{Matrix4, SquareMatrix, Vector2, Vector3};
+use wgpu::{
+    BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingType, Buffer, BufferBindingType, BufferDescriptor, BufferSize,
+    BufferUsages, CommandEncoder, ComputePassDescriptor, ComputePipeline,
+    ComputePipelineDescriptor, Device, PipelineCompilationOptions, PipelineLayoutDescriptor, Queue,
+    ShaderStages,
+};
+
+use crate::resources::realizations::{Camera, Shader};
+
+use super::LightStorage;
+
+/// Splits the view frustum into a grid of clusters and finds all lights
+/// reaching into each cluster on the GPU.
+/// Used by the [DeferredRenderer](super::DeferredRenderer) to only
+/// shade pixels with the lights close to them.
+///
+/// Clusters are screen space tiles, sliced exponentially along the view
+/// depth.
+/// Check [LightClusters::GRID_SIZE].
+///
+/// Call [LightClusters::update] once per frame, **after**
+/// [LightStorage::update].
+pub struct LightClusters {
+    pipeline: ComputePipeline,
+    bind_group_layout: BindGroupLayout,
+    uniform_buffer: Buffer,
+    cluster_buffer: Buffer,
+}
+
+impl LightClusters {
+    /// Amount of clusters along the screen width (X), height (Y) and
+    /// view depth (Z).
+    pub const GRID_SIZE: Vector3<u32> = Vector3::new(16, 9, 24);
+
+    /// Lights reaching into a cluster beyond this are ignored.
+    /// ⚠️ Must match the shaders!
+    pub const MAX_LIGHTS_PER_CLUSTER: u32 = 127;
+
+    /// Light count (u32) + Light indices (u32 each)
+    pub const CLUSTER_SIZE: u64 = 4 * (1 + Self::MAX_LIGHTS_PER_CLUSTER as u64);
+
+    /// Matrices (3x 4x4 f32) + Screen size, near and far (4x f32) + Grid
+    /// size and padding (4x u32)
+    pub const UNIFORM_SIZE: u64 = 3 * 4 * 4 * 4 + 4 * 4 + 4 * 4;
+
+    pub const WORKGROUP_SIZE: u32 = 64;
+
+    pub fn new(device: &Device, queue: &Queue) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Light Clusters"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: BufferSize::new(Self::UNIFORM_SIZE),
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let shader = Shader::from_descriptor(
+            include_str!("../resources/descriptors/shader/light_clustering.wgsl"),
+            device,
+            queue,
+        )
+        .expect("Light clustering shader realization failed!");
+
+        let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Light Clustering Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("Light Clustering Pipeline"),
+            layout: Some(&layout),
+            module: shader.shader_module(),
+            entry_point: "entrypoint_compute",
+            compilation_options: PipelineCompilationOptions::default(),
+        });
+
+        let uniform_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Light Cluster Uniform Buffer"),
+            size: Self::UNIFORM_SIZE,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let cluster_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Light Cluster Buffer"),
+            size: Self::CLUSTER_SIZE * Self::cluster_count() as u64,
+            usage: BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            uniform_buffer,
+            cluster_buffer,
+        }
+    }
+
+    pub fn cluster_count() -> u32 {
+        Self::GRID_SIZE.x * Self::GRID_SIZE.y * Self::GRID_SIZE.z
+    }
+
+    /// Records assigning all [Light](crate::resources::realizations::Light)s
+    /// of the [LightStorage] to the clusters of the [Camera] into the
+    /// [CommandEncoder].
+    pub fn update(
+        &mut self,
+        camera: &Camera,
+        resolution: Vector2<u32>,
+        light_storage: &LightStorage,
+        encoder: &mut CommandEncoder,
+        device: &Device,
+        queue: &Queue,
+    ) {
+        queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            &Self::uniform_bytes(camera, resolution),
+        );
+
+        // The light storage may be remade any frame
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Light Clustering Bind Group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: self.uniform_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: light_storage.light_buffer().as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: self.cluster_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut compute_pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: Some("Light Clustering"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(&self.pipeline);
+        compute_pass.set_bind_group(0, &bind_group, &[]);
+        compute_pass.dispatch_workgroups(
+            Self::cluster_count().div_ceil(Self::WORKGROUP_SIZE),
+            1,
+            1,
+        );
+    }
+
+    /// Makes the uniform data, matching `ClusterUniform` in the shaders.
+    fn uniform_bytes(camera: &Camera, resolution: Vector2<u32>) -> Vec<u8> {
+        let inverse = |matrix: Matrix4<f32>| matrix.invert().unwrap_or_else(Matrix4::identity);
+        let descriptor = camera.descriptor();
+
+        let matrices = [
+            inverse(camera.calculate_view_projection_matrix()),
+            camera.calculate_view_matrix(),
+            inverse(camera.calculate_projection_matrix()),
+        ];
+
+        matrices
+            .iter()
+            .flat_map(|matrix| AsRef::<[f32; 16]>::as_ref(matrix).to_vec())
+            .chain([
+                resolution.x as f32,
+                resolution.y as f32,
+                descriptor.near,
+                descriptor.far,
+            ])
+            .flat_map(|x| x.to_le_bytes())
+            .chain(
+                [Self::GRID_SIZE.x, Self::GRID_SIZE.y, Self::GRID_SIZE.z, 0]
+                    .iter()
+                    .flat_map(|x| x.to_le_bytes()),
+            )
+            .collect()
+    }
+
+    /// Uniform buffer, matching `ClusterUniform` in the shaders
+    pub fn uniform_buffer(&self) -> &Buffer {
+        &self.uniform_buffer
+    }
+
+    /// Storage buffer with one `Cluster` per cluster, check
+    /// [LightClusters::CLUSTER_SIZE]
+    pub fn cluster_buffer(&self) -> &Buffer {
+        &self.cluster_buffer
+    }
+}
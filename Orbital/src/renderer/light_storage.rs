@@ -0,0 +1,158 @@
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindingResource, Buffer, BufferDescriptor,
+    BufferUsages, CommandEncoder, Device, Queue, TextureFormat,
+};
+
+use crate::resources::realizations::{Camera, Light, Model};
+
+use super::ShadowAtlas;
+
+/// Gathers all [Light]s and their shadows into GPU storage, matching
+/// [Light::bind_group_layout_descriptor].
+///
+/// Intended to be used by [Renderer](super::Renderer)s.
+/// Call [LightStorage::update] once per frame, **before** any render pass
+/// using the [LightStorage::bind_group] is recorded.
+pub struct LightStorage {
+    light_buffer: Buffer,
+    shadow_buffer: Buffer,
+    bind_group: BindGroup,
+    capacity: usize,
+    shadow_atlas: ShadowAtlas,
+}
+
+impl LightStorage {
+    pub fn new(device: &Device, queue: &Queue) -> Self {
+        let shadow_atlas = ShadowAtlas::new(device, queue);
+        let (light_buffer, shadow_buffer, bind_group) =
+            Self::make_storage(0, &shadow_atlas, device);
+
+        Self {
+            light_buffer,
+            shadow_buffer,
+            bind_group,
+            capacity: 0,
+            shadow_atlas,
+        }
+    }
+
+    /// Creates the light and shadow storage buffers, able to hold up to
+    /// `capacity` [Light]s, and their bind group.
+    fn make_storage(
+        capacity: usize,
+        shadow_atlas: &ShadowAtlas,
+        device: &Device,
+    ) -> (Buffer, Buffer, BindGroup) {
+        // A storage buffer can't be empty, thus we always have room for
+        // at least one light.
+        let capacity = capacity.max(1) as u64;
+
+        let light_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Light Storage Buffer"),
+            size: Light::STORAGE_HEADER_SIZE + Light::BUFFER_SIZE * capacity,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let shadow_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Shadow Storage Buffer"),
+            size: ShadowAtlas::SHADOW_DATA_SIZE * capacity,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Light Bind Group"),
+            layout: &device.create_bind_group_layout(&Light::bind_group_layout_descriptor()),
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: light_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: shadow_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: BindingResource::TextureView(shadow_atlas.texture().view()),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: BindingResource::Sampler(shadow_atlas.comparison_sampler()),
+                },
+            ],
+        });
+
+        (light_buffer, shadow_buffer, bind_group)
+    }
+
+    /// Copies all [Light]s into the light storage buffer and renders
+    /// their shadows.
+    /// `surface_format` and `sample_count` are the ones the [Model]s are
+    /// drawn with, check [ShadowAtlas::render].
+    /// If the storage buffers are too small, they will be remade.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update(
+        &mut self,
+        lights: &[&Light],
+        models: &[&Model],
+        camera: &Camera,
+        surface_format: &TextureFormat,
+        sample_count: u32,
+        encoder: &mut CommandEncoder,
+        device: &Device,
+        queue: &Queue,
+    ) {
+        if lights.len() > self.capacity {
+            let (light_buffer, shadow_buffer, bind_group) =
+                Self::make_storage(lights.len(), &self.shadow_atlas, device);
+
+            self.light_buffer = light_buffer;
+            self.shadow_buffer = shadow_buffer;
+            self.bind_group = bind_group;
+            self.capacity = lights.len();
+        }
+
+        queue.write_buffer(&self.light_buffer, 0, &(lights.len() as u32).to_le_bytes());
+
+        for (i, light) in lights.iter().enumerate() {
+            encoder.copy_buffer_to_buffer(
+                light.buffer(),
+                0,
+                &self.light_buffer,
+                Light::STORAGE_HEADER_SIZE + Light::BUFFER_SIZE * i as u64,
+                Light::BUFFER_SIZE,
+            );
+        }
+
+        let shadow_data = self.shadow_atlas.render(
+            lights,
+            models,
+            camera,
+            surface_format,
+            sample_count,
+            encoder,
+            device,
+            queue,
+        );
+        if !shadow_data.is_empty() {
+            queue.write_buffer(&self.shadow_buffer, 0, &shadow_data);
+        }
+    }
+
+    pub fn bind_group(&self) -> &BindGroup {
+        &self.bind_group
+    }
+
+    /// Storage buffer of all [Light]s, matching `LightStorage` in the
+    /// shaders.
+    /// ⚠️ Gets remade once it's too small, thus shouldn't be kept.
+    pub fn light_buffer(&self) -> &Buffer {
+        &self.light_buffer
+    }
+
+    pub fn shadow_atlas(&self) -> &ShadowAtlas {
+        &self.shadow_atlas
+    }
+}
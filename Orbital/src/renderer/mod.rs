@@ -1,11 +1,45 @@
 use cgmath::Vector2;
 use wgpu::{Device, Queue, TextureFormat, TextureView};
 
-use crate::resources::realizations::{Camera, Model};
+use crate::resources::realizations::{Camera, Environment, Light, Model};
 
 pub mod standard;
 pub use standard::*;
 
+pub mod deferred;
+pub use deferred::*;
+
+pub mod g_buffer;
+pub use g_buffer::*;
+
+pub mod light_storage;
+pub use light_storage::*;
+
+pub mod light_clusters;
+pub use light_clusters::*;
+
+pub mod shadow_atlas;
+pub use shadow_atlas::*;
+
+pub mod skybox;
+pub use skybox::*;
+
+pub mod gpu_culling;
+pub use gpu_culling::*;
+
+pub mod post_processing;
+pub use post_processing::*;
+
+pub mod settings;
+pub use settings::*;
+
+pub mod stats;
+pub use stats::*;
+
+mod draw;
+
+pub mod testing;
+
 pub trait Renderer {
     fn new(
         surface_texture_format: TextureFormat,
@@ -23,14 +57,59 @@ pub trait Renderer {
 
     fn change_resolution(&mut self, resolution: Vector2<u32>, device: &Device, queue: &Queue);
 
+    /// Changes the amount of samples per pixel, i.e. MSAA.
+    /// `1` disables MSAA.
+    ///
+    /// ⚠️ The sample count is expected to be validated already, check
+    /// ⚠️ [AppSettings::sample_count](crate::app::AppSettings::sample_count).  
+    /// Renderers not supporting MSAA ignore this.
+    fn change_sample_count(&mut self, _sample_count: u32, _device: &Device, _queue: &Queue) {}
+
+    /// Format of the color targets which get multisampled.
+    /// Used to validate the sample count.
+    /// Defaults to [PostProcessing::HDR_FORMAT], which the built-in
+    /// renderers render into.
+    fn color_format(&self) -> TextureFormat {
+        PostProcessing::HDR_FORMAT
+    }
+
+    /// Applies [RendererSettings].
+    /// Unsupported settings are ignored.
+    fn change_settings(&mut self, _settings: &RendererSettings, _device: &Device, _queue: &Queue) {}
+
+    /// Checks if [Instance](crate::resources::realizations::Instance)s
+    /// are culled on the GPU.
+    /// If so, the [World](crate::game::World) doesn't need to cull them.
+    /// Check [RendererSettings::gpu_culling].
+    fn is_gpu_culling(&self) -> bool {
+        false
+    }
+
     fn update(&mut self, delta_time: f64);
 
+    /// Gets called before each render with how far (`0.0` to `1.0`) the
+    /// frame is in between the last and the next fixed update.
+    /// Check [FixedTimestep::alpha](crate::game::FixedTimestep::alpha).
+    ///
+    /// ⚠️ None of the built-in renderers interpolate yet, they render the
+    /// ⚠️ state of the last fixed update.
+    fn set_interpolation_alpha(&mut self, _alpha: f64) {}
+
+    /// Returns the [RenderStats] of the last frame.
+    /// Renderers not keeping track of them return empty [RenderStats].
+    fn stats(&self) -> RenderStats {
+        RenderStats::default()
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn render(
         &mut self,
         target_view: &TextureView,
         device: &Device,
         queue: &Queue,
         models: &[&Model],
+        lights: &[&Light],
+        environment: Option<&Environment>,
         camera: &Camera,
     );
 }
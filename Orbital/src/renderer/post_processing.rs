@@ -0,0 +1,944 @@
+use cgmath::{Vector2, Vector4};
+use wgpu::{
+    AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, BlendComponent,
+    BlendFactor, BlendOperation, BlendState, Buffer, BufferBindingType, BufferDescriptor,
+    BufferSize, BufferUsages, Color, ColorTargetState, ColorWrites, CommandEncoder, Device,
+    Extent3d, FilterMode, FragmentState, LoadOp, MultisampleState, Operations,
+    PipelineCompilationOptions, PipelineLayout, PipelineLayoutDescriptor, PrimitiveState, Queue,
+    RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor,
+    Sampler, SamplerBindingType, SamplerDescriptor, ShaderStages, StoreOp, Texture as WTexture,
+    TextureDescriptor as WTextureDescriptor, TextureDimension, TextureFormat, TextureSampleType,
+    TextureUsages, TextureView, TextureViewDescriptor, TextureViewDimension, VertexState,
+};
+
+use crate::{
+    log::warn,
+    resources::{
+        descriptors::TextureDescriptor,
+        realizations::{Shader, Texture},
+    },
+};
+
+use super::{CustomPostProcessingPass, PostProcessingSettings, PostProcessingStage};
+
+/// Turns the high dynamic range image of a frame into the final image on
+/// the surface.
+///
+/// [Renderer](super::Renderer)s render into [PostProcessing::hdr_texture]
+/// and call [PostProcessing::render] at the end of each frame.
+/// The stack always runs at least the tonemapping pass, converting to the
+/// surface format.
+/// Which passes run is defined by [PostProcessingSettings].
+///
+/// ⚠️ Invalid custom shaders panic, like any other shader!
+pub struct PostProcessing {
+    settings: PostProcessingSettings,
+    surface_texture_format: TextureFormat,
+    resolution: Vector2<u32>,
+    /// The frame is rendered into this
+    hdr_texture: Texture,
+    /// Passes render back and forth between these
+    intermediate_textures: [Texture; 2],
+    sampler: Sampler,
+    uniform_buffer: Buffer,
+    /// Bound as the parameters of built-in passes
+    empty_parameters_buffer: Buffer,
+    /// Group 0 of every pass
+    bind_group_layout: BindGroupLayout,
+    vertex_shader: Shader,
+    tonemapping: FullscreenPass,
+    /// Bloom and color grading, group 1 of the tonemapping pass
+    tonemapping_bind_group_layout: BindGroupLayout,
+    fxaa: FullscreenPass,
+    bloom: Option<Bloom>,
+    color_grading_lut: Option<Texture>,
+    /// Bound in place of disabled bloom or color grading
+    placeholder_texture: Texture,
+    custom_passes: Vec<CustomPass>,
+    bind_groups: BindGroups,
+}
+
+/// Bind groups of all passes, remade whenever their inputs change.
+#[derive(Default)]
+struct BindGroups {
+    /// Per [Step], in order
+    steps: Vec<Vec<BindGroup>>,
+    /// Per mip
+    bloom_downsample: Vec<BindGroup>,
+    /// From the smallest mip up
+    bloom_upsample: Vec<BindGroup>,
+}
+
+/// A fullscreen pass, which can either render into an intermediate
+/// texture or onto the surface, in case it's the last one.
+struct FullscreenPass {
+    shader: Shader,
+    entry_point: &'static str,
+    layout: PipelineLayout,
+    intermediate_pipeline: RenderPipeline,
+    output_pipeline: RenderPipeline,
+}
+
+struct CustomPass {
+    stage: PostProcessingStage,
+    pass: FullscreenPass,
+    parameters_buffer: Buffer,
+}
+
+/// Downsampled mip chain of the bright parts of the image, blurred while
+/// upsampling back to the first mip.
+struct Bloom {
+    /// Only kept alive for the views
+    _texture: WTexture,
+    mip_views: Vec<TextureView>,
+    prefilter_pipeline: RenderPipeline,
+    downsample_pipeline: RenderPipeline,
+    upsample_pipeline: RenderPipeline,
+}
+
+enum Step<'a> {
+    Custom(&'a CustomPass),
+    Tonemapping,
+    Fxaa,
+}
+
+impl PostProcessing {
+    /// Format of the frame and all intermediate results.
+    /// High dynamic range, as lights can add up beyond `1.0`.
+    pub const HDR_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+    /// Exposure, tonemapping (u32), bloom intensity and threshold,
+    /// vignette intensity, radius and smoothness, color grading lookup
+    /// table size (all f32)
+    pub const UNIFORM_SIZE: u64 = 8 * 4;
+
+    /// [CustomPostProcessingPass::parameters] (4x f32)
+    pub const PARAMETERS_SIZE: u64 = 4 * 4;
+
+    pub fn new(
+        surface_texture_format: TextureFormat,
+        resolution: Vector2<u32>,
+        device: &Device,
+        queue: &Queue,
+    ) -> Self {
+        let bind_group_layout =
+            device.create_bind_group_layout(&Self::bind_group_layout_descriptor());
+        let tonemapping_bind_group_layout =
+            device.create_bind_group_layout(&Self::tonemapping_bind_group_layout_descriptor());
+
+        let vertex_shader = Shader::from_descriptor(
+            include_str!("../resources/descriptors/shader/post_processing.wgsl"),
+            device,
+            queue,
+        )
+        .expect("Post processing shader realization failed!");
+
+        let tonemapping = FullscreenPass::new(
+            "Tonemapping",
+            Shader::from_descriptor(
+                include_str!("../resources/descriptors/shader/post_tonemapping.wgsl"),
+                device,
+                queue,
+            )
+            .expect("Tonemapping shader realization failed!"),
+            "entrypoint_fragment",
+            &[&bind_group_layout, &tonemapping_bind_group_layout],
+            &vertex_shader,
+            surface_texture_format,
+            device,
+        );
+        let fxaa = FullscreenPass::new(
+            "FXAA",
+            Shader::from_descriptor(
+                include_str!("../resources/descriptors/shader/post_fxaa.wgsl"),
+                device,
+                queue,
+            )
+            .expect("FXAA shader realization failed!"),
+            "entrypoint_fragment",
+            &[&bind_group_layout],
+            &vertex_shader,
+            surface_texture_format,
+            device,
+        );
+
+        let uniform_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Post Processing Uniform Buffer"),
+            size: Self::UNIFORM_SIZE,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let empty_parameters_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Post Processing Empty Parameters Buffer"),
+            size: Self::PARAMETERS_SIZE,
+            usage: BufferUsages::UNIFORM,
+            mapped_at_creation: false,
+        });
+
+        let mut post_processing = Self {
+            settings: PostProcessingSettings::default(),
+            surface_texture_format,
+            resolution,
+            hdr_texture: Self::make_target("HDR Texture", &resolution, device, queue),
+            intermediate_textures: [
+                Self::make_target("Post Processing Texture", &resolution, device, queue),
+                Self::make_target("Post Processing Texture", &resolution, device, queue),
+            ],
+            sampler: device.create_sampler(&SamplerDescriptor {
+                label: Some("Post Processing Sampler"),
+                address_mode_u: AddressMode::ClampToEdge,
+                address_mode_v: AddressMode::ClampToEdge,
+                address_mode_w: AddressMode::ClampToEdge,
+                mag_filter: FilterMode::Linear,
+                min_filter: FilterMode::Linear,
+                ..Default::default()
+            }),
+            uniform_buffer,
+            empty_parameters_buffer,
+            bind_group_layout,
+            vertex_shader,
+            tonemapping,
+            tonemapping_bind_group_layout,
+            fxaa,
+            bloom: None,
+            color_grading_lut: None,
+            placeholder_texture: Texture::uniform_color(Vector4::new(0, 0, 0, 255), device, queue),
+            custom_passes: Vec::new(),
+            bind_groups: BindGroups::default(),
+        };
+        post_processing.write_uniform(queue);
+        post_processing.bind_groups = post_processing.make_bind_groups(device);
+
+        post_processing
+    }
+
+    fn make_target(
+        label: &str,
+        resolution: &Vector2<u32>,
+        device: &Device,
+        queue: &Queue,
+    ) -> Texture {
+        Texture::from_descriptors(
+            &WTextureDescriptor {
+                label: Some(label),
+                size: Extent3d {
+                    width: resolution.x,
+                    height: resolution.y,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: Self::HDR_FORMAT,
+                usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            &TextureViewDescriptor::default(),
+            &SamplerDescriptor {
+                address_mode_u: AddressMode::ClampToEdge,
+                address_mode_v: AddressMode::ClampToEdge,
+                address_mode_w: AddressMode::ClampToEdge,
+                mag_filter: FilterMode::Linear,
+                min_filter: FilterMode::Linear,
+                ..Default::default()
+            },
+            device,
+            queue,
+        )
+    }
+
+    /// Input, sampler, uniform and parameters, bound as group 0 of every
+    /// pass.
+    /// Check [CustomPostProcessingPass].
+    pub fn bind_group_layout_descriptor() -> BindGroupLayoutDescriptor<'static> {
+        const fn uniform_entry(binding: u32, size: u64) -> BindGroupLayoutEntry {
+            BindGroupLayoutEntry {
+                binding,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: BufferSize::new(size),
+                },
+                count: None,
+            }
+        }
+        const ENTRIES: &[BindGroupLayoutEntry] = &[
+            BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture {
+                    sample_type: TextureSampleType::Float { filterable: true },
+                    view_dimension: TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            BindGroupLayoutEntry {
+                binding: 1,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                count: None,
+            },
+            uniform_entry(2, PostProcessing::UNIFORM_SIZE),
+            uniform_entry(3, PostProcessing::PARAMETERS_SIZE),
+        ];
+
+        BindGroupLayoutDescriptor {
+            label: Some("Post Processing"),
+            entries: ENTRIES,
+        }
+    }
+
+    /// Bloom and color grading lookup table, bound as group 1 of the
+    /// tonemapping pass.
+    fn tonemapping_bind_group_layout_descriptor() -> BindGroupLayoutDescriptor<'static> {
+        const fn texture_entry(binding: u32) -> BindGroupLayoutEntry {
+            BindGroupLayoutEntry {
+                binding,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture {
+                    sample_type: TextureSampleType::Float { filterable: true },
+                    view_dimension: TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }
+        }
+        const ENTRIES: &[BindGroupLayoutEntry] = &[texture_entry(0), texture_entry(1)];
+
+        BindGroupLayoutDescriptor {
+            label: Some("Tonemapping"),
+            entries: ENTRIES,
+        }
+    }
+
+    /// Remakes the output pipelines to match a new surface texture format.
+    pub fn change_surface_texture_format(
+        &mut self,
+        surface_texture_format: TextureFormat,
+        device: &Device,
+    ) {
+        self.surface_texture_format = surface_texture_format;
+
+        let passes = [&mut self.tonemapping, &mut self.fxaa]
+            .into_iter()
+            .chain(self.custom_passes.iter_mut().map(|x| &mut x.pass));
+        for pass in passes {
+            pass.change_surface_texture_format(&self.vertex_shader, surface_texture_format, device);
+        }
+    }
+
+    /// Remakes all textures to match the new size.
+    pub fn change_resolution(&mut self, resolution: Vector2<u32>, device: &Device, queue: &Queue) {
+        self.resolution = resolution;
+
+        self.hdr_texture = Self::make_target("HDR Texture", &resolution, device, queue);
+        self.intermediate_textures = [
+            Self::make_target("Post Processing Texture", &resolution, device, queue),
+            Self::make_target("Post Processing Texture", &resolution, device, queue),
+        ];
+
+        if let Some(bloom) = &mut self.bloom {
+            (bloom._texture, bloom.mip_views) = Bloom::make_texture(&resolution, device);
+        }
+
+        self.bind_groups = self.make_bind_groups(device);
+    }
+
+    /// Applies new [PostProcessingSettings].
+    /// Only realizes what changed.
+    pub fn change_settings(
+        &mut self,
+        settings: &PostProcessingSettings,
+        device: &Device,
+        queue: &Queue,
+    ) {
+        if settings.bloom.is_none() {
+            self.bloom = None;
+        } else if self.bloom.is_none() {
+            self.bloom = Some(Bloom::new(
+                &self.resolution,
+                &self.vertex_shader,
+                &self.bind_group_layout,
+                device,
+                queue,
+            ));
+        }
+
+        if settings.color_grading_lut != self.settings.color_grading_lut {
+            self.color_grading_lut = settings
+                .color_grading_lut
+                .as_ref()
+                .and_then(|descriptor| Self::realize_color_grading_lut(descriptor, device, queue));
+        }
+
+        if settings.custom_passes != self.settings.custom_passes {
+            self.custom_passes = settings
+                .custom_passes
+                .iter()
+                .map(|descriptor| self.make_custom_pass(descriptor, device, queue))
+                .collect();
+        }
+
+        self.settings = settings.clone();
+        self.write_uniform(queue);
+        self.bind_groups = self.make_bind_groups(device);
+    }
+
+    fn realize_color_grading_lut(
+        descriptor: &TextureDescriptor,
+        device: &Device,
+        queue: &Queue,
+    ) -> Option<Texture> {
+        let texture = match Texture::from_descriptor(descriptor, device, queue) {
+            Ok(texture) => texture,
+            Err(e) => {
+                warn!("Color grading lookup table realization failed, color grading is disabled: {e:?}");
+                return None;
+            }
+        };
+
+        let size = texture.texture().size();
+        if size.width != size.height * size.height {
+            warn!(
+                "Color grading lookup table must be a strip of square slices, but is {}x{}! Color grading is disabled.",
+                size.width, size.height
+            );
+            return None;
+        }
+
+        Some(texture)
+    }
+
+    fn make_custom_pass(
+        &self,
+        descriptor: &CustomPostProcessingPass,
+        device: &Device,
+        queue: &Queue,
+    ) -> CustomPass {
+        let shader = Shader::from_descriptor(descriptor.shader, device, queue)
+            .expect("Custom post processing shader realization failed!");
+
+        let parameters_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Custom Post Processing Parameters Buffer"),
+            size: Self::PARAMETERS_SIZE,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let parameters: &[f32; 4] = descriptor.parameters.as_ref();
+        queue.write_buffer(
+            &parameters_buffer,
+            0,
+            &parameters
+                .iter()
+                .flat_map(|x| x.to_le_bytes())
+                .collect::<Vec<_>>(),
+        );
+
+        CustomPass {
+            stage: descriptor.stage,
+            pass: FullscreenPass::new(
+                "Custom Post Processing",
+                shader,
+                "entrypoint_fragment",
+                &[&self.bind_group_layout],
+                &self.vertex_shader,
+                self.surface_texture_format,
+                device,
+            ),
+            parameters_buffer,
+        }
+    }
+
+    /// Writes the uniform data, matching `PostProcessingUniform` in the
+    /// shaders.
+    fn write_uniform(&self, queue: &Queue) {
+        let settings = &self.settings;
+        let bloom = settings.bloom.unwrap_or_default();
+        let vignette = settings.vignette.unwrap_or_default();
+
+        let bytes = [
+            settings.exposure.to_le_bytes(),
+            (settings.tonemapping as u32).to_le_bytes(),
+            if settings.bloom.is_some() {
+                bloom.intensity
+            } else {
+                0.0
+            }
+            .to_le_bytes(),
+            bloom.threshold.to_le_bytes(),
+            if settings.vignette.is_some() {
+                vignette.intensity
+            } else {
+                0.0
+            }
+            .to_le_bytes(),
+            vignette.radius.to_le_bytes(),
+            // Smoothstep is undefined for equal edges
+            vignette.smoothness.max(0.0001).to_le_bytes(),
+            self.color_grading_lut
+                .as_ref()
+                .map_or(0.0, |x| x.texture().height() as f32)
+                .to_le_bytes(),
+        ]
+        .concat();
+
+        queue.write_buffer(&self.uniform_buffer, 0, &bytes);
+    }
+
+    /// The frame should be rendered into this, with
+    /// [PostProcessing::HDR_FORMAT].
+    pub fn hdr_texture(&self) -> &Texture {
+        &self.hdr_texture
+    }
+
+    pub fn settings(&self) -> &PostProcessingSettings {
+        &self.settings
+    }
+
+    fn make_bind_group(
+        &self,
+        input: &TextureView,
+        parameters_buffer: &Buffer,
+        device: &Device,
+    ) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Post Processing Bind Group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(input),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(&self.sampler),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: self.uniform_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: parameters_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+
+    /// The passes to run, in order.
+    fn steps(&self) -> Vec<Step<'_>> {
+        let custom_passes = |stage| {
+            self.custom_passes
+                .iter()
+                .filter(move |x| x.stage == stage)
+                .map(Step::Custom)
+        };
+        custom_passes(PostProcessingStage::Hdr)
+            .chain([Step::Tonemapping])
+            .chain(self.settings.fxaa.then_some(Step::Fxaa))
+            .chain(custom_passes(PostProcessingStage::Ldr))
+            .collect()
+    }
+
+    /// Input of the step at the index, which is the intermediate texture
+    /// the previous step rendered into.
+    fn step_input(&self, index: usize) -> &TextureView {
+        match index {
+            0 => self.hdr_texture.view(),
+            _ => self.intermediate_textures[(index - 1) % 2].view(),
+        }
+    }
+
+    /// Makes the bind groups of all steps.
+    /// Has to be called whenever the steps, textures or settings change.
+    fn make_bind_groups(&self, device: &Device) -> BindGroups {
+        let mut bind_groups = BindGroups::default();
+
+        for (i, step) in self.steps().iter().enumerate() {
+            let input = self.step_input(i);
+
+            let step_bind_groups = match step {
+                Step::Custom(custom_pass) => {
+                    vec![self.make_bind_group(input, &custom_pass.parameters_buffer, device)]
+                }
+                Step::Tonemapping => {
+                    // Bloom is based on the high dynamic range image
+                    if let Some(bloom) = &self.bloom {
+                        bind_groups.bloom_downsample = bloom
+                            .mip_views
+                            .iter()
+                            .enumerate()
+                            .map(|(level, _)| {
+                                let source = match level {
+                                    0 => input,
+                                    _ => &bloom.mip_views[level - 1],
+                                };
+                                self.make_bind_group(source, &self.empty_parameters_buffer, device)
+                            })
+                            .collect();
+                        bind_groups.bloom_upsample = (1..bloom.mip_views.len())
+                            .rev()
+                            .map(|level| {
+                                self.make_bind_group(
+                                    &bloom.mip_views[level],
+                                    &self.empty_parameters_buffer,
+                                    device,
+                                )
+                            })
+                            .collect();
+                    }
+
+                    let tonemapping_bind_group = device.create_bind_group(&BindGroupDescriptor {
+                        label: Some("Tonemapping Bind Group"),
+                        layout: &self.tonemapping_bind_group_layout,
+                        entries: &[
+                            BindGroupEntry {
+                                binding: 0,
+                                resource: BindingResource::TextureView(
+                                    self.bloom
+                                        .as_ref()
+                                        .map_or(self.placeholder_texture.view(), |x| {
+                                            &x.mip_views[0]
+                                        }),
+                                ),
+                            },
+                            BindGroupEntry {
+                                binding: 1,
+                                resource: BindingResource::TextureView(
+                                    self.color_grading_lut
+                                        .as_ref()
+                                        .unwrap_or(&self.placeholder_texture)
+                                        .view(),
+                                ),
+                            },
+                        ],
+                    });
+
+                    vec![
+                        self.make_bind_group(input, &self.empty_parameters_buffer, device),
+                        tonemapping_bind_group,
+                    ]
+                }
+                Step::Fxaa => {
+                    vec![self.make_bind_group(input, &self.empty_parameters_buffer, device)]
+                }
+            };
+            bind_groups.steps.push(step_bind_groups);
+        }
+
+        bind_groups
+    }
+
+    /// Records all passes into the [CommandEncoder].
+    /// The last pass renders onto the target.
+    pub fn render(&self, target_view: &TextureView, encoder: &mut CommandEncoder) {
+        let steps = self.steps();
+
+        for (i, (step, bind_groups)) in steps.iter().zip(&self.bind_groups.steps).enumerate() {
+            let output = i == steps.len() - 1;
+            let target = if output {
+                target_view
+            } else {
+                self.intermediate_textures[i % 2].view()
+            };
+            let bind_groups = bind_groups.iter().collect::<Vec<_>>();
+
+            let (label, pipeline) = match step {
+                Step::Custom(custom_pass) => (
+                    "Custom Post Processing Pass",
+                    custom_pass.pass.pipeline(output),
+                ),
+                Step::Tonemapping => {
+                    if let Some(bloom) = &self.bloom {
+                        self.render_bloom(bloom, encoder);
+                    }
+                    ("Tonemapping Pass", self.tonemapping.pipeline(output))
+                }
+                Step::Fxaa => ("FXAA Pass", self.fxaa.pipeline(output)),
+            };
+            Self::draw(
+                label,
+                pipeline,
+                &bind_groups,
+                target,
+                LoadOp::Clear(Color::BLACK),
+                encoder,
+            );
+        }
+    }
+
+    /// Downsamples the input through the mip chain, then blurs each mip
+    /// back onto the one above.
+    fn render_bloom(&self, bloom: &Bloom, encoder: &mut CommandEncoder) {
+        for (level, (target, bind_group)) in bloom
+            .mip_views
+            .iter()
+            .zip(&self.bind_groups.bloom_downsample)
+            .enumerate()
+        {
+            let pipeline = match level {
+                0 => &bloom.prefilter_pipeline,
+                _ => &bloom.downsample_pipeline,
+            };
+
+            Self::draw(
+                "Bloom Downsample Pass",
+                pipeline,
+                &[bind_group],
+                target,
+                LoadOp::Clear(Color::BLACK),
+                encoder,
+            );
+        }
+
+        for (level, bind_group) in (1..bloom.mip_views.len())
+            .rev()
+            .zip(&self.bind_groups.bloom_upsample)
+        {
+            Self::draw(
+                "Bloom Upsample Pass",
+                &bloom.upsample_pipeline,
+                &[bind_group],
+                &bloom.mip_views[level - 1],
+                LoadOp::Load,
+                encoder,
+            );
+        }
+    }
+
+    /// Draws a fullscreen triangle.
+    fn draw(
+        label: &str,
+        pipeline: &RenderPipeline,
+        bind_groups: &[&BindGroup],
+        target: &TextureView,
+        load: LoadOp<Color>,
+        encoder: &mut CommandEncoder,
+    ) {
+        let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some(label),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: Operations {
+                    load,
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        render_pass.set_pipeline(pipeline);
+        for (i, bind_group) in bind_groups.iter().enumerate() {
+            render_pass.set_bind_group(i as u32, bind_group, &[]);
+        }
+        render_pass.draw(0..3, 0..1);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn make_pipeline(
+        label: &str,
+        vertex_shader: &Shader,
+        fragment_shader: &Shader,
+        entry_point: &str,
+        layout: &PipelineLayout,
+        format: TextureFormat,
+        blend: BlendState,
+        device: &Device,
+    ) -> RenderPipeline {
+        device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some(label),
+            layout: Some(layout),
+            vertex: VertexState {
+                module: vertex_shader.shader_module(),
+                entry_point: "entrypoint_vertex",
+                buffers: &[],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: Some(FragmentState {
+                module: fragment_shader.shader_module(),
+                entry_point,
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: Some(blend),
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: PipelineCompilationOptions::default(),
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+        })
+    }
+}
+
+impl FullscreenPass {
+    fn new(
+        label: &str,
+        shader: Shader,
+        entry_point: &'static str,
+        bind_group_layouts: &[&BindGroupLayout],
+        vertex_shader: &Shader,
+        surface_texture_format: TextureFormat,
+        device: &Device,
+    ) -> Self {
+        let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some(label),
+            bind_group_layouts,
+            push_constant_ranges: &[],
+        });
+
+        let make_pipeline = |format| {
+            PostProcessing::make_pipeline(
+                label,
+                vertex_shader,
+                &shader,
+                entry_point,
+                &layout,
+                format,
+                BlendState::REPLACE,
+                device,
+            )
+        };
+        let intermediate_pipeline = make_pipeline(PostProcessing::HDR_FORMAT);
+        let output_pipeline = make_pipeline(surface_texture_format);
+
+        Self {
+            shader,
+            entry_point,
+            layout,
+            intermediate_pipeline,
+            output_pipeline,
+        }
+    }
+
+    fn change_surface_texture_format(
+        &mut self,
+        vertex_shader: &Shader,
+        surface_texture_format: TextureFormat,
+        device: &Device,
+    ) {
+        self.output_pipeline = PostProcessing::make_pipeline(
+            "Post Processing Output",
+            vertex_shader,
+            &self.shader,
+            self.entry_point,
+            &self.layout,
+            surface_texture_format,
+            BlendState::REPLACE,
+            device,
+        );
+    }
+
+    fn pipeline(&self, output: bool) -> &RenderPipeline {
+        if output {
+            &self.output_pipeline
+        } else {
+            &self.intermediate_pipeline
+        }
+    }
+}
+
+impl Bloom {
+    /// Mips of the bloom texture, starting at half the resolution.
+    /// More mips spread the bloom further.
+    const MAX_MIP_LEVELS: u32 = 6;
+
+    fn new(
+        resolution: &Vector2<u32>,
+        vertex_shader: &Shader,
+        bind_group_layout: &BindGroupLayout,
+        device: &Device,
+        queue: &Queue,
+    ) -> Self {
+        let shader = Shader::from_descriptor(
+            include_str!("../resources/descriptors/shader/post_bloom.wgsl"),
+            device,
+            queue,
+        )
+        .expect("Bloom shader realization failed!");
+
+        let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Bloom Pipeline Layout"),
+            bind_group_layouts: &[bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let make_pipeline = |entry_point, blend| {
+            PostProcessing::make_pipeline(
+                "Bloom Pipeline",
+                vertex_shader,
+                &shader,
+                entry_point,
+                &layout,
+                PostProcessing::HDR_FORMAT,
+                blend,
+                device,
+            )
+        };
+        let additive = BlendComponent {
+            src_factor: BlendFactor::One,
+            dst_factor: BlendFactor::One,
+            operation: BlendOperation::Add,
+        };
+
+        let (texture, mip_views) = Self::make_texture(resolution, device);
+
+        Self {
+            _texture: texture,
+            mip_views,
+            prefilter_pipeline: make_pipeline("entrypoint_fragment_prefilter", BlendState::REPLACE),
+            downsample_pipeline: make_pipeline(
+                "entrypoint_fragment_downsample",
+                BlendState::REPLACE,
+            ),
+            upsample_pipeline: make_pipeline(
+                "entrypoint_fragment_upsample",
+                BlendState {
+                    color: additive,
+                    alpha: additive,
+                },
+            ),
+        }
+    }
+
+    /// Makes the mip chain, with a view per mip.
+    fn make_texture(resolution: &Vector2<u32>, device: &Device) -> (WTexture, Vec<TextureView>) {
+        let size = resolution.map(|x| (x / 2).max(1));
+        let mip_level_count = (size.x.min(size.y).ilog2() + 1).min(Self::MAX_MIP_LEVELS);
+
+        let texture = device.create_texture(&WTextureDescriptor {
+            label: Some("Bloom Texture"),
+            size: Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: PostProcessing::HDR_FORMAT,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+
+        let mip_views = (0..mip_level_count)
+            .map(|level| {
+                texture.create_view(&TextureViewDescriptor {
+                    label: Some("Bloom Mip View"),
+                    base_mip_level: level,
+                    mip_level_count: Some(1),
+                    ..Default::default()
+                })
+            })
+            .collect();
+
+        (texture, mip_views)
+    }
+}
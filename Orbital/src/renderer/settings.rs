@@ -0,0 +1,187 @@
+use cgmath::Vector4;
+
+use crate::resources::descriptors::{ShaderDescriptor, TextureDescriptor};
+
+/// Settings of a [Renderer](super::Renderer).
+/// Check [Renderer::change_settings](super::Renderer::change_settings).
+///
+/// Not every [Renderer](super::Renderer) supports every setting.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RendererSettings {
+    /// Culls [Instance](crate::resources::realizations::Instance)s in a
+    /// compute pass and draws them indirectly, from arguments written on
+    /// the GPU.
+    /// Meant for scenes with lots of instances, e.g. crowds or foliage.
+    ///
+    /// ⚠️ Falls back to culling on the CPU if the device doesn't support
+    /// compute shaders.  
+    /// ⚠️ [Model](crate::resources::realizations::Model)s with
+    /// [Lod](crate::resources::realizations::Lod)s or deformations are
+    /// always culled on the CPU.
+    pub gpu_culling: bool,
+    /// Passes applied to the high dynamic range image of a frame, before
+    /// it ends up on the surface.
+    /// Check [PostProcessing](super::PostProcessing).
+    pub post_processing: PostProcessingSettings,
+}
+
+/// Settings of the [PostProcessing](super::PostProcessing) stack.
+///
+/// The stack is applied in this order:
+/// 1. [CustomPostProcessingPass]es of [PostProcessingStage::Hdr]
+/// 2. Bloom
+/// 3. Exposure, tonemapping, color grading and vignette (single pass)
+/// 4. FXAA
+/// 5. [CustomPostProcessingPass]es of [PostProcessingStage::Ldr]
+///
+/// The defaults only clamp the image, i.e. look the same as without
+/// post processing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PostProcessingSettings {
+    /// Exposure in stops (EV).
+    /// Each stop doubles the brightness, negative stops halve it.
+    pub exposure: f32,
+    pub tonemapping: Tonemapping,
+    /// Bright parts of the image bleeding into their surroundings.
+    /// Disabled if [None].
+    pub bloom: Option<BloomSettings>,
+    /// Lookup table for color grading, applied after tonemapping.
+    /// Disabled if [None].
+    ///
+    /// The table is a horizontal strip of `N` slices, each `N` by `N`
+    /// pixels (e.g. 256x16 for `N = 16`).
+    /// Red increases along X and green along Y inside a slice, blue
+    /// increases from slice to slice.
+    ///
+    /// ⚠️ Tables are looked up with sRGB encoded colors, like images
+    /// ⚠️ exported from image editors.
+    pub color_grading_lut: Option<TextureDescriptor>,
+    /// Darkens the corners of the image.
+    /// Disabled if [None].
+    pub vignette: Option<VignetteSettings>,
+    /// Fast approximate anti-aliasing.
+    pub fxaa: bool,
+    /// User defined passes, applied in order.
+    pub custom_passes: Vec<CustomPostProcessingPass>,
+}
+
+impl Default for PostProcessingSettings {
+    fn default() -> Self {
+        Self {
+            exposure: 0.0,
+            tonemapping: Tonemapping::default(),
+            bloom: None,
+            color_grading_lut: None,
+            vignette: None,
+            fxaa: false,
+            custom_passes: Vec::new(),
+        }
+    }
+}
+
+/// Maps high dynamic range colors into the displayable range.
+///
+/// ⚠️ Must match the shaders!
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tonemapping {
+    /// Clamps colors, anything brighter than `1.0` is cut off.
+    #[default]
+    None = 0,
+    /// Simple `color / (1 + color)` curve.
+    Reinhard = 1,
+    /// Filmic curve, fitted to the ACES reference rendering transform.
+    Aces = 2,
+    /// Filmic curve, desaturating bright colors more naturally than
+    /// [Tonemapping::Aces].
+    AgX = 3,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BloomSettings {
+    /// Brightness above which colors start to bloom.
+    /// Applied before the exposure.
+    pub threshold: f32,
+    /// Strength of the bloom added back onto the image.
+    pub intensity: f32,
+}
+
+impl Default for BloomSettings {
+    fn default() -> Self {
+        Self {
+            threshold: 1.0,
+            intensity: 0.1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VignetteSettings {
+    /// How dark the corners get, `0.0` to `1.0`.
+    pub intensity: f32,
+    /// Distance from the center at which darkening starts.
+    /// `0.0` is the center, `1.0` the corners.
+    pub radius: f32,
+    /// Distance over which darkening fades in.
+    pub smoothness: f32,
+}
+
+impl Default for VignetteSettings {
+    fn default() -> Self {
+        Self {
+            intensity: 0.4,
+            radius: 0.5,
+            smoothness: 0.5,
+        }
+    }
+}
+
+/// When a [CustomPostProcessingPass] is applied.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PostProcessingStage {
+    /// Before bloom and tonemapping, on high dynamic range colors.
+    Hdr,
+    /// After all built-in passes, on tonemapped colors.
+    #[default]
+    Ldr,
+}
+
+/// A user defined fullscreen pass of the
+/// [PostProcessing](super::PostProcessing) stack.
+///
+/// The shader needs to provide a fragment entrypoint called
+/// `entrypoint_fragment`, the fullscreen triangle is drawn by the stack.
+/// The following interface is available:
+/// ```wgsl
+/// struct FragmentData {
+///     @builtin(position) position: vec4<f32>,
+///     @location(0) uv: vec2<f32>,
+/// }
+///
+/// // Result of the previous pass
+/// @group(0) @binding(0)
+/// var input_texture: texture_2d<f32>;
+/// // Linear, clamped to the edges
+/// @group(0) @binding(1)
+/// var input_sampler: sampler;
+/// // Check `post_processing.wgsl`
+/// @group(0) @binding(2)
+/// var<uniform> post_processing: PostProcessingUniform;
+/// // CustomPostProcessingPass::parameters
+/// @group(0) @binding(3)
+/// var<uniform> parameters: vec4<f32>;
+///
+/// @fragment
+/// fn entrypoint_fragment(fragment: FragmentData) -> @location(0) vec4<f32> {
+///     return textureSample(input_texture, input_sampler, fragment.uv);
+/// }
+/// ```
+///
+/// ⚠️ Bindings which aren't used may be left out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomPostProcessingPass {
+    /// WGSL source of the pass.
+    pub shader: ShaderDescriptor,
+    pub stage: PostProcessingStage,
+    /// Passed to the shader as is.
+    pub parameters: Vector4<f32>,
+}
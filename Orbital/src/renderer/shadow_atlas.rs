@@ -0,0 +1,555 @@
+use std::{collections::HashMap, num::NonZeroU64};
+
+use cgmath::{Matrix4, Vector2};
+use log::{error, warn};
+use wgpu::{
+    AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, Buffer,
+    BufferBinding, BufferBindingType, BufferDescriptor, BufferUsages, CommandEncoder,
+    CompareFunction, DepthBiasState, DepthStencilState, Device, FilterMode, FragmentState,
+    IndexFormat, LoadOp, MultisampleState, Operations, PipelineCompilationOptions,
+    PipelineLayoutDescriptor, PrimitiveState, Queue, RenderPassDepthStencilAttachment,
+    RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor, Sampler, SamplerDescriptor,
+    ShaderStages, StencilState, StoreOp, TextureFormat, VertexState,
+};
+
+use crate::resources::{
+    descriptors::{AlphaMode, TextureDescriptor},
+    realizations::{Camera, Light, Model, Pipeline, Shader, Texture},
+};
+
+/// A single depth texture, containing the shadow maps of all
+/// shadow casting [Light]s.
+///
+/// Each frame, every shadow casting [Light] gets a square tile of it's
+/// [ShadowDescriptor::resolution] assigned inside the atlas.
+/// All [Model]s are then rendered from the perspective of each [Light]
+/// into it's tile.
+///
+/// [AlphaMode::Mask] [Model]s discard fragments below their cutoff, so
+/// e.g. foliage casts the shadow of it's leaves instead of whole quads.
+/// [AlphaMode::Blend] [Model]s don't cast shadows at all, as partially
+/// see-through shadows can't be stored in a depth texture.
+///
+/// [ShadowDescriptor::resolution]: crate::resources::descriptors::ShadowDescriptor::resolution
+pub struct ShadowAtlas {
+    texture: Texture,
+    comparison_sampler: Sampler,
+    pipeline: RenderPipeline,
+    /// Same as `pipeline`, but for [Model]s with a
+    /// [Skeleton](crate::resources::realizations::Skeleton).
+    skinned_pipeline: RenderPipeline,
+    /// Same as `pipeline`, but for [Model]s with
+    /// [MorphTarget](crate::resources::realizations::MorphTarget)s.
+    morphed_pipeline: RenderPipeline,
+    /// Same as `pipeline`, but for [Model]s with both.
+    skinned_morphed_pipeline: RenderPipeline,
+    /// Variants of the above for [AlphaMode::Mask] [Model]s, keyed by
+    /// skinned, morphed and the alpha cutoff.
+    /// Made once needed.
+    masked_pipelines: HashMap<(bool, bool, u8), RenderPipeline>,
+    matrix_bind_group_layout: BindGroupLayout,
+    matrix_buffer: Buffer,
+    matrix_bind_group: BindGroup,
+    matrix_stride: u64,
+    matrix_capacity: usize,
+}
+
+/// A tile inside the [ShadowAtlas], assigned to a single [Light].
+struct ShadowTile {
+    view_projection_matrix: Matrix4<f32>,
+    offset: Vector2<u32>,
+    resolution: u32,
+    bias: f32,
+}
+
+impl ShadowAtlas {
+    /// Width and height of the shadow atlas in pixels.
+    pub const SIZE: u32 = 4096;
+
+    /// Size of a single `ShadowData` entry inside a buffer in bytes.
+    /// Check [Light] for the layout.
+    pub const SHADOW_DATA_SIZE: u64 = 4 * 24;
+
+    /// Size of a single view projection matrix in bytes.
+    const MATRIX_SIZE: u64 = 4 * 16;
+
+    pub fn new(device: &Device, queue: &Queue) -> Self {
+        let texture = Texture::from_descriptor(
+            &TextureDescriptor::Depth(Vector2::new(Self::SIZE, Self::SIZE)),
+            device,
+            queue,
+        )
+        .expect("Shadow atlas realization failed!");
+
+        let comparison_sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("Shadow Atlas Sampler"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: FilterMode::Nearest,
+            compare: Some(CompareFunction::LessEqual),
+            ..Default::default()
+        });
+
+        let matrix_bind_group_layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("Shadow Matrix"),
+                entries: &[BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::VERTEX,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: NonZeroU64::new(Self::MATRIX_SIZE),
+                    },
+                    count: None,
+                }],
+            });
+
+        let pipeline =
+            Self::make_pipeline(&matrix_bind_group_layout, false, false, None, device, queue);
+        let skinned_pipeline =
+            Self::make_pipeline(&matrix_bind_group_layout, true, false, None, device, queue);
+        let morphed_pipeline =
+            Self::make_pipeline(&matrix_bind_group_layout, false, true, None, device, queue);
+        let skinned_morphed_pipeline =
+            Self::make_pipeline(&matrix_bind_group_layout, true, true, None, device, queue);
+
+        // Dynamic offsets must be aligned
+        let alignment = device.limits().min_uniform_buffer_offset_alignment as u64;
+        let matrix_stride = Self::MATRIX_SIZE.div_ceil(alignment) * alignment;
+
+        let (matrix_buffer, matrix_bind_group) =
+            Self::make_matrix_storage(1, matrix_stride, &matrix_bind_group_layout, device);
+
+        Self {
+            texture,
+            comparison_sampler,
+            pipeline,
+            skinned_pipeline,
+            morphed_pipeline,
+            skinned_morphed_pipeline,
+            masked_pipelines: HashMap::new(),
+            matrix_bind_group_layout,
+            matrix_buffer,
+            matrix_bind_group,
+            matrix_stride,
+            matrix_capacity: 1,
+        }
+    }
+
+    /// Makes a shadow pipeline for the given deformations.
+    /// Pipelines with an `alpha_cutoff` additionally sample the albedo
+    /// texture and discard fragments below it, check [AlphaMode::Mask].
+    fn make_pipeline(
+        matrix_bind_group_layout: &BindGroupLayout,
+        skinned: bool,
+        morphed: bool,
+        alpha_cutoff: Option<u8>,
+        device: &Device,
+        queue: &Queue,
+    ) -> RenderPipeline {
+        let shader = Shader::from_descriptor(
+            include_str!("../resources/descriptors/shader/shadow.wgsl"),
+            device,
+            queue,
+        )
+        .expect("Shadow shader realization failed!");
+
+        // Deformed models additionally bind their deformation buffers,
+        // masked ones their albedo texture too
+        let deformation_bind_group_layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("Deformation"),
+                entries: &match alpha_cutoff {
+                    Some(_) => Pipeline::masked_shadow_bind_group_layout_entries(skinned, morphed),
+                    None => Pipeline::deformation_bind_group_layout_entries(skinned, morphed, 0),
+                },
+            });
+        let bind_group_layouts = if skinned || morphed || alpha_cutoff.is_some() {
+            vec![matrix_bind_group_layout, &deformation_bind_group_layout]
+        } else {
+            vec![matrix_bind_group_layout]
+        };
+
+        let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Shadow Pipeline Layout"),
+            bind_group_layouts: &bind_group_layouts,
+            push_constant_ranges: &[],
+        });
+        let entry_point = Pipeline::vertex_entry_point(skinned, morphed);
+        let buffers = Pipeline::vertex_buffer_layouts(skinned);
+        let constants = alpha_cutoff
+            .map(|cutoff| Pipeline::fragment_constants(&AlphaMode::Mask { cutoff }))
+            .unwrap_or_default();
+
+        device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Shadow Pipeline"),
+            layout: Some(&layout),
+            vertex: VertexState {
+                module: shader.shader_module(),
+                entry_point,
+                buffers: &buffers,
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            // Depth only, masked pipelines discard fragments below the
+            // cutoff
+            fragment: alpha_cutoff.map(|_| FragmentState {
+                module: shader.shader_module(),
+                entry_point: "entrypoint_fragment_masked",
+                targets: &[],
+                compilation_options: PipelineCompilationOptions {
+                    constants: &constants,
+                    ..Default::default()
+                },
+            }),
+            primitive: PrimitiveState {
+                // Culling is disabled, as not every mesh is closed.
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: Some(DepthStencilState {
+                format: TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: CompareFunction::Less,
+                stencil: StencilState::default(),
+                // Slope scaled bias to reduce shadow acne on steep surfaces.
+                // The per-light bias is applied during sampling.
+                bias: DepthBiasState {
+                    constant: 2,
+                    slope_scale: 2.0,
+                    clamp: 0.0,
+                },
+            }),
+            multisample: MultisampleState::default(),
+            multiview: None,
+        })
+    }
+
+    /// Returns the pipeline matching the deformations of a [Model].
+    fn pipeline(&self, skinned: bool, morphed: bool) -> &RenderPipeline {
+        match (skinned, morphed) {
+            (false, false) => &self.pipeline,
+            (true, false) => &self.skinned_pipeline,
+            (false, true) => &self.morphed_pipeline,
+            (true, true) => &self.skinned_morphed_pipeline,
+        }
+    }
+
+    /// Makes the masked pipeline matching a [Model], if it doesn't
+    /// exist yet.
+    fn prepare_masked_pipeline(
+        &mut self,
+        skinned: bool,
+        morphed: bool,
+        cutoff: u8,
+        device: &Device,
+        queue: &Queue,
+    ) {
+        if self
+            .masked_pipelines
+            .contains_key(&(skinned, morphed, cutoff))
+        {
+            return;
+        }
+
+        let pipeline = Self::make_pipeline(
+            &self.matrix_bind_group_layout,
+            skinned,
+            morphed,
+            Some(cutoff),
+            device,
+            queue,
+        );
+        self.masked_pipelines
+            .insert((skinned, morphed, cutoff), pipeline);
+    }
+
+    fn make_matrix_storage(
+        capacity: usize,
+        matrix_stride: u64,
+        matrix_bind_group_layout: &BindGroupLayout,
+        device: &Device,
+    ) -> (Buffer, BindGroup) {
+        let matrix_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Shadow Matrix Buffer"),
+            size: matrix_stride * capacity as u64,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let matrix_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Shadow Matrix Bind Group"),
+            layout: matrix_bind_group_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: BindingResource::Buffer(BufferBinding {
+                    buffer: &matrix_buffer,
+                    offset: 0,
+                    size: NonZeroU64::new(Self::MATRIX_SIZE),
+                }),
+            }],
+        });
+
+        (matrix_buffer, matrix_bind_group)
+    }
+
+    /// Assigns a tile inside the atlas to each shadow casting [Light].
+    ///
+    /// Tiles are packed in rows (_shelves_), biggest resolution first.
+    /// [Light]s which don't fit anymore won't cast shadows.
+    fn allocate(lights: &[&Light], camera: &Camera) -> Vec<Option<ShadowTile>> {
+        let mut tiles = lights.iter().map(|_| None).collect::<Vec<_>>();
+
+        let mut shadow_casters = lights
+            .iter()
+            .enumerate()
+            .filter_map(|(i, light)| {
+                let shadow = light.descriptor().shadow?;
+                let matrix =
+                    light.calculate_shadow_view_projection_matrix(camera.descriptor().position)?;
+
+                Some((i, shadow, matrix))
+            })
+            .collect::<Vec<_>>();
+        shadow_casters.sort_by_key(|(_, shadow, _)| std::cmp::Reverse(shadow.resolution));
+
+        let mut cursor = Vector2::new(0, 0);
+        let mut shelf_height = 0;
+        for (i, shadow, view_projection_matrix) in shadow_casters {
+            let resolution = shadow.resolution.clamp(1, Self::SIZE);
+
+            // Start a new shelf, if this row is full
+            if cursor.x + resolution > Self::SIZE {
+                cursor = Vector2::new(0, cursor.y + shelf_height);
+                shelf_height = 0;
+            }
+
+            if cursor.y + resolution > Self::SIZE {
+                warn!(
+                    "Shadow atlas is full! Light '{}' won't cast shadows.",
+                    lights[i].descriptor().identifier
+                );
+                continue;
+            }
+
+            tiles[i] = Some(ShadowTile {
+                view_projection_matrix,
+                offset: cursor,
+                resolution,
+                bias: shadow.bias,
+            });
+
+            cursor.x += resolution;
+            shelf_height = shelf_height.max(resolution);
+        }
+
+        tiles
+    }
+
+    /// Renders the shadow maps of all shadow casting [Light]s into the atlas.
+    ///
+    /// Returns the `ShadowData` for each [Light], in the same order as
+    /// `lights`, ready to be uploaded into a storage buffer.
+    ///
+    /// `surface_format` and `sample_count` must match the ones the
+    /// [Model]s are drawn with afterwards, as the albedo of
+    /// [AlphaMode::Mask] [Model]s is taken from their [Material].
+    ///
+    /// [Material]: crate::resources::realizations::Material
+    #[allow(clippy::too_many_arguments)]
+    pub fn render(
+        &mut self,
+        lights: &[&Light],
+        models: &[&Model],
+        camera: &Camera,
+        surface_format: &TextureFormat,
+        sample_count: u32,
+        encoder: &mut CommandEncoder,
+        device: &Device,
+        queue: &Queue,
+    ) -> Vec<u8> {
+        let tiles = Self::allocate(lights, camera);
+        let active_tiles = tiles.iter().flatten().collect::<Vec<_>>();
+
+        if active_tiles.len() > self.matrix_capacity {
+            let (matrix_buffer, matrix_bind_group) = Self::make_matrix_storage(
+                active_tiles.len(),
+                self.matrix_stride,
+                &self.matrix_bind_group_layout,
+                device,
+            );
+
+            self.matrix_buffer = matrix_buffer;
+            self.matrix_bind_group = matrix_bind_group;
+            self.matrix_capacity = active_tiles.len();
+        }
+
+        for (i, tile) in active_tiles.iter().enumerate() {
+            let matrix: &[f32; 16] = tile.view_projection_matrix.as_ref();
+            let bytes = matrix
+                .iter()
+                .flat_map(|x| x.to_le_bytes())
+                .collect::<Vec<_>>();
+
+            queue.write_buffer(&self.matrix_buffer, self.matrix_stride * i as u64, &bytes);
+        }
+
+        // Nothing to render, the atlas won't be sampled anyways.
+        if !active_tiles.is_empty() {
+            // Blended models can't be stored in a depth texture, thus
+            // they don't cast shadows at all.
+            // Masked ones are cut out by their albedo, check
+            // `entrypoint_fragment_masked`.
+            let mut shadow_casters = Vec::new();
+            for model in models {
+                let masked_bind_group = match model.alpha_mode() {
+                    AlphaMode::Opaque => None,
+                    AlphaMode::Mask { cutoff } => {
+                        match model.material(surface_format, sample_count, device, queue) {
+                            Ok(material) => {
+                                self.prepare_masked_pipeline(
+                                    model.is_skinned(),
+                                    model.is_morphed(),
+                                    cutoff,
+                                    device,
+                                    queue,
+                                );
+
+                                Some((cutoff, model.masked_shadow_bind_group(material, device)))
+                            }
+                            Err(e) => {
+                                error!("Material failure, casting an opaque shadow: {:#?}", e);
+                                None
+                            }
+                        }
+                    }
+                    AlphaMode::Blend => continue,
+                };
+
+                shadow_casters.push((model, masked_bind_group));
+            }
+
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Shadow Pass"),
+                color_attachments: &[],
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                    view: self.texture.view(),
+                    depth_ops: Some(Operations {
+                        load: LoadOp::Clear(1.0),
+                        store: StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            for (i, tile) in active_tiles.iter().enumerate() {
+                render_pass.set_viewport(
+                    tile.offset.x as f32,
+                    tile.offset.y as f32,
+                    tile.resolution as f32,
+                    tile.resolution as f32,
+                    0.0,
+                    1.0,
+                );
+                render_pass.set_scissor_rect(
+                    tile.offset.x,
+                    tile.offset.y,
+                    tile.resolution,
+                    tile.resolution,
+                );
+
+                for (model, masked_bind_group) in &shadow_casters {
+                    let mesh = model.mesh();
+                    let (skinned, morphed) = (model.is_skinned(), model.is_morphed());
+
+                    match masked_bind_group {
+                        Some((cutoff, masked_bind_group)) => {
+                            render_pass
+                                .set_pipeline(&self.masked_pipelines[&(skinned, morphed, *cutoff)]);
+                            render_pass.set_bind_group(1, masked_bind_group, &[]);
+                        }
+                        None => {
+                            render_pass.set_pipeline(self.pipeline(skinned, morphed));
+                            if let Some(deformation_bind_group) =
+                                model.deformation_bind_group(device)
+                            {
+                                render_pass.set_bind_group(1, deformation_bind_group, &[]);
+                            }
+                        }
+                    }
+                    if let (true, Some(skin_buffer)) = (skinned, mesh.skin_buffer()) {
+                        render_pass.set_vertex_buffer(2, skin_buffer.slice(..));
+                    }
+                    render_pass.set_bind_group(
+                        0,
+                        &self.matrix_bind_group,
+                        &[(self.matrix_stride * i as u64) as u32],
+                    );
+
+                    render_pass.set_vertex_buffer(0, mesh.vertex_buffer().slice(..));
+                    render_pass.set_vertex_buffer(1, model.instance_buffer().slice(..));
+                    render_pass
+                        .set_index_buffer(mesh.index_buffer().slice(..), IndexFormat::Uint32);
+
+                    render_pass.draw_indexed(
+                        0..mesh.index_count(),
+                        0,
+                        0..model.instances().len() as u32,
+                    );
+                }
+            }
+        }
+
+        tiles
+            .iter()
+            .flat_map(|tile| Self::shadow_data_to_bytes(tile.as_ref()))
+            .collect()
+    }
+
+    /// Converts a [ShadowTile] into bytes, matching the `ShadowData` layout.
+    /// [None] results in a disabled shadow.
+    fn shadow_data_to_bytes(tile: Option<&ShadowTile>) -> Vec<u8> {
+        let Some(tile) = tile else {
+            return vec![0; Self::SHADOW_DATA_SIZE as usize];
+        };
+
+        let matrix: &[f32; 16] = tile.view_projection_matrix.as_ref();
+        let atlas_rect = [
+            tile.offset.x as f32 / Self::SIZE as f32,
+            tile.offset.y as f32 / Self::SIZE as f32,
+            tile.resolution as f32 / Self::SIZE as f32,
+            tile.resolution as f32 / Self::SIZE as f32,
+        ];
+
+        [
+            matrix
+                .iter()
+                .flat_map(|x| x.to_le_bytes())
+                .collect::<Vec<_>>(),
+            atlas_rect
+                .iter()
+                .flat_map(|x| x.to_le_bytes())
+                .collect::<Vec<_>>(),
+            tile.bias.to_le_bytes().to_vec(),
+            // Enabled
+            1u32.to_le_bytes().to_vec(),
+            // Padding
+            vec![0; 8],
+        ]
+        .concat()
+    }
+
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    pub fn comparison_sampler(&self) -> &Sampler {
+        &self.comparison_sampler
+    }
+}
@@ -0,0 +1,253 @@
+use cgmath::SquareMatrix;
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingResource, BindingType, BlendState, Buffer, BufferBindingType,
+    BufferDescriptor, BufferUsages, ColorTargetState, ColorWrites, CompareFunction,
+    DepthStencilState, Device, FragmentState, Id, MultisampleState, PipelineCompilationOptions,
+    PipelineLayoutDescriptor, PrimitiveState, Queue, RenderPass, RenderPipeline,
+    RenderPipelineDescriptor, SamplerBindingType, ShaderStages, StencilState, TextureFormat,
+    TextureSampleType, TextureView, TextureViewDimension, VertexState,
+};
+
+use crate::resources::realizations::{Camera, Environment, Shader};
+
+/// Draws an [Environment] as a skybox behind everything else.
+///
+/// The skybox is drawn at the far plane, with depth testing enabled.
+/// Thus, it should be drawn **after** all opaque geometry to only shade
+/// pixels which haven't been covered yet.
+pub struct Skybox {
+    surface_texture_format: TextureFormat,
+    sample_count: u32,
+    pipeline: RenderPipeline,
+    bind_group_layout: BindGroupLayout,
+    buffer: Buffer,
+    /// Made once per environment cube, identified by its view
+    bind_group: Option<(Id<TextureView>, BindGroup)>,
+}
+
+impl Skybox {
+    /// `sample_count` must match the render pass the skybox is drawn in.
+    pub fn new(
+        surface_texture_format: TextureFormat,
+        sample_count: u32,
+        device: &Device,
+        queue: &Queue,
+    ) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Skybox"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::Cube,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Skybox Buffer"),
+            // Inverse View Projection Matrix (4x4 f32) + Camera Position (3x f32) + Intensity (f32)
+            size: 4 * (4 * 4 + 4),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let pipeline = Self::make_pipeline(
+            surface_texture_format,
+            sample_count,
+            &bind_group_layout,
+            device,
+            queue,
+        );
+
+        Self {
+            surface_texture_format,
+            sample_count,
+            pipeline,
+            bind_group_layout,
+            buffer,
+            bind_group: None,
+        }
+    }
+
+    fn make_pipeline(
+        surface_texture_format: TextureFormat,
+        sample_count: u32,
+        bind_group_layout: &BindGroupLayout,
+        device: &Device,
+        queue: &Queue,
+    ) -> RenderPipeline {
+        let shader = Shader::from_descriptor(
+            include_str!("../resources/descriptors/shader/skybox.wgsl"),
+            device,
+            queue,
+        )
+        .expect("Skybox shader realization failed!");
+
+        let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Skybox Pipeline Layout"),
+            bind_group_layouts: &[bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Skybox Pipeline"),
+            layout: Some(&layout),
+            vertex: VertexState {
+                module: shader.shader_module(),
+                entry_point: "entrypoint_vertex",
+                buffers: &[],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: Some(FragmentState {
+                module: shader.shader_module(),
+                entry_point: "entrypoint_fragment",
+                targets: &[Some(ColorTargetState {
+                    format: surface_texture_format,
+                    blend: Some(BlendState::REPLACE),
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: PipelineCompilationOptions::default(),
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: Some(DepthStencilState {
+                format: TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                // The skybox is exactly at the far plane
+                depth_compare: CompareFunction::LessEqual,
+                stencil: StencilState::default(),
+                bias: Default::default(),
+            }),
+            multisample: MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+        })
+    }
+
+    /// Remakes the pipeline to match a new surface texture format.
+    pub fn change_surface_texture_format(
+        &mut self,
+        surface_texture_format: TextureFormat,
+        device: &Device,
+        queue: &Queue,
+    ) {
+        self.surface_texture_format = surface_texture_format;
+        self.pipeline = Self::make_pipeline(
+            surface_texture_format,
+            self.sample_count,
+            &self.bind_group_layout,
+            device,
+            queue,
+        );
+    }
+
+    /// Remakes the pipeline to match a new sample count.
+    pub fn change_sample_count(&mut self, sample_count: u32, device: &Device, queue: &Queue) {
+        self.sample_count = sample_count;
+        self.pipeline = Self::make_pipeline(
+            self.surface_texture_format,
+            sample_count,
+            &self.bind_group_layout,
+            device,
+            queue,
+        );
+    }
+
+    /// Updates the skybox to match the given [Camera] and [Environment].
+    /// Must be called before [Skybox::draw].
+    /// The bind group is only remade if the [Environment] changed.
+    pub fn update(
+        &mut self,
+        camera: &Camera,
+        environment: &Environment,
+        device: &Device,
+        queue: &Queue,
+    ) {
+        let inverse_view_projection_matrix = camera
+            .calculate_view_projection_matrix()
+            .invert()
+            .unwrap_or_else(SquareMatrix::identity);
+        let matrix: &[f32; 16] = inverse_view_projection_matrix.as_ref();
+        let position = camera.descriptor().position;
+
+        let bytes = [
+            matrix
+                .iter()
+                .flat_map(|x| x.to_le_bytes())
+                .collect::<Vec<_>>(),
+            [
+                position.x.to_le_bytes(),
+                position.y.to_le_bytes(),
+                position.z.to_le_bytes(),
+                environment.descriptor().intensity.to_le_bytes(),
+            ]
+            .concat(),
+        ]
+        .concat();
+
+        queue.write_buffer(&self.buffer, 0, &bytes);
+
+        let environment_cube = environment.environment_cube();
+        if self
+            .bind_group
+            .as_ref()
+            .is_some_and(|(id, _)| *id == environment_cube.view().global_id())
+        {
+            return;
+        }
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Skybox Bind Group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(environment_cube.view()),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(environment_cube.sampler()),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: self.buffer.as_entire_binding(),
+                },
+            ],
+        });
+        self.bind_group = Some((environment_cube.view().global_id(), bind_group));
+    }
+
+    /// Draws the skybox.
+    /// If [Skybox::update] hasn't been called yet, nothing will be drawn.
+    pub fn draw<'a>(&'a self, render_pass: &mut RenderPass<'a>) {
+        if let Some((_, bind_group)) = &self.bind_group {
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+    }
+}
@@ -1,23 +1,51 @@
 use cgmath::Vector2;
 use wgpu::{
-    Color, CommandEncoderDescriptor, Device, IndexFormat, LoadOp, Operations, Queue,
-    RenderPassColorAttachment, RenderPassDepthStencilAttachment, RenderPassDescriptor, StoreOp,
-    TextureFormat, TextureView,
+    Buffer, Color, CommandEncoderDescriptor, Device, Extent3d, LoadOp, Operations, Queue,
+    RenderPassColorAttachment, RenderPassDepthStencilAttachment, RenderPassDescriptor,
+    SamplerDescriptor, StoreOp, TextureDescriptor as WTextureDescriptor, TextureDimension,
+    TextureFormat, TextureUsages, TextureView, TextureViewDescriptor,
 };
 
 use crate::{
-    log::error,
+    log::warn,
     resources::{
-        descriptors::TextureDescriptor,
-        realizations::{Camera, Model, Pipeline, Texture},
+        descriptors::{EnvironmentDescriptor, PipelineDescriptor, TextureDescriptor},
+        realizations::{Camera, Environment, Light, Model, Texture},
     },
 };
 
-use super::Renderer;
+use super::{
+    draw::Draw, GpuCulling, LightStorage, PostProcessing, RenderStats, Renderer, RendererSettings,
+    Skybox,
+};
 
+/// Renders all [Model]s in a single forward pass into the high dynamic
+/// range target of the [PostProcessing] stack.
+/// Opaque [Model]s are drawn first, followed by blended ones sorted back
+/// to front (check
+/// [AlphaMode::Blend](crate::resources::descriptors::AlphaMode::Blend)).
+///
+/// Supports MSAA, check [Renderer::change_sample_count].
+/// Multisampled results are resolved into the high dynamic range target.
 pub struct StandardRenderer {
-    surface_texture_format: TextureFormat,
+    resolution: Vector2<u32>,
+    sample_count: u32,
     depth_texture: Texture,
+    /// Only set if MSAA is enabled, resolved into the
+    /// [PostProcessing::hdr_texture]
+    multisampled_texture: Option<Texture>,
+    light_storage: LightStorage,
+    /// Used in case the [World](crate::game::World) has no [Environment].
+    fallback_environment: Environment,
+    skybox: Skybox,
+    post_processing: PostProcessing,
+    /// Instances of merged draws, copied one after another.
+    /// Made once draws get merged.
+    batch_instance_buffer: Option<Buffer>,
+    /// Only set if enabled via [RendererSettings::gpu_culling] and
+    /// supported
+    gpu_culling: Option<GpuCulling>,
+    stats: RenderStats,
 }
 
 impl Renderer for StandardRenderer {
@@ -28,13 +56,27 @@ impl Renderer for StandardRenderer {
         queue: &wgpu::Queue,
     ) -> Self {
         Self {
-            surface_texture_format,
+            resolution,
+            sample_count: 1,
             depth_texture: Texture::from_descriptor(
                 &TextureDescriptor::Depth(resolution),
                 device,
                 queue,
             )
             .expect("Depth texture realization failed!"),
+            multisampled_texture: None,
+            light_storage: LightStorage::new(device, queue),
+            fallback_environment: Environment::from_descriptor(
+                EnvironmentDescriptor::default(),
+                device,
+                queue,
+            )
+            .expect("Fallback environment realization failed!"),
+            skybox: Skybox::new(PostProcessing::HDR_FORMAT, 1, device, queue),
+            post_processing: PostProcessing::new(surface_texture_format, resolution, device, queue),
+            batch_instance_buffer: None,
+            gpu_culling: None,
+            stats: RenderStats::default(),
         }
     }
 
@@ -42,19 +84,52 @@ impl Renderer for StandardRenderer {
         &mut self,
         surface_texture_format: TextureFormat,
         device: &Device,
-        queue: &Queue,
+        _queue: &Queue,
     ) {
-        // Set the format internally
-        self.surface_texture_format = surface_texture_format;
-
-        // The cache will automatically recompile itself
-        // once a new format is used to access the cache.
-        let _ = Pipeline::prepare_cache_access(Some(&surface_texture_format), device, queue);
+        // Everything else renders into the HDR texture, which keeps its
+        // format
+        self.post_processing
+            .change_surface_texture_format(surface_texture_format, device);
     }
 
     fn change_resolution(&mut self, resolution: Vector2<u32>, device: &Device, queue: &Queue) {
-        // Remake the depth texture with the new size
-        self.depth_texture = Texture::depth_texture(&resolution, device, queue);
+        self.resolution = resolution;
+
+        // Remake the render targets with the new size
+        self.make_render_targets(device, queue);
+        self.post_processing
+            .change_resolution(resolution, device, queue);
+    }
+
+    fn change_sample_count(&mut self, sample_count: u32, device: &Device, queue: &Queue) {
+        if sample_count == self.sample_count {
+            return;
+        }
+        self.sample_count = sample_count;
+
+        // The pipeline cache recompiles itself once a new sample count is
+        // used to access the cache.
+        self.make_render_targets(device, queue);
+        self.skybox.change_sample_count(sample_count, device, queue);
+    }
+
+    fn change_settings(&mut self, settings: &RendererSettings, device: &Device, queue: &Queue) {
+        if !settings.gpu_culling {
+            self.gpu_culling = None;
+        } else if self.gpu_culling.is_none() {
+            if GpuCulling::is_supported(device) {
+                self.gpu_culling = Some(GpuCulling::new(device, queue));
+            } else {
+                warn!("GPU culling isn't supported by this device, falling back to CPU culling!");
+            }
+        }
+
+        self.post_processing
+            .change_settings(&settings.post_processing, device, queue);
+    }
+
+    fn is_gpu_culling(&self) -> bool {
+        self.gpu_culling.is_some()
     }
 
     fn update(&mut self, _delta_time: f64) {}
@@ -65,20 +140,78 @@ impl Renderer for StandardRenderer {
         device: &Device,
         queue: &Queue,
         models: &[&Model],
+        lights: &[&Light],
+        environment: Option<&Environment>,
         camera: &Camera,
     ) {
+        let draw_skybox = environment.is_some_and(|x| x.descriptor().draw_skybox);
+        let environment = environment.unwrap_or(&self.fallback_environment);
+
         let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor { label: None });
+
+        self.light_storage.update(
+            lights,
+            models,
+            camera,
+            &PostProcessing::HDR_FORMAT,
+            self.sample_count,
+            &mut encoder,
+            device,
+            queue,
+        );
+
+        if draw_skybox {
+            self.skybox.update(camera, environment, device, queue);
+        }
+
+        let draws = Draw::from_models(
+            models,
+            camera,
+            &PostProcessing::HDR_FORMAT,
+            self.sample_count,
+            PipelineDescriptor::clone,
+            self.gpu_culling.is_some(),
+            device,
+            queue,
+        );
+
+        self.stats = RenderStats::default();
+        let mut draws = Draw::sort_and_merge(draws, &mut self.stats);
+        Draw::copy_batched_instances(
+            &mut draws,
+            &mut self.batch_instance_buffer,
+            &mut encoder,
+            device,
+        );
+        if let Some(gpu_culling) = &mut self.gpu_culling {
+            Draw::cull_on_gpu(&mut draws, gpu_culling, camera, &mut encoder, device, queue);
+        }
+
         {
-            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
-                label: Some("Render Pass"),
-                color_attachments: &[Some(RenderPassColorAttachment {
-                    view: target_view,
+            // Multisampled results only need to survive until resolved
+            let hdr_view = self.post_processing.hdr_texture().view();
+            let color_attachment = match &self.multisampled_texture {
+                Some(multisampled_texture) => RenderPassColorAttachment {
+                    view: multisampled_texture.view(),
+                    resolve_target: Some(hdr_view),
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::BLACK),
+                        store: StoreOp::Discard,
+                    },
+                },
+                None => RenderPassColorAttachment {
+                    view: hdr_view,
                     resolve_target: None,
                     ops: Operations {
                         load: LoadOp::Clear(Color::BLACK),
                         store: StoreOp::Store,
                     },
-                })],
+                },
+            };
+
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Render Pass"),
+                color_attachments: &[Some(color_attachment)],
                 depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
                     view: self.depth_texture.view(),
                     depth_ops: Some(Operations {
@@ -91,47 +224,78 @@ impl Renderer for StandardRenderer {
                 occlusion_query_set: None,
             });
 
-            for model in models {
-                let mesh = model.mesh();
-                let material = match model.material(&self.surface_texture_format, device, queue) {
-                    Ok(material) => material,
-                    Err(e) => {
-                        error!("Material failure: {:#?}", e);
-                        error!("Skipping model render!");
-                        continue;
-                    }
-                };
-
-                let pipeline = match Pipeline::from_descriptor(
-                    material.pipeline_descriptor(),
-                    &self.surface_texture_format,
-                    device,
-                    queue,
-                ) {
-                    Ok(pipeline) => pipeline,
-                    Err(e) => {
-                        error!("Pipeline in invalid state! Error: {:?}", e);
-                        continue;
-                    }
-                };
-
-                render_pass.set_pipeline(pipeline.render_pipeline());
-
-                render_pass.set_bind_group(0, material.bind_group(), &[]);
-                render_pass.set_bind_group(1, camera.bind_group(), &[]);
-
-                render_pass.set_vertex_buffer(0, mesh.vertex_buffer().slice(..));
-                render_pass.set_vertex_buffer(1, model.instance_buffer().slice(..));
-                render_pass.set_index_buffer(mesh.index_buffer().slice(..), IndexFormat::Uint32);
-
-                render_pass.draw_indexed(
-                    0..mesh.index_count(),
-                    0,
-                    0..model.instances().len() as u32,
-                );
+            let bind_groups = [
+                camera.bind_group(),
+                self.light_storage.bind_group(),
+                environment.bind_group(),
+            ];
+            let (opaque_draws, transparent_draws) = draws.split_at(Draw::first_transparent(&draws));
+
+            Draw::record(
+                opaque_draws,
+                &mut render_pass,
+                &bind_groups,
+                self.batch_instance_buffer.as_ref(),
+                self.gpu_culling.as_ref(),
+                &mut self.stats,
+            );
+
+            // The skybox is drawn after all opaque draws, to only shade
+            // uncovered pixels
+            if draw_skybox {
+                self.skybox.draw(&mut render_pass);
             }
+
+            // Transparent draws don't write depth, thus they come last
+            // to blend with everything behind them
+            Draw::record(
+                transparent_draws,
+                &mut render_pass,
+                &bind_groups,
+                self.batch_instance_buffer.as_ref(),
+                self.gpu_culling.as_ref(),
+                &mut self.stats,
+            );
         }
 
+        self.post_processing.render(target_view, &mut encoder);
+
         queue.submit(Some(encoder.finish()));
     }
+
+    fn stats(&self) -> RenderStats {
+        self.stats
+    }
+}
+
+impl StandardRenderer {
+    /// Remakes the depth texture and, if MSAA is enabled, the
+    /// multisampled texture to match the resolution and sample count.
+    fn make_render_targets(&mut self, device: &Device, queue: &Queue) {
+        self.depth_texture =
+            Texture::multisampled_depth_texture(&self.resolution, self.sample_count, device, queue);
+
+        self.multisampled_texture = (self.sample_count > 1).then(|| {
+            Texture::from_descriptors(
+                &WTextureDescriptor {
+                    label: Some("Multisampled Texture"),
+                    size: Extent3d {
+                        width: self.resolution.x,
+                        height: self.resolution.y,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: self.sample_count,
+                    dimension: TextureDimension::D2,
+                    format: PostProcessing::HDR_FORMAT,
+                    usage: TextureUsages::RENDER_ATTACHMENT,
+                    view_formats: &[],
+                },
+                &TextureViewDescriptor::default(),
+                &SamplerDescriptor::default(),
+                device,
+                queue,
+            )
+        });
+    }
 }
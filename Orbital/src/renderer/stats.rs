@@ -0,0 +1,23 @@
+/// Counts of the work done by a [Renderer](super::Renderer) in the last
+/// frame.
+/// Check [Renderer::stats](super::Renderer::stats).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RenderStats {
+    /// Amount of draw calls issued
+    pub draw_calls: u32,
+    /// Amount of draw calls issued indirectly, with instances culled on
+    /// the GPU.
+    /// Check [RendererSettings::gpu_culling](super::RendererSettings::gpu_culling).
+    pub indirect_draws: u32,
+    /// Amount of instances drawn across all draw calls.
+    /// Indirect draws count their instances before culling.
+    pub instances: u32,
+    /// Amount of draws merged into another draw, i.e. saved draw calls
+    pub merged_draws: u32,
+    /// Amount of times a different pipeline was set
+    pub pipeline_changes: u32,
+    /// Amount of times a different bind group was set
+    pub bind_group_changes: u32,
+    /// Amount of times a different vertex or index buffer was set
+    pub buffer_changes: u32,
+}
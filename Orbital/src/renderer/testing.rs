@@ -0,0 +1,478 @@
+//! Golden image (_reference image_) regression testing for [Renderer]s.
+//!
+//! A setup is rendered offscreen, the last frame is read back and compared
+//! against a reference PNG.
+//! If they differ more than the given [GoldenImageTolerance] allows, a
+//! diff image is written next to the reference, highlighting mismatching
+//! pixels in red.
+//!
+//! Setups are either a [Game], via [assert_golden_game], or a plain
+//! [World], via [assert_golden_world].
+//!
+//! # Usage
+//!
+//! ```rust,no_run
+//! use orbital::{
+//!     app::HeadlessSettings,
+//!     game::{Game, GameSettings},
+//!     renderer::{
+//!         testing::{assert_golden_game, GoldenImageTolerance},
+//!         StandardRenderer,
+//!     },
+//! };
+//!
+//! struct MyGame;
+//!
+//! impl Game for MyGame {
+//!     fn init() -> Self {
+//!         Self
+//!     }
+//! }
+//!
+//! // Inside a test
+//! assert_golden_game::<MyGame, StandardRenderer>(
+//!     "tests/golden/my_game.png",
+//!     GameSettings::default(),
+//!     HeadlessSettings::default(),
+//!     GoldenImageTolerance::default(),
+//! )
+//! .expect("Golden image mismatch");
+//! ```
+//!
+//! References are never written automatically.
+//! Set the environment variable `ORBITAL_UPDATE_GOLDEN_IMAGES` to write
+//! (or overwrite) them instead of comparing.
+
+use std::path::{Path, PathBuf};
+
+use image::{Rgba, RgbaImage};
+use log::{info, warn};
+use wgpu::{Device, Queue, SurfaceConfiguration, TextureView};
+
+use crate::{
+    app::{App, AppChange, AppRuntime, HeadlessSettings},
+    error::Error,
+    game::{Game, GameRuntime, GameSettings, World},
+};
+
+use super::Renderer;
+
+/// If this environment variable is set, references are written instead
+/// of compared.
+pub const UPDATE_GOLDEN_IMAGES_ENV: &str = "ORBITAL_UPDATE_GOLDEN_IMAGES";
+
+/// How much a rendered image may differ from it's reference.
+#[derive(Debug, Clone, Copy)]
+pub struct GoldenImageTolerance {
+    /// Maximum difference of a single channel for a pixel to still match
+    pub channel_tolerance: u8,
+    /// Fraction (`0.0` to `1.0`) of pixels allowed to mismatch.
+    /// Accounts for e.g. rasterization differences between adapters.
+    pub mismatch_tolerance: f32,
+}
+
+impl Default for GoldenImageTolerance {
+    fn default() -> Self {
+        Self {
+            channel_tolerance: 2,
+            mismatch_tolerance: 0.001,
+        }
+    }
+}
+
+/// Result of comparing a rendered image against it's reference.
+#[derive(Debug, Clone)]
+pub struct GoldenImageComparison {
+    /// Amount of pixels exceeding [GoldenImageTolerance::channel_tolerance]
+    pub mismatched_pixels: usize,
+    pub total_pixels: usize,
+    /// Biggest difference of any channel of any pixel
+    pub max_channel_difference: u8,
+    /// The reference in grayscale, with mismatching pixels in red
+    pub diff_image: RgbaImage,
+}
+
+impl GoldenImageComparison {
+    /// Fraction (`0.0` to `1.0`) of mismatching pixels.
+    pub fn mismatch_ratio(&self) -> f32 {
+        if self.total_pixels == 0 {
+            return 0.0;
+        }
+
+        self.mismatched_pixels as f32 / self.total_pixels as f32
+    }
+
+    /// Checks if the comparison is within the given tolerance.
+    pub fn is_within(&self, tolerance: &GoldenImageTolerance) -> bool {
+        self.mismatch_ratio() <= tolerance.mismatch_tolerance
+    }
+}
+
+/// Compares two images pixel by pixel.
+///
+/// Both images must be of the same size.
+pub fn compare_images(
+    actual: &RgbaImage,
+    reference: &RgbaImage,
+    tolerance: &GoldenImageTolerance,
+) -> Result<GoldenImageComparison, Error> {
+    if actual.dimensions() != reference.dimensions() {
+        return Err(Error::GoldenImageSizeMismatch(
+            actual.dimensions(),
+            reference.dimensions(),
+        ));
+    }
+
+    let mut mismatched_pixels = 0;
+    let mut max_channel_difference = 0;
+    let mut diff_image = RgbaImage::new(reference.width(), reference.height());
+
+    for ((actual_pixel, reference_pixel), diff_pixel) in actual
+        .pixels()
+        .zip(reference.pixels())
+        .zip(diff_image.pixels_mut())
+    {
+        let difference = actual_pixel
+            .0
+            .iter()
+            .zip(reference_pixel.0)
+            .map(|(a, b)| a.abs_diff(b))
+            .max()
+            .unwrap_or(0);
+        max_channel_difference = max_channel_difference.max(difference);
+
+        *diff_pixel = if difference > tolerance.channel_tolerance {
+            mismatched_pixels += 1;
+
+            // Brighter means bigger difference, but always clearly visible
+            Rgba([difference.max(128), 0, 0, 255])
+        } else {
+            // Dimmed, so that mismatches stand out
+            let [r, g, b, _] = reference_pixel.0;
+            let luminance = (r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000 / 3;
+            Rgba([luminance as u8, luminance as u8, luminance as u8, 255])
+        };
+    }
+
+    Ok(GoldenImageComparison {
+        mismatched_pixels,
+        total_pixels: (reference.width() * reference.height()) as usize,
+        max_channel_difference,
+        diff_image,
+    })
+}
+
+/// Compares an image against the reference PNG at `reference_path`.
+///
+/// On a mismatch, the rendered image and the diff image are written next
+/// to the reference as `<name>.actual.png` and `<name>.diff.png`.
+///
+/// If [UPDATE_GOLDEN_IMAGES_ENV] is set, the reference is written
+/// instead.
+pub fn assert_golden_image<P: AsRef<Path>>(
+    actual: &RgbaImage,
+    reference_path: P,
+    tolerance: &GoldenImageTolerance,
+) -> Result<GoldenImageComparison, Error> {
+    let reference_path = reference_path.as_ref();
+
+    if std::env::var_os(UPDATE_GOLDEN_IMAGES_ENV).is_some() {
+        info!("Updating golden image {:?}", reference_path);
+
+        if let Some(parent) = reference_path.parent() {
+            std::fs::create_dir_all(parent).map_err(Error::IOError)?;
+        }
+        actual.save(reference_path).map_err(Error::ImageError)?;
+
+        return compare_images(actual, actual, tolerance);
+    }
+
+    if !reference_path.exists() {
+        return Err(Error::GoldenImageMissing(reference_path.to_path_buf()));
+    }
+    let reference = image::open(reference_path)
+        .map_err(Error::ImageError)?
+        .into_rgba8();
+
+    let comparison = compare_images(actual, &reference, tolerance)?;
+    if comparison.is_within(tolerance) {
+        return Ok(comparison);
+    }
+
+    warn!(
+        "Golden image {:?} mismatched: {} of {} pixels differ (max. channel difference: {})",
+        reference_path,
+        comparison.mismatched_pixels,
+        comparison.total_pixels,
+        comparison.max_channel_difference
+    );
+
+    actual
+        .save(sibling_path(reference_path, "actual"))
+        .map_err(Error::ImageError)?;
+    comparison
+        .diff_image
+        .save(sibling_path(reference_path, "diff"))
+        .map_err(Error::ImageError)?;
+
+    Err(Error::GoldenImageMismatch(
+        reference_path.to_path_buf(),
+        comparison.mismatched_pixels,
+    ))
+}
+
+/// `dir/name.png` -> `dir/name.<suffix>.png`
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|x| x.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    path.with_file_name(format!("{}.{}.png", stem, suffix))
+}
+
+/// Renders a [Game] offscreen with the given [Renderer] and returns the
+/// last frame.
+///
+/// Check [GameRuntime::liftoff_headless] for more.
+pub fn render_game<GameImpl: Game, RendererImpl: Renderer>(
+    settings: GameSettings,
+    headless_settings: HeadlessSettings,
+) -> Result<RgbaImage, Error> {
+    let headless_settings = HeadlessSettings {
+        output_directory: None,
+        ..headless_settings
+    };
+
+    GameRuntime::<GameImpl, RendererImpl>::liftoff_headless(settings, headless_settings)?
+        .pop()
+        .ok_or(Error::NoFrames)
+}
+
+/// Renders a [World] offscreen with the given [Renderer] and returns the
+/// last frame.
+///
+/// `setup` is called once with an empty [World], e.g. to queue
+/// [WorldChanges](crate::game::WorldChange).
+pub fn render_world<RendererImpl: Renderer, F: FnOnce(&mut World)>(
+    setup: F,
+    settings: HeadlessSettings,
+) -> Result<RgbaImage, Error> {
+    let settings = HeadlessSettings {
+        output_directory: None,
+        ..settings
+    };
+    let timestep = settings.timestep;
+
+    AppRuntime::<WorldApp<RendererImpl>>::__liftoff_headless(settings, |config, device, queue| {
+        let mut world = World::new();
+        setup(&mut world);
+
+        WorldApp {
+            world,
+            renderer: RendererImpl::new(
+                config.format,
+                (config.width, config.height).into(),
+                device,
+                queue,
+            ),
+            timestep,
+        }
+    })?
+    .pop()
+    .ok_or(Error::NoFrames)
+}
+
+/// Renders a [Game] and compares the last frame against a reference.
+/// Check [render_game] and [assert_golden_image].
+pub fn assert_golden_game<GameImpl: Game, RendererImpl: Renderer>(
+    reference_path: impl AsRef<Path>,
+    settings: GameSettings,
+    headless_settings: HeadlessSettings,
+    tolerance: GoldenImageTolerance,
+) -> Result<GoldenImageComparison, Error> {
+    let actual = render_game::<GameImpl, RendererImpl>(settings, headless_settings)?;
+
+    assert_golden_image(&actual, reference_path, &tolerance)
+}
+
+/// Renders a [World] and compares the last frame against a reference.
+/// Check [render_world] and [assert_golden_image].
+pub fn assert_golden_world<RendererImpl: Renderer, F: FnOnce(&mut World)>(
+    reference_path: impl AsRef<Path>,
+    setup: F,
+    settings: HeadlessSettings,
+    tolerance: GoldenImageTolerance,
+) -> Result<GoldenImageComparison, Error> {
+    let actual = render_world::<RendererImpl, F>(setup, settings)?;
+
+    assert_golden_image(&actual, reference_path, &tolerance)
+}
+
+/// Minimal [App] rendering a [World], without any [Game].
+struct WorldApp<RendererImpl: Renderer> {
+    world: World,
+    renderer: RendererImpl,
+    timestep: f64,
+}
+
+impl<RendererImpl: Renderer> App for WorldApp<RendererImpl> {
+    fn init(config: &SurfaceConfiguration, device: &Device, queue: &Queue) -> Self
+    where
+        Self: Sized,
+    {
+        Self {
+            world: World::new(),
+            renderer: RendererImpl::new(
+                config.format,
+                (config.width, config.height).into(),
+                device,
+                queue,
+            ),
+            timestep: 0.0,
+        }
+    }
+
+    fn on_update(&mut self) -> Option<Vec<AppChange>>
+    where
+        Self: Sized,
+    {
+        let app_changes = self.world.update(self.timestep);
+        self.renderer.update(self.timestep);
+
+        if app_changes.is_empty() {
+            None
+        } else {
+            Some(app_changes)
+        }
+    }
+
+    fn on_render(&mut self, target_view: &TextureView, device: &Device, queue: &Queue)
+    where
+        Self: Sized,
+    {
+        self.world.prepare_render(device, queue);
+
+        let (camera, models, lights, environment) = self.world.gather_render_resources();
+
+        self.renderer.render(
+            target_view,
+            device,
+            queue,
+            &models,
+            &lights,
+            environment,
+            camera,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: u32, height: u32, pixel: [u8; 4]) -> RgbaImage {
+        RgbaImage::from_pixel(width, height, Rgba(pixel))
+    }
+
+    #[test]
+    fn identical_images_match() {
+        let reference = image(4, 4, [10, 20, 30, 255]);
+
+        let comparison =
+            compare_images(&reference, &reference, &GoldenImageTolerance::default()).unwrap();
+
+        assert_eq!(comparison.mismatched_pixels, 0);
+        assert_eq!(comparison.total_pixels, 16);
+        assert_eq!(comparison.max_channel_difference, 0);
+        assert!(comparison.is_within(&GoldenImageTolerance::default()));
+    }
+
+    #[test]
+    fn channel_tolerance_is_inclusive() {
+        let reference = image(2, 2, [100, 100, 100, 255]);
+        let actual = image(2, 2, [102, 98, 100, 255]);
+        let tolerance = GoldenImageTolerance {
+            channel_tolerance: 2,
+            mismatch_tolerance: 0.0,
+        };
+
+        let comparison = compare_images(&actual, &reference, &tolerance).unwrap();
+
+        assert_eq!(comparison.mismatched_pixels, 0);
+        assert_eq!(comparison.max_channel_difference, 2);
+        assert!(comparison.is_within(&tolerance));
+    }
+
+    #[test]
+    fn mismatch_tolerance_is_a_ratio() {
+        let reference = image(10, 10, [0, 0, 0, 255]);
+        let mut actual = reference.clone();
+        actual.put_pixel(3, 7, Rgba([0, 50, 0, 255]));
+
+        let comparison =
+            compare_images(&actual, &reference, &GoldenImageTolerance::default()).unwrap();
+
+        assert_eq!(comparison.mismatched_pixels, 1);
+        assert_eq!(comparison.max_channel_difference, 50);
+        assert_eq!(comparison.mismatch_ratio(), 0.01);
+        assert!(!comparison.is_within(&GoldenImageTolerance {
+            channel_tolerance: 2,
+            mismatch_tolerance: 0.005,
+        }));
+        assert!(comparison.is_within(&GoldenImageTolerance {
+            channel_tolerance: 2,
+            mismatch_tolerance: 0.01,
+        }));
+    }
+
+    #[test]
+    fn diff_image_highlights_mismatches() {
+        let reference = image(2, 1, [255, 255, 255, 255]);
+        let mut actual = reference.clone();
+        actual.put_pixel(1, 0, Rgba([55, 255, 255, 255]));
+
+        let comparison =
+            compare_images(&actual, &reference, &GoldenImageTolerance::default()).unwrap();
+        let diff = &comparison.diff_image;
+
+        assert_eq!(diff.dimensions(), (2, 1));
+        // Matching: dimmed grayscale of the reference
+        assert_eq!(diff.get_pixel(0, 0).0, [85, 85, 85, 255]);
+        // Mismatching: red, brighter for bigger differences
+        assert_eq!(diff.get_pixel(1, 0).0, [200, 0, 0, 255]);
+    }
+
+    #[test]
+    fn small_differences_are_still_visible() {
+        let reference = image(1, 1, [0, 0, 0, 255]);
+        let actual = image(1, 1, [10, 0, 0, 255]);
+
+        let comparison =
+            compare_images(&actual, &reference, &GoldenImageTolerance::default()).unwrap();
+
+        assert_eq!(comparison.diff_image.get_pixel(0, 0).0, [128, 0, 0, 255]);
+    }
+
+    #[test]
+    fn size_mismatch_is_an_error() {
+        let result = compare_images(
+            &image(4, 4, [0; 4]),
+            &image(4, 3, [0; 4]),
+            &GoldenImageTolerance::default(),
+        );
+
+        assert!(matches!(
+            result,
+            Err(Error::GoldenImageSizeMismatch((4, 4), (4, 3)))
+        ));
+    }
+
+    #[test]
+    fn sibling_path_keeps_directory() {
+        assert_eq!(
+            sibling_path(Path::new("tests/golden/cubes.png"), "diff"),
+            Path::new("tests/golden/cubes.diff.png")
+        );
+    }
+}
@@ -0,0 +1,35 @@
+use super::TextureDescriptor;
+
+/// Describes the environment surrounding a [World](crate::game::World).
+///
+/// The environment is used for IBL (= Image-Based-Lighting), i.e. as
+/// ambient light coming from all directions, and can optionally be drawn
+/// as a skybox.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvironmentDescriptor {
+    /// The source of the environment.
+    /// Usually, this will be a [TextureDescriptor::EquirectangularHDR].
+    ///
+    /// Any other color texture will be interpreted as an equirectangular
+    /// map too.
+    /// ⚠️ [TextureDescriptor::Depth] is not supported!
+    pub texture: TextureDescriptor,
+    /// Multiplier applied to any light coming from the environment,
+    /// including the skybox.
+    pub intensity: f32,
+    /// If `true`, the environment will be drawn as a skybox behind
+    /// everything else.
+    /// Otherwise, the background will be black.
+    pub draw_skybox: bool,
+}
+
+impl Default for EnvironmentDescriptor {
+    /// Default is a dim, uniform ambient light without a skybox.
+    fn default() -> Self {
+        Self {
+            texture: TextureDescriptor::UNIFORM_WHITE,
+            intensity: 0.03,
+            draw_skybox: false,
+        }
+    }
+}
@@ -1,4 +1,6 @@
-use cgmath::{Quaternion, Vector3, Zero};
+use cgmath::{InnerSpace, Matrix3, Matrix4, One, Quaternion, SquareMatrix, Vector3, Zero};
+
+use crate::game::TransformChange;
 
 #[derive(Debug, Clone)]
 pub enum Instancing {
@@ -13,11 +15,52 @@ pub struct InstanceDescriptor {
     pub scale: Vector3<f32>,
 }
 
+impl InstanceDescriptor {
+    /// Decomposes a transformation matrix (e.g. a _glTF node transform_)
+    /// into position, rotation and scale.
+    ///
+    /// ⚠️ Shearing can't be represented and will be lost!
+    pub fn from_matrix(matrix: Matrix4<f32>) -> Self {
+        let position = matrix.w.truncate();
+
+        let mut scale = Vector3::new(
+            matrix.x.truncate().magnitude(),
+            matrix.y.truncate().magnitude(),
+            matrix.z.truncate().magnitude(),
+        );
+
+        // A mirrored transform can't be a rotation, flip one axis instead
+        if matrix.determinant() < 0.0 {
+            scale.x = -scale.x;
+        }
+
+        let rotation = if scale.x == 0.0 || scale.y == 0.0 || scale.z == 0.0 {
+            Quaternion::one()
+        } else {
+            Quaternion::from(Matrix3::from_cols(
+                matrix.x.truncate() / scale.x,
+                matrix.y.truncate() / scale.y,
+                matrix.z.truncate() / scale.z,
+            ))
+        };
+
+        Self {
+            position,
+            rotation,
+            scale,
+        }
+    }
+
+    pub fn apply_change(&mut self, change: TransformChange) {
+        change.apply_to(&mut self.position, &mut self.rotation, &mut self.scale);
+    }
+}
+
 impl Default for InstanceDescriptor {
     fn default() -> Self {
         Self {
             position: Vector3::zero(),
-            rotation: Quaternion::zero(),
+            rotation: Quaternion::one(),
             scale: Vector3::new(1.0, 1.0, 1.0),
         }
     }
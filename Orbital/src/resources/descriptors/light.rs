@@ -0,0 +1,161 @@
+use cgmath::{InnerSpace, Vector3, Zero};
+use log::warn;
+
+use crate::game::{LightChange, Mode};
+
+use super::ShadowDescriptor;
+
+/// Describes a light source inside the [World](crate::game::World).
+///
+/// Each light needs a unique `identifier`, similar to a
+/// [CameraDescriptor](super::CameraDescriptor).
+/// The `identifier` is used to update or despawn the light later on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LightDescriptor {
+    pub identifier: String,
+    pub light_type: LightType,
+    /// Linear RGB color of the light.
+    /// Each channel should be between 0.0 and 1.0.
+    pub color: Vector3<f32>,
+    /// Strength of the light.
+    ///
+    /// Follows the glTF convention:
+    /// [LightType::Directional] is in lux (lm/m²),
+    /// [LightType::Point] and [LightType::Spot] are in candela (lm/sr).
+    pub intensity: f32,
+    /// If set to `Some(...)`, the light will cast shadows.
+    /// Check [ShadowDescriptor] for more.
+    pub shadow: Option<ShadowDescriptor>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LightType {
+    /// Emits light in all directions from `position`.
+    /// The light attenuates with the inverse square of the distance.
+    ///
+    /// If `range` is bigger than `0.0`, the light will smoothly fade out
+    /// towards `range` and won't have any effect past it.
+    /// Otherwise, the range is infinite.
+    Point { position: Vector3<f32>, range: f32 },
+    /// Emits light into `direction`, as if the light source is infinitely
+    /// far away (e.g. the sun).
+    /// The light doesn't attenuate.
+    Directional { direction: Vector3<f32> },
+    /// Emits light into `direction` from `position` in a cone.
+    ///
+    /// Inside the `inner_cone_angle` the light has full intensity.
+    /// Between `inner_cone_angle` and `outer_cone_angle` the light fades out.
+    /// Both angles are in radians and measured from `direction`.
+    ///
+    /// `range` works the same as with [LightType::Point].
+    Spot {
+        position: Vector3<f32>,
+        direction: Vector3<f32>,
+        range: f32,
+        inner_cone_angle: f32,
+        outer_cone_angle: f32,
+    },
+}
+
+impl LightDescriptor {
+    pub const DEFAULT_NAME: &'static str = "Default";
+
+    /// Returns the position of the light.
+    /// [LightType::Directional] lights don't have a position and return
+    /// [Vector3::zero].
+    pub fn position(&self) -> Vector3<f32> {
+        match self.light_type {
+            LightType::Point { position, .. } | LightType::Spot { position, .. } => position,
+            LightType::Directional { .. } => Vector3::zero(),
+        }
+    }
+
+    /// Returns the direction of the light.
+    /// [LightType::Point] lights don't have a direction and return
+    /// [Vector3::zero].
+    pub fn direction(&self) -> Vector3<f32> {
+        match self.light_type {
+            LightType::Directional { direction } | LightType::Spot { direction, .. } => direction,
+            LightType::Point { .. } => Vector3::zero(),
+        }
+    }
+
+    pub fn apply_change(&mut self, change: LightChange) {
+        if let Some(mode) = change.position {
+            match &mut self.light_type {
+                LightType::Point { position, .. } | LightType::Spot { position, .. } => {
+                    match mode {
+                        Mode::Overwrite(new_position) => *position = new_position,
+                        Mode::Offset(offset) | Mode::OffsetViewAligned(offset) => {
+                            *position += offset
+                        }
+                    }
+                }
+                LightType::Directional { .. } => {
+                    warn!(
+                        "Trying to change the position of directional light '{}', which has no position!",
+                        self.identifier
+                    );
+                }
+            }
+        }
+
+        if let Some(mode) = change.direction {
+            match &mut self.light_type {
+                LightType::Directional { direction } | LightType::Spot { direction, .. } => {
+                    match mode {
+                        Mode::Overwrite(new_direction) => *direction = new_direction,
+                        Mode::Offset(offset) | Mode::OffsetViewAligned(offset) => {
+                            *direction += offset
+                        }
+                    }
+
+                    if !direction.is_zero() {
+                        *direction = direction.normalize();
+                    }
+                }
+                LightType::Point { .. } => {
+                    warn!(
+                        "Trying to change the direction of point light '{}', which has no direction!",
+                        self.identifier
+                    );
+                }
+            }
+        }
+
+        if let Some(color) = change.color {
+            self.color = color;
+        }
+
+        if let Some(shadow) = change.shadow {
+            self.shadow = shadow;
+        }
+
+        if let Some(mode) = change.intensity {
+            match mode {
+                Mode::Overwrite(intensity) => self.intensity = intensity,
+                Mode::Offset(intensity) | Mode::OffsetViewAligned(intensity) => {
+                    self.intensity += intensity
+                }
+            }
+
+            if self.intensity < 0.0 {
+                self.intensity = 0.0;
+            }
+        }
+    }
+}
+
+impl Default for LightDescriptor {
+    fn default() -> Self {
+        Self {
+            identifier: Self::DEFAULT_NAME.into(),
+            light_type: LightType::Directional {
+                direction: Vector3::new(-0.5, -1.0, -0.3).normalize(),
+            },
+            color: Vector3::new(1.0, 1.0, 1.0),
+            intensity: 3.0,
+            shadow: None,
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use super::MeshDescriptor;
+
+/// Describes when a [LodDescriptor] is switched to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LodThreshold {
+    /// Used once an [Instance](crate::resources::realizations::Instance)
+    /// is at least this far away from the active
+    /// [Camera](crate::resources::realizations::Camera).
+    Distance(f32),
+    /// Used once an [Instance](crate::resources::realizations::Instance)
+    /// covers at most this fraction of the viewport height.
+    /// I.e. `1.0` covers the whole height, `0.1` a tenth of it.
+    ScreenSize(f32),
+}
+
+/// A coarser level of detail of a
+/// [ModelDescriptor::WithLods](super::ModelDescriptor::WithLods).
+#[derive(Debug, Clone)]
+pub struct LodDescriptor {
+    pub mesh: MeshDescriptor,
+    pub threshold: LodThreshold,
+}
+
+/// Describes all levels of detail of a
+/// [ModelDescriptor::WithLods](super::ModelDescriptor::WithLods).
+///
+/// The level of detail is selected per
+/// [Instance](crate::resources::realizations::Instance) each frame.
+/// The coarsest [LodDescriptor] whose [LodThreshold] is reached is used.
+/// If none is reached, `mesh` is used.
+#[derive(Debug, Clone)]
+pub struct LodsDescriptor {
+    /// The most detailed mesh.
+    /// Also used for shadows and spatial queries, like
+    /// [World::raycast](crate::game::World::raycast).
+    pub mesh: MeshDescriptor,
+    /// Coarser levels, ordered from most to least detailed.
+    pub levels: Vec<LodDescriptor>,
+    /// Fraction by which a [LodThreshold] has to be exceeded to switch
+    /// levels, to avoid popping back and forth around a threshold.
+    /// E.g. `0.1` for 10%.  
+    /// `0.0` disables hysteresis.
+    pub hysteresis: f32,
+}
@@ -1,14 +1,74 @@
 use cgmath::Vector4;
+use image::DynamicImage;
 
 use super::{ShaderDescriptor, TextureDescriptor};
 
+/// Defines how the alpha of the albedo of a [MaterialDescriptor] is
+/// interpreted.
+/// Matches the _alpha modes_ of _glTF_.
+#[derive(Debug, Default, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum AlphaMode {
+    /// Alpha is ignored, the surface is fully opaque.
+    #[default]
+    Opaque,
+    /// Fragments with an alpha below `cutoff` are discarded, all others
+    /// are fully opaque.  
+    /// Like [TextureDescriptor]s, `255` equals `1.0`.
+    /// Shadows are cut out the same way, using the alpha of the albedo.
+    ///
+    /// ⚠️ Custom shaders need to declare
+    /// ⚠️ `override alpha_cutoff: f32 = 0.0;` and discard fragments
+    /// ⚠️ themselves, if they are used with this mode!
+    Mask { cutoff: u8 },
+    /// The surface is blended with whatever is behind it.
+    /// Blended surfaces don't write depth and are drawn after all opaque
+    /// ones, sorted back to front.
+    /// Blended surfaces don't cast shadows.
+    ///
+    /// ⚠️ Sorting happens per model, by the center of all it's instances.
+    /// ⚠️ Instances of the same model are drawn in their given order, thus
+    /// ⚠️ overlapping instances may blend in the wrong order.
+    /// ⚠️ Use separate models if their order matters.
+    ///
+    /// The [DeferredRenderer](crate::renderer::DeferredRenderer) draws
+    /// blended surfaces in a forward pass after lighting.
+    Blend,
+}
+
+#[cfg(feature = "gltf")]
+impl From<&gltf::Material<'_>> for AlphaMode {
+    fn from(value: &gltf::Material<'_>) -> Self {
+        match value.alpha_mode() {
+            gltf::material::AlphaMode::Opaque => Self::Opaque,
+            // The cutoff defaults to 0.5 as per specification
+            gltf::material::AlphaMode::Mask => Self::Mask {
+                cutoff: (255f32 * value.alpha_cutoff().unwrap_or(0.5)).round() as u8,
+            },
+            gltf::material::AlphaMode::Blend => Self::Blend,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub enum MaterialDescriptor {
     /// Creates a standard PBR (= Physically-Based-Rendering) material.
+    ///
+    /// The `normal` map is expected to be in tangent space and **linear**
+    /// (check [TextureDescriptor::LinearRGBAu8Data]).
+    /// The `occlusion` map is a single channel texture where 255 means
+    /// _not occluded_.
+    /// The `emissive` map defines the color emitted by the surface, use
+    /// [TextureDescriptor::UNIFORM_BLACK] if the surface doesn't emit anything.
+    /// The alpha of the `albedo` map is interpreted as defined by the
+    /// [AlphaMode].
     PBR {
         albedo: TextureDescriptor,
         metallic: TextureDescriptor,
         roughness: TextureDescriptor,
+        normal: TextureDescriptor,
+        occlusion: TextureDescriptor,
+        emissive: TextureDescriptor,
+        alpha_mode: AlphaMode,
     },
     /// Creates a PBR (= Physically-Based-Rendering) material
     /// with a custom shader.
@@ -16,10 +76,40 @@ pub enum MaterialDescriptor {
         albedo: TextureDescriptor,
         metallic: TextureDescriptor,
         roughness: TextureDescriptor,
+        normal: TextureDescriptor,
+        occlusion: TextureDescriptor,
+        emissive: TextureDescriptor,
+        alpha_mode: AlphaMode,
         custom_shader: ShaderDescriptor,
     },
 }
 
+impl MaterialDescriptor {
+    /// A flat tangent space normal, pointing straight "up" (i.e. +Z).
+    /// Used in case no normal map is supplied.
+    pub fn default_normal() -> TextureDescriptor {
+        TextureDescriptor::LinearRGBAu8Data(vec![128, 128, 255, 255], (1, 1).into())
+    }
+
+    pub fn alpha_mode(&self) -> AlphaMode {
+        match self {
+            Self::PBR { alpha_mode, .. } | Self::PBRCustomShader { alpha_mode, .. } => *alpha_mode,
+        }
+    }
+
+    /// Returns the same [MaterialDescriptor], but with the given
+    /// [AlphaMode].
+    pub fn with_alpha_mode(mut self, new_alpha_mode: AlphaMode) -> Self {
+        match &mut self {
+            Self::PBR { alpha_mode, .. } | Self::PBRCustomShader { alpha_mode, .. } => {
+                *alpha_mode = new_alpha_mode
+            }
+        }
+
+        self
+    }
+}
+
 impl From<&easy_gltf::Material> for MaterialDescriptor {
     fn from(value: &easy_gltf::Material) -> Self {
         let metallic_texture_descriptor = if let Some(metallic_buffer) = &value.pbr.metallic_texture
@@ -60,10 +150,48 @@ impl From<&easy_gltf::Material> for MaterialDescriptor {
             ))
         };
 
+        // Normal maps are RGB only, but the GPU expects RGBA.
+        let normal_texture_descriptor = if let Some(normal) = &value.normal {
+            let rgba = DynamicImage::ImageRgb8((*normal.texture).clone()).to_rgba8();
+
+            TextureDescriptor::LinearRGBAu8Data(rgba.to_vec(), rgba.dimensions().into())
+        } else {
+            Self::default_normal()
+        };
+
+        let occlusion_texture_descriptor = if let Some(occlusion) = &value.occlusion {
+            TextureDescriptor::Luma {
+                data: occlusion.texture.to_vec(),
+                size: occlusion.texture.dimensions().into(),
+            }
+        } else {
+            TextureDescriptor::UniformLuma { data: 255 }
+        };
+
+        // Emissive maps are RGB only, but the GPU expects RGBA.
+        let emissive_texture_descriptor = if let Some(emissive) = &value.emissive.texture {
+            let rgba = DynamicImage::ImageRgb8((**emissive).clone()).to_rgba8();
+
+            TextureDescriptor::StandardSRGBu8Data(rgba.to_vec(), rgba.dimensions().into())
+        } else {
+            TextureDescriptor::UniformColor(Vector4::new(
+                (255f32 * value.emissive.factor.x) as u8,
+                (255f32 * value.emissive.factor.y) as u8,
+                (255f32 * value.emissive.factor.z) as u8,
+                255,
+            ))
+        };
+
         Self::PBR {
             albedo: albedo_texture_descriptor,
             metallic: metallic_texture_descriptor,
             roughness: roughness_texture_descriptor,
+            normal: normal_texture_descriptor,
+            occlusion: occlusion_texture_descriptor,
+            emissive: emissive_texture_descriptor,
+            // easy_gltf doesn't provide the alpha mode, check
+            // `From<&gltf::Material>` for `AlphaMode`
+            alpha_mode: AlphaMode::Opaque,
         }
     }
 }
@@ -1,21 +1,29 @@
 pub mod camera;
 pub mod composition;
+pub mod environment;
 pub mod import;
 pub mod instance;
+pub mod light;
+pub mod lod;
 pub mod material;
 pub mod mesh;
 pub mod model;
 pub mod pipeline;
 pub mod shader;
+pub mod shadow;
 pub mod texture;
 
 pub use camera::*;
 pub use composition::*;
+pub use environment::*;
 pub use import::*;
 pub use instance::*;
+pub use light::*;
+pub use lod::*;
 pub use material::*;
 pub use mesh::*;
 pub use model::*;
 pub use pipeline::*;
 pub use shader::*;
+pub use shadow::*;
 pub use texture::*;
@@ -1,4 +1,4 @@
-use super::{ImportDescriptor, Instancing, MaterialDescriptor, MeshDescriptor};
+use super::{ImportDescriptor, Instancing, LodsDescriptor, MaterialDescriptor, MeshDescriptor};
 
 /// Descriptor for a model
 ///
@@ -14,6 +14,7 @@ use super::{ImportDescriptor, Instancing, MaterialDescriptor, MeshDescriptor};
 /// Some parameters, like position, rotation and scale, can be altered via
 /// instancing. However, you can't instance a different model or material.
 #[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
 pub enum ModelDescriptor {
     /// Describes a model to be created from a mesh and a material descriptor.
     ///
@@ -26,6 +27,20 @@ pub enum ModelDescriptor {
     /// 3.: Instancing  
     ///     Check super description for [Instancing] explanation.
     FromDescriptors(MeshDescriptor, MaterialDescriptor, Instancing),
+    /// Describes a model with multiple levels of detail, switching
+    /// [Mesh](crate::resources::realizations::Mesh)es depending on the
+    /// distance or screen size relative to the active
+    /// [Camera](crate::resources::realizations::Camera).
+    ///
+    /// # Arguments
+    ///
+    /// 1.: Levels of detail, check [LodsDescriptor]
+    ///
+    /// 2.: Material descriptor, shared by all levels of detail
+    ///
+    /// 3.: Instancing  
+    ///     Check super description for [Instancing] explanation.
+    WithLods(LodsDescriptor, MaterialDescriptor, Instancing),
     /// Describes a model to be imported from a _glTF file_.
     ///
     /// Note, that this **only** imports a [Model](crate::resources::realizations::Model) (i.e. a [Mesh](crate::resources::realizations::Mesh) + a [Material](crate::resources::realizations::Material)).
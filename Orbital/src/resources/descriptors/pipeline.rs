@@ -1,6 +1,6 @@
 use wgpu::{Face, FrontFace, PolygonMode, PrimitiveTopology};
 
-use super::ShaderDescriptor;
+use super::{AlphaMode, ShaderDescriptor};
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct PipelineDescriptor {
@@ -10,13 +10,79 @@ pub struct PipelineDescriptor {
     pub front_face_order: FrontFace,
     pub cull_mode: Option<Face>,
     pub polygon_mode: PolygonMode,
+    /// Skinning variant of the pipeline, used for [Model]s with a
+    /// [Skeleton].
+    ///
+    /// Adds a joint matrix storage buffer to the material bind group and
+    /// a [SkinVertex] buffer.
+    /// The vertex entrypoint `entrypoint_vertex_skinned` is used instead of
+    /// `entrypoint_vertex`.  
+    /// ⚠️ Custom shaders need to provide said entrypoint, if they are
+    /// ⚠️ used with skinned [Model]s!
+    ///
+    /// Usually, there is no need to set this manually.
+    /// Renderers will use [PipelineDescriptor::skinned_variant] as needed.
+    ///
+    /// [Model]: crate::resources::realizations::Model
+    /// [Skeleton]: crate::resources::realizations::Skeleton
+    /// [SkinVertex]: crate::resources::realizations::SkinVertex
+    pub skinned: bool,
+    /// Morphing variant of the pipeline, used for [Model]s with
+    /// [MorphTarget]s.
+    ///
+    /// Adds a morph delta and a morph weight storage buffer to the
+    /// material bind group.
+    /// The vertex entrypoint `entrypoint_vertex_morphed` is used instead of
+    /// `entrypoint_vertex`.
+    /// If combined with [PipelineDescriptor::skinned],
+    /// `entrypoint_vertex_skinned_morphed` is used.  
+    /// ⚠️ Custom shaders need to provide said entrypoints, if they are
+    /// ⚠️ used with morphed [Model]s!
+    ///
+    /// Usually, there is no need to set this manually.
+    /// Renderers will use [PipelineDescriptor::morphed_variant] as needed.
+    ///
+    /// [Model]: crate::resources::realizations::Model
+    /// [MorphTarget]: crate::resources::realizations::MorphTarget
+    pub morphed: bool,
+    /// G-buffer variant of the pipeline, used by deferred renderers like
+    /// the [DeferredRenderer].
+    ///
+    /// Instead of shading, the surface properties are written into the
+    /// G-buffer targets (check [Pipeline::G_BUFFER_FORMATS]).
+    /// Only the material and camera bind groups are bound.
+    /// The fragment entrypoint `entrypoint_fragment_deferred` is used
+    /// instead of `entrypoint_fragment`.  
+    /// ⚠️ Custom shaders need to provide said entrypoint, if they are
+    /// ⚠️ used with a deferred renderer!
+    ///
+    /// Blended surfaces can't be stored in the G-buffer, thus the
+    /// [DeferredRenderer] keeps drawing them with the forward variant.
+    ///
+    /// Usually, there is no need to set this manually.
+    /// Renderers will use [PipelineDescriptor::deferred_variant] as needed.
+    ///
+    /// [DeferredRenderer]: crate::renderer::DeferredRenderer
+    /// [Pipeline::G_BUFFER_FORMATS]: crate::resources::realizations::Pipeline::G_BUFFER_FORMATS
+    pub deferred: bool,
+    /// Alpha mode of the [MaterialDescriptor](super::MaterialDescriptor)
+    /// drawn with the pipeline.
+    ///
+    /// Blended pipelines blend with the color target and don't write
+    /// depth, check [PipelineDescriptor::is_blended].
+    /// Masking pipelines pass the cutoff as the pipeline-overridable
+    /// constant `alpha_cutoff`, normalized to `0.0..=1.0`.
+    pub alpha_mode: AlphaMode,
 }
 
 impl Default for PipelineDescriptor {
     /// Default is PBR
     fn default() -> Self {
         Self {
-            shader_descriptor: include_str!("shader/standard_pbr.wgsl"),
+            shader_descriptor: concat!(
+                include_str!("shader/lighting.wgsl"),
+                include_str!("shader/standard_pbr.wgsl")
+            ),
             // TODO
             // bind_group_entries: vec![
             //     BindGroupLayoutEntry {
@@ -40,6 +106,10 @@ impl Default for PipelineDescriptor {
             front_face_order: Default::default(),
             cull_mode: Some(Face::Back),
             polygon_mode: Default::default(),
+            skinned: false,
+            morphed: false,
+            deferred: false,
+            alpha_mode: AlphaMode::Opaque,
         }
     }
 }
@@ -52,4 +122,37 @@ impl PipelineDescriptor {
             ..Default::default()
         }
     }
+
+    /// Returns the same [PipelineDescriptor], but with skinning enabled.
+    /// Check [PipelineDescriptor::skinned] for more.
+    pub fn skinned_variant(&self) -> Self {
+        Self {
+            skinned: true,
+            ..self.clone()
+        }
+    }
+
+    /// Returns the same [PipelineDescriptor], but with morphing enabled.
+    /// Check [PipelineDescriptor::morphed] for more.
+    pub fn morphed_variant(&self) -> Self {
+        Self {
+            morphed: true,
+            ..self.clone()
+        }
+    }
+
+    /// Returns the same [PipelineDescriptor], but as G-buffer variant.
+    /// Check [PipelineDescriptor::deferred] for more.
+    pub fn deferred_variant(&self) -> Self {
+        Self {
+            deferred: true,
+            ..self.clone()
+        }
+    }
+
+    /// Checks if the pipeline blends with the color target.
+    /// G-buffer variants can't blend, check [PipelineDescriptor::deferred].
+    pub fn is_blended(&self) -> bool {
+        self.alpha_mode == AlphaMode::Blend && !self.deferred
+    }
 }
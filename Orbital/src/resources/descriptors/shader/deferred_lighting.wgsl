@@ -0,0 +1,174 @@
+struct FragmentData {
+    @builtin(position) position: vec4<f32>,
+}
+
+struct ClusterUniform {
+    inverse_view_projection_matrix: mat4x4<f32>,
+    view_matrix: mat4x4<f32>,
+    inverse_projection_matrix: mat4x4<f32>,
+    screen_size: vec2<f32>,
+    near: f32,
+    far: f32,
+    grid_size: vec3<u32>,
+    _padding: u32,
+}
+
+/// Indices of all lights reaching into a cluster.
+/// Lights beyond `LightClusters::MAX_LIGHTS_PER_CLUSTER` are dropped.
+struct Cluster {
+    count: u32,
+    // Must match `LightClusters::MAX_LIGHTS_PER_CLUSTER`
+    lights: array<u32, 127>,
+}
+
+struct CameraUniform {
+    view_projection_matrix: mat4x4<f32>,
+    position: vec4<f32>,
+}
+
+struct LightStorage {
+    count: u32,
+    lights: array<LightData>,
+}
+
+struct EnvironmentUniform {
+    intensity: f32,
+    specular_mip_count: f32,
+    _padding: vec2<f32>,
+}
+
+// G-buffer, check `GBufferData` of the standard PBR shader
+@group(0) @binding(0) var albedo_texture: texture_2d<f32>;
+@group(0) @binding(1) var normal_texture: texture_2d<f32>;
+@group(0) @binding(2) var material_texture: texture_2d<f32>;
+@group(0) @binding(3) var emissive_texture: texture_2d<f32>;
+@group(0) @binding(4) var depth_texture: texture_2d<f32>;
+@group(0) @binding(5) var<uniform> cluster_uniform: ClusterUniform;
+@group(0) @binding(6) var<storage, read> clusters: array<Cluster>;
+
+@group(1) @binding(0)
+var<uniform> camera: CameraUniform;
+
+@group(2) @binding(0)
+var<storage, read> lights: LightStorage;
+@group(2) @binding(1)
+var<storage, read> shadows: array<ShadowData>;
+@group(2) @binding(2)
+var shadow_atlas: texture_depth_2d;
+@group(2) @binding(3)
+var shadow_sampler: sampler_comparison;
+
+@group(3) @binding(0)
+var irradiance_map: texture_cube<f32>;
+@group(3) @binding(1)
+var specular_map: texture_cube<f32>;
+@group(3) @binding(2)
+var brdf_lut: texture_2d<f32>;
+@group(3) @binding(3)
+var environment_sampler: sampler;
+@group(3) @binding(4)
+var<uniform> environment: EnvironmentUniform;
+
+/// Draws a single triangle covering the whole screen.
+@vertex
+fn entrypoint_vertex(@builtin(vertex_index) vertex_index: u32) -> FragmentData {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+
+    var out: FragmentData;
+    out.position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    return out;
+}
+
+/// Returns the index of the cluster containing the given pixel.
+/// Check the light clustering shader for the layout.
+fn cluster_index(pixel: vec2<f32>, world_position: vec3<f32>) -> u32 {
+    let grid_size = cluster_uniform.grid_size;
+
+    let tile = min(
+        vec2<u32>(pixel / cluster_uniform.screen_size * vec2<f32>(grid_size.xy)),
+        grid_size.xy - 1u,
+    );
+
+    // The camera looks along the negative Z axis
+    let depth = -(cluster_uniform.view_matrix * vec4<f32>(world_position, 1.0)).z;
+    let slice_ratio = log(max(depth, cluster_uniform.near) / cluster_uniform.near)
+        / log(cluster_uniform.far / cluster_uniform.near);
+    let slice = min(u32(max(slice_ratio, 0.0) * f32(grid_size.z)), grid_size.z - 1u);
+
+    return tile.x + tile.y * grid_size.x + slice * grid_size.x * grid_size.y;
+}
+
+/// Shades every covered pixel of the G-buffer.
+/// Only the lights of the pixels cluster are considered.
+/// Uncovered pixels stay black.
+@fragment
+fn entrypoint_fragment(fragment: FragmentData) -> @location(0) vec4<f32> {
+    let pixel = vec2<i32>(fragment.position.xy);
+
+    let depth = textureLoad(depth_texture, pixel, 0).r;
+    if depth >= 1.0 {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+
+    let albedo_sample = textureLoad(albedo_texture, pixel, 0);
+    let albedo = albedo_sample.rgb;
+    let occlusion = albedo_sample.a;
+    let n = normalize(textureLoad(normal_texture, pixel, 0).xyz);
+    let material = textureLoad(material_texture, pixel, 0);
+    let metallic = material.r;
+    let roughness = material.g;
+    let emissive = textureLoad(emissive_texture, pixel, 0).rgb;
+
+    // Reconstruct the world position from the depth
+    let ndc = vec2<f32>(
+        fragment.position.x / cluster_uniform.screen_size.x * 2.0 - 1.0,
+        1.0 - fragment.position.y / cluster_uniform.screen_size.y * 2.0,
+    );
+    let unprojected = cluster_uniform.inverse_view_projection_matrix * vec4<f32>(ndc, depth, 1.0);
+    let world_position = unprojected.xyz / unprojected.w;
+
+    let v = normalize(camera.position.xyz - world_position);
+
+    // Surface reflection at zero incidence.
+    // Dielectrics use a constant 4%, metals tint by their albedo.
+    let f0 = mix(vec3<f32>(0.04), albedo, metallic);
+
+    // Image based (ambient) lighting
+    let n_dot_v = max(dot(n, v), 0.0001);
+    let ambient_fresnel = fresnel_schlick_roughness(n_dot_v, f0, roughness);
+    let ambient_k_diffuse = (vec3<f32>(1.0) - ambient_fresnel) * (1.0 - metallic);
+
+    let irradiance = textureSampleLevel(irradiance_map, environment_sampler, n, 0.0).rgb;
+    let ambient_diffuse = irradiance * albedo;
+
+    let r = reflect(-v, n);
+    let prefiltered = textureSampleLevel(
+        specular_map,
+        environment_sampler,
+        r,
+        roughness * (environment.specular_mip_count - 1.0)
+    ).rgb;
+    let brdf = textureSampleLevel(brdf_lut, environment_sampler, vec2<f32>(n_dot_v, roughness), 0.0).rg;
+    let ambient_specular = prefiltered * (ambient_fresnel * brdf.x + brdf.y);
+
+    let ambient = (ambient_k_diffuse * ambient_diffuse + ambient_specular) * occlusion * environment.intensity;
+
+    let cluster = cluster_index(fragment.position.xy, world_position);
+    let cluster_light_count = clusters[cluster].count;
+
+    var lo = vec3<f32>(0.0);
+    for (var i = 0u; i < cluster_light_count; i++) {
+        let light_index = clusters[cluster].lights[i];
+        let light = lights.lights[light_index];
+        let direction_attenuation = light_direction_attenuation(light, world_position);
+
+        let visibility = shadow_visibility(shadows[light_index], world_position);
+
+        let radiance = light.color * light.intensity * direction_attenuation.w * visibility;
+        lo += cook_torrance(n, v, direction_attenuation.xyz, radiance, albedo, metallic, roughness, f0);
+    }
+
+    let color = ambient + lo + emissive;
+
+    return vec4<f32>(color, 1.0);
+}
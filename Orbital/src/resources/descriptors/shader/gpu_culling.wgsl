@@ -0,0 +1,72 @@
+/// Frustum planes pointing inwards, `xyz` is the normal, `w` the distance.
+/// Order: Left, right, bottom, top, near, far
+struct Frustum {
+    planes: array<vec4<f32>, 6>,
+}
+
+/// A range of instances to cull.
+struct CullJob {
+    /// Mesh bounds, model space
+    bounds_min: vec4<f32>,
+    bounds_max: vec4<f32>,
+    /// Index of the first instance inside `input_instances`
+    first_input: u32,
+    instance_count: u32,
+    /// Index of the first instance of the draw inside `output_instances`
+    first_output: u32,
+    /// Index of the draw inside `indirect_draws`
+    draw_index: u32,
+}
+
+/// Arguments of `draw_indexed_indirect`
+struct DrawIndexedIndirect {
+    index_count: u32,
+    instance_count: atomic<u32>,
+    first_index: u32,
+    base_vertex: i32,
+    first_instance: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> frustum: Frustum;
+@group(0) @binding(1)
+var<uniform> job: CullJob;
+@group(0) @binding(2)
+var<storage, read> input_instances: array<mat4x4<f32>>;
+@group(0) @binding(3)
+var<storage, read_write> output_instances: array<mat4x4<f32>>;
+@group(0) @binding(4)
+var<storage, read_write> indirect_draws: array<DrawIndexedIndirect>;
+
+/// Checks the world space bounds of each instance against the frustum.
+/// Visible instances are appended to the draw's range of
+/// `output_instances` and counted in it's indirect arguments.
+@compute @workgroup_size(64, 1, 1)
+fn entrypoint_compute(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= job.instance_count {
+        return;
+    }
+
+    let model_matrix = input_instances[job.first_input + id.x];
+
+    // Transforming the center and extent keeps the world space AABB tight
+    let center = (job.bounds_min.xyz + job.bounds_max.xyz) * 0.5;
+    let extent = (job.bounds_max.xyz - job.bounds_min.xyz) * 0.5;
+    let world_center = (model_matrix * vec4<f32>(center, 1.0)).xyz;
+    let world_extent = mat3x3<f32>(
+        abs(model_matrix[0].xyz),
+        abs(model_matrix[1].xyz),
+        abs(model_matrix[2].xyz),
+    ) * extent;
+
+    for (var i = 0u; i < 6u; i++) {
+        let plane = frustum.planes[i];
+        let radius = dot(world_extent, abs(plane.xyz));
+        if dot(plane.xyz, world_center) + plane.w + radius < 0.0 {
+            return;
+        }
+    }
+
+    let slot = atomicAdd(&indirect_draws[job.draw_index].instance_count, 1u);
+    output_instances[job.first_output + slot] = model_matrix;
+}
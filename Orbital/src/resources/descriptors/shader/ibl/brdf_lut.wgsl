@@ -0,0 +1,73 @@
+@group(0) @binding(0)
+var destination: texture_storage_2d<rgba16float, write>;
+
+const PI: f32 = 3.14159265359;
+const SAMPLE_COUNT: u32 = 512u;
+
+/// Low-discrepancy sequence, used for quasi-random sampling.
+fn hammersley(i: u32, count: u32) -> vec2<f32> {
+    return vec2<f32>(f32(i) / f32(count), f32(reverseBits(i)) * 2.3283064365386963e-10);
+}
+
+/// Returns a half vector around +Z, biased towards the GGX lobe.
+fn importance_sample_ggx(xi: vec2<f32>, roughness: f32) -> vec3<f32> {
+    let a = roughness * roughness;
+
+    let phi = 2.0 * PI * xi.x;
+    let cos_theta = sqrt((1.0 - xi.y) / (1.0 + (a * a - 1.0) * xi.y));
+    let sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+
+    return vec3<f32>(cos(phi) * sin_theta, sin(phi) * sin_theta, cos_theta);
+}
+
+/// Schlick-GGX geometry function for a single direction.
+/// Note, that IBL uses a different `k` than direct lighting.
+fn geometry_schlick_ggx(n_dot_x: f32, roughness: f32) -> f32 {
+    let k = (roughness * roughness) / 2.0;
+
+    return n_dot_x / (n_dot_x * (1.0 - k) + k);
+}
+
+/// Integrates the specular BRDF for a given view angle (x) and
+/// roughness (y).
+/// The result is a scale (red) and bias (green) to the surface reflection
+/// at zero incidence (i.e. F0).
+@compute @workgroup_size(8, 8, 1)
+fn entrypoint_compute(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(destination);
+    if id.x >= size.x || id.y >= size.y {
+        return;
+    }
+
+    let n_dot_v = max((f32(id.x) + 0.5) / f32(size.x), 0.0001);
+    let roughness = (f32(id.y) + 0.5) / f32(size.y);
+
+    let view = vec3<f32>(sqrt(1.0 - n_dot_v * n_dot_v), 0.0, n_dot_v);
+
+    var scale = 0.0;
+    var bias = 0.0;
+    for (var i = 0u; i < SAMPLE_COUNT; i++) {
+        let xi = hammersley(i, SAMPLE_COUNT);
+        let h = importance_sample_ggx(xi, roughness);
+        let l = normalize(2.0 * dot(view, h) * h - view);
+
+        let n_dot_l = max(l.z, 0.0);
+        let n_dot_h = max(h.z, 0.0);
+        let v_dot_h = max(dot(view, h), 0.0);
+
+        if n_dot_l > 0.0 {
+            let g = geometry_schlick_ggx(n_dot_v, roughness) * geometry_schlick_ggx(n_dot_l, roughness);
+            let g_visibility = (g * v_dot_h) / (n_dot_h * n_dot_v);
+            let fresnel = pow(1.0 - v_dot_h, 5.0);
+
+            scale += (1.0 - fresnel) * g_visibility;
+            bias += fresnel * g_visibility;
+        }
+    }
+
+    textureStore(
+        destination,
+        id.xy,
+        vec4<f32>(scale / f32(SAMPLE_COUNT), bias / f32(SAMPLE_COUNT), 0.0, 1.0)
+    );
+}
@@ -0,0 +1,22 @@
+@group(0) @binding(0)
+var source: texture_2d_array<f32>;
+@group(0) @binding(1)
+var destination: texture_storage_2d_array<rgba16float, write>;
+
+/// Downsamples one mip level of a cube into the next one,
+/// by averaging 2x2 texels.
+@compute @workgroup_size(8, 8, 1)
+fn entrypoint_compute(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(destination);
+    if id.x >= size.x || id.y >= size.y {
+        return;
+    }
+
+    let base = id.xy * 2u;
+    let color = textureLoad(source, base, id.z, 0)
+        + textureLoad(source, base + vec2<u32>(1u, 0u), id.z, 0)
+        + textureLoad(source, base + vec2<u32>(0u, 1u), id.z, 0)
+        + textureLoad(source, base + vec2<u32>(1u, 1u), id.z, 0);
+
+    textureStore(destination, id.xy, id.z, color * 0.25);
+}
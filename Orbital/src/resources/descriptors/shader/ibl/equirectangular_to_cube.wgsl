@@ -0,0 +1,66 @@
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+@group(0) @binding(1)
+var destination: texture_storage_2d_array<rgba16float, write>;
+
+const PI: f32 = 3.14159265359;
+
+/// Converts a texel of a cube face into a direction.
+/// Faces are in the order: +X, -X, +Y, -Y, +Z, -Z
+fn cube_direction(face: u32, uv: vec2<f32>) -> vec3<f32> {
+    let st = uv * 2.0 - 1.0;
+
+    switch face {
+        case 0u: { return vec3<f32>(1.0, -st.y, -st.x); }
+        case 1u: { return vec3<f32>(-1.0, -st.y, st.x); }
+        case 2u: { return vec3<f32>(st.x, 1.0, st.y); }
+        case 3u: { return vec3<f32>(st.x, -1.0, -st.y); }
+        case 4u: { return vec3<f32>(st.x, -st.y, 1.0); }
+        default: { return vec3<f32>(-st.x, -st.y, -1.0); }
+    }
+}
+
+/// The source texture isn't filterable, thus we have to filter manually.
+fn sample_bilinear(uv: vec2<f32>) -> vec3<f32> {
+    let size = vec2<i32>(textureDimensions(source));
+    let position = uv * vec2<f32>(size) - 0.5;
+    let base = floor(position);
+    let fraction = position - base;
+
+    // Wrap horizontally, clamp vertically
+    let x0 = (i32(base.x) % size.x + size.x) % size.x;
+    let x1 = (x0 + 1) % size.x;
+    let y0 = clamp(i32(base.y), 0, size.y - 1);
+    let y1 = clamp(i32(base.y) + 1, 0, size.y - 1);
+
+    let top = mix(
+        textureLoad(source, vec2<i32>(x0, y0), 0).rgb,
+        textureLoad(source, vec2<i32>(x1, y0), 0).rgb,
+        fraction.x
+    );
+    let bottom = mix(
+        textureLoad(source, vec2<i32>(x0, y1), 0).rgb,
+        textureLoad(source, vec2<i32>(x1, y1), 0).rgb,
+        fraction.x
+    );
+
+    return mix(top, bottom, fraction.y);
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn entrypoint_compute(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(destination);
+    if id.x >= size.x || id.y >= size.y {
+        return;
+    }
+
+    let uv = (vec2<f32>(id.xy) + 0.5) / vec2<f32>(size);
+    let direction = normalize(cube_direction(id.z, uv));
+
+    let equirectangular_uv = vec2<f32>(
+        atan2(direction.z, direction.x) / (2.0 * PI) + 0.5,
+        acos(clamp(direction.y, -1.0, 1.0)) / PI,
+    );
+
+    textureStore(destination, id.xy, id.z, vec4<f32>(sample_bilinear(equirectangular_uv), 1.0));
+}
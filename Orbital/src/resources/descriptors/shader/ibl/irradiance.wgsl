@@ -0,0 +1,65 @@
+@group(0) @binding(0)
+var source: texture_cube<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+@group(0) @binding(2)
+var destination: texture_storage_2d_array<rgba16float, write>;
+
+const PI: f32 = 3.14159265359;
+const PHI_SAMPLES: u32 = 64u;
+const THETA_SAMPLES: u32 = 16u;
+
+/// Converts a texel of a cube face into a direction.
+/// Faces are in the order: +X, -X, +Y, -Y, +Z, -Z
+fn cube_direction(face: u32, uv: vec2<f32>) -> vec3<f32> {
+    let st = uv * 2.0 - 1.0;
+
+    switch face {
+        case 0u: { return vec3<f32>(1.0, -st.y, -st.x); }
+        case 1u: { return vec3<f32>(-1.0, -st.y, st.x); }
+        case 2u: { return vec3<f32>(st.x, 1.0, st.y); }
+        case 3u: { return vec3<f32>(st.x, -1.0, -st.y); }
+        case 4u: { return vec3<f32>(st.x, -st.y, 1.0); }
+        default: { return vec3<f32>(-st.x, -st.y, -1.0); }
+    }
+}
+
+/// Convolutes the hemisphere around each direction, resulting in the
+/// diffuse irradiance coming from that direction.
+@compute @workgroup_size(8, 8, 1)
+fn entrypoint_compute(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(destination);
+    if id.x >= size.x || id.y >= size.y {
+        return;
+    }
+
+    let uv = (vec2<f32>(id.xy) + 0.5) / vec2<f32>(size);
+    let normal = normalize(cube_direction(id.z, uv));
+
+    var up = vec3<f32>(0.0, 1.0, 0.0);
+    if abs(normal.y) > 0.999 {
+        up = vec3<f32>(0.0, 0.0, 1.0);
+    }
+    let right = normalize(cross(up, normal));
+    up = cross(normal, right);
+
+    // Sample a mip level roughly matching the sample density
+    let lod = max(log2(f32(textureDimensions(source).x) / f32(THETA_SAMPLES)), 0.0);
+
+    var irradiance = vec3<f32>(0.0);
+    for (var p = 0u; p < PHI_SAMPLES; p++) {
+        let phi = (f32(p) + 0.5) / f32(PHI_SAMPLES) * 2.0 * PI;
+
+        for (var t = 0u; t < THETA_SAMPLES; t++) {
+            let theta = (f32(t) + 0.5) / f32(THETA_SAMPLES) * 0.5 * PI;
+
+            let tangent_sample = vec3<f32>(sin(theta) * cos(phi), sin(theta) * sin(phi), cos(theta));
+            let direction = tangent_sample.x * right + tangent_sample.y * up + tangent_sample.z * normal;
+
+            irradiance += textureSampleLevel(source, source_sampler, direction, lod).rgb * cos(theta) * sin(theta);
+        }
+    }
+    irradiance = PI * irradiance / f32(PHI_SAMPLES * THETA_SAMPLES);
+
+    textureStore(destination, id.xy, id.z, vec4<f32>(irradiance, 1.0));
+}
@@ -0,0 +1,117 @@
+struct PrefilterParameters {
+    roughness: f32,
+    _padding_0: f32,
+    _padding_1: f32,
+    _padding_2: f32,
+}
+
+@group(0) @binding(0)
+var source: texture_cube<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+@group(0) @binding(2)
+var destination: texture_storage_2d_array<rgba16float, write>;
+@group(0) @binding(3)
+var<uniform> parameters: PrefilterParameters;
+
+const PI: f32 = 3.14159265359;
+const SAMPLE_COUNT: u32 = 512u;
+
+/// Converts a texel of a cube face into a direction.
+/// Faces are in the order: +X, -X, +Y, -Y, +Z, -Z
+fn cube_direction(face: u32, uv: vec2<f32>) -> vec3<f32> {
+    let st = uv * 2.0 - 1.0;
+
+    switch face {
+        case 0u: { return vec3<f32>(1.0, -st.y, -st.x); }
+        case 1u: { return vec3<f32>(-1.0, -st.y, st.x); }
+        case 2u: { return vec3<f32>(st.x, 1.0, st.y); }
+        case 3u: { return vec3<f32>(st.x, -1.0, -st.y); }
+        case 4u: { return vec3<f32>(st.x, -st.y, 1.0); }
+        default: { return vec3<f32>(-st.x, -st.y, -1.0); }
+    }
+}
+
+/// Low-discrepancy sequence, used for quasi-random sampling.
+fn hammersley(i: u32, count: u32) -> vec2<f32> {
+    return vec2<f32>(f32(i) / f32(count), f32(reverseBits(i)) * 2.3283064365386963e-10);
+}
+
+/// Returns a half vector around `normal`, biased towards the GGX lobe.
+fn importance_sample_ggx(xi: vec2<f32>, normal: vec3<f32>, roughness: f32) -> vec3<f32> {
+    let a = roughness * roughness;
+
+    let phi = 2.0 * PI * xi.x;
+    let cos_theta = sqrt((1.0 - xi.y) / (1.0 + (a * a - 1.0) * xi.y));
+    let sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+
+    let h = vec3<f32>(cos(phi) * sin_theta, sin(phi) * sin_theta, cos_theta);
+
+    var up = vec3<f32>(0.0, 0.0, 1.0);
+    if abs(normal.z) > 0.999 {
+        up = vec3<f32>(1.0, 0.0, 0.0);
+    }
+    let tangent = normalize(cross(up, normal));
+    let bitangent = cross(normal, tangent);
+
+    return normalize(tangent * h.x + bitangent * h.y + normal * h.z);
+}
+
+/// Trowbridge-Reitz GGX normal distribution function.
+fn distribution_ggx(n_dot_h: f32, roughness: f32) -> f32 {
+    let a = roughness * roughness;
+    let a2 = a * a;
+    let denominator = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+
+    return a2 / (PI * denominator * denominator);
+}
+
+/// Prefilters the environment for the given roughness.
+/// Each mip level of the destination corresponds to a different roughness.
+@compute @workgroup_size(8, 8, 1)
+fn entrypoint_compute(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(destination);
+    if id.x >= size.x || id.y >= size.y {
+        return;
+    }
+
+    let uv = (vec2<f32>(id.xy) + 0.5) / vec2<f32>(size);
+    let normal = normalize(cube_direction(id.z, uv));
+
+    // A perfect mirror doesn't need any filtering
+    if parameters.roughness <= 0.0 {
+        textureStore(destination, id.xy, id.z, textureSampleLevel(source, source_sampler, normal, 0.0));
+        return;
+    }
+
+    // Assume the view direction equals the reflection direction
+    let view = normal;
+
+    // Solid angle of a single source texel
+    let source_size = f32(textureDimensions(source).x);
+    let texel_solid_angle = 4.0 * PI / (6.0 * source_size * source_size);
+
+    var color = vec3<f32>(0.0);
+    var total_weight = 0.0;
+    for (var i = 0u; i < SAMPLE_COUNT; i++) {
+        let xi = hammersley(i, SAMPLE_COUNT);
+        let h = importance_sample_ggx(xi, normal, parameters.roughness);
+        let l = normalize(2.0 * dot(view, h) * h - view);
+
+        let n_dot_l = dot(normal, l);
+        if n_dot_l > 0.0 {
+            // Sample from a lower mip level, depending on the probability
+            // of this sample, to reduce artifacts from bright spots.
+            let n_dot_h = max(dot(normal, h), 0.0);
+            let h_dot_v = max(dot(h, view), 0.0);
+            let pdf = distribution_ggx(n_dot_h, parameters.roughness) * n_dot_h / (4.0 * h_dot_v) + 0.0001;
+            let sample_solid_angle = 1.0 / (f32(SAMPLE_COUNT) * pdf + 0.0001);
+            let lod = max(0.5 * log2(sample_solid_angle / texel_solid_angle), 0.0);
+
+            color += textureSampleLevel(source, source_sampler, l, lod).rgb * n_dot_l;
+            total_weight += n_dot_l;
+        }
+    }
+
+    textureStore(destination, id.xy, id.z, vec4<f32>(color / max(total_weight, 0.0001), 1.0));
+}
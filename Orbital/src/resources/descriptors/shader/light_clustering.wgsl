@@ -0,0 +1,133 @@
+struct ClusterUniform {
+    inverse_view_projection_matrix: mat4x4<f32>,
+    view_matrix: mat4x4<f32>,
+    inverse_projection_matrix: mat4x4<f32>,
+    screen_size: vec2<f32>,
+    near: f32,
+    far: f32,
+    grid_size: vec3<u32>,
+    _padding: u32,
+}
+
+/// Indices of all lights reaching into a cluster.
+/// Lights beyond `LightClusters::MAX_LIGHTS_PER_CLUSTER` are dropped.
+struct Cluster {
+    count: u32,
+    // Must match `LightClusters::MAX_LIGHTS_PER_CLUSTER`
+    lights: array<u32, 127>,
+}
+
+struct LightData {
+    position: vec3<f32>,
+    light_type: u32,
+    direction: vec3<f32>,
+    range: f32,
+    color: vec3<f32>,
+    intensity: f32,
+    inner_cone_cos: f32,
+    outer_cone_cos: f32,
+    _padding: vec2<f32>,
+}
+
+struct LightStorage {
+    count: u32,
+    lights: array<LightData>,
+}
+
+const MAX_LIGHTS_PER_CLUSTER: u32 = 127u;
+
+const LIGHT_TYPE_DIRECTIONAL: u32 = 1u;
+
+@group(0) @binding(0)
+var<uniform> cluster_uniform: ClusterUniform;
+@group(0) @binding(1)
+var<storage, read> lights: LightStorage;
+@group(0) @binding(2)
+var<storage, read_write> clusters: array<Cluster>;
+
+/// Returns the view space position on the plane at `depth` in front of the
+/// camera, which is seen at the given NDC position.
+fn view_position_at_depth(ndc: vec2<f32>, depth: f32) -> vec3<f32> {
+    let near_position = cluster_uniform.inverse_projection_matrix * vec4<f32>(ndc, 0.0, 1.0);
+    let direction = near_position.xyz / near_position.w;
+
+    // The camera looks along the negative Z axis
+    return direction * (depth / -direction.z);
+}
+
+/// View space depth of the near side of the given slice.
+/// Slices are distributed exponentially, i.e. get deeper with distance.
+fn slice_depth(slice: u32) -> f32 {
+    let ratio = f32(slice) / f32(cluster_uniform.grid_size.z);
+    return cluster_uniform.near * pow(cluster_uniform.far / cluster_uniform.near, ratio);
+}
+
+/// Checks if a sphere intersects an axis aligned box.
+fn sphere_intersects_aabb(center: vec3<f32>, radius: f32, aabb_min: vec3<f32>, aabb_max: vec3<f32>) -> bool {
+    let closest = clamp(center, aabb_min, aabb_max);
+    let offset = center - closest;
+    return dot(offset, offset) <= radius * radius;
+}
+
+/// Finds all lights reaching into a single cluster.
+/// One invocation per cluster, ordered X, then Y, then Z (slice).
+@compute @workgroup_size(64)
+fn entrypoint_compute(@builtin(global_invocation_id) id: vec3<u32>) {
+    let grid_size = cluster_uniform.grid_size;
+    let index = id.x;
+    if index >= grid_size.x * grid_size.y * grid_size.z {
+        return;
+    }
+
+    let cluster = vec3<u32>(
+        index % grid_size.x,
+        (index / grid_size.x) % grid_size.y,
+        index / (grid_size.x * grid_size.y),
+    );
+
+    // Tiles go down the screen, NDC go up
+    let ndc_min = vec2<f32>(
+        f32(cluster.x) / f32(grid_size.x) * 2.0 - 1.0,
+        1.0 - f32(cluster.y + 1u) / f32(grid_size.y) * 2.0,
+    );
+    let ndc_max = vec2<f32>(
+        f32(cluster.x + 1u) / f32(grid_size.x) * 2.0 - 1.0,
+        1.0 - f32(cluster.y) / f32(grid_size.y) * 2.0,
+    );
+    let near_depth = slice_depth(cluster.z);
+    let far_depth = slice_depth(cluster.z + 1u);
+
+    // Bounds around the corners of the cluster
+    var aabb_min = vec3<f32>(3.4e38);
+    var aabb_max = vec3<f32>(-3.4e38);
+    for (var corner = 0u; corner < 8u; corner++) {
+        let ndc = vec2<f32>(
+            select(ndc_min.x, ndc_max.x, (corner & 1u) != 0u),
+            select(ndc_min.y, ndc_max.y, (corner & 2u) != 0u),
+        );
+        let depth = select(near_depth, far_depth, (corner & 4u) != 0u);
+
+        let position = view_position_at_depth(ndc, depth);
+        aabb_min = min(aabb_min, position);
+        aabb_max = max(aabb_max, position);
+    }
+
+    var count = 0u;
+    for (var i = 0u; i < lights.count && count < MAX_LIGHTS_PER_CLUSTER; i++) {
+        let light = lights.lights[i];
+
+        // Directional lights and lights without range reach everywhere
+        var reaches = light.light_type == LIGHT_TYPE_DIRECTIONAL || light.range <= 0.0;
+        if !reaches {
+            let view_position = (cluster_uniform.view_matrix * vec4<f32>(light.position, 1.0)).xyz;
+            reaches = sphere_intersects_aabb(view_position, light.range, aabb_min, aabb_max);
+        }
+
+        if reaches {
+            clusters[index].lights[count] = i;
+            count++;
+        }
+    }
+
+    clusters[index].count = count;
+}
@@ -0,0 +1,166 @@
+// Lighting functions shared by `standard_pbr.wgsl` and `deferred_lighting.wgsl`,
+// prepended to both.
+// The including shader has to declare `shadow_atlas` and `shadow_sampler`.
+
+struct LightData {
+    position: vec3<f32>,
+    light_type: u32,
+    direction: vec3<f32>,
+    range: f32,
+    color: vec3<f32>,
+    intensity: f32,
+    inner_cone_cos: f32,
+    outer_cone_cos: f32,
+    _padding: vec2<f32>,
+}
+
+struct ShadowData {
+    view_projection_matrix: mat4x4<f32>,
+    atlas_rect: vec4<f32>,
+    bias: f32,
+    enabled: u32,
+    _padding: vec2<f32>,
+}
+
+const PI: f32 = 3.14159265359;
+
+const LIGHT_TYPE_POINT: u32 = 0u;
+const LIGHT_TYPE_DIRECTIONAL: u32 = 1u;
+const LIGHT_TYPE_SPOT: u32 = 2u;
+
+/// Trowbridge-Reitz GGX normal distribution function.
+fn distribution_ggx(n_dot_h: f32, roughness: f32) -> f32 {
+    let a = roughness * roughness;
+    let a2 = a * a;
+    let denominator = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+
+    return a2 / (PI * denominator * denominator);
+}
+
+/// Schlick-GGX geometry function for a single direction.
+fn geometry_schlick_ggx(n_dot_x: f32, roughness: f32) -> f32 {
+    let r = roughness + 1.0;
+    let k = (r * r) / 8.0;
+
+    return n_dot_x / (n_dot_x * (1.0 - k) + k);
+}
+
+/// Smith's method, combining view and light direction occlusion.
+fn geometry_smith(n_dot_v: f32, n_dot_l: f32, roughness: f32) -> f32 {
+    return geometry_schlick_ggx(n_dot_v, roughness) * geometry_schlick_ggx(n_dot_l, roughness);
+}
+
+/// Fresnel-Schlick approximation.
+fn fresnel_schlick(cos_theta: f32, f0: vec3<f32>) -> vec3<f32> {
+    return f0 + (1.0 - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+/// Fresnel-Schlick approximation, accounting for roughness.
+/// Used for ambient light, where there is no single half vector.
+fn fresnel_schlick_roughness(cos_theta: f32, f0: vec3<f32>, roughness: f32) -> vec3<f32> {
+    return f0 + (max(vec3<f32>(1.0 - roughness), f0) - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+/// Cook-Torrance BRDF for a single light.
+/// Returns the outgoing radiance towards the viewer.
+fn cook_torrance(
+    n: vec3<f32>,
+    v: vec3<f32>,
+    l: vec3<f32>,
+    radiance: vec3<f32>,
+    albedo: vec3<f32>,
+    metallic: f32,
+    roughness: f32,
+    f0: vec3<f32>,
+) -> vec3<f32> {
+    let h = normalize(v + l);
+
+    let n_dot_v = max(dot(n, v), 0.0001);
+    let n_dot_l = max(dot(n, l), 0.0);
+    let n_dot_h = max(dot(n, h), 0.0);
+    let h_dot_v = max(dot(h, v), 0.0);
+
+    let ndf = distribution_ggx(n_dot_h, roughness);
+    let g = geometry_smith(n_dot_v, n_dot_l, roughness);
+    let f = fresnel_schlick(h_dot_v, f0);
+
+    let specular = (ndf * g * f) / (4.0 * n_dot_v * n_dot_l + 0.0001);
+
+    // Energy conservation: Whatever isn't reflected is refracted.
+    // Metals don't refract (i.e. have no diffuse part).
+    let k_diffuse = (vec3<f32>(1.0) - f) * (1.0 - metallic);
+
+    return (k_diffuse * albedo / PI + specular) * radiance * n_dot_l;
+}
+
+/// Smoothly fades a light out towards it's range.
+/// A range of zero (or less) means infinite range.
+fn range_attenuation(distance: f32, range: f32) -> f32 {
+    if range <= 0.0 {
+        return 1.0;
+    }
+
+    let ratio = distance / range;
+    let falloff = clamp(1.0 - ratio * ratio * ratio * ratio, 0.0, 1.0);
+    return falloff * falloff;
+}
+
+/// Returns the direction towards the light (xyz) and
+/// the attenuation of the light (w) at the given position.
+fn light_direction_attenuation(light: LightData, world_position: vec3<f32>) -> vec4<f32> {
+    if light.light_type == LIGHT_TYPE_DIRECTIONAL {
+        return vec4<f32>(normalize(-light.direction), 1.0);
+    }
+
+    let to_light = light.position - world_position;
+    let distance = max(length(to_light), 0.0001);
+    let l = to_light / distance;
+
+    var attenuation = range_attenuation(distance, light.range) / (distance * distance);
+
+    if light.light_type == LIGHT_TYPE_SPOT {
+        let cos_theta = dot(normalize(light.direction), -l);
+        attenuation *= smoothstep(light.outer_cone_cos, light.inner_cone_cos, cos_theta);
+    }
+
+    return vec4<f32>(l, attenuation);
+}
+
+/// Returns how much of the light reaches the given position.
+/// 1.0 is fully lit, 0.0 is fully in shadow.
+///
+/// Uses a 3x3 PCF (= Percentage-Closer-Filtering) kernel to soften the edges.
+fn shadow_visibility(shadow: ShadowData, world_position: vec3<f32>) -> f32 {
+    if shadow.enabled == 0u {
+        return 1.0;
+    }
+
+    let clip_position = shadow.view_projection_matrix * vec4<f32>(world_position, 1.0);
+    let ndc = clip_position.xyz / clip_position.w;
+
+    // Anything outside the shadow map is considered lit
+    if ndc.z < 0.0 || ndc.z > 1.0 || abs(ndc.x) > 1.0 || abs(ndc.y) > 1.0 {
+        return 1.0;
+    }
+
+    // NDC to UV coordinates inside the tile, then into the atlas
+    let tile_uv = vec2<f32>(ndc.x * 0.5 + 0.5, ndc.y * -0.5 + 0.5);
+    let uv = shadow.atlas_rect.xy + tile_uv * shadow.atlas_rect.zw;
+
+    // Keep samples inside the tile, otherwise neighbouring shadow maps bleed in
+    let texel_size = 1.0 / vec2<f32>(textureDimensions(shadow_atlas));
+    let uv_min = shadow.atlas_rect.xy + texel_size * 0.5;
+    let uv_max = shadow.atlas_rect.xy + shadow.atlas_rect.zw - texel_size * 0.5;
+
+    let depth = ndc.z - shadow.bias;
+
+    var visibility = 0.0;
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            let sample_uv = clamp(uv + vec2<f32>(f32(x), f32(y)) * texel_size, uv_min, uv_max);
+            visibility += textureSampleCompareLevel(shadow_atlas, shadow_sampler, sample_uv, depth);
+        }
+    }
+
+    return visibility / 9.0;
+}
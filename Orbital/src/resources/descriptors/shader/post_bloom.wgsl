@@ -0,0 +1,73 @@
+struct PostProcessingUniform {
+    exposure: f32,
+    tonemapping: u32,
+    bloom_intensity: f32,
+    bloom_threshold: f32,
+    vignette_intensity: f32,
+    vignette_radius: f32,
+    vignette_smoothness: f32,
+    color_grading_lut_size: f32,
+}
+
+struct FragmentData {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@group(0) @binding(0)
+var input_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var input_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> post_processing: PostProcessingUniform;
+
+fn texel_size() -> vec2<f32> {
+    return 1.0 / vec2<f32>(textureDimensions(input_texture));
+}
+
+/// Averages 16 texels around the given position, using 4 bilinear samples.
+fn downsample(uv: vec2<f32>) -> vec3<f32> {
+    let offset = texel_size();
+
+    return (
+        textureSample(input_texture, input_sampler, uv + vec2<f32>(-offset.x, -offset.y)).rgb
+        + textureSample(input_texture, input_sampler, uv + vec2<f32>(offset.x, -offset.y)).rgb
+        + textureSample(input_texture, input_sampler, uv + vec2<f32>(-offset.x, offset.y)).rgb
+        + textureSample(input_texture, input_sampler, uv + vec2<f32>(offset.x, offset.y)).rgb
+    ) * 0.25;
+}
+
+/// First downsample, only keeping colors above the threshold.
+/// The threshold is soft, to avoid hard edges around blooming areas.
+@fragment
+fn entrypoint_fragment_prefilter(fragment: FragmentData) -> @location(0) vec4<f32> {
+    let color = downsample(fragment.uv);
+
+    let brightness = max(color.r, max(color.g, color.b));
+    let contribution = max(brightness - post_processing.bloom_threshold, 0.0) / max(brightness, 0.0001);
+
+    return vec4<f32>(color * contribution, 1.0);
+}
+
+@fragment
+fn entrypoint_fragment_downsample(fragment: FragmentData) -> @location(0) vec4<f32> {
+    return vec4<f32>(downsample(fragment.uv), 1.0);
+}
+
+/// Blurs the smaller mip with a 3x3 tent filter.
+/// Blended additively onto the bigger mip.
+@fragment
+fn entrypoint_fragment_upsample(fragment: FragmentData) -> @location(0) vec4<f32> {
+    let offset = texel_size();
+
+    var color = vec3<f32>(0.0);
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            let weight = f32((2 - abs(x)) * (2 - abs(y))) / 16.0;
+            let uv = fragment.uv + vec2<f32>(f32(x), f32(y)) * offset;
+            color += textureSample(input_texture, input_sampler, uv).rgb * weight;
+        }
+    }
+
+    return vec4<f32>(color, 1.0);
+}
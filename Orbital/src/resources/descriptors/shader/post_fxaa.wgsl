@@ -0,0 +1,65 @@
+struct FragmentData {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+const REDUCE_MIN: f32 = 1.0 / 128.0;
+const REDUCE_MUL: f32 = 1.0 / 8.0;
+const SPAN_MAX: f32 = 8.0;
+
+@group(0) @binding(0)
+var input_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var input_sampler: sampler;
+
+/// Perceived brightness of a linear color.
+fn luma(color: vec3<f32>) -> f32 {
+    return dot(sqrt(max(color, vec3<f32>(0.0))), vec3<f32>(0.299, 0.587, 0.114));
+}
+
+fn sample(uv: vec2<f32>) -> vec3<f32> {
+    return textureSampleLevel(input_texture, input_sampler, uv, 0.0).rgb;
+}
+
+/// Blurs along edges, found by comparing the brightness of the diagonal
+/// neighbours.
+@fragment
+fn entrypoint_fragment(fragment: FragmentData) -> @location(0) vec4<f32> {
+    let texel_size = 1.0 / vec2<f32>(textureDimensions(input_texture));
+    let uv = fragment.uv;
+
+    let center = textureSampleLevel(input_texture, input_sampler, uv, 0.0);
+    let luma_center = luma(center.rgb);
+    let luma_nw = luma(sample(uv + vec2<f32>(-1.0, -1.0) * texel_size));
+    let luma_ne = luma(sample(uv + vec2<f32>(1.0, -1.0) * texel_size));
+    let luma_sw = luma(sample(uv + vec2<f32>(-1.0, 1.0) * texel_size));
+    let luma_se = luma(sample(uv + vec2<f32>(1.0, 1.0) * texel_size));
+
+    let luma_min = min(luma_center, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_center, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    // Along the edge, perpendicular to the brightness gradient
+    var direction = vec2<f32>(
+        (luma_sw + luma_se) - (luma_nw + luma_ne),
+        (luma_nw + luma_sw) - (luma_ne + luma_se),
+    );
+    let reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+    let scale = 1.0 / (min(abs(direction.x), abs(direction.y)) + reduce);
+    direction = clamp(direction * scale, vec2<f32>(-SPAN_MAX), vec2<f32>(SPAN_MAX)) * texel_size;
+
+    let near = 0.5 * (
+        sample(uv + direction * (1.0 / 3.0 - 0.5))
+        + sample(uv + direction * (2.0 / 3.0 - 0.5))
+    );
+    let far = near * 0.5 + 0.25 * (
+        sample(uv + direction * -0.5)
+        + sample(uv + direction * 0.5)
+    );
+
+    // Sampling too far crossed another edge
+    let luma_far = luma(far);
+    if luma_far < luma_min || luma_far > luma_max {
+        return vec4<f32>(near, center.a);
+    }
+    return vec4<f32>(far, center.a);
+}
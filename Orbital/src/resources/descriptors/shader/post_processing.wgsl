@@ -0,0 +1,32 @@
+/// Settings shared by all post processing passes.
+/// Check `PostProcessingSettings`.
+struct PostProcessingUniform {
+    exposure: f32,
+    tonemapping: u32,
+    bloom_intensity: f32,
+    bloom_threshold: f32,
+    vignette_intensity: f32,
+    vignette_radius: f32,
+    vignette_smoothness: f32,
+    // Zero if there is no lookup table
+    color_grading_lut_size: f32,
+}
+
+struct FragmentData {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+/// Draws a single triangle covering the whole screen.
+/// Shared by all post processing passes, which only provide fragment
+/// entrypoints.
+@vertex
+fn entrypoint_vertex(@builtin(vertex_index) vertex_index: u32) -> FragmentData {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+
+    var out: FragmentData;
+    // Texture coordinates go down, NDC go up
+    out.position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
@@ -0,0 +1,155 @@
+struct PostProcessingUniform {
+    exposure: f32,
+    tonemapping: u32,
+    bloom_intensity: f32,
+    bloom_threshold: f32,
+    vignette_intensity: f32,
+    vignette_radius: f32,
+    vignette_smoothness: f32,
+    color_grading_lut_size: f32,
+}
+
+struct FragmentData {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// Must match `Tonemapping`
+const TONEMAPPING_REINHARD: u32 = 1u;
+const TONEMAPPING_ACES: u32 = 2u;
+const TONEMAPPING_AGX: u32 = 3u;
+
+@group(0) @binding(0)
+var input_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var input_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> post_processing: PostProcessingUniform;
+
+// Black if bloom is disabled
+@group(1) @binding(0)
+var bloom_texture: texture_2d<f32>;
+// Only sampled if `color_grading_lut_size` is set
+@group(1) @binding(1)
+var color_grading_lut: texture_2d<f32>;
+
+fn tonemap_reinhard(color: vec3<f32>) -> vec3<f32> {
+    return color / (1.0 + color);
+}
+
+/// Stephen Hill's fit of the ACES reference rendering and output
+/// device transforms.
+fn tonemap_aces(color: vec3<f32>) -> vec3<f32> {
+    let input_matrix = mat3x3<f32>(
+        0.59719, 0.07600, 0.02840,
+        0.35458, 0.90834, 0.13383,
+        0.04823, 0.01566, 0.83777,
+    );
+    let output_matrix = mat3x3<f32>(
+        1.60475, -0.10208, -0.00327,
+        -0.53108, 1.10813, -0.07276,
+        -0.07367, -0.00605, 1.07602,
+    );
+
+    let v = input_matrix * color;
+    let a = v * (v + 0.0245786) - 0.000090537;
+    let b = v * (0.983729 * v + 0.4329510) + 0.238081;
+    return saturate(output_matrix * (a / b));
+}
+
+/// Benjamin Wrensch's polynomial approximation of AgX.
+fn tonemap_agx(color: vec3<f32>) -> vec3<f32> {
+    let input_matrix = mat3x3<f32>(
+        0.842479062253094, 0.0423282422610123, 0.0423756549057051,
+        0.0784335999999992, 0.878468636469772, 0.0784336,
+        0.0792237451477643, 0.0791661274605434, 0.879142973793104,
+    );
+    let output_matrix = mat3x3<f32>(
+        1.19687900512017, -0.0528968517574562, -0.0529716355144438,
+        -0.0980208811401368, 1.15190312990417, -0.0980434501171241,
+        -0.0990297440797205, -0.0989611768448433, 1.15107367264116,
+    );
+    let min_ev = -12.47393;
+    let max_ev = 4.026069;
+
+    // Log encoding
+    var v = input_matrix * color;
+    v = clamp(log2(max(v, vec3<f32>(1e-10))), vec3<f32>(min_ev), vec3<f32>(max_ev));
+    v = (v - min_ev) / (max_ev - min_ev);
+
+    // Sigmoid contrast curve
+    let v2 = v * v;
+    let v4 = v2 * v2;
+    v = 15.5 * v4 * v2 - 40.14 * v4 * v + 31.96 * v4 - 6.868 * v2 * v + 0.4298 * v2 + 0.1191 * v - 0.00232;
+
+    // Back to linear
+    v = output_matrix * v;
+    return pow(max(v, vec3<f32>(0.0)), vec3<f32>(2.2));
+}
+
+fn tonemap(color: vec3<f32>) -> vec3<f32> {
+    switch post_processing.tonemapping {
+        case TONEMAPPING_REINHARD: {
+            return tonemap_reinhard(color);
+        }
+        case TONEMAPPING_ACES: {
+            return tonemap_aces(color);
+        }
+        case TONEMAPPING_AGX: {
+            return tonemap_agx(color);
+        }
+        default: {
+            return saturate(color);
+        }
+    }
+}
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+/// Looks the color up in the lookup table strip, interpolating between
+/// the two closest blue slices.
+fn color_grade(color: vec3<f32>) -> vec3<f32> {
+    let size = post_processing.color_grading_lut_size;
+    let encoded = linear_to_srgb(saturate(color));
+
+    let blue = encoded.b * (size - 1.0);
+    let slice = floor(blue);
+    let next_slice = min(slice + 1.0, size - 1.0);
+
+    // Centers of the texels, inside a slice
+    let texel = encoded.rg * (size - 1.0) + 0.5;
+    let strip_size = vec2<f32>(size * size, size);
+
+    let a = textureSampleLevel(color_grading_lut, input_sampler, vec2<f32>(slice * size + texel.x, texel.y) / strip_size, 0.0).rgb;
+    let b = textureSampleLevel(color_grading_lut, input_sampler, vec2<f32>(next_slice * size + texel.x, texel.y) / strip_size, 0.0).rgb;
+    return mix(a, b, blue - slice);
+}
+
+fn vignette(uv: vec2<f32>) -> f32 {
+    // Zero in the center, one in the corners
+    let distance = length(uv - 0.5) * sqrt(2.0);
+    let edge = post_processing.vignette_radius;
+
+    return 1.0 - post_processing.vignette_intensity * smoothstep(edge, edge + post_processing.vignette_smoothness, distance);
+}
+
+@fragment
+fn entrypoint_fragment(fragment: FragmentData) -> @location(0) vec4<f32> {
+    let hdr = textureSample(input_texture, input_sampler, fragment.uv);
+    let bloom = textureSample(bloom_texture, input_sampler, fragment.uv).rgb;
+
+    var color = (hdr.rgb + bloom * post_processing.bloom_intensity) * exp2(post_processing.exposure);
+    color = tonemap(color);
+
+    if post_processing.color_grading_lut_size > 0.0 {
+        color = color_grade(color);
+    }
+
+    color *= vignette(fragment.uv);
+
+    return vec4<f32>(color, hdr.a);
+}
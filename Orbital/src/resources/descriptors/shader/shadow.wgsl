@@ -0,0 +1,140 @@
+struct VertexData {
+    @builtin(vertex_index) vertex_index: u32,
+    @location(0) position: vec3<f32>,
+    @location(4) uv: vec2<f32>,
+}
+
+struct InstanceData {
+    @location(5) model_space_matrix_0: vec4<f32>,
+    @location(6) model_space_matrix_1: vec4<f32>,
+    @location(7) model_space_matrix_2: vec4<f32>,
+    @location(8) model_space_matrix_3: vec4<f32>,
+}
+
+struct SkinData {
+    @location(9) joints: vec4<u32>,
+    @location(10) weights: vec4<f32>,
+}
+
+struct MorphDelta {
+    position: vec4<f32>,
+    normal: vec4<f32>,
+    tangent: vec4<f32>,
+}
+
+struct MorphWeights {
+    vertex_count: u32,
+    target_count: u32,
+    weights: array<f32>,
+}
+
+struct FragmentData {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+struct ShadowUniform {
+    view_projection_matrix: mat4x4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> shadow: ShadowUniform;
+
+// Only bound by the skinning variant of the pipeline
+@group(1) @binding(0)
+var<storage, read> joint_matrices: array<mat4x4<f32>>;
+// Only bound by the morphing variant of the pipeline
+@group(1) @binding(1)
+var<storage, read> morph_deltas: array<MorphDelta>;
+@group(1) @binding(2)
+var<storage, read> morph_weights: MorphWeights;
+
+// Only bound by the masked variant of the pipeline
+@group(1) @binding(3)
+var albedo_texture: texture_2d<f32>;
+@group(1) @binding(4)
+var albedo_sampler: sampler;
+
+// Set by the masked variant of the pipeline
+override alpha_cutoff: f32 = 0.0;
+
+// Depth only, thus only the masked variant has a fragment stage.
+@vertex
+fn entrypoint_vertex(
+    vertex: VertexData,
+    instance: InstanceData
+) -> FragmentData {
+    return FragmentData(transform_position(vertex.position, instance), vertex.uv);
+}
+
+@vertex
+fn entrypoint_vertex_skinned(
+    vertex: VertexData,
+    instance: InstanceData,
+    skin: SkinData
+) -> FragmentData {
+    return FragmentData(transform_position(apply_skin(vertex.position, skin), instance), vertex.uv);
+}
+
+@vertex
+fn entrypoint_vertex_morphed(
+    vertex: VertexData,
+    instance: InstanceData
+) -> FragmentData {
+    return FragmentData(transform_position(apply_morph_targets(vertex), instance), vertex.uv);
+}
+
+@vertex
+fn entrypoint_vertex_skinned_morphed(
+    vertex: VertexData,
+    instance: InstanceData,
+    skin: SkinData
+) -> FragmentData {
+    return FragmentData(transform_position(apply_skin(apply_morph_targets(vertex), skin), instance), vertex.uv);
+}
+
+/// Discards fragments below the alpha cutoff, like the masked surface does.
+@fragment
+fn entrypoint_fragment_masked(fragment: FragmentData) {
+    if textureSample(albedo_texture, albedo_sampler, fragment.uv).a < alpha_cutoff {
+        discard;
+    }
+}
+
+/// Adds the weighted position deltas of all morph targets onto the vertex.
+fn apply_morph_targets(vertex: VertexData) -> vec3<f32> {
+    var position = vertex.position;
+
+    for (var i = 0u; i < morph_weights.target_count; i++) {
+        let weight = morph_weights.weights[i];
+        if weight == 0.0 {
+            continue;
+        }
+
+        position += weight * morph_deltas[i * morph_weights.vertex_count + vertex.vertex_index].position.xyz;
+    }
+
+    return position;
+}
+
+/// Transforms the position by the weighted joint matrices.
+fn apply_skin(position: vec3<f32>, skin: SkinData) -> vec3<f32> {
+    let skin_matrix = skin.weights.x * joint_matrices[skin.joints.x]
+        + skin.weights.y * joint_matrices[skin.joints.y]
+        + skin.weights.z * joint_matrices[skin.joints.z]
+        + skin.weights.w * joint_matrices[skin.joints.w];
+
+    return (skin_matrix * vec4<f32>(position, 1.0)).xyz;
+}
+
+/// Transforms a position from model space into the light's clip space.
+fn transform_position(position: vec3<f32>, instance: InstanceData) -> vec4<f32> {
+    let model_space_matrix = mat4x4<f32>(
+        instance.model_space_matrix_0,
+        instance.model_space_matrix_1,
+        instance.model_space_matrix_2,
+        instance.model_space_matrix_3,
+    );
+
+    return shadow.view_projection_matrix * model_space_matrix * vec4<f32>(position, 1.0);
+}
@@ -0,0 +1,41 @@
+struct SkyboxUniform {
+    inverse_view_projection_matrix: mat4x4<f32>,
+    camera_position: vec3<f32>,
+    intensity: f32,
+}
+
+struct FragmentData {
+    @builtin(position) position: vec4<f32>,
+    @location(0) ndc: vec2<f32>,
+}
+
+@group(0) @binding(0)
+var environment_map: texture_cube<f32>;
+@group(0) @binding(1)
+var environment_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> skybox: SkyboxUniform;
+
+/// Draws a single triangle covering the whole screen.
+/// The depth is set to the far plane, so that the skybox is only visible
+/// where nothing else has been drawn.
+@vertex
+fn entrypoint_vertex(@builtin(vertex_index) vertex_index: u32) -> FragmentData {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    let ndc = uv * 2.0 - 1.0;
+
+    var out: FragmentData;
+    out.position = vec4<f32>(ndc, 1.0, 1.0);
+    out.ndc = ndc;
+    return out;
+}
+
+@fragment
+fn entrypoint_fragment(fragment: FragmentData) -> @location(0) vec4<f32> {
+    let world_position = skybox.inverse_view_projection_matrix * vec4<f32>(fragment.ndc, 1.0, 1.0);
+    let direction = normalize(world_position.xyz / world_position.w - skybox.camera_position);
+
+    let color = textureSampleLevel(environment_map, environment_sampler, direction, 0.0).rgb;
+
+    return vec4<f32>(color * skybox.intensity, 1.0);
+}
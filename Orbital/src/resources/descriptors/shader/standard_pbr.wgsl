@@ -14,28 +14,217 @@ struct InstanceData {
     @location(8) model_space_matrix_3: vec4<f32>,
 }
 
+struct SkinData {
+    @location(9) joints: vec4<u32>,
+    @location(10) weights: vec4<f32>,
+}
+
+struct MorphDelta {
+    position: vec4<f32>,
+    normal: vec4<f32>,
+    tangent: vec4<f32>,
+}
+
+struct MorphWeights {
+    vertex_count: u32,
+    target_count: u32,
+    weights: array<f32>,
+}
+
+/// A vertex in model space, after skinning and morphing.
+struct DeformedVertex {
+    position: vec3<f32>,
+    normal: vec3<f32>,
+    tangent: vec3<f32>,
+    bitangent: vec3<f32>,
+}
+
 struct FragmentData {
     @builtin(position) position: vec4<f32>,
     @location(0) uv: vec2<f32>,
     @location(1) normal: vec3<f32>,
     @location(2) tangent: vec3<f32>,
     @location(3) bitangent: vec3<f32>,
+    @location(4) world_position: vec3<f32>,
+}
+
+/// Surface properties of a fragment, sampled from the material.
+struct SurfaceData {
+    albedo: vec3<f32>,
+    alpha: f32,
+    metallic: f32,
+    roughness: f32,
+    occlusion: f32,
+    emissive: vec3<f32>,
+    /// World space normal, after normal mapping
+    normal: vec3<f32>,
+}
+
+/// Targets of the G-buffer variant of the pipeline.
+struct GBufferData {
+    /// Albedo (RGB) and occlusion (A)
+    @location(0) albedo: vec4<f32>,
+    /// World space normal (RGB)
+    @location(1) normal: vec4<f32>,
+    /// Metallic (R) and roughness (G)
+    @location(2) material: vec4<f32>,
+    /// Emissive (RGB)
+    @location(3) emissive: vec4<f32>,
 }
 
 struct CameraUniform {
     view_projection_matrix: mat4x4<f32>,
+    position: vec4<f32>,
+}
+
+struct LightStorage {
+    count: u32,
+    lights: array<LightData>,
+}
+
+struct EnvironmentUniform {
+    intensity: f32,
+    specular_mip_count: f32,
+    _padding: vec2<f32>,
 }
 
+// Only set by masking pipelines, fragments with a lower alpha are discarded
+override alpha_cutoff: f32 = 0.0;
+
 @group(0) @binding(0) var albedo_texture: texture_2d<f32>;
 @group(0) @binding(1) var albedo_sampler: sampler;
+@group(0) @binding(2) var metallic_texture: texture_2d<f32>;
+@group(0) @binding(3) var metallic_sampler: sampler;
+@group(0) @binding(4) var roughness_texture: texture_2d<f32>;
+@group(0) @binding(5) var roughness_sampler: sampler;
+@group(0) @binding(6) var normal_texture: texture_2d<f32>;
+@group(0) @binding(7) var normal_sampler: sampler;
+@group(0) @binding(8) var occlusion_texture: texture_2d<f32>;
+@group(0) @binding(9) var occlusion_sampler: sampler;
+@group(0) @binding(10) var emissive_texture: texture_2d<f32>;
+@group(0) @binding(11) var emissive_sampler: sampler;
+
+// Only bound by the skinning variant of the pipeline
+@group(0) @binding(12) var<storage, read> joint_matrices: array<mat4x4<f32>>;
+// Only bound by the morphing variant of the pipeline
+@group(0) @binding(13) var<storage, read> morph_deltas: array<MorphDelta>;
+@group(0) @binding(14) var<storage, read> morph_weights: MorphWeights;
 
-@group(1) @binding(0) 
+@group(1) @binding(0)
 var<uniform> camera: CameraUniform;
 
+@group(2) @binding(0)
+var<storage, read> lights: LightStorage;
+@group(2) @binding(1)
+var<storage, read> shadows: array<ShadowData>;
+@group(2) @binding(2)
+var shadow_atlas: texture_depth_2d;
+@group(2) @binding(3)
+var shadow_sampler: sampler_comparison;
+
+@group(3) @binding(0)
+var irradiance_map: texture_cube<f32>;
+@group(3) @binding(1)
+var specular_map: texture_cube<f32>;
+@group(3) @binding(2)
+var brdf_lut: texture_2d<f32>;
+@group(3) @binding(3)
+var environment_sampler: sampler;
+@group(3) @binding(4)
+var<uniform> environment: EnvironmentUniform;
+
 @vertex
 fn entrypoint_vertex(
     vertex: VertexData,
     instance: InstanceData
+) -> FragmentData {
+    return transform_vertex(undeformed_vertex(vertex), vertex.uv, instance);
+}
+
+@vertex
+fn entrypoint_vertex_skinned(
+    vertex: VertexData,
+    instance: InstanceData,
+    skin: SkinData
+) -> FragmentData {
+    return transform_vertex(apply_skin(undeformed_vertex(vertex), skin), vertex.uv, instance);
+}
+
+@vertex
+fn entrypoint_vertex_morphed(
+    vertex: VertexData,
+    instance: InstanceData
+) -> FragmentData {
+    return transform_vertex(apply_morph_targets(vertex), vertex.uv, instance);
+}
+
+@vertex
+fn entrypoint_vertex_skinned_morphed(
+    vertex: VertexData,
+    instance: InstanceData,
+    skin: SkinData
+) -> FragmentData {
+    return transform_vertex(apply_skin(apply_morph_targets(vertex), skin), vertex.uv, instance);
+}
+
+fn undeformed_vertex(vertex: VertexData) -> DeformedVertex {
+    return DeformedVertex(vertex.position, vertex.normal, vertex.tangent, vertex.bitangent);
+}
+
+/// Adds the weighted deltas of all morph targets onto the vertex.
+fn apply_morph_targets(vertex: VertexData) -> DeformedVertex {
+    var position = vertex.position;
+    var normal = vertex.normal;
+    var tangent = vertex.tangent;
+
+    for (var i = 0u; i < morph_weights.target_count; i++) {
+        let weight = morph_weights.weights[i];
+        if weight == 0.0 {
+            continue;
+        }
+
+        let delta = morph_deltas[i * morph_weights.vertex_count + vertex.vertex_index];
+        position += weight * delta.position.xyz;
+        normal += weight * delta.normal.xyz;
+        tangent += weight * delta.tangent.xyz;
+    }
+
+    // The bitangent isn't morphed, but rebuilt while keeping it's handedness
+    let handedness = select(-1.0, 1.0, dot(cross(vertex.normal, vertex.tangent), vertex.bitangent) >= 0.0);
+
+    return DeformedVertex(
+        position,
+        normal,
+        tangent,
+        cross(normal, tangent) * handedness,
+    );
+}
+
+/// Transforms the vertex by the weighted joint matrices.
+fn apply_skin(vertex: DeformedVertex, skin: SkinData) -> DeformedVertex {
+    let skin_matrix = skin.weights.x * joint_matrices[skin.joints.x]
+        + skin.weights.y * joint_matrices[skin.joints.y]
+        + skin.weights.z * joint_matrices[skin.joints.z]
+        + skin.weights.w * joint_matrices[skin.joints.w];
+    let skin_normal_matrix = mat3x3<f32>(
+        skin_matrix[0].xyz,
+        skin_matrix[1].xyz,
+        skin_matrix[2].xyz,
+    );
+
+    return DeformedVertex(
+        (skin_matrix * vec4<f32>(vertex.position, 1.0)).xyz,
+        skin_normal_matrix * vertex.normal,
+        skin_normal_matrix * vertex.tangent,
+        skin_normal_matrix * vertex.bitangent,
+    );
+}
+
+/// Transforms a vertex from model space into world and clip space.
+fn transform_vertex(
+    vertex: DeformedVertex,
+    uv: vec2<f32>,
+    instance: InstanceData
 ) -> FragmentData {
     let model_space_matrix = mat4x4<f32>(
         instance.model_space_matrix_0,
@@ -43,32 +232,125 @@ fn entrypoint_vertex(
         instance.model_space_matrix_2,
         instance.model_space_matrix_3,
     );
+    // Note: This is only correct for uniform scaling.
+    // Non-uniform scaling would require the inverse transpose.
+    let normal_matrix = mat3x3<f32>(
+        model_space_matrix[0].xyz,
+        model_space_matrix[1].xyz,
+        model_space_matrix[2].xyz,
+    );
+
+    let world_position = model_space_matrix * vec4<f32>(vertex.position, 1.0);
 
     var out: FragmentData;
 
     // Calculate actual position
-    out.position = camera.view_projection_matrix * model_space_matrix * vec4<f32>(vertex.position, 1.0);
+    out.position = camera.view_projection_matrix * world_position;
+    out.world_position = world_position.xyz;
+
+    // Transform the tangent basis into world space
+    out.normal = normalize(normal_matrix * vertex.normal);
+    out.tangent = normalize(normal_matrix * vertex.tangent);
+    out.bitangent = normalize(normal_matrix * vertex.bitangent);
 
     // Passthrough variables
-    out.uv = vertex.uv;
-    out.normal = vertex.normal;
-    out.tangent = vertex.tangent;
-    out.bitangent = vertex.bitangent;
+    out.uv = uv;
 
     return out;
 }
 
-@fragment
-fn entrypoint_fragment(fragment: FragmentData) -> @location(0) vec4<f32> {
+/// Samples all material textures of the fragment.
+fn sample_surface(fragment: FragmentData) -> SurfaceData {
+    let albedo_sample = textureSample(albedo_texture, albedo_sampler, fragment.uv);
+
+    var surface: SurfaceData;
+    surface.albedo = albedo_sample.rgb;
+    surface.alpha = albedo_sample.a;
+    surface.metallic = textureSample(metallic_texture, metallic_sampler, fragment.uv).r;
+    // Fully smooth surfaces cause a singularity in the distribution function
+    surface.roughness = clamp(textureSample(roughness_texture, roughness_sampler, fragment.uv).r, 0.04, 1.0);
+    surface.occlusion = textureSample(occlusion_texture, occlusion_sampler, fragment.uv).r;
+    surface.emissive = textureSample(emissive_texture, emissive_sampler, fragment.uv).rgb;
+
+    // Normal mapping
     let tangent_basis = mat3x3<f32>(
-        fragment.tangent,
-        fragment.bitangent,
-        fragment.normal
+        normalize(fragment.tangent),
+        normalize(fragment.bitangent),
+        normalize(fragment.normal),
     );
+    let tangent_normal = textureSample(normal_texture, normal_sampler, fragment.uv).xyz * 2.0 - 1.0;
+    surface.normal = normalize(tangent_basis * tangent_normal);
 
-    return textureSample(
-        albedo_texture,
-        albedo_sampler,
-        fragment.uv
-    );
+    if surface.alpha < alpha_cutoff {
+        discard;
+    }
+
+    return surface;
+}
+
+/// Writes the surface into the G-buffer, shading happens later on.
+/// Only used by the G-buffer variant of the pipeline.
+@fragment
+fn entrypoint_fragment_deferred(fragment: FragmentData) -> GBufferData {
+    let surface = sample_surface(fragment);
+
+    var out: GBufferData;
+    out.albedo = vec4<f32>(surface.albedo, surface.occlusion);
+    out.normal = vec4<f32>(surface.normal, 0.0);
+    out.material = vec4<f32>(surface.metallic, surface.roughness, 0.0, 0.0);
+    out.emissive = vec4<f32>(surface.emissive, 0.0);
+
+    return out;
+}
+
+@fragment
+fn entrypoint_fragment(fragment: FragmentData) -> @location(0) vec4<f32> {
+    let surface = sample_surface(fragment);
+    let albedo = surface.albedo;
+    let metallic = surface.metallic;
+    let roughness = surface.roughness;
+    let occlusion = surface.occlusion;
+    let emissive = surface.emissive;
+    let n = surface.normal;
+
+    let v = normalize(camera.position.xyz - fragment.world_position);
+
+    // Surface reflection at zero incidence.
+    // Dielectrics use a constant 4%, metals tint by their albedo.
+    let f0 = mix(vec3<f32>(0.04), albedo, metallic);
+
+    // Image based (ambient) lighting
+    let n_dot_v = max(dot(n, v), 0.0001);
+    let ambient_fresnel = fresnel_schlick_roughness(n_dot_v, f0, roughness);
+    let ambient_k_diffuse = (vec3<f32>(1.0) - ambient_fresnel) * (1.0 - metallic);
+
+    let irradiance = textureSample(irradiance_map, environment_sampler, n).rgb;
+    let ambient_diffuse = irradiance * albedo;
+
+    let r = reflect(-v, n);
+    let prefiltered = textureSampleLevel(
+        specular_map,
+        environment_sampler,
+        r,
+        roughness * (environment.specular_mip_count - 1.0)
+    ).rgb;
+    let brdf = textureSample(brdf_lut, environment_sampler, vec2<f32>(n_dot_v, roughness)).rg;
+    let ambient_specular = prefiltered * (ambient_fresnel * brdf.x + brdf.y);
+
+    let ambient = (ambient_k_diffuse * ambient_diffuse + ambient_specular) * occlusion * environment.intensity;
+
+    var lo = vec3<f32>(0.0);
+    for (var i = 0u; i < lights.count; i++) {
+        let light = lights.lights[i];
+        let direction_attenuation = light_direction_attenuation(light, fragment.world_position);
+
+        let visibility = shadow_visibility(shadows[i], fragment.world_position);
+
+        let radiance = light.color * light.intensity * direction_attenuation.w * visibility;
+        lo += cook_torrance(n, v, direction_attenuation.xyz, radiance, albedo, metallic, roughness, f0);
+    }
+
+    let color = ambient + lo + emissive;
+
+    return vec4<f32>(color, surface.alpha);
 }
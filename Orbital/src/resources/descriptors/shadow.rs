@@ -0,0 +1,38 @@
+/// Describes the shadow casting settings of a
+/// [LightDescriptor](super::LightDescriptor).
+///
+/// Only [LightType::Directional](super::LightType::Directional) and
+/// [LightType::Spot](super::LightType::Spot) lights can cast shadows.
+/// For [LightType::Point](super::LightType::Point) lights this will be
+/// ignored.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShadowDescriptor {
+    /// Width and height of the shadow map in pixels.
+    ///
+    /// All shadow maps share a single shadow atlas.
+    /// If a shadow map doesn't fit into the atlas anymore, the light won't
+    /// cast shadows.
+    pub resolution: u32,
+    /// Depth bias, applied when comparing against the shadow map.
+    /// Increase this if you see _shadow acne_ (i.e. stripes on lit surfaces),
+    /// decrease it if shadows start to detach from their objects
+    /// (i.e. _peter panning_).
+    pub bias: f32,
+    /// Half the width, height and depth of the area covered by a
+    /// [LightType::Directional](super::LightType::Directional) shadow map.
+    /// The area is centered around the active camera.
+    ///
+    /// Bigger values cover more of the scene, but result in blurrier shadows.
+    /// Spot lights use their cone and `range` instead.
+    pub extent: f32,
+}
+
+impl Default for ShadowDescriptor {
+    fn default() -> Self {
+        Self {
+            resolution: 2048,
+            bias: 0.0005,
+            extent: 20.0,
+        }
+    }
+}
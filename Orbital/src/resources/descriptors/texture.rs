@@ -13,6 +13,14 @@ pub enum TextureDescriptor {
     /// 1st.: A Vector of bytes, containing the image data in RGBA pixels
     /// 2nd.: The size of the texture, must be accurate or this will panic!
     StandardSRGBu8Data(Vec<u8>, Vector2<u32>),
+    /// Creates a linear (i.e. **non**-SRGB) texture from bytes (`u8`).
+    /// Use this for any texture that doesn't contain color information,
+    /// like e.g. normal maps.
+    ///
+    /// # Parameters
+    /// 1st.: A Vector of bytes, containing the image data in RGBA pixels
+    /// 2nd.: The size of the texture, must be accurate or this will panic!
+    LinearRGBAu8Data(Vec<u8>, Vector2<u32>),
     /// Creates a texture with a single uniform color.
     ///
     /// The format is:  
@@ -58,6 +66,18 @@ pub enum TextureDescriptor {
     UniformLuma { data: u8 },
     /// Creates a depth texture
     Depth(Vector2<u32>),
+    /// Creates a HDR (= High-Dynamic-Range) texture by loading an
+    /// equirectangular (i.e. latitude/longitude) environment map from a file.
+    /// ⚠️ This file must be accessible during runtime!
+    ///
+    /// The texture will be stored as 32-bit floats per channel and is
+    /// **not** filterable.
+    /// Mainly intended to be used as the source of an
+    /// [EnvironmentDescriptor](super::EnvironmentDescriptor).
+    ///
+    /// For supported formats check the [Image documentation](https://github.com/image-rs/image/blob/main/README.md#supported-image-formats).
+    /// Usually, this will be a Radiance HDR (`.hdr`) file.
+    EquirectangularHDR(&'static str),
 }
 
 impl TextureDescriptor {
@@ -0,0 +1,319 @@
+use std::ops::{Add, Mul};
+
+use cgmath::{InnerSpace, Quaternion, Vector3, VectorSpace};
+
+use crate::game::Transform;
+
+/// How values between two keyframes of an [AnimationChannel] are
+/// calculated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationInterpolation {
+    /// The value of the previous keyframe is kept until the next keyframe.
+    Step,
+    /// Values are interpolated linearly.
+    /// Rotations are interpolated spherically.
+    Linear,
+    /// Values are interpolated with a cubic _Hermite spline_.
+    /// Each keyframe consists of an in-tangent, a value and an out-tangent.
+    CubicSpline,
+}
+
+/// Keyframe values of an [AnimationChannel].
+///
+/// ⚠️ In case of [AnimationInterpolation::CubicSpline], there are three
+/// ⚠️ values per keyframe: in-tangent, value and out-tangent.
+#[derive(Debug, Clone)]
+pub enum AnimationValues {
+    Translations(Vec<Vector3<f32>>),
+    Rotations(Vec<Quaternion<f32>>),
+    Scales(Vec<Vector3<f32>>),
+    /// Weights of a single [MorphTarget](super::MorphTarget)
+    MorphWeights(Vec<f32>),
+}
+
+/// What an [AnimationChannel] animates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationTarget {
+    /// Index of a joint inside the [Skeleton](super::Skeleton)
+    Joint(usize),
+    /// Index of a [MorphTarget](super::MorphTarget) inside the
+    /// [Mesh](super::Mesh)
+    MorphWeight(usize),
+}
+
+/// The animated state of a [Model](super::Model).
+#[derive(Debug, Clone, Default)]
+pub struct AnimationPose {
+    /// Pose of each joint of the [Skeleton](super::Skeleton), relative to
+    /// it's parent
+    pub joints: Vec<Transform>,
+    /// Weight of each [MorphTarget](super::MorphTarget)
+    pub morph_weights: Vec<f32>,
+}
+
+/// Animates a single property of a single joint of a
+/// [Skeleton](super::Skeleton), or the weight of a single
+/// [MorphTarget](super::MorphTarget).
+#[derive(Debug, Clone)]
+pub struct AnimationChannel {
+    pub target: AnimationTarget,
+    pub interpolation: AnimationInterpolation,
+    /// Time of each keyframe in seconds, ascending
+    pub times: Vec<f32>,
+    pub values: AnimationValues,
+}
+
+impl AnimationChannel {
+    /// Samples the channel at the given time and applies the result to
+    /// the matching joint pose or morph weight.
+    pub fn apply(&self, time: f32, pose: &mut AnimationPose) {
+        match (self.target, &self.values) {
+            (AnimationTarget::Joint(joint), values) => {
+                let Some(joint) = pose.joints.get_mut(joint) else {
+                    return;
+                };
+
+                match values {
+                    AnimationValues::Translations(values) => {
+                        if let Some(x) = self.sample(values, time, Vector3::lerp) {
+                            joint.position = x;
+                        }
+                    }
+                    AnimationValues::Rotations(values) => {
+                        if let Some(x) = self.sample(values, time, Self::slerp) {
+                            joint.rotation = x.normalize();
+                        }
+                    }
+                    AnimationValues::Scales(values) => {
+                        if let Some(x) = self.sample(values, time, Vector3::lerp) {
+                            joint.scale = x;
+                        }
+                    }
+                    // Not a joint property
+                    AnimationValues::MorphWeights(_) => (),
+                }
+            }
+            (AnimationTarget::MorphWeight(i), AnimationValues::MorphWeights(values)) => {
+                if let (Some(weight), Some(x)) = (
+                    pose.morph_weights.get_mut(i),
+                    self.sample(values, time, |a, b, factor| a + (b - a) * factor),
+                ) {
+                    *weight = x;
+                }
+            }
+            // Only weights can be applied to morph targets
+            (AnimationTarget::MorphWeight(_), _) => (),
+        }
+    }
+
+    /// Spherical interpolation along the shortest path.
+    pub fn slerp(a: Quaternion<f32>, b: Quaternion<f32>, factor: f32) -> Quaternion<f32> {
+        let b = if a.dot(b) < 0.0 { -b } else { b };
+
+        a.slerp(b, factor)
+    }
+
+    fn sample<T>(&self, values: &[T], time: f32, lerp: fn(T, T, f32) -> T) -> Option<T>
+    where
+        T: Copy + Add<Output = T> + Mul<f32, Output = T>,
+    {
+        let stride = match self.interpolation {
+            AnimationInterpolation::CubicSpline => 3,
+            _ => 1,
+        };
+        // In case of a cubic spline, the actual value is in the middle
+        let value_at = |keyframe: usize| values.get(keyframe * stride + stride / 2).copied();
+
+        let last = self.times.len().checked_sub(1)?;
+        if time <= self.times[0] {
+            return value_at(0);
+        }
+        if time >= self.times[last] {
+            return value_at(last);
+        }
+
+        // First keyframe after `time`, thus never zero
+        let next = self.times.partition_point(|x| *x <= time);
+        let previous = next - 1;
+
+        let delta = self.times[next] - self.times[previous];
+        let factor = (time - self.times[previous]) / delta;
+
+        match self.interpolation {
+            AnimationInterpolation::Step => value_at(previous),
+            AnimationInterpolation::Linear => {
+                Some(lerp(value_at(previous)?, value_at(next)?, factor))
+            }
+            AnimationInterpolation::CubicSpline => {
+                let start = value_at(previous)?;
+                let start_out_tangent = *values.get(previous * 3 + 2)?;
+                let end = value_at(next)?;
+                let end_in_tangent = *values.get(next * 3)?;
+
+                let t = factor;
+                let t2 = t * t;
+                let t3 = t2 * t;
+
+                Some(
+                    start * (2.0 * t3 - 3.0 * t2 + 1.0)
+                        + start_out_tangent * ((t3 - 2.0 * t2 + t) * delta)
+                        + end * (-2.0 * t3 + 3.0 * t2)
+                        + end_in_tangent * ((t3 - t2) * delta),
+                )
+            }
+        }
+    }
+}
+
+/// A named set of [AnimationChannel]s, e.g. _walking_ or _jumping_.
+/// Played by an [AnimationPlayer](super::AnimationPlayer).
+#[derive(Debug, Clone)]
+pub struct AnimationClip {
+    name: Option<String>,
+    duration: f32,
+    channels: Vec<AnimationChannel>,
+}
+
+impl AnimationClip {
+    /// Creates a new [AnimationClip].
+    /// The duration is the time of the last keyframe of any channel.
+    pub fn new(name: Option<String>, channels: Vec<AnimationChannel>) -> Self {
+        let duration = channels
+            .iter()
+            .filter_map(|x| x.times.last())
+            .fold(0.0, |a: f32, b| a.max(*b));
+
+        Self {
+            name,
+            duration,
+            channels,
+        }
+    }
+
+    /// Creates an [AnimationClip] from a _glTF Animation_.
+    ///
+    /// Only channels targeting one of the given `joint_nodes` are kept.
+    /// The position inside `joint_nodes` is used as the joint index.
+    ///
+    /// Morph target weight channels are kept, if they target `morph_node`.
+    /// They are split into one channel per [MorphTarget](super::MorphTarget).
+    #[cfg(feature = "gltf")]
+    pub fn from_gltf(
+        animation: &gltf::Animation,
+        buffers: &[gltf::buffer::Data],
+        joint_nodes: &[usize],
+        morph_node: Option<usize>,
+    ) -> Self {
+        use gltf::animation::{util::ReadOutputs, Interpolation};
+
+        let channels = animation
+            .channels()
+            .filter_map(|channel| {
+                let node = channel.target().node().index();
+                let joint = joint_nodes.iter().position(|x| *x == node);
+                let is_morph_node = morph_node == Some(node);
+                if joint.is_none() && !is_morph_node {
+                    return None;
+                }
+
+                let interpolation = match channel.sampler().interpolation() {
+                    Interpolation::Step => AnimationInterpolation::Step,
+                    Interpolation::Linear => AnimationInterpolation::Linear,
+                    Interpolation::CubicSpline => AnimationInterpolation::CubicSpline,
+                };
+
+                let reader = channel.reader(|buffer| Some(&buffers[buffer.index()]));
+                let times = reader.read_inputs()?.collect::<Vec<_>>();
+                let values = match reader.read_outputs()? {
+                    ReadOutputs::Translations(x) => {
+                        AnimationValues::Translations(x.map(Vector3::from).collect())
+                    }
+                    ReadOutputs::Rotations(x) => AnimationValues::Rotations(
+                        x.into_f32()
+                            .map(|[x, y, z, w]| Quaternion::new(w, x, y, z))
+                            .collect(),
+                    ),
+                    ReadOutputs::Scales(x) => {
+                        AnimationValues::Scales(x.map(Vector3::from).collect())
+                    }
+                    ReadOutputs::MorphTargetWeights(x) if is_morph_node => {
+                        return Some(Self::split_gltf_morph_weights(
+                            x.into_f32().collect(),
+                            interpolation,
+                            times,
+                        ));
+                    }
+                    // Weights of a node without morph targets
+                    ReadOutputs::MorphTargetWeights(_) => return None,
+                };
+
+                Some(vec![AnimationChannel {
+                    target: AnimationTarget::Joint(joint?),
+                    interpolation,
+                    times,
+                    values,
+                }])
+            })
+            .flatten()
+            .collect();
+
+        Self::new(animation.name().map(String::from), channels)
+    }
+
+    /// _glTF_ stores the weights of all morph targets per keyframe.
+    /// Splits them into one [AnimationChannel] per morph target.
+    #[cfg(feature = "gltf")]
+    fn split_gltf_morph_weights(
+        weights: Vec<f32>,
+        interpolation: AnimationInterpolation,
+        times: Vec<f32>,
+    ) -> Vec<AnimationChannel> {
+        let values_per_keyframe = match interpolation {
+            AnimationInterpolation::CubicSpline => 3,
+            _ => 1,
+        } * times.len();
+        if values_per_keyframe == 0 {
+            return Vec::new();
+        }
+        let target_count = weights.len() / values_per_keyframe;
+
+        // Either [weights] or [in-tangents, weights, out-tangents] per
+        // keyframe, thus every n-th value belongs to the same target.
+        (0..target_count)
+            .map(|i| AnimationChannel {
+                target: AnimationTarget::MorphWeight(i),
+                interpolation,
+                times: times.clone(),
+                values: AnimationValues::MorphWeights(
+                    weights
+                        .iter()
+                        .skip(i)
+                        .step_by(target_count)
+                        .copied()
+                        .collect(),
+                ),
+            })
+            .collect()
+    }
+
+    /// Samples all channels at the given time in seconds and applies
+    /// them onto the given pose.
+    /// Joints and morph weights without a channel keep their value.
+    pub fn apply(&self, time: f32, pose: &mut AnimationPose) {
+        for channel in &self.channels {
+            channel.apply(time, pose);
+        }
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.duration
+    }
+
+    pub fn channels(&self) -> &[AnimationChannel] {
+        &self.channels
+    }
+}
@@ -0,0 +1,206 @@
+use cgmath::VectorSpace;
+use log::warn;
+
+use crate::{game::AnimationChange, resources::descriptors::ImportDescriptor};
+
+use super::{AnimationChannel, AnimationClip, AnimationPose};
+
+/// Playback state of a single [AnimationClip].
+#[derive(Debug, Clone, Copy)]
+struct ClipPlayback {
+    clip: usize,
+    time: f32,
+}
+
+/// An [AnimationClip] being blended out.
+#[derive(Debug, Clone, Copy)]
+struct Blend {
+    from: ClipPlayback,
+    duration: f32,
+    elapsed: f32,
+}
+
+/// Plays [AnimationClip]s of a [Model](super::Model).
+///
+/// Controlled via [AnimationChange]s.
+#[derive(Debug, Clone)]
+pub struct AnimationPlayer {
+    current: Option<ClipPlayback>,
+    blend: Option<Blend>,
+    speed: f32,
+    looping: bool,
+    paused: bool,
+    /// Set if the pose needs to be recalculated
+    changed: bool,
+}
+
+impl Default for AnimationPlayer {
+    fn default() -> Self {
+        Self {
+            current: None,
+            blend: None,
+            speed: 1.0,
+            looping: true,
+            paused: false,
+            changed: false,
+        }
+    }
+}
+
+impl AnimationPlayer {
+    pub fn apply_change(&mut self, change: AnimationChange, clips: &[AnimationClip]) {
+        match change {
+            AnimationChange::Play(clip) => {
+                if let Some(clip) = Self::find_clip(&clip, clips) {
+                    self.current = Some(ClipPlayback { clip, time: 0.0 });
+                    self.blend = None;
+                    self.paused = false;
+                }
+            }
+            AnimationChange::BlendTo(clip, duration) => {
+                if let Some(clip) = Self::find_clip(&clip, clips) {
+                    self.blend = match self.current {
+                        Some(from) if duration > 0.0 => Some(Blend {
+                            from,
+                            duration,
+                            elapsed: 0.0,
+                        }),
+                        _ => None,
+                    };
+                    self.current = Some(ClipPlayback { clip, time: 0.0 });
+                    self.paused = false;
+                }
+            }
+            AnimationChange::Pause => self.paused = true,
+            AnimationChange::Resume => self.paused = false,
+            AnimationChange::Stop => {
+                self.current = None;
+                self.blend = None;
+            }
+            AnimationChange::SetLooping(looping) => self.looping = looping,
+            AnimationChange::SetSpeed(speed) => self.speed = speed,
+            AnimationChange::Seek(time) => {
+                if let Some(current) = &mut self.current {
+                    current.time = Self::wrap_time(time, &clips[current.clip], self.looping);
+                }
+            }
+            // Changes the rest pose, which is owned by the Model.
+            // The pose still needs to be recalculated.
+            AnimationChange::SetMorphWeights(_) => (),
+        }
+
+        self.changed = true;
+    }
+
+    fn find_clip(import_descriptor: &ImportDescriptor, clips: &[AnimationClip]) -> Option<usize> {
+        let index = match import_descriptor {
+            ImportDescriptor::Index(i) => Some(*i as usize).filter(|i| *i < clips.len()),
+            ImportDescriptor::Name(name) => clips.iter().position(|x| x.name() == Some(*name)),
+        };
+
+        if index.is_none() {
+            warn!(
+                "Animation clip {:?} not found! Model has {} clips.",
+                import_descriptor,
+                clips.len()
+            );
+        }
+
+        index
+    }
+
+    fn wrap_time(time: f32, clip: &AnimationClip, looping: bool) -> f32 {
+        if looping && clip.duration() > 0.0 {
+            time.rem_euclid(clip.duration())
+        } else {
+            time.clamp(0.0, clip.duration())
+        }
+    }
+
+    /// Advances playback by the given time in seconds.
+    ///
+    /// Returns `true` if the pose changed and needs to be recalculated.
+    pub fn advance(&mut self, delta_time: f32, clips: &[AnimationClip]) -> bool {
+        if !self.paused {
+            if let Some(current) = &mut self.current {
+                let time = Self::wrap_time(
+                    current.time + delta_time * self.speed,
+                    &clips[current.clip],
+                    self.looping,
+                );
+
+                // Finished, non-looping clips don't change anymore
+                if time != current.time {
+                    current.time = time;
+                    self.changed = true;
+                }
+            }
+
+            if let Some(blend) = &mut self.blend {
+                blend.from.time = Self::wrap_time(
+                    blend.from.time + delta_time * self.speed,
+                    &clips[blend.from.clip],
+                    self.looping,
+                );
+                blend.elapsed += delta_time.abs();
+                self.changed = true;
+
+                if blend.elapsed >= blend.duration {
+                    self.blend = None;
+                }
+            }
+        }
+
+        std::mem::take(&mut self.changed)
+    }
+
+    /// Calculates the current pose of each joint and the current morph
+    /// weights, starting from the given rest pose.
+    pub fn sample(&self, clips: &[AnimationClip], rest_pose: &AnimationPose) -> AnimationPose {
+        let mut pose = rest_pose.clone();
+        let Some(current) = self.current else {
+            return pose;
+        };
+
+        clips[current.clip].apply(current.time, &mut pose);
+
+        if let Some(blend) = self.blend {
+            let mut from_pose = rest_pose.clone();
+            clips[blend.from.clip].apply(blend.from.time, &mut from_pose);
+
+            let factor = (blend.elapsed / blend.duration).clamp(0.0, 1.0);
+            for (to, from) in pose.joints.iter_mut().zip(from_pose.joints) {
+                to.position = from.position.lerp(to.position, factor);
+                to.rotation = AnimationChannel::slerp(from.rotation, to.rotation, factor);
+                to.scale = from.scale.lerp(to.scale, factor);
+            }
+            for (to, from) in pose.morph_weights.iter_mut().zip(from_pose.morph_weights) {
+                *to = from + (*to - from) * factor;
+            }
+        }
+
+        pose
+    }
+
+    /// Index of the currently playing [AnimationClip], if any.
+    pub fn current_clip(&self) -> Option<usize> {
+        self.current.map(|x| x.clip)
+    }
+
+    /// Playback time of the current [AnimationClip] in seconds.
+    pub fn time(&self) -> Option<f32> {
+        self.current.map(|x| x.time)
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn is_looping(&self) -> bool {
+        self.looping
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+}
@@ -1,4 +1,4 @@
-use cgmath::{perspective, Deg, InnerSpace, Matrix4, Vector3};
+use cgmath::{perspective, Deg, InnerSpace, Matrix4, SquareMatrix, Vector2, Vector3, Vector4};
 use std::mem;
 use wgpu::{
     BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutDescriptor,
@@ -6,7 +6,13 @@ use wgpu::{
     Device, Queue, ShaderStages,
 };
 
-use crate::{game::CameraChange, resources::descriptors::CameraDescriptor};
+use winit::dpi::PhysicalPosition;
+
+use crate::{
+    game::CameraChange,
+    physics::{Frustum, Ray},
+    resources::descriptors::CameraDescriptor,
+};
 
 pub struct Camera {
     descriptor: CameraDescriptor,
@@ -37,8 +43,8 @@ impl Camera {
             size: (
                 // Main data type
                 mem::size_of::<f32>() *
-                // View Matrix (4x4 f32)
-                (4 * 4)
+                // View Matrix (4x4 f32) + Position (4x f32, last one is padding)
+                (4 * 4 + 4)
             ) as u64,
             usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
             mapped_at_creation: false,
@@ -91,44 +97,61 @@ impl Camera {
                 view_projection_matrix.w.y.to_le_bytes(),
                 view_projection_matrix.w.z.to_le_bytes(),
                 view_projection_matrix.w.w.to_le_bytes(),
+                self.descriptor.position.x.to_le_bytes(),
+                self.descriptor.position.y.to_le_bytes(),
+                self.descriptor.position.z.to_le_bytes(),
+                // Padding
+                1.0f32.to_le_bytes(),
             ]
             .concat(),
         );
     }
 
+    /// WGPU uses the same coordinate system as found in e.g. DirectX or
+    /// Metal. Meaning, that the clipping zone is expected to be between
+    /// -1.0 and +1.0 for the X and Y axis, but 0.0 to +1.0 for the Z axis.
+    ///
+    /// However, most computer graphics related library expect OpenGL's
+    /// coordinate system. OpenGL uses the same X and Y axis normalized
+    /// space, but puts the Z axis **also from -1.0** to +1.0.
+    ///
+    /// This isn't needed! But an object at origin (0.0, 0.0, 0.0) would be
+    /// halfway in the clipping zone. Using this matrix converts FROM the
+    /// OpenGL system (as produced by cgmath) INTO the WGPU/DirectX/Metal
+    /// system.
+    ///
+    /// ⚠️ [Matrix4::new] takes **columns**, not rows!
+    #[rustfmt::skip]
+    pub const OPEN_GL_MATRIX: Matrix4<f32> = Matrix4::new(
+        1.0, 0.0, 0.0, 0.0,
+        0.0, 1.0, 0.0, 0.0,
+        0.0, 0.0, 0.5, 0.0,
+        0.0, 0.0, 0.5, 1.0,
+    );
+
     pub fn calculate_view_projection_matrix(&self) -> Matrix4<f32> {
-        // WGPU uses the same coordinate system as found in e.g. DirectX or
-        // Metal. Meaning, that the clipping zone is expected to be between
-        // -1.0 and +1.0 for the X and Y axis, but 0.0 to +1.0 for the Z axis.
-        //
-        // However, most computer graphics related library expect OpenGL's
-        // coordinate system. OpenGL uses the same X and Y axis normalized
-        // space, but puts the Z axis **also from -1.0** to +1.0.
-        //
-        // This isn't needed! But an object at origin (0.0, 0.0, 0.0) would be
-        // halfway in the clipping zone. Using this matrix converts FROM the
-        // OpenGL system (as produced by cgmath) INTO the WGPU/DirectX/Metal
-        // system.
-        #[rustfmt::skip]
-        const OPEN_GL_MATRIX: Matrix4<f32> = Matrix4::new(
-            1.0, 0.0, 0.0, 0.0,
-            0.0, 1.0, 0.0, 0.0,
-            0.0, 0.0, 0.5, 0.5,
-            0.0, 0.0, 0.0, 1.0,
-        );
+        // Final result :)
+        self.calculate_projection_matrix() * self.calculate_view_matrix()
+    }
 
+    /// Transforms from world space into view space.
+    /// The [Camera] looks along the negative Z axis of view space.
+    pub fn calculate_view_matrix(&self) -> Matrix4<f32> {
         // Takes yaw and pitch values and converts them into a target vector for our camera.
         let (pitch_sin, pitch_cos) = self.descriptor.pitch.sin_cos();
         let (yaw_sin, yaw_cos) = self.descriptor.yaw.sin_cos();
 
-        // Calculates the view project matrix
-        let view_projection_matrix: Matrix4<f32> = Matrix4::look_to_rh(
+        Matrix4::look_to_rh(
             self.descriptor.position,
             Vector3::new(pitch_cos * yaw_cos, pitch_sin, pitch_cos * yaw_sin).normalize(),
             Vector3::unit_y(),
-        );
+        )
+    }
 
-        // Calculates the perspective matrix
+    /// Transforms from view space into clip space.
+    /// Already converted into the WGPU coordinate system, check
+    /// [Camera::OPEN_GL_MATRIX].
+    pub fn calculate_projection_matrix(&self) -> Matrix4<f32> {
         let perspective_matrix = perspective(
             Deg(self.descriptor.fovy),
             self.descriptor.aspect,
@@ -136,8 +159,45 @@ impl Camera {
             self.descriptor.far,
         );
 
-        // Final result :)
-        OPEN_GL_MATRIX * perspective_matrix * view_projection_matrix
+        Self::OPEN_GL_MATRIX * perspective_matrix
+    }
+
+    /// Returns the view [Frustum], e.g. for culling.
+    pub fn frustum(&self) -> Frustum {
+        Frustum::from_matrix(&self.calculate_view_projection_matrix())
+    }
+
+    /// Converts a pixel position inside the viewport (e.g. the cursor
+    /// position, see
+    /// [InputHandler::get_cursor_position](crate::util::InputHandler::get_cursor_position))
+    /// into a world space [Ray].
+    ///
+    /// The [Ray] starts on the near plane and has a normalized direction.
+    /// It's returned together with the distance to the far plane along
+    /// it, e.g. to be used as maximum distance of a raycast.  
+    /// Returns [None] for an empty viewport.
+    pub fn screen_to_ray(
+        &self,
+        position: PhysicalPosition<f64>,
+        viewport_size: Vector2<u32>,
+    ) -> Option<(Ray, f32)> {
+        if viewport_size.x == 0 || viewport_size.y == 0 {
+            return None;
+        }
+
+        // Pixels go down, normalized device coordinates go up
+        let x = (position.x as f32 / viewport_size.x as f32) * 2.0 - 1.0;
+        let y = 1.0 - (position.y as f32 / viewport_size.y as f32) * 2.0;
+
+        let inverse = self.calculate_view_projection_matrix().invert()?;
+        let unproject = |z: f32| {
+            let point = inverse * Vector4::new(x, y, z, 1.0);
+            point.truncate() / point.w
+        };
+
+        let near = unproject(0.0);
+        let far = unproject(1.0);
+        Some((Ray::new(near, far - near), (far - near).magnitude()))
     }
 
     pub fn descriptor(&self) -> &CameraDescriptor {
@@ -152,3 +212,28 @@ impl Camera {
         &self.buffer
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_gl_matrix_maps_depth_into_zero_to_one() {
+        let near = Camera::OPEN_GL_MATRIX * Vector4::new(0.0, 0.0, -1.0, 1.0);
+        let far = Camera::OPEN_GL_MATRIX * Vector4::new(0.0, 0.0, 1.0, 1.0);
+
+        assert_eq!(near, Vector4::new(0.0, 0.0, 0.0, 1.0));
+        assert_eq!(far, Vector4::new(0.0, 0.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn projection_puts_near_and_far_plane_at_zero_and_one() {
+        let matrix = Camera::OPEN_GL_MATRIX * perspective(Deg(60.0), 1.0, 0.1, 100.0);
+
+        let near = matrix * Vector4::new(0.0, 0.0, -0.1, 1.0);
+        let far = matrix * Vector4::new(0.0, 0.0, -100.0, 1.0);
+
+        assert!((near.z / near.w).abs() < 1e-5);
+        assert!((far.z / far.w - 1.0).abs() < 1e-5);
+    }
+}
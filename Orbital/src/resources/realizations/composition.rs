@@ -1,18 +1,62 @@
+#[cfg(feature = "gltf")]
+use cgmath::{InnerSpace, Matrix4, Point3, SquareMatrix};
+#[cfg(feature = "gltf")]
+use log::warn;
 use wgpu::{Device, Queue};
 
+#[cfg(feature = "gltf")]
+use crate::resources::descriptors::{InstanceDescriptor, LightType};
 use crate::{
     error::Error,
-    resources::{
-        descriptors::{CompositionDescriptor, ImportDescriptor, ModelDescriptor},
-        realizations::Instance,
+    resources::descriptors::{
+        CameraDescriptor, CompositionDescriptor, ImportDescriptor, LightDescriptor, ModelDescriptor,
     },
 };
 
 use super::Model;
 
+/// A _glTF Node_ containing a mesh primitive.
+///
+/// Used to look up information easy_gltf doesn't provide.
+#[cfg(feature = "gltf")]
+pub(crate) struct GltfModelNode {
+    /// World transform of the node
+    pub transform: Matrix4<f32>,
+    /// Index of the node inside the _glTF Document_
+    pub node: usize,
+    /// Index of the primitive inside the mesh of the node
+    pub primitive: usize,
+}
+
+/// A _glTF file_, loaded once and shared by all [Model]s imported from it.
+///
+/// easy_gltf can't be handed an already parsed _glTF Document_, thus it
+/// still parses the file on it's own.
+/// Everything easy_gltf doesn't provide is looked up in the same
+/// [gltf::Document] and buffers.
+#[cfg(feature = "gltf")]
+pub(crate) struct GltfFile {
+    pub scenes: Vec<easy_gltf::Scene>,
+    pub document: gltf::Document,
+    /// Only needed, and thus only loaded, for skins and morph targets
+    pub buffers: Vec<gltf::buffer::Data>,
+}
+#[cfg(feature = "gltf")]
+use super::{Instance, Mesh};
+
+/// A realized [CompositionDescriptor].
+///
+/// Contains all realized [Model]s, as well as descriptors for any
+/// [Camera](super::Camera)s and [Light](super::Light)s found.
+/// The latter are kept as descriptors, as the [World](crate::game::World)
+/// realizes them itself.
 #[derive(Default)]
 pub struct Composition {
     models: Vec<Model>,
+    /// Optional label of each [Model], in the same order as `models`
+    model_labels: Vec<Option<String>>,
+    cameras: Vec<CameraDescriptor>,
+    lights: Vec<LightDescriptor>,
 }
 
 impl Composition {
@@ -45,25 +89,30 @@ impl Composition {
         device: &Device,
         queue: &Queue,
     ) -> Result<Self, Error> {
-        let mut iter = descriptors
+        let models = descriptors
             .iter()
-            .map(|x| Model::from_descriptor(x, device, queue));
-
-        // If any error is found, return it.
-        if let Some(x) = iter.find(|x| x.is_err()) {
-            if let Err(e) = x {
-                return Err(e);
-            }
-        }
+            .map(|x| Model::from_descriptor(x, device, queue))
+            .collect::<Result<Vec<_>, _>>()?;
 
-        // No errors should be in iter, unwrap all
         Ok(Self {
-            models: iter.map(|x| x.unwrap()).collect(),
+            model_labels: vec![None; models.len()],
+            models,
+            ..Default::default()
         })
     }
 
     /// Creates a composition from a _glTF file_.
     ///
+    /// Each _glTF Node_ containing a mesh becomes a [Model] with the
+    /// _Node_ transform as it's [Instance].
+    /// The _glTF Mesh_ name, if any, is used as the [Model] label.
+    /// _glTF Cameras_ and _glTF Lights_ (`KHR_lights_punctual`) are
+    /// converted into [CameraDescriptor]s and [LightDescriptor]s.
+    ///
+    /// Cameras and lights are identified by their _glTF_ name.
+    /// Unnamed ones are identified as `<path>#Camera<index>` and
+    /// `<path>#Light<index>` respectively.
+    ///
     /// # Returns
     /// Either, a [Composition] with all models loaded, or, the first [Error] found.
     #[cfg(feature = "gltf")]
@@ -73,41 +122,277 @@ impl Composition {
         device: &Device,
         queue: &Queue,
     ) -> Result<Self, Error> {
-        // Load glTF file
-        let gltf_file = easy_gltf::load(path).map_err(|e| Error::GltfError(e))?;
+        let gltf_file = GltfFile::load(path)?;
+        let (scene_index, scene) = gltf_file.scene(import_descriptor)?;
 
-        // Query for scene. If found we continue.
-        let scene = if let Some(scene) = match import_descriptor {
-            ImportDescriptor::Index(i) => gltf_file.get(*i as usize),
-            ImportDescriptor::Name(name) => gltf_file
-                .iter()
-                .find(|x| x.name.is_some() && x.name.as_ref().unwrap() == *name),
-        } {
-            scene
-        } else {
-            return Err(Error::SceneNotFound);
-        };
+        // easy_gltf bakes the node transforms into the vertices.
+        // Thus, we have to look them up ourselves.
+        let model_nodes = Self::gltf_model_nodes(&gltf_file.document, scene_index)?;
+        if model_nodes.len() != scene.models.len() {
+            return Err(Error::ModelNotFound);
+        }
 
         let mut models = Vec::<Model>::new();
-        for gltf_model in &scene.models {
-            match Model::from_gltf_model(gltf_model, vec![Instance::default()], device, queue) {
-                Ok(model) => models.push(model),
-                // Return the first error that occurs
-                Err(e) => return Err(e),
+        let mut model_labels = Vec::<Option<String>>::new();
+        for (gltf_model, model_node) in scene.models.iter().zip(model_nodes) {
+            let node_transform = model_node.transform;
+
+            if Model::is_gltf_deformed(&gltf_file.document, &model_node) {
+                // Skinned models are placed by their skeleton instead
+                let is_skinned = gltf_file
+                    .document
+                    .nodes()
+                    .nth(model_node.node)
+                    .is_some_and(|x| x.skin().is_some());
+                let instance_descriptor = if is_skinned {
+                    InstanceDescriptor::default()
+                } else {
+                    InstanceDescriptor::from_matrix(node_transform)
+                };
+
+                if let Some(model) = Model::from_gltf_deformed(
+                    gltf_model,
+                    &model_node,
+                    &gltf_file.document,
+                    &gltf_file.buffers,
+                    Matrix4::identity(),
+                    vec![Instance::from_descriptor(&instance_descriptor)],
+                    device,
+                    queue,
+                )? {
+                    models.push(model);
+                    model_labels.push(gltf_model.mesh_name().map(String::from));
+                    continue;
+                }
+            }
+
+            let inverse_transform = node_transform.invert().unwrap_or(Matrix4::identity());
+
+            let model = Model::from_existing(
+                Mesh::from_gltf_transformed(gltf_model, inverse_transform, device)?,
+                Model::gltf_material_descriptor(gltf_model, &gltf_file.document, &model_node),
+                vec![Instance::from_descriptor(&InstanceDescriptor::from_matrix(
+                    node_transform,
+                ))],
+                device,
+                queue,
+            );
+            models.push(model);
+            model_labels.push(gltf_model.mesh_name().map(String::from));
+        }
+
+        let cameras = scene
+            .cameras
+            .iter()
+            .enumerate()
+            .map(|(i, camera)| Self::convert_gltf_camera(path, i, camera))
+            .collect();
+
+        let lights = scene
+            .lights
+            .iter()
+            .enumerate()
+            .map(|(i, light)| Self::convert_gltf_light(path, i, light))
+            .collect();
+
+        Ok(Self {
+            models,
+            model_labels,
+            cameras,
+            lights,
+        })
+    }
+
+    /// Walks the given _glTF Scene_ and returns the world transform,
+    /// node and primitive index of each mesh primitive.
+    /// The order matches [easy_gltf::Scene::models].
+    #[cfg(feature = "gltf")]
+    pub(crate) fn gltf_model_nodes(
+        document: &gltf::Document,
+        scene_index: usize,
+    ) -> Result<Vec<GltfModelNode>, Error> {
+        fn walk(node: gltf::Node, parent_transform: Matrix4<f32>, out: &mut Vec<GltfModelNode>) {
+            let transform = parent_transform * Matrix4::from(node.transform().matrix());
+
+            // Same order as easy_gltf: Children first, then the node itself
+            for child in node.children() {
+                walk(child, transform, out);
+            }
+
+            if let Some(mesh) = node.mesh() {
+                for primitive in mesh.primitives() {
+                    out.push(GltfModelNode {
+                        transform,
+                        node: node.index(),
+                        primitive: primitive.index(),
+                    });
+                }
+            }
+        }
+
+        let scene = document
+            .scenes()
+            .nth(scene_index)
+            .ok_or(Error::SceneNotFound)?;
+
+        let mut model_nodes = Vec::new();
+        for node in scene.nodes() {
+            walk(node, Matrix4::identity(), &mut model_nodes);
+        }
+
+        Ok(model_nodes)
+    }
+
+    #[cfg(feature = "gltf")]
+    fn convert_gltf_camera(
+        path: &str,
+        index: usize,
+        camera: &easy_gltf::Camera,
+    ) -> CameraDescriptor {
+        let default = CameraDescriptor::default();
+
+        let position = camera.position();
+        // glTF cameras look along their local -Z axis
+        let forward = -camera.forward();
+
+        let (fovy, aspect) = match camera.projection {
+            easy_gltf::Projection::Perspective { yfov, aspect_ratio } => {
+                (yfov.0.to_degrees(), aspect_ratio.unwrap_or(default.aspect))
+            }
+            easy_gltf::Projection::Orthographic { .. } => {
+                warn!(
+                    "Orthographic glTF camera #{} in '{}' is not supported, using a perspective projection instead!",
+                    index,
+                    path
+                );
+                (default.fovy, default.aspect)
             }
+        };
+
+        CameraDescriptor {
+            identifier: camera
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("{path}#Camera{index}")),
+            position: Point3::new(position.x, position.y, position.z),
+            yaw: forward.z.atan2(forward.x),
+            pitch: forward.y.clamp(-1.0, 1.0).asin(),
+            aspect,
+            fovy,
+            near: if camera.znear > 0.0 {
+                camera.znear
+            } else {
+                default.near
+            },
+            far: if camera.zfar.is_finite() {
+                camera.zfar
+            } else {
+                default.far
+            },
         }
+    }
 
-        Ok(Self { models })
+    #[cfg(feature = "gltf")]
+    fn convert_gltf_light(path: &str, index: usize, light: &easy_gltf::Light) -> LightDescriptor {
+        let (name, light_type, color, intensity) = match light {
+            easy_gltf::Light::Directional {
+                name,
+                direction,
+                color,
+                intensity,
+            } => (
+                name,
+                LightType::Directional {
+                    direction: direction.normalize(),
+                },
+                color,
+                intensity,
+            ),
+            easy_gltf::Light::Point {
+                name,
+                position,
+                color,
+                intensity,
+            } => (
+                name,
+                LightType::Point {
+                    position: *position,
+                    range: 0.0,
+                },
+                color,
+                intensity,
+            ),
+            easy_gltf::Light::Spot {
+                name,
+                position,
+                direction,
+                color,
+                intensity,
+                inner_cone_angle,
+                outer_cone_angle,
+            } => (
+                name,
+                LightType::Spot {
+                    position: *position,
+                    direction: direction.normalize(),
+                    range: 0.0,
+                    inner_cone_angle: *inner_cone_angle,
+                    outer_cone_angle: *outer_cone_angle,
+                },
+                color,
+                intensity,
+            ),
+        };
+
+        LightDescriptor {
+            identifier: name
+                .clone()
+                .unwrap_or_else(|| format!("{path}#Light{index}")),
+            light_type,
+            color: *color,
+            intensity: *intensity,
+            shadow: None,
+        }
     }
 
-    pub fn add_model(&mut self, model: Model) {
+    pub fn add_model(&mut self, model: Model, label: Option<String>) {
         self.models.push(model);
+        self.model_labels.push(label);
     }
 
     pub fn models(&self) -> &[Model] {
         &self.models
     }
 
+    pub fn model_labels(&self) -> &[Option<String>] {
+        &self.model_labels
+    }
+
+    pub fn cameras(&self) -> &[CameraDescriptor] {
+        &self.cameras
+    }
+
+    pub fn lights(&self) -> &[LightDescriptor] {
+        &self.lights
+    }
+
+    /// Splits the [Composition] into it's [Model]s (with their labels),
+    /// [CameraDescriptor]s and [LightDescriptor]s.
+    #[allow(clippy::type_complexity)]
+    pub fn into_parts(
+        self,
+    ) -> (
+        Vec<(Model, Option<String>)>,
+        Vec<CameraDescriptor>,
+        Vec<LightDescriptor>,
+    ) {
+        (
+            self.models.into_iter().zip(self.model_labels).collect(),
+            self.cameras,
+            self.lights,
+        )
+    }
+
     pub fn size(&self) -> usize {
         self.models.len()
     }
@@ -118,3 +403,53 @@ impl Composition {
 }
 
 pub type Scene = Composition;
+
+#[cfg(feature = "gltf")]
+impl GltfFile {
+    pub fn load(path: &str) -> Result<Self, Error> {
+        let scenes = easy_gltf::load(path).map_err(|e| Error::GltfError(e))?;
+        let gltf = gltf::Gltf::open(path).map_err(|e| Error::GltfError(Box::new(e)))?;
+
+        let buffers = if gltf.document.skins().next().is_some()
+            || gltf
+                .document
+                .meshes()
+                .flat_map(|x| x.primitives())
+                .any(|x| x.morph_targets().next().is_some())
+        {
+            gltf::import_buffers(
+                &gltf.document,
+                std::path::Path::new(path).parent(),
+                gltf.blob.clone(),
+            )
+            .map_err(|e| Error::GltfError(Box::new(e)))?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            scenes,
+            document: gltf.document,
+            buffers,
+        })
+    }
+
+    /// Queries for a scene, returning it together with it's index.
+    pub fn scene(
+        &self,
+        import_descriptor: &ImportDescriptor,
+    ) -> Result<(usize, &easy_gltf::Scene), Error> {
+        match import_descriptor {
+            ImportDescriptor::Index(i) => self
+                .scenes
+                .get(*i as usize)
+                .map(|scene| (*i as usize, scene)),
+            ImportDescriptor::Name(name) => self
+                .scenes
+                .iter()
+                .enumerate()
+                .find(|(_, x)| x.name.as_deref() == Some(*name)),
+        }
+        .ok_or(Error::SceneNotFound)
+    }
+}
@@ -0,0 +1,693 @@
+use wgpu::{
+    AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, Buffer,
+    BufferBindingType, BufferDescriptor, BufferUsages, CommandEncoder, CommandEncoderDescriptor,
+    ComputePassDescriptor, ComputePipeline, ComputePipelineDescriptor, Device, Extent3d,
+    FilterMode, PipelineCompilationOptions, PipelineLayoutDescriptor, Queue, SamplerBindingType,
+    SamplerDescriptor, ShaderStages, StorageTextureAccess, TextureDescriptor as WTextureDescriptor,
+    TextureDimension, TextureFormat, TextureSampleType, TextureUsages, TextureView,
+    TextureViewDescriptor, TextureViewDimension,
+};
+
+use crate::{
+    error::Error,
+    resources::descriptors::{EnvironmentDescriptor, ShaderDescriptor},
+};
+
+use super::{Shader, Texture};
+
+/// A realized environment, used for IBL (= Image-Based-Lighting) and
+/// skyboxes.
+///
+/// Upon realization, the source texture of the [EnvironmentDescriptor] is
+/// converted into a cube map.
+/// From there, a diffuse irradiance cube map and a specular prefiltered
+/// cube map (one roughness level per mip) are computed.
+/// Additionally, a BRDF LUT (= Look-Up-Texture) is generated.
+///
+/// Inside a shader, the [Environment] is expected like so:
+///
+/// ```wgsl
+/// struct EnvironmentUniform {
+///     intensity: f32,
+///     specular_mip_count: f32,
+///     _padding: vec2<f32>,
+/// }
+///
+/// @group(3) @binding(0) var irradiance_map: texture_cube<f32>;
+/// @group(3) @binding(1) var specular_map: texture_cube<f32>;
+/// @group(3) @binding(2) var brdf_lut: texture_2d<f32>;
+/// @group(3) @binding(3) var environment_sampler: sampler;
+/// @group(3) @binding(4) var<uniform> environment: EnvironmentUniform;
+/// ```
+pub struct Environment {
+    descriptor: EnvironmentDescriptor,
+    environment_cube: Texture,
+    irradiance_cube: Texture,
+    specular_cube: Texture,
+    brdf_lut: Texture,
+    buffer: Buffer,
+    bind_group: BindGroup,
+}
+
+impl Environment {
+    /// Width and height of each face of the environment cube map.
+    pub const CUBE_SIZE: u32 = 512;
+    /// Width and height of each face of the irradiance cube map.
+    pub const IRRADIANCE_SIZE: u32 = 32;
+    /// Width and height of each face of the specular cube map.
+    pub const SPECULAR_SIZE: u32 = 128;
+    /// Mip levels of the specular cube map.
+    /// The first level is a perfect mirror, the last one is fully rough.
+    pub const SPECULAR_MIP_COUNT: u32 = 5;
+    /// Width and height of the BRDF LUT.
+    pub const BRDF_LUT_SIZE: u32 = 256;
+
+    /// Format used for all generated textures.
+    const FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+    /// Workgroup size used by all compute shaders.
+    const WORKGROUP_SIZE: u32 = 8;
+
+    pub fn bind_group_layout_descriptor() -> BindGroupLayoutDescriptor<'static> {
+        BindGroupLayoutDescriptor {
+            label: Some("Environment"),
+            entries: &[
+                // Irradiance
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::Cube,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                // Specular
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::Cube,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                // BRDF LUT
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                // Sampler
+                BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+                // Uniform
+                BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        }
+    }
+
+    pub fn from_descriptor(
+        descriptor: EnvironmentDescriptor,
+        device: &Device,
+        queue: &Queue,
+    ) -> Result<Self, Error> {
+        let source = Texture::from_descriptor(&descriptor.texture, device, queue)?;
+
+        let environment_cube = Self::make_cube(
+            "Environment Cube",
+            Self::CUBE_SIZE,
+            Self::CUBE_SIZE.ilog2() + 1,
+            device,
+            queue,
+        );
+        let irradiance_cube =
+            Self::make_cube("Irradiance Cube", Self::IRRADIANCE_SIZE, 1, device, queue);
+        let specular_cube = Self::make_cube(
+            "Specular Cube",
+            Self::SPECULAR_SIZE,
+            Self::SPECULAR_MIP_COUNT,
+            device,
+            queue,
+        );
+        let brdf_lut = Self::make_brdf_lut_texture(device, queue);
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("Environment Encoder"),
+        });
+
+        Self::convert_equirectangular(&source, &environment_cube, &mut encoder, device, queue);
+        Self::generate_mipmaps(&environment_cube, &mut encoder, device, queue);
+        Self::convolute_irradiance(
+            &environment_cube,
+            &irradiance_cube,
+            &mut encoder,
+            device,
+            queue,
+        );
+        Self::prefilter_specular(
+            &environment_cube,
+            &specular_cube,
+            &mut encoder,
+            device,
+            queue,
+        );
+        Self::integrate_brdf(&brdf_lut, &mut encoder, device, queue);
+
+        queue.submit(Some(encoder.finish()));
+
+        let buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Environment Buffer"),
+            // Intensity + Specular mip count + 2x Padding
+            size: 4 * 4,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Environment Bind Group"),
+            layout: &device.create_bind_group_layout(&Self::bind_group_layout_descriptor()),
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(irradiance_cube.view()),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(specular_cube.view()),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: BindingResource::TextureView(brdf_lut.view()),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: BindingResource::Sampler(specular_cube.sampler()),
+                },
+                BindGroupEntry {
+                    binding: 4,
+                    resource: buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let environment = Self {
+            descriptor,
+            environment_cube,
+            irradiance_cube,
+            specular_cube,
+            brdf_lut,
+            buffer,
+            bind_group,
+        };
+        environment.update_buffer(queue);
+        Ok(environment)
+    }
+
+    fn update_buffer(&self, queue: &Queue) {
+        queue.write_buffer(
+            &self.buffer,
+            0,
+            &[
+                self.descriptor.intensity.to_le_bytes(),
+                (Self::SPECULAR_MIP_COUNT as f32).to_le_bytes(),
+                // Padding
+                0f32.to_le_bytes(),
+                0f32.to_le_bytes(),
+            ]
+            .concat(),
+        );
+    }
+
+    /// Creates an empty cube map, which can be written to by compute shaders.
+    fn make_cube(
+        label: &'static str,
+        size: u32,
+        mip_level_count: u32,
+        device: &Device,
+        queue: &Queue,
+    ) -> Texture {
+        Texture::from_descriptors(
+            &WTextureDescriptor {
+                label: Some(label),
+                size: Extent3d {
+                    width: size,
+                    height: size,
+                    depth_or_array_layers: 6,
+                },
+                mip_level_count,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: Self::FORMAT,
+                usage: TextureUsages::TEXTURE_BINDING | TextureUsages::STORAGE_BINDING,
+                view_formats: &[],
+            },
+            &TextureViewDescriptor {
+                label: Some(label),
+                dimension: Some(TextureViewDimension::Cube),
+                ..Default::default()
+            },
+            &SamplerDescriptor {
+                label: Some(label),
+                address_mode_u: AddressMode::ClampToEdge,
+                address_mode_v: AddressMode::ClampToEdge,
+                address_mode_w: AddressMode::ClampToEdge,
+                mag_filter: FilterMode::Linear,
+                min_filter: FilterMode::Linear,
+                mipmap_filter: FilterMode::Linear,
+                ..Default::default()
+            },
+            device,
+            queue,
+        )
+    }
+
+    fn make_brdf_lut_texture(device: &Device, queue: &Queue) -> Texture {
+        Texture::from_descriptors(
+            &WTextureDescriptor {
+                label: Some("BRDF LUT"),
+                size: Extent3d {
+                    width: Self::BRDF_LUT_SIZE,
+                    height: Self::BRDF_LUT_SIZE,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: Self::FORMAT,
+                usage: TextureUsages::TEXTURE_BINDING | TextureUsages::STORAGE_BINDING,
+                view_formats: &[],
+            },
+            &TextureViewDescriptor::default(),
+            &SamplerDescriptor {
+                label: Some("BRDF LUT"),
+                address_mode_u: AddressMode::ClampToEdge,
+                address_mode_v: AddressMode::ClampToEdge,
+                address_mode_w: AddressMode::ClampToEdge,
+                mag_filter: FilterMode::Linear,
+                min_filter: FilterMode::Linear,
+                ..Default::default()
+            },
+            device,
+            queue,
+        )
+    }
+
+    /// Creates a view of a single mip level of a cube map, as an array of
+    /// 2D textures (one per face).
+    fn cube_mip_view(cube: &Texture, mip_level: u32) -> TextureView {
+        cube.texture().create_view(&TextureViewDescriptor {
+            label: Some("Cube Mip View"),
+            dimension: Some(TextureViewDimension::D2Array),
+            base_mip_level: mip_level,
+            mip_level_count: Some(1),
+            ..Default::default()
+        })
+    }
+
+    fn make_compute_pipeline(
+        shader_descriptor: ShaderDescriptor,
+        entries: &[BindGroupLayoutEntry],
+        device: &Device,
+        queue: &Queue,
+    ) -> (ComputePipeline, BindGroupLayout) {
+        let shader = Shader::from_descriptor(shader_descriptor, device, queue)
+            .expect("Environment shader realization failed!");
+
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: None,
+            entries,
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            module: shader.shader_module(),
+            entry_point: "entrypoint_compute",
+            compilation_options: PipelineCompilationOptions::default(),
+        });
+
+        (pipeline, bind_group_layout)
+    }
+
+    fn texture_entry(
+        binding: u32,
+        sample_type: TextureSampleType,
+        view_dimension: TextureViewDimension,
+    ) -> BindGroupLayoutEntry {
+        BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Texture {
+                sample_type,
+                view_dimension,
+                multisampled: false,
+            },
+            count: None,
+        }
+    }
+
+    fn storage_texture_entry(
+        binding: u32,
+        view_dimension: TextureViewDimension,
+    ) -> BindGroupLayoutEntry {
+        BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::StorageTexture {
+                access: StorageTextureAccess::WriteOnly,
+                format: Self::FORMAT,
+                view_dimension,
+            },
+            count: None,
+        }
+    }
+
+    fn sampler_entry(binding: u32) -> BindGroupLayoutEntry {
+        BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Sampler(SamplerBindingType::Filtering),
+            count: None,
+        }
+    }
+
+    fn dispatch(
+        pipeline: &ComputePipeline,
+        bind_group: &BindGroup,
+        size: u32,
+        layers: u32,
+        encoder: &mut CommandEncoder,
+    ) {
+        let mut compute_pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: None,
+            timestamp_writes: None,
+        });
+
+        compute_pass.set_pipeline(pipeline);
+        compute_pass.set_bind_group(0, bind_group, &[]);
+
+        let workgroups = size.div_ceil(Self::WORKGROUP_SIZE);
+        compute_pass.dispatch_workgroups(workgroups, workgroups, layers);
+    }
+
+    fn convert_equirectangular(
+        source: &Texture,
+        environment_cube: &Texture,
+        encoder: &mut CommandEncoder,
+        device: &Device,
+        queue: &Queue,
+    ) {
+        let (pipeline, bind_group_layout) = Self::make_compute_pipeline(
+            include_str!("../descriptors/shader/ibl/equirectangular_to_cube.wgsl"),
+            &[
+                // HDR textures aren't filterable
+                Self::texture_entry(
+                    0,
+                    TextureSampleType::Float { filterable: false },
+                    TextureViewDimension::D2,
+                ),
+                Self::storage_texture_entry(1, TextureViewDimension::D2Array),
+            ],
+            device,
+            queue,
+        );
+
+        let destination_view = Self::cube_mip_view(environment_cube, 0);
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: None,
+            layout: &bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(source.view()),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(&destination_view),
+                },
+            ],
+        });
+
+        Self::dispatch(&pipeline, &bind_group, Self::CUBE_SIZE, 6, encoder);
+    }
+
+    fn generate_mipmaps(
+        environment_cube: &Texture,
+        encoder: &mut CommandEncoder,
+        device: &Device,
+        queue: &Queue,
+    ) {
+        let (pipeline, bind_group_layout) = Self::make_compute_pipeline(
+            include_str!("../descriptors/shader/ibl/cube_mipmap.wgsl"),
+            &[
+                Self::texture_entry(
+                    0,
+                    TextureSampleType::Float { filterable: false },
+                    TextureViewDimension::D2Array,
+                ),
+                Self::storage_texture_entry(1, TextureViewDimension::D2Array),
+            ],
+            device,
+            queue,
+        );
+
+        for mip_level in 1..environment_cube.texture().mip_level_count() {
+            let source_view = Self::cube_mip_view(environment_cube, mip_level - 1);
+            let destination_view = Self::cube_mip_view(environment_cube, mip_level);
+
+            let bind_group = device.create_bind_group(&BindGroupDescriptor {
+                label: None,
+                layout: &bind_group_layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: BindingResource::TextureView(&source_view),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::TextureView(&destination_view),
+                    },
+                ],
+            });
+
+            Self::dispatch(
+                &pipeline,
+                &bind_group,
+                Self::CUBE_SIZE >> mip_level,
+                6,
+                encoder,
+            );
+        }
+    }
+
+    fn convolute_irradiance(
+        environment_cube: &Texture,
+        irradiance_cube: &Texture,
+        encoder: &mut CommandEncoder,
+        device: &Device,
+        queue: &Queue,
+    ) {
+        let (pipeline, bind_group_layout) = Self::make_compute_pipeline(
+            include_str!("../descriptors/shader/ibl/irradiance.wgsl"),
+            &[
+                Self::texture_entry(
+                    0,
+                    TextureSampleType::Float { filterable: true },
+                    TextureViewDimension::Cube,
+                ),
+                Self::sampler_entry(1),
+                Self::storage_texture_entry(2, TextureViewDimension::D2Array),
+            ],
+            device,
+            queue,
+        );
+
+        let destination_view = Self::cube_mip_view(irradiance_cube, 0);
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: None,
+            layout: &bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(environment_cube.view()),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(environment_cube.sampler()),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: BindingResource::TextureView(&destination_view),
+                },
+            ],
+        });
+
+        Self::dispatch(&pipeline, &bind_group, Self::IRRADIANCE_SIZE, 6, encoder);
+    }
+
+    fn prefilter_specular(
+        environment_cube: &Texture,
+        specular_cube: &Texture,
+        encoder: &mut CommandEncoder,
+        device: &Device,
+        queue: &Queue,
+    ) {
+        let (pipeline, bind_group_layout) = Self::make_compute_pipeline(
+            include_str!("../descriptors/shader/ibl/prefilter.wgsl"),
+            &[
+                Self::texture_entry(
+                    0,
+                    TextureSampleType::Float { filterable: true },
+                    TextureViewDimension::Cube,
+                ),
+                Self::sampler_entry(1),
+                Self::storage_texture_entry(2, TextureViewDimension::D2Array),
+                BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+            device,
+            queue,
+        );
+
+        for mip_level in 0..Self::SPECULAR_MIP_COUNT {
+            let roughness = mip_level as f32 / (Self::SPECULAR_MIP_COUNT - 1) as f32;
+
+            let parameter_buffer = device.create_buffer(&BufferDescriptor {
+                label: Some("Prefilter Parameters"),
+                // Roughness + 3x Padding
+                size: 4 * 4,
+                usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            queue.write_buffer(
+                &parameter_buffer,
+                0,
+                &[
+                    roughness.to_le_bytes(),
+                    // Padding
+                    0f32.to_le_bytes(),
+                    0f32.to_le_bytes(),
+                    0f32.to_le_bytes(),
+                ]
+                .concat(),
+            );
+
+            let destination_view = Self::cube_mip_view(specular_cube, mip_level);
+            let bind_group = device.create_bind_group(&BindGroupDescriptor {
+                label: None,
+                layout: &bind_group_layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: BindingResource::TextureView(environment_cube.view()),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::Sampler(environment_cube.sampler()),
+                    },
+                    BindGroupEntry {
+                        binding: 2,
+                        resource: BindingResource::TextureView(&destination_view),
+                    },
+                    BindGroupEntry {
+                        binding: 3,
+                        resource: parameter_buffer.as_entire_binding(),
+                    },
+                ],
+            });
+
+            Self::dispatch(
+                &pipeline,
+                &bind_group,
+                Self::SPECULAR_SIZE >> mip_level,
+                6,
+                encoder,
+            );
+        }
+    }
+
+    fn integrate_brdf(
+        brdf_lut: &Texture,
+        encoder: &mut CommandEncoder,
+        device: &Device,
+        queue: &Queue,
+    ) {
+        let (pipeline, bind_group_layout) = Self::make_compute_pipeline(
+            include_str!("../descriptors/shader/ibl/brdf_lut.wgsl"),
+            &[Self::storage_texture_entry(0, TextureViewDimension::D2)],
+            device,
+            queue,
+        );
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: None,
+            layout: &bind_group_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: BindingResource::TextureView(brdf_lut.view()),
+            }],
+        });
+
+        Self::dispatch(&pipeline, &bind_group, Self::BRDF_LUT_SIZE, 1, encoder);
+    }
+
+    pub fn descriptor(&self) -> &EnvironmentDescriptor {
+        &self.descriptor
+    }
+
+    /// The full resolution environment cube map, e.g. for drawing a skybox.
+    pub fn environment_cube(&self) -> &Texture {
+        &self.environment_cube
+    }
+
+    pub fn irradiance_cube(&self) -> &Texture {
+        &self.irradiance_cube
+    }
+
+    pub fn specular_cube(&self) -> &Texture {
+        &self.specular_cube
+    }
+
+    pub fn brdf_lut(&self) -> &Texture {
+        &self.brdf_lut
+    }
+
+    pub fn bind_group(&self) -> &BindGroup {
+        &self.bind_group
+    }
+}
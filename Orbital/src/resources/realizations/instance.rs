@@ -1,6 +1,6 @@
 use std::mem;
 
-use crate::resources::descriptors::InstanceDescriptor;
+use crate::{game::TransformChange, resources::descriptors::InstanceDescriptor};
 use cgmath::Matrix4;
 use wgpu::{VertexAttribute, VertexBufferLayout, VertexFormat, VertexStepMode};
 
@@ -16,6 +16,14 @@ impl Instance {
         }
     }
 
+    pub fn update_from_change(&mut self, change: TransformChange) {
+        self.descriptor.apply_change(change);
+    }
+
+    pub fn descriptor(&self) -> &InstanceDescriptor {
+        &self.descriptor
+    }
+
     pub fn make_model_space_matrix(&self) -> Matrix4<f32> {
         let matrix_position = Matrix4::from_translation(self.descriptor.position);
 
@@ -0,0 +1,253 @@
+use cgmath::{ortho, perspective, InnerSpace, Matrix4, Point3, Rad, Vector3};
+use wgpu::{
+    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, Buffer, BufferBindingType,
+    BufferDescriptor, BufferUsages, Device, Queue, SamplerBindingType, ShaderStages,
+    TextureSampleType, TextureViewDimension,
+};
+
+use crate::{
+    game::LightChange,
+    resources::descriptors::{LightDescriptor, LightType},
+};
+
+use super::Camera;
+
+/// A realized light source.
+///
+/// Each [Light] owns a small GPU [Buffer] containing it's light data.
+/// A [Renderer](crate::renderer::Renderer) is expected to gather all
+/// [Light]s into a single storage buffer, with the layout defined by
+/// [Light::bind_group_layout_descriptor]:
+///
+/// ```wgsl
+/// @group(2) @binding(0) var<storage, read> lights: LightStorage;
+/// @group(2) @binding(1) var<storage, read> shadows: array<ShadowData>;
+/// @group(2) @binding(2) var shadow_atlas: texture_depth_2d;
+/// @group(2) @binding(3) var shadow_sampler: sampler_comparison;
+///
+/// struct LightData {
+///     position: vec3<f32>,
+///     light_type: u32,
+///     direction: vec3<f32>,
+///     range: f32,
+///     color: vec3<f32>,
+///     intensity: f32,
+///     inner_cone_cos: f32,
+///     outer_cone_cos: f32,
+///     _padding: vec2<f32>,
+/// }
+///
+/// struct LightStorage {
+///     count: u32,
+///     lights: array<LightData>,
+/// }
+///
+/// struct ShadowData {
+///     view_projection_matrix: mat4x4<f32>,
+///     atlas_rect: vec4<f32>,
+///     bias: f32,
+///     enabled: u32,
+///     _padding: vec2<f32>,
+/// }
+/// ```
+///
+/// `shadows` has one entry per light, in the same order as `lights`.
+pub struct Light {
+    descriptor: LightDescriptor,
+    buffer: Buffer,
+}
+
+impl Light {
+    /// Size of a single [Light] inside a buffer in bytes.
+    pub const BUFFER_SIZE: u64 = 4 * 16;
+
+    /// Size of the header (i.e. light count + padding) in front of the light
+    /// array in bytes.
+    pub const STORAGE_HEADER_SIZE: u64 = 16;
+
+    pub const LIGHT_TYPE_POINT: u32 = 0;
+    pub const LIGHT_TYPE_DIRECTIONAL: u32 = 1;
+    pub const LIGHT_TYPE_SPOT: u32 = 2;
+
+    pub fn bind_group_layout_descriptor() -> BindGroupLayoutDescriptor<'static> {
+        BindGroupLayoutDescriptor {
+            label: Some("Lights"),
+            entries: &[
+                // Light storage
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // Shadow storage
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // Shadow atlas
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Depth,
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                // Shadow atlas sampler
+                BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Comparison),
+                    count: None,
+                },
+            ],
+        }
+    }
+
+    pub fn from_descriptor(descriptor: LightDescriptor, device: &Device, queue: &Queue) -> Self {
+        let buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Light Buffer"),
+            size: Self::BUFFER_SIZE,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut light = Self { descriptor, buffer };
+        light.update_buffer(queue);
+        light
+    }
+
+    pub fn update_from_change(&mut self, change: LightChange, _device: &Device, queue: &Queue) {
+        self.descriptor.apply_change(change);
+        self.update_buffer(queue);
+    }
+
+    fn update_buffer(&mut self, queue: &Queue) {
+        queue.write_buffer(&self.buffer, 0, &self.to_bytes());
+    }
+
+    /// Converts the [Light] into bytes, matching the `LightData` layout.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let (light_type, range, inner_cone_angle, outer_cone_angle) =
+            match self.descriptor.light_type {
+                LightType::Point { range, .. } => (Self::LIGHT_TYPE_POINT, range, 0.0, 0.0),
+                LightType::Directional { .. } => (Self::LIGHT_TYPE_DIRECTIONAL, 0.0, 0.0, 0.0),
+                LightType::Spot {
+                    range,
+                    inner_cone_angle,
+                    outer_cone_angle,
+                    ..
+                } => (
+                    Self::LIGHT_TYPE_SPOT,
+                    range,
+                    inner_cone_angle,
+                    outer_cone_angle,
+                ),
+            };
+
+        let position = self.descriptor.position();
+        let direction = self.descriptor.direction();
+        let color = self.descriptor.color;
+
+        [
+            position.x.to_le_bytes(),
+            position.y.to_le_bytes(),
+            position.z.to_le_bytes(),
+            light_type.to_le_bytes(),
+            direction.x.to_le_bytes(),
+            direction.y.to_le_bytes(),
+            direction.z.to_le_bytes(),
+            range.to_le_bytes(),
+            color.x.to_le_bytes(),
+            color.y.to_le_bytes(),
+            color.z.to_le_bytes(),
+            self.descriptor.intensity.to_le_bytes(),
+            inner_cone_angle.cos().to_le_bytes(),
+            outer_cone_angle.cos().to_le_bytes(),
+            // Padding
+            0f32.to_le_bytes(),
+            0f32.to_le_bytes(),
+        ]
+        .concat()
+    }
+
+    /// Calculates the view projection matrix used to render this [Light]s
+    /// shadow map.
+    ///
+    /// [LightType::Directional] shadows cover a box of
+    /// [ShadowDescriptor::extent](crate::resources::descriptors::ShadowDescriptor::extent)
+    /// around `focus` (usually the active camera position).
+    ///
+    /// Returns [None] if this [Light] can't cast shadows (i.e. point lights)
+    /// or if shadows are disabled.
+    pub fn calculate_shadow_view_projection_matrix(
+        &self,
+        focus: Point3<f32>,
+    ) -> Option<Matrix4<f32>> {
+        let shadow = self.descriptor.shadow?;
+
+        match self.descriptor.light_type {
+            LightType::Point { .. } => None,
+            LightType::Directional { direction } => {
+                let extent = shadow.extent;
+                let eye = focus - direction * extent * 2.0;
+
+                let view_matrix = Matrix4::look_to_rh(eye, direction, Self::up_vector(direction));
+                let projection_matrix = ortho(-extent, extent, -extent, extent, 0.0, extent * 4.0);
+
+                Some(Camera::OPEN_GL_MATRIX * projection_matrix * view_matrix)
+            }
+            LightType::Spot {
+                position,
+                direction,
+                range,
+                outer_cone_angle,
+                ..
+            } => {
+                let view_matrix = Matrix4::look_to_rh(
+                    Point3::new(position.x, position.y, position.z),
+                    direction,
+                    Self::up_vector(direction),
+                );
+                let projection_matrix = perspective(
+                    Rad((outer_cone_angle * 2.0).clamp(0.01, 3.1)),
+                    1.0,
+                    0.05,
+                    if range > 0.0 { range } else { 100.0 },
+                );
+
+                Some(Camera::OPEN_GL_MATRIX * projection_matrix * view_matrix)
+            }
+        }
+    }
+
+    /// Returns an up vector which isn't parallel to `direction`.
+    fn up_vector(direction: Vector3<f32>) -> Vector3<f32> {
+        if direction.normalize().y.abs() > 0.99 {
+            Vector3::unit_z()
+        } else {
+            Vector3::unit_y()
+        }
+    }
+
+    pub fn descriptor(&self) -> &LightDescriptor {
+        &self.descriptor
+    }
+
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+}
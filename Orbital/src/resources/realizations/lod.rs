@@ -0,0 +1,103 @@
+use cgmath::{Deg, InnerSpace, Rad, Vector3};
+use wgpu::{Buffer, Device, Queue};
+
+use crate::{
+    physics::Aabb,
+    resources::descriptors::{LodDescriptor, LodThreshold},
+};
+
+use super::Mesh;
+
+/// A coarser level of detail of a [Model](super::Model).
+/// Check [LodsDescriptor](crate::resources::descriptors::LodsDescriptor).
+pub struct Lod {
+    mesh: Mesh,
+    threshold: LodThreshold,
+    /// The [Instance](super::Instance)s using this [Lod] and visible
+    /// after the last [Model::cull](super::Model::cull)
+    instance_buffer: Option<Buffer>,
+    instance_count: u32,
+}
+
+impl Lod {
+    pub fn from_descriptor(descriptor: &LodDescriptor, device: &Device, queue: &Queue) -> Self {
+        Self {
+            mesh: Mesh::from_descriptor(&descriptor.mesh, device, queue),
+            threshold: descriptor.threshold,
+            instance_buffer: None,
+            instance_count: 0,
+        }
+    }
+
+    /// Selects the level of detail for an [Instance](super::Instance)
+    /// with the given world space bounds.
+    /// `0` is the most detailed level, i.e. the
+    /// [Model::mesh](super::Model::mesh), `1` the first [Lod] and so on.
+    ///
+    /// `current_level` and `hysteresis` are used to make switching away
+    /// from the current level harder.
+    pub fn select_level(
+        lods: &[Lod],
+        bounds: &Aabb,
+        camera_position: Vector3<f32>,
+        fovy: Deg<f32>,
+        current_level: usize,
+        hysteresis: f32,
+    ) -> usize {
+        let radius = bounds.half_extents().magnitude();
+        let distance = (bounds.center() - camera_position).magnitude();
+        let screen_size = if distance <= radius {
+            // Inside the bounds, covering everything
+            f32::INFINITY
+        } else {
+            radius / (distance * (Rad::from(fovy).0 * 0.5).tan())
+        };
+
+        lods.iter()
+            .enumerate()
+            .filter(|(index, lod)| {
+                // Reaching a coarser level is harder, leaving it is too
+                let bias = if *index < current_level {
+                    -hysteresis
+                } else {
+                    hysteresis
+                };
+
+                match lod.threshold {
+                    LodThreshold::Distance(x) => distance >= x * (1.0 + bias),
+                    LodThreshold::ScreenSize(x) => screen_size <= x * (1.0 - bias),
+                }
+            })
+            .last()
+            .map_or(0, |(index, _)| index + 1)
+    }
+
+    pub fn mesh(&self) -> &Mesh {
+        &self.mesh
+    }
+
+    pub fn threshold(&self) -> LodThreshold {
+        self.threshold
+    }
+
+    pub(crate) fn instance_buffer_mut(&mut self) -> &mut Option<Buffer> {
+        &mut self.instance_buffer
+    }
+
+    pub(crate) fn set_instance_count(&mut self, instance_count: u32) {
+        self.instance_count = instance_count;
+    }
+
+    /// The instance buffer containing the [Instance](super::Instance)s
+    /// using this [Lod].
+    /// [None] if it was never used.
+    pub fn instance_buffer(&self) -> Option<&Buffer> {
+        self.instance_buffer.as_ref()
+    }
+
+    /// Amount of [Instance](super::Instance)s using this [Lod] and
+    /// visible after the last [Model::cull](super::Model::cull).
+    pub fn instance_count(&self) -> u32 {
+        self.instance_count
+    }
+}
@@ -9,7 +9,7 @@ use crate::{
     cache::Cache,
     error::Error,
     resources::descriptors::{
-        MaterialDescriptor, PipelineDescriptor, ShaderDescriptor, TextureDescriptor,
+        AlphaMode, MaterialDescriptor, PipelineDescriptor, ShaderDescriptor, TextureDescriptor,
     },
 };
 
@@ -23,6 +23,9 @@ pub struct Material {
     albedo_texture: Texture,
     metallic_texture: Texture,
     roughness_texture: Texture,
+    normal_texture: Texture,
+    occlusion_texture: Texture,
+    emissive_texture: Texture,
 }
 
 impl Material {
@@ -77,6 +80,7 @@ impl Material {
     pub fn from_descriptor(
         descriptor: &MaterialDescriptor,
         surface_format: &TextureFormat,
+        sample_count: u32,
         device: &Device,
         queue: &Queue,
     ) -> Result<&'static Self, Error> {
@@ -87,12 +91,21 @@ impl Material {
                 albedo,
                 metallic,
                 roughness,
+                normal,
+                occlusion,
+                emissive,
+                alpha_mode,
             } => Self::standard_pbr(
                 albedo,
                 metallic,
                 roughness,
+                normal,
+                occlusion,
+                emissive,
+                *alpha_mode,
                 None,
                 surface_format,
+                sample_count,
                 device,
                 queue,
             ),
@@ -100,25 +113,40 @@ impl Material {
                 albedo,
                 metallic,
                 roughness,
+                normal,
+                occlusion,
+                emissive,
+                alpha_mode,
                 custom_shader,
             } => Self::standard_pbr(
                 albedo,
                 metallic,
                 roughness,
+                normal,
+                occlusion,
+                emissive,
+                *alpha_mode,
                 Some(custom_shader),
                 surface_format,
+                sample_count,
                 device,
                 queue,
             ),
         })
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn standard_pbr(
         albedo_texture_descriptor: &TextureDescriptor,
         metallic_texture_descriptor: &TextureDescriptor,
         roughness_texture_descriptor: &TextureDescriptor,
+        normal_texture_descriptor: &TextureDescriptor,
+        occlusion_texture_descriptor: &TextureDescriptor,
+        emissive_texture_descriptor: &TextureDescriptor,
+        alpha_mode: AlphaMode,
         shader_descriptor: Option<&ShaderDescriptor>,
         surface_format: &TextureFormat,
+        sample_count: u32,
         device: &Device,
         queue: &Queue,
     ) -> Result<Self, Error> {
@@ -127,29 +155,40 @@ impl Material {
             Texture::from_descriptor(metallic_texture_descriptor, device, queue)?;
         let roughness_texture =
             Texture::from_descriptor(roughness_texture_descriptor, device, queue)?;
+        let normal_texture = Texture::from_descriptor(normal_texture_descriptor, device, queue)?;
+        let occlusion_texture =
+            Texture::from_descriptor(occlusion_texture_descriptor, device, queue)?;
+        let emissive_texture =
+            Texture::from_descriptor(emissive_texture_descriptor, device, queue)?;
 
-        let pipeline_descriptor = if let Some(shader_descriptor) = shader_descriptor {
-            PipelineDescriptor::default_with_shader(shader_descriptor)
-        } else {
-            PipelineDescriptor::default()
+        let pipeline_descriptor = PipelineDescriptor {
+            alpha_mode,
+            ..if let Some(shader_descriptor) = shader_descriptor {
+                PipelineDescriptor::default_with_shader(shader_descriptor)
+            } else {
+                PipelineDescriptor::default()
+            }
         };
 
-        let pipeline =
-            Pipeline::from_descriptor(&pipeline_descriptor, surface_format, device, queue)?;
+        let pipeline = Pipeline::from_descriptor(
+            &pipeline_descriptor,
+            surface_format,
+            sample_count,
+            device,
+            queue,
+        )?;
 
         let bind_group = device.create_bind_group(&BindGroupDescriptor {
             label: None,
             layout: pipeline.bind_group_layout(),
-            entries: &[
-                BindGroupEntry {
-                    binding: 0,
-                    resource: BindingResource::TextureView(albedo_texture.view()),
-                },
-                BindGroupEntry {
-                    binding: 1,
-                    resource: BindingResource::Sampler(albedo_texture.sampler()),
-                },
-            ],
+            entries: &Self::make_texture_bind_group_entries([
+                &albedo_texture,
+                &metallic_texture,
+                &roughness_texture,
+                &normal_texture,
+                &occlusion_texture,
+                &emissive_texture,
+            ]),
         });
 
         Ok(Self::from_existing(
@@ -158,15 +197,72 @@ impl Material {
             albedo_texture,
             metallic_texture,
             roughness_texture,
+            normal_texture,
+            occlusion_texture,
+            emissive_texture,
         ))
     }
 
+    /// Each PBR texture comes as a pair of texture and sampler.
+    /// Even bindings are textures, odd bindings are samplers.
+    /// Check [Pipeline::PBR_TEXTURE_COUNT] for the order.
+    fn make_texture_bind_group_entries(textures: [&Texture; 6]) -> Vec<BindGroupEntry<'_>> {
+        textures
+            .iter()
+            .enumerate()
+            .flat_map(|(i, texture)| {
+                [
+                    BindGroupEntry {
+                        binding: i as u32 * 2,
+                        resource: BindingResource::TextureView(texture.view()),
+                    },
+                    BindGroupEntry {
+                        binding: i as u32 * 2 + 1,
+                        resource: BindingResource::Sampler(texture.sampler()),
+                    },
+                ]
+            })
+            .collect()
+    }
+
+    /// Makes a [BindGroup] for the skinning and/or morphing variant of
+    /// the given [Pipeline].
+    /// Contains the same textures as [Material::bind_group], followed by
+    /// the given deformation buffers.
+    /// Check [Pipeline::DEFORMATION_BINDING] for their bindings.
+    pub fn make_deformed_bind_group(
+        &self,
+        deformation_entries: Vec<BindGroupEntry>,
+        pipeline: &Pipeline,
+        device: &Device,
+    ) -> BindGroup {
+        let mut entries = Self::make_texture_bind_group_entries([
+            &self.albedo_texture,
+            &self.metallic_texture,
+            &self.roughness_texture,
+            &self.normal_texture,
+            &self.occlusion_texture,
+            &self.emissive_texture,
+        ]);
+        entries.extend(deformation_entries);
+
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Deformed Material Bind Group"),
+            layout: pipeline.bind_group_layout(),
+            entries: &entries,
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn from_existing(
         bind_group: BindGroup,
         pipeline_descriptor: PipelineDescriptor,
         albedo_texture: Texture,
         metallic_texture: Texture,
         roughness_texture: Texture,
+        normal_texture: Texture,
+        occlusion_texture: Texture,
+        emissive_texture: Texture,
     ) -> Self {
         Self {
             bind_group,
@@ -174,6 +270,9 @@ impl Material {
             albedo_texture,
             metallic_texture,
             roughness_texture,
+            normal_texture,
+            occlusion_texture,
+            emissive_texture,
         }
     }
 
@@ -184,4 +283,28 @@ impl Material {
     pub fn pipeline_descriptor(&self) -> &PipelineDescriptor {
         &self.pipeline_descriptor
     }
+
+    pub fn albedo_texture(&self) -> &Texture {
+        &self.albedo_texture
+    }
+
+    pub fn metallic_texture(&self) -> &Texture {
+        &self.metallic_texture
+    }
+
+    pub fn roughness_texture(&self) -> &Texture {
+        &self.roughness_texture
+    }
+
+    pub fn normal_texture(&self) -> &Texture {
+        &self.normal_texture
+    }
+
+    pub fn occlusion_texture(&self) -> &Texture {
+        &self.occlusion_texture
+    }
+
+    pub fn emissive_texture(&self) -> &Texture {
+        &self.emissive_texture
+    }
 }
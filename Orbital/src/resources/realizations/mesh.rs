@@ -1,17 +1,90 @@
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::{Arc, Mutex, OnceLock, PoisonError, Weak},
+};
+
+#[cfg(feature = "gltf")]
+use cgmath::{InnerSpace, Matrix4, Vector3, Zero};
 use log::warn;
 use wgpu::{
     util::{BufferInitDescriptor, DeviceExt},
-    Buffer, BufferUsages, Device, Queue,
+    Buffer, BufferUsages, Device, Id, Queue,
 };
 
-use crate::{error::Error, resources::descriptors::MeshDescriptor};
+use crate::{
+    error::Error,
+    physics::{Aabb, MeshBvh},
+    resources::descriptors::MeshDescriptor,
+};
 
-use super::Vertex;
+use super::{MorphTarget, SkinVertex, Vertex};
 
-pub struct Mesh {
+/// Vertex and index buffers of a [Mesh].
+/// Shared by all [Mesh]es made from the same data, check
+/// [Mesh::from_data].
+struct MeshBuffers {
     vertex_buffer: Buffer,
     index_buffer: Buffer,
+    /// Only set if the buffers are shared
+    shared_data: Option<SharedMeshData>,
+}
+
+/// What [MeshBuffers] are shared by.
+#[derive(PartialEq, Eq)]
+struct SharedMeshData {
+    device: Id<Device>,
+    hash: u64,
+    vertex_data: Vec<u8>,
+    index_data: Vec<u8>,
+}
+
+/// All shared [MeshBuffers], by the hash of their [SharedMeshData].
+/// Hashes only narrow down the candidates, the data itself is compared.
+type SharedMeshBuffers = HashMap<u64, Vec<Weak<MeshBuffers>>>;
+
+fn shared_mesh_buffers() -> &'static Mutex<SharedMeshBuffers> {
+    static SHARED_MESH_BUFFERS: OnceLock<Mutex<SharedMeshBuffers>> = OnceLock::new();
+
+    SHARED_MESH_BUFFERS.get_or_init(Default::default)
+}
+
+impl Drop for MeshBuffers {
+    fn drop(&mut self) {
+        let Some(shared_data) = &self.shared_data else {
+            return;
+        };
+
+        // This one is already gone, as are possibly others
+        let mut shared_mesh_buffers = shared_mesh_buffers()
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(candidates) = shared_mesh_buffers.get_mut(&shared_data.hash) {
+            candidates.retain(|x| x.strong_count() > 0);
+            if candidates.is_empty() {
+                shared_mesh_buffers.remove(&shared_data.hash);
+            }
+        }
+    }
+}
+
+pub struct Mesh {
+    buffers: Arc<MeshBuffers>,
     index_count: u32,
+    /// Only set for skinned [Mesh]es.
+    /// Check [SkinVertex] for more.
+    skin_buffer: Option<Buffer>,
+    /// Only set for [Mesh]es with [MorphTarget]s.
+    /// Contains the deltas of all [MorphTarget]s, one after another.
+    morph_buffer: Option<Buffer>,
+    morph_target_count: usize,
+    /// Only set if the [Mesh] was made from vertex data.
+    /// Used for spatial queries, e.g.
+    /// [World::raycast](crate::game::World::raycast).
+    bvh: Option<MeshBvh>,
+    /// Bounds around all vertices, in the rest pose.
+    /// Only set if the [Mesh] was made from vertex data.
+    bounds: Option<Aabb>,
 }
 
 impl Mesh {
@@ -19,36 +92,142 @@ impl Mesh {
         Self::from_data(&descriptor.vertices, &descriptor.indices, device)
     }
 
+    /// [Mesh]es made from the same data on the same [Device] share their
+    /// vertex and index buffers, check [Mesh::shares_buffers].
     pub fn from_data(vertices: &[Vertex], indices: &[u32], device: &Device) -> Self {
-        let vertex_buffer = device.create_buffer_init(&BufferInitDescriptor {
-            label: Some("Mesh Vertex Buffer"),
-            contents: &vertices
-                .iter()
-                .flat_map(|x| x.to_bytes())
-                .collect::<Vec<u8>>(),
-            usage: BufferUsages::VERTEX,
-        });
+        let vertex_data = vertices
+            .iter()
+            .flat_map(|x| x.to_bytes())
+            .collect::<Vec<u8>>();
+        let index_data = indices
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect::<Vec<u8>>();
+
+        let mut mesh = Self::from_buffers(
+            Self::shared_buffers(vertex_data, index_data, device),
+            indices.len() as u32,
+        );
+        mesh.bvh = Some(MeshBvh::from_data(vertices, indices));
+        mesh.bounds = Aabb::from_points(vertices.iter().map(|x| x.position));
+
+        mesh
+    }
+
+    /// Returns the [MeshBuffers] of an existing [Mesh] with the same data
+    /// on the same [Device] or, if there is none, makes new ones.
+    fn shared_buffers(
+        vertex_data: Vec<u8>,
+        index_data: Vec<u8>,
+        device: &Device,
+    ) -> Arc<MeshBuffers> {
+        let mut hasher = DefaultHasher::new();
+        vertex_data.hash(&mut hasher);
+        index_data.hash(&mut hasher);
+        let shared_data = SharedMeshData {
+            device: device.global_id(),
+            hash: hasher.finish(),
+            vertex_data,
+            index_data,
+        };
 
-        let index_buffer = device.create_buffer_init(&BufferInitDescriptor {
-            label: Some("Mesh Vertex Buffer"),
-            contents: &indices
-                .iter()
-                .flat_map(|x| x.to_le_bytes())
-                .collect::<Vec<u8>>(),
-            usage: BufferUsages::INDEX,
+        let mut shared_mesh_buffers = shared_mesh_buffers()
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let candidates = shared_mesh_buffers.entry(shared_data.hash).or_default();
+        if let Some(buffers) = candidates
+            .iter()
+            .filter_map(Weak::upgrade)
+            .find(|x| x.shared_data.as_ref() == Some(&shared_data))
+        {
+            return buffers;
+        }
+
+        let buffers = Arc::new(MeshBuffers {
+            vertex_buffer: device.create_buffer_init(&BufferInitDescriptor {
+                label: Some("Mesh Vertex Buffer"),
+                contents: &shared_data.vertex_data,
+                usage: BufferUsages::VERTEX,
+            }),
+            index_buffer: device.create_buffer_init(&BufferInitDescriptor {
+                label: Some("Mesh Index Buffer"),
+                contents: &shared_data.index_data,
+                usage: BufferUsages::INDEX,
+            }),
+            shared_data: Some(shared_data),
         });
+        candidates.push(Arc::downgrade(&buffers));
 
-        Self::from_buffer(vertex_buffer, index_buffer, indices.len() as u32)
+        buffers
     }
 
+    /// Buffers made this way are never shared with other [Mesh]es.
     pub fn from_buffer(vertex_buffer: Buffer, index_buffer: Buffer, index_count: u32) -> Self {
+        Self::from_buffers(
+            Arc::new(MeshBuffers {
+                vertex_buffer,
+                index_buffer,
+                shared_data: None,
+            }),
+            index_count,
+        )
+    }
+
+    fn from_buffers(buffers: Arc<MeshBuffers>, index_count: u32) -> Self {
         Self {
-            vertex_buffer,
-            index_buffer,
+            buffers,
             index_count,
+            skin_buffer: None,
+            morph_buffer: None,
+            morph_target_count: 0,
+            bvh: None,
+            bounds: None,
         }
     }
 
+    /// Attaches skinning information to the [Mesh].
+    /// Must contain exactly one [SkinVertex] per [Vertex].
+    pub fn attach_skin(&mut self, skin_vertices: &[SkinVertex], device: &Device) {
+        self.skin_buffer = Some(
+            device.create_buffer_init(&BufferInitDescriptor {
+                label: Some("Mesh Skin Buffer"),
+                contents: &skin_vertices
+                    .iter()
+                    .flat_map(|x| x.to_bytes())
+                    .collect::<Vec<u8>>(),
+                usage: BufferUsages::VERTEX,
+            }),
+        );
+    }
+
+    /// Attaches [MorphTarget]s to the [Mesh].
+    /// `vertex_count` must match the amount of [Vertex]es.
+    ///
+    /// The deltas are laid out target by target, i.e. the delta of a
+    /// [Vertex] is at `target * vertex_count + vertex`.
+    pub fn attach_morph_targets(
+        &mut self,
+        morph_targets: &[MorphTarget],
+        vertex_count: usize,
+        device: &Device,
+    ) {
+        let mut contents = morph_targets
+            .iter()
+            .flat_map(|target| (0..vertex_count).flat_map(|i| target.delta_to_bytes(i)))
+            .collect::<Vec<u8>>();
+        // A buffer can't be empty
+        if contents.is_empty() {
+            contents.resize(MorphTarget::DELTA_SIZE as usize, 0);
+        }
+
+        self.morph_buffer = Some(device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Mesh Morph Buffer"),
+            contents: &contents,
+            usage: BufferUsages::STORAGE,
+        }));
+        self.morph_target_count = morph_targets.len();
+    }
+
     #[cfg(feature = "gltf")]
     pub fn from_gltf(gltf_model: &easy_gltf::Model, device: &Device) -> Result<Self, Error> {
         let vertices = gltf_model
@@ -67,15 +246,96 @@ impl Mesh {
         Ok(Self::from_data(&vertices, indices, device))
     }
 
+    /// Same as [Mesh::from_gltf], but transforms every [Vertex] by
+    /// `transform` first.
+    ///
+    /// _easy_gltf_ bakes the node transform into the vertices.
+    /// Passing the inverse node transform here returns the [Mesh] into
+    /// it's local space, so that the node transform can be applied as an
+    /// [Instance](super::Instance) instead.
+    #[cfg(feature = "gltf")]
+    pub fn from_gltf_transformed(
+        gltf_model: &easy_gltf::Model,
+        transform: Matrix4<f32>,
+        device: &Device,
+    ) -> Result<Self, Error> {
+        let transform_vector = |x: Vector3<f32>| {
+            let transformed = (transform * x.extend(0.0)).truncate();
+            if transformed.is_zero() {
+                transformed
+            } else {
+                transformed.normalize()
+            }
+        };
+
+        let vertices = gltf_model
+            .vertices()
+            .iter()
+            .map(|vertex| Into::<Vertex>::into(*vertex))
+            .map(|vertex| Vertex {
+                position: (transform * vertex.position.extend(1.0)).truncate(),
+                normal: transform_vector(vertex.normal),
+                tangent: transform_vector(vertex.tangent),
+                bitangent: transform_vector(vertex.bitangent),
+                uv: vertex.uv,
+            })
+            .collect::<Vec<Vertex>>();
+        let indices = match gltf_model.indices() {
+            Some(i) => i,
+            None => {
+                warn!("Trying to realize model from glTF without indices!");
+                return Err(Error::NoIndices);
+            }
+        };
+
+        Ok(Self::from_data(&vertices, indices, device))
+    }
+
     pub fn vertex_buffer(&self) -> &Buffer {
-        &self.vertex_buffer
+        &self.buffers.vertex_buffer
     }
 
     pub fn index_buffer(&self) -> &Buffer {
-        &self.index_buffer
+        &self.buffers.index_buffer
     }
 
     pub fn index_count(&self) -> u32 {
         self.index_count
     }
+
+    pub fn skin_buffer(&self) -> Option<&Buffer> {
+        self.skin_buffer.as_ref()
+    }
+
+    pub fn morph_buffer(&self) -> Option<&Buffer> {
+        self.morph_buffer.as_ref()
+    }
+
+    pub fn morph_target_count(&self) -> usize {
+        self.morph_target_count
+    }
+
+    /// Only set if the [Mesh] was made from vertex data, i.e. not via
+    /// [Mesh::from_buffer].
+    pub fn bvh(&self) -> Option<&MeshBvh> {
+        self.bvh.as_ref()
+    }
+
+    /// Bounds around all vertices in the rest pose, i.e. ignoring
+    /// skinning and morph targets.  
+    /// Only set if the [Mesh] was made from vertex data, i.e. not via
+    /// [Mesh::from_buffer].
+    pub fn bounds(&self) -> Option<Aabb> {
+        self.bounds
+    }
+
+    /// Checks if both [Mesh]es draw from the same vertex and index
+    /// buffers, i.e. can be drawn in place of each other, e.g. to batch
+    /// draws.
+    /// Check [Mesh::from_data].
+    ///
+    /// ⚠️ Skins and [MorphTarget]s aren't part of the shared buffers.
+    pub fn shares_buffers(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.buffers, &other.buffers)
+    }
 }
@@ -1,21 +1,35 @@
+pub mod animation;
+pub mod animation_player;
 pub mod camera;
 pub mod composition;
+pub mod environment;
 pub mod instance;
+pub mod light;
+pub mod lod;
 pub mod material;
 pub mod mesh;
 pub mod model;
+pub mod morph_target;
 pub mod pipeline;
 pub mod shader;
+pub mod skeleton;
 pub mod texture;
 pub mod vertex;
 
+pub use animation::*;
+pub use animation_player::*;
 pub use camera::*;
 pub use composition::*;
+pub use environment::*;
 pub use instance::*;
+pub use light::*;
+pub use lod::*;
 pub use material::*;
 pub use mesh::*;
 pub use model::*;
+pub use morph_target::*;
 pub use pipeline::*;
 pub use shader::*;
+pub use skeleton::*;
 pub use texture::*;
 pub use vertex::*;
@@ -1,22 +1,63 @@
+use std::sync::OnceLock;
+
+use cgmath::{Deg, EuclideanSpace, InnerSpace, Matrix4, SquareMatrix, Vector3};
+use log::warn;
 use wgpu::{
-    util::{BufferInitDescriptor, DeviceExt},
-    Buffer, BufferUsages, Device, Queue, TextureFormat,
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutDescriptor, BindingResource,
+    Buffer, BufferDescriptor, BufferUsages, Device, Queue, TextureFormat,
 };
 
 use crate::{
     error::Error,
+    game::{AnimationChange, InstanceChange},
+    physics::Aabb,
     resources::descriptors::{
-        ImportDescriptor, Instancing, MaterialDescriptor, MeshDescriptor, ModelDescriptor,
+        AlphaMode, ImportDescriptor, Instancing, LodsDescriptor, MaterialDescriptor,
+        MeshDescriptor, ModelDescriptor, PipelineDescriptor,
     },
 };
 
-use super::{instance::Instance, Material, Mesh};
+use super::{
+    instance::Instance, AnimationClip, AnimationPlayer, AnimationPose, Camera, Lod, Material, Mesh,
+    MorphWeights, Pipeline, Skeleton,
+};
+#[cfg(feature = "gltf")]
+use super::{Composition, GltfFile, GltfModelNode, MorphTarget, SkinVertex};
 
 pub struct Model {
     mesh: Mesh,
     material_descriptor: MaterialDescriptor,
     instances: Vec<Instance>,
     instance_buffer: Buffer,
+    /// Only the [Instance]s visible after the last [Model::cull].
+    /// Made once culling removes any [Instance].
+    visible_instance_buffer: Option<Buffer>,
+    /// Amount of [Instance]s visible after the last [Model::cull].
+    /// `None` if all are visible.
+    visible_instance_count: Option<u32>,
+    /// Coarser levels of detail, `mesh` being the most detailed
+    lods: Vec<Lod>,
+    lod_hysteresis: f32,
+    /// Level of detail of each [Instance], check [Lod::select_level]
+    instance_lods: Vec<usize>,
+    /// Transform applied on top of every [Instance].
+    /// Usually the world transform of the owning
+    /// [Element](crate::game::Element).
+    transform: Matrix4<f32>,
+    /// Only set for skinned [Model]s
+    skeleton: Option<Skeleton>,
+    /// Only set for [Model]s with [MorphTarget](super::MorphTarget)s
+    morph_weights: Option<MorphWeights>,
+    animations: Vec<AnimationClip>,
+    animation_player: AnimationPlayer,
+    /// Deformation buffers only, used for e.g. shadows
+    deformation_bind_group: OnceLock<BindGroup>,
+    /// Material textures + deformation buffers, made once the [Material]
+    /// is known
+    deformed_material_bind_group: OnceLock<BindGroup>,
+    /// Albedo texture + deformation buffers, used for the shadows of
+    /// [AlphaMode::Mask] [Model]s
+    masked_shadow_bind_group: OnceLock<BindGroup>,
 }
 
 impl Model {
@@ -35,6 +76,15 @@ impl Model {
                     queue,
                 )
             }
+            ModelDescriptor::WithLods(lods_descriptor, material_descriptor, instancing) => {
+                Self::from_lods(
+                    lods_descriptor,
+                    material_descriptor,
+                    instancing,
+                    device,
+                    queue,
+                )
+            }
             #[cfg(feature = "gltf")]
             ModelDescriptor::FromGLTF(
                 file,
@@ -72,6 +122,30 @@ impl Model {
         ))
     }
 
+    pub fn from_lods(
+        lods_descriptor: &LodsDescriptor,
+        material_descriptor: &MaterialDescriptor,
+        instancing: &Instancing,
+        device: &Device,
+        queue: &Queue,
+    ) -> Result<Self, Error> {
+        let mut model = Self::from_descriptors(
+            &lods_descriptor.mesh,
+            material_descriptor,
+            instancing,
+            device,
+            queue,
+        )?;
+        model.lods = lods_descriptor
+            .levels
+            .iter()
+            .map(|x| Lod::from_descriptor(x, device, queue))
+            .collect();
+        model.lod_hysteresis = lods_descriptor.hysteresis;
+
+        Ok(model)
+    }
+
     #[cfg(feature = "gltf")]
     pub fn from_gltf(
         file: &'static str,
@@ -81,26 +155,14 @@ impl Model {
         device: &Device,
         queue: &Queue,
     ) -> Result<Self, Error> {
-        // Load glTF file
-        let gltf_file = easy_gltf::load(file).map_err(|e| Error::GltfError(e))?;
-
-        // Query for scene. If found we continue.
-        let scene = if let Some(scene) = match scene_import_descriptor {
-            ImportDescriptor::Index(i) => gltf_file.get(*i as usize),
-            ImportDescriptor::Name(name) => gltf_file
-                .iter()
-                .find(|x| x.name.is_some() && x.name.as_ref().unwrap() == *name),
-        } {
-            scene
-        } else {
-            return Err(Error::SceneNotFound);
-        };
+        let gltf_file = GltfFile::load(file)?;
+        let (scene_index, scene) = gltf_file.scene(scene_import_descriptor)?;
 
         // Query for model. If found we continue.
         let models = &scene.models;
-        let model = if let Some(model) = match model_import_descriptor {
-            ImportDescriptor::Index(i) => models.get(*i as usize),
-            ImportDescriptor::Name(name) => models.iter().find(|x| {
+        let (model_index, model) = if let Some(model) = match model_import_descriptor {
+            ImportDescriptor::Index(i) => models.get(*i as usize).map(|model| (*i as usize, model)),
+            ImportDescriptor::Name(name) => models.iter().enumerate().find(|(_, x)| {
                 let mesh_name = x.mesh_name();
 
                 mesh_name.is_some() && mesh_name.unwrap() == *name
@@ -111,14 +173,227 @@ impl Model {
             return Err(Error::ModelNotFound);
         };
 
-        Ok(Self::from_gltf_model(
-            model,
+        // Skinned and morphed models need information easy_gltf doesn't
+        // provide
+        let model_node = Composition::gltf_model_nodes(&gltf_file.document, scene_index)?
+            .into_iter()
+            .nth(model_index)
+            .ok_or(Error::ModelNotFound)?;
+
+        if Self::is_gltf_deformed(&gltf_file.document, &model_node) {
+            // easy_gltf bakes the node transform, which we keep doing
+            if let Some(model) = Self::from_gltf_deformed(
+                model,
+                &model_node,
+                &gltf_file.document,
+                &gltf_file.buffers,
+                model_node.transform,
+                Self::convert_instancing(instancing),
+                device,
+                queue,
+            )? {
+                return Ok(model);
+            }
+        }
+
+        Ok(Self::from_existing(
+            Mesh::from_gltf(model, device)?,
+            Self::gltf_material_descriptor(model, &gltf_file.document, &model_node),
             Self::convert_instancing(instancing),
             device,
             queue,
-        )?)
+        ))
+    }
+
+    /// Converts the material of a _glTF mesh primitive_.
+    /// easy_gltf doesn't provide the [AlphaMode], thus it's read from the
+    /// _glTF Document_.
+    #[cfg(feature = "gltf")]
+    pub(crate) fn gltf_material_descriptor(
+        gltf_model: &easy_gltf::Model,
+        document: &gltf::Document,
+        model_node: &GltfModelNode,
+    ) -> MaterialDescriptor {
+        let alpha_mode = document
+            .nodes()
+            .nth(model_node.node)
+            .and_then(|x| x.mesh())
+            .and_then(|x| x.primitives().nth(model_node.primitive))
+            .map(|x| AlphaMode::from(&x.material()))
+            .unwrap_or_default();
+
+        MaterialDescriptor::from(gltf_model.material().as_ref()).with_alpha_mode(alpha_mode)
+    }
+
+    /// Checks if the node of the given [GltfModelNode] is skinned or it's
+    /// primitive has morph targets.
+    #[cfg(feature = "gltf")]
+    pub(crate) fn is_gltf_deformed(document: &gltf::Document, model_node: &GltfModelNode) -> bool {
+        document.nodes().nth(model_node.node).is_some_and(|node| {
+            node.skin().is_some()
+                || node
+                    .mesh()
+                    .and_then(|x| x.primitives().nth(model_node.primitive))
+                    .is_some_and(|x| x.morph_targets().next().is_some())
+        })
+    }
+
+    /// Creates a skinned and/or morphed [Model] from a _glTF mesh
+    /// primitive_, including it's [Skeleton], [MorphTarget]s and any
+    /// [AnimationClip]s affecting them.
+    ///
+    /// Returns [None] if the primitive is neither skinned nor morphed.
+    ///
+    /// Skinned vertices are relative to the [Skeleton], the node transform
+    /// doesn't apply.
+    /// easy_gltf bakes the node transform into the vertices anyways,
+    /// thus it is reverted.
+    /// Vertices of morphed-only primitives, as well as their morph
+    /// deltas, are transformed by `mesh_transform` instead.
+    #[cfg(feature = "gltf")]
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn from_gltf_deformed(
+        gltf_model: &easy_gltf::Model,
+        model_node: &GltfModelNode,
+        document: &gltf::Document,
+        buffers: &[gltf::buffer::Data],
+        mesh_transform: Matrix4<f32>,
+        instances: Vec<Instance>,
+        device: &Device,
+        queue: &Queue,
+    ) -> Result<Option<Self>, Error> {
+        let Some(node) = document.nodes().nth(model_node.node) else {
+            return Ok(None);
+        };
+        let Some(primitive) = node
+            .mesh()
+            .and_then(|x| x.primitives().nth(model_node.primitive))
+        else {
+            return Ok(None);
+        };
+        let vertex_count = gltf_model.vertices().len();
+
+        let skin = node.skin().and_then(|skin| {
+            Self::read_gltf_skin_vertices(&primitive, buffers, vertex_count).map(|x| (skin, x))
+        });
+
+        // Deltas are relative, thus they are only rotated and scaled
+        let mut morph_targets = MorphTarget::from_gltf(
+            &primitive,
+            buffers,
+            match skin {
+                Some(_) => Matrix4::identity(),
+                None => mesh_transform,
+            },
+        );
+        if morph_targets.iter().any(|target| {
+            [
+                &target.position_deltas,
+                &target.normal_deltas,
+                &target.tangent_deltas,
+            ]
+            .iter()
+            .any(|x| !x.is_empty() && x.len() != vertex_count)
+        }) {
+            warn!("glTF morph targets have a mismatching amount of deltas, morph targets ignored!");
+            morph_targets.clear();
+        }
+
+        if skin.is_none() && morph_targets.is_empty() {
+            return Ok(None);
+        }
+
+        let inverse_transform = model_node.transform.invert().unwrap_or(Matrix4::identity());
+        let mut mesh = match skin {
+            Some(_) => Mesh::from_gltf_transformed(gltf_model, inverse_transform, device)?,
+            None => {
+                Mesh::from_gltf_transformed(gltf_model, mesh_transform * inverse_transform, device)?
+            }
+        };
+
+        let mut joint_nodes = Vec::new();
+        let mut skeleton = None;
+        if let Some((skin, skin_vertices)) = &skin {
+            mesh.attach_skin(skin_vertices, device);
+
+            joint_nodes = skin.joints().map(|x| x.index()).collect::<Vec<_>>();
+            skeleton = Some(Skeleton::from_gltf(document, skin, buffers, device, queue));
+        }
+
+        let mut morph_node = None;
+        let mut morph_weights = None;
+        if !morph_targets.is_empty() {
+            mesh.attach_morph_targets(&morph_targets, vertex_count, device);
+
+            // Node weights take precedence over mesh weights
+            let default_weights = node
+                .weights()
+                .or(node.mesh().and_then(|x| x.weights()))
+                .unwrap_or_default();
+
+            morph_node = Some(node.index());
+            morph_weights = Some(MorphWeights::new(
+                morph_targets.len(),
+                vertex_count as u32,
+                default_weights,
+                device,
+                queue,
+            ));
+        }
+
+        let animations = document
+            .animations()
+            .map(|x| AnimationClip::from_gltf(&x, buffers, &joint_nodes, morph_node))
+            .collect();
+
+        let mut model = Self::from_existing(
+            mesh,
+            Self::gltf_material_descriptor(gltf_model, document, model_node),
+            instances,
+            device,
+            queue,
+        );
+        model.skeleton = skeleton;
+        model.morph_weights = morph_weights;
+        model.animations = animations;
+
+        Ok(Some(model))
     }
 
+    /// Reads the joints and weights of a skinned _glTF primitive_.
+    ///
+    /// Returns [None] if they are missing or don't match the amount of
+    /// vertices.
+    #[cfg(feature = "gltf")]
+    fn read_gltf_skin_vertices(
+        primitive: &gltf::Primitive,
+        buffers: &[gltf::buffer::Data],
+        vertex_count: usize,
+    ) -> Option<Vec<SkinVertex>> {
+        let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+        let (Some(joints), Some(weights)) = (reader.read_joints(0), reader.read_weights(0)) else {
+            warn!("Skinned glTF primitive is missing joints or weights, skinning ignored!");
+            return None;
+        };
+
+        let skin_vertices = joints
+            .into_u16()
+            .zip(weights.into_f32())
+            .map(|(joints, weights)| SkinVertex {
+                joints: joints.map(u32::from),
+                weights: weights.into(),
+            })
+            .collect::<Vec<_>>();
+        if skin_vertices.len() != vertex_count {
+            warn!("Skinned glTF primitive has a mismatching amount of joints and weights, skinning ignored!");
+            return None;
+        }
+
+        Some(skin_vertices)
+    }
+
+    /// ⚠️ easy_gltf doesn't provide the [AlphaMode] of the material,
+    /// ⚠️ thus it's always [AlphaMode::Opaque].
     #[cfg(feature = "gltf")]
     pub fn from_gltf_model(
         model: &easy_gltf::Model,
@@ -144,20 +419,68 @@ impl Model {
         device: &Device,
         queue: &Queue,
     ) -> Self {
-        let instance_buffer = Self::make_instance_buffer(&instances, device, queue);
+        let transform = Matrix4::identity();
+        let instance_buffer =
+            Self::make_instance_buffer(&instances, transform, instances.len(), device, queue);
 
         Self {
             mesh,
             material_descriptor,
             instances,
             instance_buffer,
+            visible_instance_buffer: None,
+            visible_instance_count: None,
+            lods: Vec::new(),
+            lod_hysteresis: 0.0,
+            instance_lods: Vec::new(),
+            transform,
+            skeleton: None,
+            morph_weights: None,
+            animations: Vec::new(),
+            animation_player: AnimationPlayer::default(),
+            deformation_bind_group: OnceLock::new(),
+            deformed_material_bind_group: OnceLock::new(),
+            masked_shadow_bind_group: OnceLock::new(),
+        }
+    }
+
+    /// Size of a single [Instance] inside the instance buffer in bytes.
+    pub const INSTANCE_SIZE: u64 = 4 * 4 * 4;
+
+    /// Creates an instance buffer with room for `capacity` [Instance]s
+    /// and fills it with the given `instances`.
+    ///
+    /// A [Buffer] can't be empty, thus the capacity is at least one.
+    /// It can be copied from, e.g. to batch draws of multiple [Model]s.
+    fn make_instance_buffer(
+        instances: &[Instance],
+        transform: Matrix4<f32>,
+        capacity: usize,
+        device: &Device,
+        queue: &Queue,
+    ) -> Buffer {
+        let instance_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Instance Buffer"),
+            size: Self::INSTANCE_SIZE * capacity.max(instances.len()).max(1) as u64,
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        if !instances.is_empty() {
+            queue.write_buffer(
+                &instance_buffer,
+                0,
+                &Self::make_instance_data(instances, transform),
+            );
         }
+
+        instance_buffer
     }
 
-    fn make_instance_buffer(instances: &[Instance], device: &Device, _queue: &Queue) -> Buffer {
-        let instance_data: Vec<u8> = instances
+    fn make_instance_data(instances: &[Instance], transform: Matrix4<f32>) -> Vec<u8> {
+        instances
             .iter()
-            .map(|x| x.make_model_space_matrix())
+            .map(|x| transform * x.make_model_space_matrix())
             .flat_map(|x| {
                 vec![
                     x.x.x.to_le_bytes(),
@@ -179,13 +502,7 @@ impl Model {
                 ]
             })
             .flatten()
-            .collect();
-
-        device.create_buffer_init(&BufferInitDescriptor {
-            label: None,
-            contents: &instance_data,
-            usage: BufferUsages::VERTEX,
-        })
+            .collect()
     }
 
     pub fn convert_instancing(instancing: &Instancing) -> Vec<Instance> {
@@ -195,8 +512,323 @@ impl Model {
         }
     }
 
+    /// Applies [InstanceChange]s in order and updates the instance buffer.
+    pub fn update_instances_from_changes(
+        &mut self,
+        changes: Vec<InstanceChange>,
+        device: &Device,
+        queue: &Queue,
+    ) {
+        for change in changes {
+            match change {
+                InstanceChange::Update(index, transform_change) => {
+                    match self.instances.get_mut(index) {
+                        Some(instance) => instance.update_from_change(transform_change),
+                        None => warn!(
+                            "Trying to update instance #{}, but model only has {} instances!",
+                            index,
+                            self.instances.len()
+                        ),
+                    }
+                }
+                InstanceChange::UpdateAll(transform_change) => {
+                    for instance in &mut self.instances {
+                        instance.update_from_change(transform_change.clone());
+                    }
+                }
+                InstanceChange::Add(instance_descriptor) => self
+                    .instances
+                    .push(Instance::from_descriptor(&instance_descriptor)),
+                InstanceChange::Remove(index) => {
+                    if index < self.instances.len() {
+                        self.instances.remove(index);
+                    } else {
+                        warn!(
+                            "Trying to remove instance #{}, but model only has {} instances!",
+                            index,
+                            self.instances.len()
+                        );
+                    }
+                }
+            }
+        }
+
+        self.update_instance_buffer(device, queue);
+    }
+
+    /// Writes all [Instance]s into the instance buffer.
+    ///
+    /// If the instance buffer is big enough, it is updated in place.
+    /// Otherwise, a bigger instance buffer is made, leaving room for
+    /// further [Instance]s.
     pub fn update_instance_buffer(&mut self, device: &Device, queue: &Queue) {
-        self.instance_buffer = Self::make_instance_buffer(&self.instances, device, queue);
+        let required_size = Self::INSTANCE_SIZE * self.instances.len() as u64;
+
+        if required_size <= self.instance_buffer.size() {
+            if !self.instances.is_empty() {
+                queue.write_buffer(
+                    &self.instance_buffer,
+                    0,
+                    &Self::make_instance_data(&self.instances, self.transform),
+                );
+            }
+        } else {
+            self.instance_buffer = Self::make_instance_buffer(
+                &self.instances,
+                self.transform,
+                self.instances.len().next_power_of_two(),
+                device,
+                queue,
+            );
+        }
+    }
+
+    /// Changes the transform applied on top of every [Instance] and
+    /// updates the instance buffer.
+    pub fn set_transform(&mut self, transform: Matrix4<f32>, device: &Device, queue: &Queue) {
+        if self.transform == transform {
+            return;
+        }
+
+        self.transform = transform;
+        self.update_instance_buffer(device, queue);
+    }
+
+    pub fn transform(&self) -> Matrix4<f32> {
+        self.transform
+    }
+
+    /// Applies an [AnimationChange] to the [AnimationPlayer].
+    pub fn apply_animation_change(&mut self, change: AnimationChange) {
+        if self.skeleton.is_none() && self.morph_weights.is_none() {
+            warn!("Trying to animate a model without a skeleton or morph targets!");
+            return;
+        }
+
+        if let AnimationChange::SetMorphWeights(weights) = &change {
+            match &mut self.morph_weights {
+                Some(morph_weights) => morph_weights.set_default_weights(weights),
+                None => warn!("Trying to set morph weights of a model without morph targets!"),
+            }
+        }
+
+        self.animation_player.apply_change(change, &self.animations);
+    }
+
+    /// Advances the [AnimationPlayer] and, if the pose changed,
+    /// recalculates the joint matrices and morph weights.
+    /// The buffers are updated with [Model::update_animation_buffers].
+    pub fn update_animation(&mut self, delta_time: f64) {
+        if self.skeleton.is_none() && self.morph_weights.is_none() {
+            return;
+        }
+
+        if self
+            .animation_player
+            .advance(delta_time as f32, &self.animations)
+        {
+            let pose = self
+                .animation_player
+                .sample(&self.animations, &self.rest_pose());
+
+            if let Some(skeleton) = &mut self.skeleton {
+                skeleton.set_pose(&pose.joints);
+            }
+            if let Some(morph_weights) = &mut self.morph_weights {
+                morph_weights.set_weights(&pose.morph_weights);
+            }
+        }
+    }
+
+    /// Returns the pose of the [Model], if not animated.
+    pub fn rest_pose(&self) -> AnimationPose {
+        AnimationPose {
+            joints: self
+                .skeleton
+                .as_ref()
+                .map(|x| x.rest_pose())
+                .unwrap_or_default(),
+            morph_weights: self
+                .morph_weights
+                .as_ref()
+                .map(|x| x.default_weights().to_vec())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Writes the joint matrices and morph weights into their buffers,
+    /// if needed.
+    pub fn update_animation_buffers(&mut self, queue: &Queue) {
+        if let Some(skeleton) = &mut self.skeleton {
+            skeleton.update_joint_buffer(queue);
+        }
+        if let Some(morph_weights) = &mut self.morph_weights {
+            morph_weights.update_weight_buffer(queue);
+        }
+    }
+
+    /// Checks if the [Model] has a [Skeleton] and the [Mesh] is skinned.
+    pub fn is_skinned(&self) -> bool {
+        self.skeleton.is_some() && self.mesh.skin_buffer().is_some()
+    }
+
+    /// Checks if the [Model] has [MorphWeights] and the [Mesh] has
+    /// [MorphTarget](super::MorphTarget)s.
+    pub fn is_morphed(&self) -> bool {
+        self.morph_weights.is_some() && self.mesh.morph_buffer().is_some()
+    }
+
+    /// Returns the variant of the given [PipelineDescriptor] matching the
+    /// deformations of this [Model].
+    /// Check [PipelineDescriptor::skinned] and [PipelineDescriptor::morphed].
+    pub fn pipeline_descriptor(
+        &self,
+        pipeline_descriptor: &PipelineDescriptor,
+    ) -> PipelineDescriptor {
+        let mut pipeline_descriptor = pipeline_descriptor.clone();
+        if self.is_skinned() {
+            pipeline_descriptor = pipeline_descriptor.skinned_variant();
+        }
+        if self.is_morphed() {
+            pipeline_descriptor = pipeline_descriptor.morphed_variant();
+        }
+
+        pipeline_descriptor
+    }
+
+    /// Returns the [BindGroupEntry]s of all deformation buffers, starting
+    /// at `first_binding`.
+    /// Check [Pipeline::deformation_bind_group_layout_entries].
+    fn deformation_bind_group_entries(&self, first_binding: u32) -> Vec<BindGroupEntry<'_>> {
+        let binding = |x: u32| x - Pipeline::DEFORMATION_BINDING + first_binding;
+
+        let mut entries = Vec::new();
+        if let (Some(skeleton), Some(_)) = (&self.skeleton, self.mesh.skin_buffer()) {
+            entries.push(BindGroupEntry {
+                binding: binding(Pipeline::JOINT_MATRICES_BINDING),
+                resource: skeleton.joint_buffer().as_entire_binding(),
+            });
+        }
+        if let (Some(morph_buffer), Some(morph_weights)) =
+            (self.mesh.morph_buffer(), &self.morph_weights)
+        {
+            entries.push(BindGroupEntry {
+                binding: binding(Pipeline::MORPH_DELTAS_BINDING),
+                resource: morph_buffer.as_entire_binding(),
+            });
+            entries.push(BindGroupEntry {
+                binding: binding(Pipeline::MORPH_WEIGHTS_BINDING),
+                resource: morph_weights.weight_buffer().as_entire_binding(),
+            });
+        }
+
+        entries
+    }
+
+    /// Returns a [BindGroup] containing the deformation buffers only,
+    /// starting at binding zero.
+    /// Returns [None] if the [Model] isn't deformed.
+    ///
+    /// ⚠️ The [BindGroup] is made once and reused afterwards.
+    pub fn deformation_bind_group(&self, device: &Device) -> Option<&BindGroup> {
+        if !self.is_skinned() && !self.is_morphed() {
+            return None;
+        }
+
+        Some(self.deformation_bind_group.get_or_init(|| {
+            device.create_bind_group(&BindGroupDescriptor {
+                label: Some("Deformation Bind Group"),
+                layout: &device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                    label: Some("Deformation"),
+                    entries: &Pipeline::deformation_bind_group_layout_entries(
+                        self.is_skinned(),
+                        self.is_morphed(),
+                        0,
+                    ),
+                }),
+                entries: &self.deformation_bind_group_entries(0),
+            })
+        }))
+    }
+
+    /// Returns a [BindGroup] containing the deformation buffers, followed
+    /// by the albedo texture of the given [Material].
+    /// Used to cut out the shadows of [AlphaMode::Mask] [Model]s, check
+    /// [Pipeline::masked_shadow_bind_group_layout_entries].
+    ///
+    /// ⚠️ The [BindGroup] is made once and reused afterwards.
+    /// ⚠️ Always pass the same [Material]!
+    pub fn masked_shadow_bind_group(&self, material: &Material, device: &Device) -> &BindGroup {
+        self.masked_shadow_bind_group.get_or_init(|| {
+            let albedo_texture = material.albedo_texture();
+
+            let mut entries = self.deformation_bind_group_entries(0);
+            entries.extend([
+                BindGroupEntry {
+                    binding: Pipeline::SHADOW_ALBEDO_BINDING,
+                    resource: BindingResource::TextureView(albedo_texture.view()),
+                },
+                BindGroupEntry {
+                    binding: Pipeline::SHADOW_ALBEDO_BINDING + 1,
+                    resource: BindingResource::Sampler(albedo_texture.sampler()),
+                },
+            ]);
+
+            device.create_bind_group(&BindGroupDescriptor {
+                label: Some("Masked Shadow Bind Group"),
+                layout: &device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                    label: Some("Masked Shadow"),
+                    entries: &Pipeline::masked_shadow_bind_group_layout_entries(
+                        self.is_skinned(),
+                        self.is_morphed(),
+                    ),
+                }),
+                entries: &entries,
+            })
+        })
+    }
+
+    /// Returns the [BindGroup] to render this [Model] with the given
+    /// [Material] and [Pipeline].
+    /// For deformed [Model]s, it additionally contains the deformation
+    /// buffers.
+    /// Otherwise, it's the [Material::bind_group].
+    ///
+    /// ⚠️ The deformed [BindGroup] is made once and reused afterwards.
+    /// ⚠️ Always pass the same [Material]!
+    pub fn material_bind_group<'a>(
+        &'a self,
+        material: &'a Material,
+        pipeline: &Pipeline,
+        device: &Device,
+    ) -> &'a BindGroup {
+        if !self.is_skinned() && !self.is_morphed() {
+            return material.bind_group();
+        }
+
+        self.deformed_material_bind_group.get_or_init(|| {
+            material.make_deformed_bind_group(
+                self.deformation_bind_group_entries(Pipeline::DEFORMATION_BINDING),
+                pipeline,
+                device,
+            )
+        })
+    }
+
+    pub fn skeleton(&self) -> Option<&Skeleton> {
+        self.skeleton.as_ref()
+    }
+
+    pub fn morph_weights(&self) -> Option<&MorphWeights> {
+        self.morph_weights.as_ref()
+    }
+
+    pub fn animations(&self) -> &[AnimationClip] {
+        &self.animations
+    }
+
+    pub fn animation_player(&self) -> &AnimationPlayer {
+        &self.animation_player
     }
 
     pub fn mesh(&self) -> &Mesh {
@@ -206,10 +838,21 @@ impl Model {
     pub fn material(
         &self,
         surface_format: &TextureFormat,
+        sample_count: u32,
         device: &Device,
         queue: &Queue,
     ) -> Result<&'static Material, Error> {
-        Material::from_descriptor(&self.material_descriptor, surface_format, device, queue)
+        Material::from_descriptor(
+            &self.material_descriptor,
+            surface_format,
+            sample_count,
+            device,
+            queue,
+        )
+    }
+
+    pub fn alpha_mode(&self) -> AlphaMode {
+        self.material_descriptor.alpha_mode()
     }
 
     pub fn instances(&self) -> &Vec<Instance> {
@@ -219,4 +862,205 @@ impl Model {
     pub fn instance_buffer(&self) -> &Buffer {
         &self.instance_buffer
     }
+
+    /// Returns the world space bounds of each [Instance].
+    ///
+    /// [None] if the [Mesh] has no bounds (check [Mesh::bounds]) or the
+    /// [Model] is deformed, as skinning and morph targets can move
+    /// vertices outside of the rest pose bounds.
+    pub fn instance_bounds(&self) -> Option<Vec<Aabb>> {
+        if self.is_skinned() || self.is_morphed() {
+            return None;
+        }
+
+        let bounds = self.mesh.bounds()?;
+        Some(
+            self.instances
+                .iter()
+                .map(|x| bounds.transform(&(self.transform * x.make_model_space_matrix())))
+                .collect(),
+        )
+    }
+
+    /// Returns the squared distance of the [Camera] to the center of all
+    /// [Instance]s.
+    /// Used to sort blended [Model]s back to front.
+    /// [Instance]s within the [Model] aren't sorted, check
+    /// [AlphaMode::Blend].
+    pub fn camera_distance_squared(&self, camera: &Camera) -> f32 {
+        let center = self
+            .instances
+            .iter()
+            .map(|x| (self.transform * x.make_model_space_matrix()).w.truncate())
+            .sum::<Vector3<f32>>()
+            / self.instances.len().max(1) as f32;
+
+        (center - camera.descriptor().position.to_vec()).magnitude2()
+    }
+
+    /// Checks each [Instance] against the [Frustum](crate::physics::Frustum)
+    /// of the [Camera] and compacts the visible ones into
+    /// [Model::visible_instance_buffer].
+    /// If the [Model] has [Lod]s, the level of detail of each [Instance]
+    /// is selected first and visible [Instance]s are compacted into the
+    /// instance buffer of their [Lod] instead.
+    ///
+    /// [Model]s without [Model::instance_bounds] are never culled and
+    /// always use the most detailed level.
+    pub fn cull(&mut self, camera: &Camera, device: &Device, queue: &Queue) {
+        let Some(instance_bounds) = self.instance_bounds() else {
+            self.visible_instance_count = None;
+            self.instance_lods.clear();
+            for lod in &mut self.lods {
+                lod.set_instance_count(0);
+            }
+            return;
+        };
+
+        let frustum = camera.frustum();
+        let camera_position = camera.descriptor().position.to_vec();
+        let fovy = Deg(camera.descriptor().fovy);
+
+        self.instance_lods.resize(self.instances.len(), 0);
+        let mut visible = vec![Vec::new(); self.lods.len() + 1];
+        for ((instance, bounds), level) in self
+            .instances
+            .iter()
+            .zip(instance_bounds)
+            .zip(&mut self.instance_lods)
+        {
+            // Selected even if not visible, to keep the hysteresis intact
+            if !self.lods.is_empty() {
+                *level = Lod::select_level(
+                    &self.lods,
+                    &bounds,
+                    camera_position,
+                    fovy,
+                    *level,
+                    self.lod_hysteresis,
+                );
+            }
+
+            if frustum.intersects_aabb(&bounds) {
+                visible[*level].push(*instance);
+            }
+        }
+
+        for (lod, instances) in self.lods.iter_mut().zip(visible.drain(1..)) {
+            lod.set_instance_count(instances.len() as u32);
+            Self::write_visible_instances(
+                lod.instance_buffer_mut(),
+                &instances,
+                self.transform,
+                self.instances.len(),
+                device,
+                queue,
+            );
+        }
+
+        let visible = visible.remove(0);
+        if visible.len() == self.instances.len() {
+            self.visible_instance_count = None;
+            return;
+        }
+
+        self.visible_instance_count = Some(visible.len() as u32);
+        Self::write_visible_instances(
+            &mut self.visible_instance_buffer,
+            &visible,
+            self.transform,
+            self.instances.len(),
+            device,
+            queue,
+        );
+    }
+
+    /// Makes all [Instance]s visible again, undoing [Model::cull].
+    pub fn reset_culling(&mut self) {
+        self.visible_instance_count = None;
+        self.instance_lods.clear();
+    }
+
+    /// Writes the given `instances` into `buffer`.
+    /// If the [Buffer] is missing or too small, a new one with room for
+    /// `capacity` [Instance]s is made.
+    fn write_visible_instances(
+        buffer: &mut Option<Buffer>,
+        instances: &[Instance],
+        transform: Matrix4<f32>,
+        capacity: usize,
+        device: &Device,
+        queue: &Queue,
+    ) {
+        if instances.is_empty() {
+            return;
+        }
+
+        match buffer {
+            Some(buffer) if buffer.size() >= Self::INSTANCE_SIZE * instances.len() as u64 => {
+                queue.write_buffer(buffer, 0, &Self::make_instance_data(instances, transform));
+            }
+            _ => {
+                *buffer = Some(Self::make_instance_buffer(
+                    instances, transform, capacity, device, queue,
+                ));
+            }
+        }
+    }
+
+    /// The instance buffer to draw [Model::mesh] with, containing only
+    /// the [Instance]s visible after the last [Model::cull] and using the
+    /// most detailed level.
+    /// Draw [Model::visible_instance_count] [Instance]s from it.
+    /// Check [Model::visible_lods] to draw all levels of detail.
+    ///
+    /// ⚠️ Shadows should still use [Model::instance_buffer], as
+    /// [Instance]s outside the view may cast shadows into it.
+    pub fn visible_instance_buffer(&self) -> &Buffer {
+        match (self.visible_instance_count, &self.visible_instance_buffer) {
+            (Some(_), Some(buffer)) => buffer,
+            _ => &self.instance_buffer,
+        }
+    }
+
+    pub fn visible_instance_count(&self) -> u32 {
+        self.visible_instance_count
+            .unwrap_or(self.instances.len() as u32)
+    }
+
+    pub fn lods(&self) -> &[Lod] {
+        &self.lods
+    }
+
+    /// Level of detail of each [Instance] after the last [Model::cull].
+    /// Check [Lod::select_level].
+    /// Empty if the [Model] has no [Lod]s or can't be culled.
+    pub fn instance_lods(&self) -> &[usize] {
+        &self.instance_lods
+    }
+
+    /// Returns each level of detail with visible [Instance]s after the
+    /// last [Model::cull], as the [Mesh] to draw, it's instance buffer and
+    /// the amount of [Instance]s to draw from it.
+    /// Empty if all [Instance]s are culled.
+    pub fn visible_lods(&self) -> Vec<(&Mesh, &Buffer, u32)> {
+        let mut visible_lods = Vec::new();
+        if self.visible_instance_count() > 0 {
+            visible_lods.push((
+                &self.mesh,
+                self.visible_instance_buffer(),
+                self.visible_instance_count(),
+            ));
+        }
+
+        for lod in &self.lods {
+            if let (Some(instance_buffer), count @ 1..) =
+                (lod.instance_buffer(), lod.instance_count())
+            {
+                visible_lods.push((lod.mesh(), instance_buffer, count));
+            }
+        }
+
+        visible_lods
+    }
 }
@@ -0,0 +1,189 @@
+use cgmath::{Vector3, Zero};
+use wgpu::{Buffer, BufferDescriptor, BufferUsages, Device, Queue};
+
+/// A single morph target (_blend shape_) of a [Mesh](super::Mesh).
+///
+/// Contains per-vertex offsets, which are added onto each [Vertex](super::Vertex),
+/// scaled by the weight of the morph target.
+/// Empty attributes don't change the [Vertex](super::Vertex).
+///
+/// Weights are set per [Model](super::Model), check [MorphWeights].
+#[derive(Debug, Clone, Default)]
+pub struct MorphTarget {
+    pub position_deltas: Vec<Vector3<f32>>,
+    pub normal_deltas: Vec<Vector3<f32>>,
+    pub tangent_deltas: Vec<Vector3<f32>>,
+}
+
+impl MorphTarget {
+    /// Size of a single `MorphDelta` (position, normal and tangent delta,
+    /// each padded to four floats) in bytes.
+    pub const DELTA_SIZE: u64 = 4 * 4 * 3;
+
+    /// Converts the deltas of the given vertex into bytes, matching the
+    /// `MorphDelta` layout of the shader.
+    pub fn delta_to_bytes(&self, vertex: usize) -> Vec<u8> {
+        let delta = |deltas: &[Vector3<f32>]| {
+            let x = deltas.get(vertex).copied().unwrap_or(Vector3::zero());
+
+            [
+                x.x.to_le_bytes(),
+                x.y.to_le_bytes(),
+                x.z.to_le_bytes(),
+                // Padding
+                0f32.to_le_bytes(),
+            ]
+            .concat()
+        };
+
+        [
+            delta(&self.position_deltas),
+            delta(&self.normal_deltas),
+            delta(&self.tangent_deltas),
+        ]
+        .concat()
+    }
+
+    /// Reads all morph targets of a _glTF Primitive_.
+    ///
+    /// All deltas are transformed by `transform`, i.e. the same transform
+    /// the vertices of the [Mesh](super::Mesh) were transformed with.
+    #[cfg(feature = "gltf")]
+    pub fn from_gltf(
+        primitive: &gltf::Primitive,
+        buffers: &[gltf::buffer::Data],
+        transform: cgmath::Matrix4<f32>,
+    ) -> Vec<Self> {
+        let transform_delta = |x: [f32; 3]| (transform * Vector3::from(x).extend(0.0)).truncate();
+
+        primitive
+            .reader(|buffer| Some(&buffers[buffer.index()]))
+            .read_morph_targets()
+            .map(|(positions, normals, tangents)| Self {
+                position_deltas: positions
+                    .map(|x| x.map(transform_delta).collect())
+                    .unwrap_or_default(),
+                normal_deltas: normals
+                    .map(|x| x.map(transform_delta).collect())
+                    .unwrap_or_default(),
+                tangent_deltas: tangents
+                    .map(|x| x.map(transform_delta).collect())
+                    .unwrap_or_default(),
+            })
+            .collect()
+    }
+}
+
+/// Weights of all [MorphTarget]s of a [Model](super::Model).
+///
+/// The weights are kept in a storage buffer, which is bound by the
+/// morphing variant of the PBR pipeline.
+/// Check [PipelineDescriptor::morphed](crate::resources::descriptors::PipelineDescriptor::morphed)
+/// for more.
+#[derive(Debug)]
+pub struct MorphWeights {
+    vertex_count: u32,
+    /// Weights used if not animated
+    default_weights: Vec<f32>,
+    weights: Vec<f32>,
+    weight_buffer: Buffer,
+    weight_buffer_outdated: bool,
+}
+
+impl MorphWeights {
+    /// Size of the header (vertex count and target count) of the weight
+    /// buffer in bytes.
+    pub const HEADER_SIZE: u64 = 4 * 2;
+
+    /// Creates [MorphWeights] for `target_count` [MorphTarget]s.
+    ///
+    /// Missing default weights are zero, surplus ones are dropped.
+    pub fn new(
+        target_count: usize,
+        vertex_count: u32,
+        default_weights: &[f32],
+        device: &Device,
+        queue: &Queue,
+    ) -> Self {
+        let mut default_weights = default_weights.to_vec();
+        default_weights.resize(target_count, 0.0);
+
+        let weight_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Morph Weight Buffer"),
+            // The weight array can't be empty
+            size: Self::HEADER_SIZE + 4 * target_count.max(1) as u64,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut morph_weights = Self {
+            vertex_count,
+            weights: default_weights.clone(),
+            default_weights,
+            weight_buffer,
+            weight_buffer_outdated: true,
+        };
+        morph_weights.update_weight_buffer(queue);
+
+        morph_weights
+    }
+
+    /// Changes the weights used if not animated.
+    /// Also applies them right away.
+    pub fn set_default_weights(&mut self, weights: &[f32]) {
+        let target_count = self.target_count();
+
+        self.default_weights = weights.to_vec();
+        self.default_weights.resize(target_count, 0.0);
+
+        self.weights.clone_from(&self.default_weights);
+        self.weight_buffer_outdated = true;
+    }
+
+    /// Changes the current weights.
+    /// Missing weights fall back to their default weight.
+    /// The weight buffer will be updated on the next
+    /// [MorphWeights::update_weight_buffer].
+    pub fn set_weights(&mut self, weights: &[f32]) {
+        for (i, weight) in self.weights.iter_mut().enumerate() {
+            *weight = weights.get(i).copied().unwrap_or(self.default_weights[i]);
+        }
+
+        self.weight_buffer_outdated = true;
+    }
+
+    /// Writes the weights into the weight buffer, if they changed.
+    pub fn update_weight_buffer(&mut self, queue: &Queue) {
+        if !self.weight_buffer_outdated {
+            return;
+        }
+
+        let data = [
+            self.vertex_count.to_le_bytes(),
+            (self.target_count() as u32).to_le_bytes(),
+        ]
+        .into_iter()
+        .chain(self.weights.iter().map(|x| x.to_le_bytes()))
+        .flatten()
+        .collect::<Vec<u8>>();
+        queue.write_buffer(&self.weight_buffer, 0, &data);
+
+        self.weight_buffer_outdated = false;
+    }
+
+    pub fn target_count(&self) -> usize {
+        self.default_weights.len()
+    }
+
+    pub fn default_weights(&self) -> &[f32] {
+        &self.default_weights
+    }
+
+    pub fn weights(&self) -> &[f32] {
+        &self.weights
+    }
+
+    pub fn weight_buffer(&self) -> &Buffer {
+        &self.weight_buffer
+    }
+}
@@ -1,21 +1,27 @@
 use log::info;
-use std::sync::{Mutex, OnceLock};
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+};
 use wgpu::{
     BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, BlendState,
-    ColorTargetState, ColorWrites, CompareFunction, DepthBiasState, DepthStencilState, Device,
-    FragmentState, MultisampleState, PipelineCompilationOptions, PipelineLayoutDescriptor,
-    PrimitiveState, Queue, RenderPipeline, RenderPipelineDescriptor, SamplerBindingType,
-    ShaderStages, StencilState, TextureFormat, TextureSampleType, TextureViewDimension,
-    VertexState,
+    BufferBindingType, ColorTargetState, ColorWrites, CompareFunction, DepthBiasState,
+    DepthStencilState, Device, FragmentState, MultisampleState, PipelineCompilationOptions,
+    PipelineLayoutDescriptor, PrimitiveState, Queue, RenderPipeline, RenderPipelineDescriptor,
+    SamplerBindingType, ShaderStages, StencilState, TextureFormat, TextureSampleType,
+    TextureViewDimension, VertexBufferLayout, VertexState,
 };
 
 use crate::{
     cache::Cache,
     error::Error,
-    resources::{descriptors::PipelineDescriptor, realizations::Shader},
+    resources::{
+        descriptors::{AlphaMode, PipelineDescriptor},
+        realizations::Shader,
+    },
 };
 
-use super::{Camera, Instance, Vertex};
+use super::{Camera, Environment, Instance, Light, SkinVertex, Vertex};
 
 #[derive(Debug)]
 pub struct Pipeline {
@@ -25,6 +31,49 @@ pub struct Pipeline {
 }
 
 impl Pipeline {
+    /// Amount of textures a PBR material binds.
+    /// I.e. albedo, metallic, roughness, normal, occlusion and emissive.
+    pub const PBR_TEXTURE_COUNT: u32 = 6;
+
+    /// First binding of the deformation buffers (joint matrices, morph
+    /// deltas and morph weights) inside the material bind group of
+    /// skinned and morphed pipelines.
+    /// Comes right after the PBR textures.
+    pub const DEFORMATION_BINDING: u32 = Self::PBR_TEXTURE_COUNT * 2;
+
+    /// Binding of the joint matrices inside the material bind group of
+    /// skinned pipelines.
+    pub const JOINT_MATRICES_BINDING: u32 = Self::DEFORMATION_BINDING;
+
+    /// Binding of the morph deltas inside the material bind group of
+    /// morphed pipelines.
+    pub const MORPH_DELTAS_BINDING: u32 = Self::DEFORMATION_BINDING + 1;
+
+    /// Binding of the morph weights inside the material bind group of
+    /// morphed pipelines.
+    pub const MORPH_WEIGHTS_BINDING: u32 = Self::DEFORMATION_BINDING + 2;
+
+    /// Binding of the albedo texture inside the bind group of masked
+    /// shadow pipelines, followed by it's sampler.
+    /// Comes right after the deformation buffers, which start at zero.
+    pub const SHADOW_ALBEDO_BINDING: u32 = 3;
+
+    /// Formats of the G-buffer targets, written by the G-buffer variant
+    /// of a pipeline.
+    /// Check [PipelineDescriptor::deferred].
+    ///
+    /// In order:
+    /// - Albedo (RGB) and occlusion (A)
+    /// - World space normal (RGB)
+    /// - Metallic (R) and roughness (G)
+    /// - Emissive (RGB)
+    pub const G_BUFFER_FORMATS: [TextureFormat; 4] = [
+        TextureFormat::Rgba8UnormSrgb,
+        TextureFormat::Rgba16Float,
+        TextureFormat::Rgba8Unorm,
+        TextureFormat::Rgba16Float,
+    ];
+
     // --- Static ---
     /// Gives access to the internal pipeline cache.
     /// If the cache doesn't exist yet, it gets initialized.
@@ -41,14 +90,18 @@ impl Pipeline {
     /// thus this should be safe.
     ///
     /// Additionally, we utilize a [Mutex] to ensure that access to the
-    /// cache map and texture format is actually exclusive.
-    pub unsafe fn cache() -> &'static mut (Cache<PipelineDescriptor, Pipeline>, TextureFormat) {
-        static mut CACHE: OnceLock<Mutex<(Cache<PipelineDescriptor, Pipeline>, TextureFormat)>> =
-            OnceLock::new();
+    /// cache map, texture format and sample count is actually exclusive.
+    #[allow(clippy::type_complexity)]
+    pub unsafe fn cache() -> &'static mut (Cache<PipelineDescriptor, Pipeline>, TextureFormat, u32)
+    {
+        static mut CACHE: OnceLock<
+            Mutex<(Cache<PipelineDescriptor, Pipeline>, TextureFormat, u32)>,
+        > = OnceLock::new();
 
         if CACHE.get().is_none() {
             info!("Pipeline cache doesn't exist! Initializing ...");
-            let _ = CACHE.get_or_init(|| Mutex::new((Cache::new(), TextureFormat::Bgra8UnormSrgb)));
+            let _ =
+                CACHE.get_or_init(|| Mutex::new((Cache::new(), TextureFormat::Bgra8UnormSrgb, 1)));
         }
 
         CACHE
@@ -64,60 +117,74 @@ impl Pipeline {
     ///
     /// This will set some cache parameters, if they don't exist yet
     /// (e.g. in case of a new cache), and make sure the pipelines
-    /// still match the correct surface texture formats.
+    /// still match the correct surface texture formats and sample counts.
     /// If needed, this will also attempt recompiling all pipelines
-    /// (and thus their shaders) to match a different format!
+    /// (and thus their shaders) to match a different format or sample
+    /// count!
     pub fn prepare_cache_access(
         potential_new_format: Option<&TextureFormat>,
+        potential_new_sample_count: Option<u32>,
         device: &Device,
         queue: &Queue,
     ) -> &'static mut Cache<PipelineDescriptor, Pipeline> {
-        let (cache, cache_format) = unsafe { Self::cache() };
+        let (cache, cache_format, cache_sample_count) = unsafe { Self::cache() };
 
-        // Check the cache format if it's set to [Some]
-        if let Some(potential_new_format) = potential_new_format {
-            // If they don't match, trigger a recompilation of the cache!
-            if cache_format != potential_new_format {
-                // If the formats don't match, we have to attempt to
-                // recompile the whole cache.
+        // Check the cache format and sample count if they are set to [Some]
+        let format = potential_new_format.copied().unwrap_or(*cache_format);
+        let sample_count = potential_new_sample_count.unwrap_or(*cache_sample_count);
 
-                // Thus, set the new cache format BEFORE cache is accessed again ...
-                *cache_format = *potential_new_format;
+        // If they don't match, trigger a recompilation of the cache!
+        if *cache_format != format || *cache_sample_count != sample_count {
+            // If the formats or sample counts don't match, we have to
+            // attempt to recompile the whole cache.
 
-                // ... and rework the cache!
-                cache.rework(|k| Self::make_pipeline(k, potential_new_format, device, queue).ok());
-            }
+            // Thus, set the new cache parameters BEFORE cache is accessed again ...
+            *cache_format = format;
+            *cache_sample_count = sample_count;
+
+            // ... and rework the cache!
+            cache.rework(|k| Self::make_pipeline(k, &format, sample_count, device, queue).ok());
         }
 
         cache
     }
 
     // --- Constructor ---
+    /// Realizes the [Pipeline] for color targets of `surface_format` with
+    /// `sample_count` samples per pixel.
+    ///
+    /// ⚠️ The sample count isn't validated, check
+    /// ⚠️ [AppSettings::sample_count](crate::app::AppSettings::sample_count).
     pub fn from_descriptor(
         pipeline_descriptor: &PipelineDescriptor,
         surface_format: &TextureFormat,
+        sample_count: u32,
         device: &Device,
         queue: &Queue,
     ) -> Result<&'static Self, Error> {
-        let cache = Self::prepare_cache_access(Some(surface_format), device, queue);
+        let cache =
+            Self::prepare_cache_access(Some(surface_format), Some(sample_count), device, queue);
 
         cache.get_or_add_fallible(pipeline_descriptor, |k| {
-            Self::make_pipeline(k, surface_format, device, queue)
+            Self::make_pipeline(k, surface_format, sample_count, device, queue)
         })
     }
 
     fn make_pipeline(
         pipeline_descriptor: &PipelineDescriptor,
         surface_format: &TextureFormat,
+        sample_count: u32,
         device: &Device,
         queue: &Queue,
     ) -> Result<Pipeline, Error> {
-        let pipeline_bind_group_layout =
-            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-                label: None,
-                entries: &[
+        // Each PBR texture (albedo, metallic, roughness, normal, occlusion
+        // and emissive) comes as a pair of texture and sampler.
+        // Even bindings are textures, odd bindings are samplers.
+        let mut pipeline_bind_group_layout_entries = (0..Self::PBR_TEXTURE_COUNT)
+            .flat_map(|i| {
+                [
                     BindGroupLayoutEntry {
-                        binding: 0,
+                        binding: i * 2,
                         visibility: ShaderStages::FRAGMENT,
                         ty: BindingType::Texture {
                             sample_type: TextureSampleType::Float { filterable: true },
@@ -127,48 +194,84 @@ impl Pipeline {
                         count: None,
                     },
                     BindGroupLayoutEntry {
-                        binding: 1,
+                        binding: i * 2 + 1,
                         visibility: ShaderStages::FRAGMENT,
                         ty: BindingType::Sampler(SamplerBindingType::Filtering),
                         count: None,
                     },
-                ],
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        // Skinned and morphed pipelines additionally need their
+        // deformation buffers
+        pipeline_bind_group_layout_entries.extend(Self::deformation_bind_group_layout_entries(
+            pipeline_descriptor.skinned,
+            pipeline_descriptor.morphed,
+            Self::DEFORMATION_BINDING,
+        ));
+
+        let pipeline_bind_group_layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: None,
+                entries: &pipeline_bind_group_layout_entries,
             });
 
+        let camera_bind_group_layout =
+            device.create_bind_group_layout(&Camera::bind_group_layout_descriptor());
+        let light_bind_group_layout =
+            device.create_bind_group_layout(&Light::bind_group_layout_descriptor());
+        let environment_bind_group_layout =
+            device.create_bind_group_layout(&Environment::bind_group_layout_descriptor());
+
+        // G-buffer variants don't shade, thus don't need lights and the
+        // environment
+        let bind_group_layouts = if pipeline_descriptor.deferred {
+            vec![&pipeline_bind_group_layout, &camera_bind_group_layout]
+        } else {
+            vec![
+                &pipeline_bind_group_layout,
+                &camera_bind_group_layout,
+                &light_bind_group_layout,
+                &environment_bind_group_layout,
+            ]
+        };
+
         let render_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
             label: None,
-            bind_group_layouts: &[
-                // Pipeline bind group layouts
-                &pipeline_bind_group_layout,
-                // Camera bind group layout
-                &device.create_bind_group_layout(&Camera::bind_group_layout_descriptor()),
-            ],
+            bind_group_layouts: &bind_group_layouts,
             push_constant_ranges: &[],
         });
 
         let shader = Shader::from_descriptor(pipeline_descriptor.shader_descriptor, device, queue)?;
 
+        let vertex_entry_point =
+            Self::vertex_entry_point(pipeline_descriptor.skinned, pipeline_descriptor.morphed);
+        let vertex_buffers = Self::vertex_buffer_layouts(pipeline_descriptor.skinned);
+        let (fragment_entry_point, color_targets) = Self::fragment_entry_point_and_targets(
+            pipeline_descriptor.deferred,
+            pipeline_descriptor.is_blended(),
+            surface_format,
+        );
+        let fragment_constants = Self::fragment_constants(&pipeline_descriptor.alpha_mode);
+
         let render_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
             label: None,
             layout: Some(&render_pipeline_layout),
             vertex: VertexState {
                 module: shader.shader_module(),
-                entry_point: "entrypoint_vertex",
-                buffers: &[
-                    Vertex::vertex_buffer_layout_descriptor(),
-                    Instance::vertex_buffer_layout_descriptor(),
-                ],
+                entry_point: vertex_entry_point,
+                buffers: &vertex_buffers,
                 compilation_options: PipelineCompilationOptions::default(),
             },
             fragment: Some(FragmentState {
                 module: shader.shader_module(),
-                entry_point: "entrypoint_fragment",
-                targets: &[Some(ColorTargetState {
-                    format: *surface_format,
-                    blend: Some(BlendState::REPLACE),
-                    write_mask: ColorWrites::ALL,
-                })],
-                compilation_options: PipelineCompilationOptions::default(),
+                entry_point: fragment_entry_point,
+                targets: &color_targets,
+                compilation_options: PipelineCompilationOptions {
+                    constants: &fragment_constants,
+                    ..Default::default()
+                },
             }),
             primitive: PrimitiveState {
                 topology: pipeline_descriptor.primitive_topology,
@@ -181,12 +284,17 @@ impl Pipeline {
             },
             depth_stencil: Some(DepthStencilState {
                 format: TextureFormat::Depth32Float,
-                depth_write_enabled: true,
+                // Blended surfaces would hide anything behind them drawn
+                // afterwards
+                depth_write_enabled: !pipeline_descriptor.is_blended(),
                 depth_compare: CompareFunction::Less,
                 stencil: StencilState::default(),
                 bias: DepthBiasState::default(),
             }),
-            multisample: MultisampleState::default(),
+            multisample: MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
             multiview: None,
         });
 
@@ -197,6 +305,144 @@ impl Pipeline {
         })
     }
 
+    /// Returns the [BindGroupLayoutEntry]s of the deformation buffers,
+    /// starting at `first_binding`.
+    ///
+    /// The bindings are relative to `first_binding` in the same way as
+    /// [Pipeline::JOINT_MATRICES_BINDING], [Pipeline::MORPH_DELTAS_BINDING]
+    /// and [Pipeline::MORPH_WEIGHTS_BINDING] are relative to
+    /// [Pipeline::DEFORMATION_BINDING].
+    /// Unused buffers are left out.
+    pub fn deformation_bind_group_layout_entries(
+        skinned: bool,
+        morphed: bool,
+        first_binding: u32,
+    ) -> Vec<BindGroupLayoutEntry> {
+        let storage_entry = |binding: u32| BindGroupLayoutEntry {
+            binding: binding - Self::DEFORMATION_BINDING + first_binding,
+            visibility: ShaderStages::VERTEX,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        let mut entries = Vec::new();
+        if skinned {
+            entries.push(storage_entry(Self::JOINT_MATRICES_BINDING));
+        }
+        if morphed {
+            entries.push(storage_entry(Self::MORPH_DELTAS_BINDING));
+            entries.push(storage_entry(Self::MORPH_WEIGHTS_BINDING));
+        }
+
+        entries
+    }
+
+    /// Returns the [BindGroupLayoutEntry]s of masked shadow pipelines.
+    /// I.e. the deformation buffers starting at zero, followed by the
+    /// albedo texture and sampler at [Pipeline::SHADOW_ALBEDO_BINDING].
+    /// Check [AlphaMode::Mask].
+    pub fn masked_shadow_bind_group_layout_entries(
+        skinned: bool,
+        morphed: bool,
+    ) -> Vec<BindGroupLayoutEntry> {
+        let mut entries = Self::deformation_bind_group_layout_entries(skinned, morphed, 0);
+        entries.extend([
+            BindGroupLayoutEntry {
+                binding: Self::SHADOW_ALBEDO_BINDING,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture {
+                    sample_type: TextureSampleType::Float { filterable: true },
+                    view_dimension: TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            BindGroupLayoutEntry {
+                binding: Self::SHADOW_ALBEDO_BINDING + 1,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                count: None,
+            },
+        ]);
+
+        entries
+    }
+
+    /// Name of the vertex entrypoint matching the given deformations.
+    /// Check [PipelineDescriptor::skinned] and [PipelineDescriptor::morphed].
+    pub fn vertex_entry_point(skinned: bool, morphed: bool) -> &'static str {
+        match (skinned, morphed) {
+            (false, false) => "entrypoint_vertex",
+            (true, false) => "entrypoint_vertex_skinned",
+            (false, true) => "entrypoint_vertex_morphed",
+            (true, true) => "entrypoint_vertex_skinned_morphed",
+        }
+    }
+
+    /// Name of the fragment entrypoint and the color targets.
+    /// G-buffer variants write into the [Pipeline::G_BUFFER_FORMATS]
+    /// instead of the surface.
+    /// Blended targets are alpha blended, check
+    /// [PipelineDescriptor::deferred] and [PipelineDescriptor::is_blended].
+    pub fn fragment_entry_point_and_targets(
+        deferred: bool,
+        blended: bool,
+        surface_format: &TextureFormat,
+    ) -> (&'static str, Vec<Option<ColorTargetState>>) {
+        let blend = if blended {
+            BlendState::ALPHA_BLENDING
+        } else {
+            BlendState::REPLACE
+        };
+        let target = |format: TextureFormat| {
+            Some(ColorTargetState {
+                format,
+                blend: Some(blend),
+                write_mask: ColorWrites::ALL,
+            })
+        };
+
+        if deferred {
+            (
+                "entrypoint_fragment_deferred",
+                Self::G_BUFFER_FORMATS.into_iter().map(target).collect(),
+            )
+        } else {
+            ("entrypoint_fragment", vec![target(*surface_format)])
+        }
+    }
+
+    /// Pipeline-overridable constants of the fragment stage.
+    /// Only masking pipelines set `alpha_cutoff`, others keep the default
+    /// of the shader.
+    /// Check [PipelineDescriptor::alpha_mode].
+    pub fn fragment_constants(alpha_mode: &AlphaMode) -> HashMap<String, f64> {
+        match alpha_mode {
+            AlphaMode::Mask { cutoff } => {
+                HashMap::from([("alpha_cutoff".to_string(), *cutoff as f64 / 255.0)])
+            }
+            _ => HashMap::new(),
+        }
+    }
+
+    /// Vertex buffers of a pipeline.
+    /// Skinned pipelines additionally take a [SkinVertex] buffer.
+    pub fn vertex_buffer_layouts(skinned: bool) -> Vec<VertexBufferLayout<'static>> {
+        let mut vertex_buffers = vec![
+            Vertex::vertex_buffer_layout_descriptor(),
+            Instance::vertex_buffer_layout_descriptor(),
+        ];
+        if skinned {
+            vertex_buffers.push(SkinVertex::vertex_buffer_layout_descriptor());
+        }
+
+        vertex_buffers
+    }
+
     pub fn render_pipeline(&self) -> &RenderPipeline {
         &self.render_pipeline
     }
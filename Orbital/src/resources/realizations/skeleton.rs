@@ -0,0 +1,217 @@
+use cgmath::{Matrix4, SquareMatrix};
+use wgpu::{Buffer, BufferDescriptor, BufferUsages, Device, Queue};
+
+use crate::game::Transform;
+
+/// A single joint (_bone_) of a [Skeleton].
+#[derive(Debug, Clone)]
+pub struct Joint {
+    /// Index of the parent joint inside the [Skeleton], if any
+    pub parent: Option<usize>,
+    /// Transform from the parent joint (or the scene origin, if there is
+    /// no parent joint) into the space this joint is relative to.
+    ///
+    /// Usually the identity matrix, but _glTF_ allows for regular nodes
+    /// in between joints.
+    pub parent_offset: Matrix4<f32>,
+    /// Pose of the joint, relative to it's parent, if not animated
+    pub rest_pose: Transform,
+    /// Transforms a vertex from model space into the space of the joint
+    pub inverse_bind_matrix: Matrix4<f32>,
+}
+
+/// A hierarchy of [Joint]s, deforming a skinned [Mesh](super::Mesh).
+///
+/// The joint matrices are kept in a storage buffer, which is bound by the
+/// skinning variant of the PBR pipeline.
+/// Check [PipelineDescriptor::skinned](crate::resources::descriptors::PipelineDescriptor::skinned)
+/// for more.
+#[derive(Debug)]
+pub struct Skeleton {
+    joints: Vec<Joint>,
+    /// Joint indices, parents always before their children
+    evaluation_order: Vec<usize>,
+    joint_matrices: Vec<Matrix4<f32>>,
+    joint_buffer: Buffer,
+    joint_buffer_outdated: bool,
+}
+
+impl Skeleton {
+    /// Size of a single joint matrix inside the joint buffer in bytes.
+    pub const JOINT_MATRIX_SIZE: u64 = 4 * 4 * 4;
+
+    pub fn new(joints: Vec<Joint>, device: &Device, queue: &Queue) -> Self {
+        let joint_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Skeleton Joint Buffer"),
+            // A buffer can't be empty
+            size: Self::JOINT_MATRIX_SIZE * joints.len().max(1) as u64,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut skeleton = Self {
+            evaluation_order: Self::make_evaluation_order(&joints),
+            joint_matrices: vec![Matrix4::identity(); joints.len()],
+            joints,
+            joint_buffer,
+            joint_buffer_outdated: true,
+        };
+
+        skeleton.set_pose(&skeleton.rest_pose());
+        skeleton.update_joint_buffer(queue);
+
+        skeleton
+    }
+
+    /// Creates a [Skeleton] from a _glTF Skin_.
+    ///
+    /// The joints of the [Skeleton] will be in the same order as the
+    /// joints of the _glTF Skin_.
+    #[cfg(feature = "gltf")]
+    pub fn from_gltf(
+        document: &gltf::Document,
+        skin: &gltf::Skin,
+        buffers: &[gltf::buffer::Data],
+        device: &Device,
+        queue: &Queue,
+    ) -> Self {
+        let mut node_parents = vec![None; document.nodes().len()];
+        for node in document.nodes() {
+            for child in node.children() {
+                node_parents[child.index()] = Some(node.index());
+            }
+        }
+
+        let local_matrices = document
+            .nodes()
+            .map(|node| Matrix4::from(node.transform().matrix()))
+            .collect::<Vec<_>>();
+
+        let joint_nodes = skin.joints().map(|x| x.index()).collect::<Vec<_>>();
+
+        let inverse_bind_matrices = skin
+            .reader(|buffer| Some(&buffers[buffer.index()]))
+            .read_inverse_bind_matrices()
+            .map(|x| x.map(Matrix4::from).collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        let joints = skin
+            .joints()
+            .enumerate()
+            .map(|(i, node)| {
+                // Walk up until we find a parent joint.
+                // Any regular node in between is accumulated.
+                let mut parent = None;
+                let mut parent_offset = Matrix4::identity();
+                let mut ancestor = node_parents[node.index()];
+                while let Some(ancestor_index) = ancestor {
+                    if let Some(joint) = joint_nodes.iter().position(|x| *x == ancestor_index) {
+                        parent = Some(joint);
+                        break;
+                    }
+
+                    parent_offset = local_matrices[ancestor_index] * parent_offset;
+                    ancestor = node_parents[ancestor_index];
+                }
+
+                let (position, rotation, scale) = node.transform().decomposed();
+
+                Joint {
+                    parent,
+                    parent_offset,
+                    rest_pose: Transform {
+                        position: position.into(),
+                        rotation: rotation.into(),
+                        scale: scale.into(),
+                    },
+                    inverse_bind_matrix: inverse_bind_matrices
+                        .get(i)
+                        .copied()
+                        .unwrap_or(Matrix4::identity()),
+                }
+            })
+            .collect();
+
+        Self::new(joints, device, queue)
+    }
+
+    /// Sorts the joints by their depth inside the hierarchy, so that
+    /// parents are always evaluated before their children.
+    fn make_evaluation_order(joints: &[Joint]) -> Vec<usize> {
+        let depth = |mut joint: usize| {
+            let mut depth = 0;
+            while let Some(parent) = joints[joint].parent {
+                joint = parent;
+                depth += 1;
+
+                // Guard against malformed, cyclic hierarchies
+                if depth > joints.len() {
+                    break;
+                }
+            }
+            depth
+        };
+
+        let mut order = (0..joints.len()).collect::<Vec<_>>();
+        order.sort_by_key(|x| depth(*x));
+        order
+    }
+
+    /// Returns the rest pose of each [Joint].
+    pub fn rest_pose(&self) -> Vec<Transform> {
+        self.joints.iter().map(|x| x.rest_pose).collect()
+    }
+
+    /// Calculates the joint matrices from the given pose of each [Joint].
+    /// The joint buffer will be updated on the next
+    /// [Skeleton::update_joint_buffer].
+    pub fn set_pose(&mut self, pose: &[Transform]) {
+        let mut world_matrices = vec![Matrix4::identity(); self.joints.len()];
+
+        for &i in &self.evaluation_order {
+            let joint = &self.joints[i];
+            let parent_matrix = joint
+                .parent
+                .map(|x| world_matrices[x])
+                .unwrap_or(Matrix4::identity());
+            let local_matrix = pose.get(i).unwrap_or(&joint.rest_pose).to_matrix();
+
+            world_matrices[i] = parent_matrix * joint.parent_offset * local_matrix;
+            self.joint_matrices[i] = world_matrices[i] * joint.inverse_bind_matrix;
+        }
+
+        self.joint_buffer_outdated = true;
+    }
+
+    /// Writes the joint matrices into the joint buffer, if they changed.
+    pub fn update_joint_buffer(&mut self, queue: &Queue) {
+        if !self.joint_buffer_outdated || self.joint_matrices.is_empty() {
+            return;
+        }
+
+        let data = self
+            .joint_matrices
+            .iter()
+            .flat_map(|x| {
+                let matrix: &[f32; 16] = x.as_ref();
+                matrix.map(f32::to_le_bytes)
+            })
+            .flatten()
+            .collect::<Vec<u8>>();
+        queue.write_buffer(&self.joint_buffer, 0, &data);
+
+        self.joint_buffer_outdated = false;
+    }
+
+    pub fn joints(&self) -> &[Joint] {
+        &self.joints
+    }
+
+    pub fn joint_matrices(&self) -> &[Matrix4<f32>] {
+        &self.joint_matrices
+    }
+
+    pub fn joint_buffer(&self) -> &Buffer {
+        &self.joint_buffer
+    }
+}
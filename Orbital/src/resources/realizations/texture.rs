@@ -29,12 +29,18 @@ impl Texture {
             TextureDescriptor::StandardSRGBu8Data(data, size) => {
                 Ok(Self::standard_srgb8_data(data, size, device, queue))
             }
+            TextureDescriptor::LinearRGBAu8Data(data, size) => {
+                Ok(Self::linear_rgba8_data(data, size, device, queue))
+            }
             TextureDescriptor::UniformColor(color) => {
                 Ok(Self::uniform_color(*color, device, queue))
             }
             TextureDescriptor::Luma { data, size } => Ok(Self::luma(data, size, device, queue)),
             TextureDescriptor::UniformLuma { data } => Ok(Self::uniform_luma(data, device, queue)),
             TextureDescriptor::Depth(size) => Ok(Self::depth_texture(size, device, queue)),
+            TextureDescriptor::EquirectangularHDR(file_path) => {
+                Self::equirectangular_hdr(file_path, device, queue)
+            }
         }
     }
 
@@ -52,6 +58,82 @@ impl Texture {
         ))
     }
 
+    /// Loads a HDR image from a file into a [TextureFormat::Rgba32Float]
+    /// texture.
+    ///
+    /// ⚠️ [TextureFormat::Rgba32Float] textures can't be filtered!
+    /// ⚠️ Use `textureLoad` instead of `textureSample` in shaders.
+    pub fn equirectangular_hdr(
+        file_path: &str,
+        device: &Device,
+        queue: &Queue,
+    ) -> Result<Self, Error> {
+        let img = ImageReader::open(file_path)
+            .map_err(Error::IOError)?
+            .decode()
+            .map_err(Error::ImageError)?
+            .to_rgba32f();
+
+        let texture = Self::from_descriptors(
+            &WTextureDescriptor {
+                label: Some("Equirectangular HDR Texture"),
+                size: Extent3d {
+                    width: img.width(),
+                    height: img.height(),
+                    ..Default::default()
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::Rgba32Float,
+                usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            &TextureViewDescriptor::default(),
+            &SamplerDescriptor {
+                label: Some("Equirectangular HDR Texture Sampler"),
+                address_mode_u: AddressMode::Repeat,
+                address_mode_v: AddressMode::ClampToEdge,
+                address_mode_w: AddressMode::ClampToEdge,
+                mag_filter: FilterMode::Nearest,
+                min_filter: FilterMode::Nearest,
+                ..Default::default()
+            },
+            device,
+            queue,
+        );
+
+        let data = img
+            .as_raw()
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect::<Vec<_>>();
+
+        queue.write_texture(
+            ImageCopyTexture {
+                texture: texture.texture(),
+                aspect: TextureAspect::All,
+                origin: Origin3d::ZERO,
+                mip_level: 0,
+            },
+            &data,
+            ImageDataLayout {
+                offset: 0,
+                // 16 bytes (RGBA, each 32-bit float), times the width
+                bytes_per_row: Some(16 * img.width()),
+                // ... times height
+                rows_per_image: Some(img.height()),
+            },
+            Extent3d {
+                width: img.width(),
+                height: img.height(),
+                ..Default::default()
+            },
+        );
+
+        Ok(texture)
+    }
+
     /// In case you want a uniform, one color, image.
     /// This results in an 1-by-1 px, i.e. 4 bytes image.
     ///
@@ -220,6 +302,45 @@ impl Texture {
         )
     }
 
+    /// Same as [Self::standard_srgb8_data], but the data is interpreted
+    /// **linear**. I.e. no SRGB conversion will happen when sampling.
+    pub fn linear_rgba8_data(
+        data: &[u8],
+        size: &Vector2<u32>,
+        device: &Device,
+        queue: &Queue,
+    ) -> Self {
+        Self::from_data_srgb8(
+            data,
+            &WTextureDescriptor {
+                label: Some("Linear RGBA u8 Data Texture"),
+                size: Extent3d {
+                    width: size.x,
+                    height: size.y,
+                    ..Default::default()
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::Rgba8Unorm,
+                usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            &TextureViewDescriptor::default(),
+            &SamplerDescriptor {
+                label: Some("Linear RGBA u8 Data Texture Sampler"),
+                address_mode_u: AddressMode::ClampToEdge,
+                address_mode_v: AddressMode::ClampToEdge,
+                address_mode_w: AddressMode::ClampToEdge,
+                mag_filter: FilterMode::Linear,
+                min_filter: FilterMode::Nearest,
+                ..Default::default()
+            },
+            device,
+            queue,
+        )
+    }
+
     pub fn from_image_srgb8(
         image: DynamicImage,
         texture_desc: &WTextureDescriptor,
@@ -276,6 +397,26 @@ impl Texture {
     }
 
     pub fn depth_texture(size: &Vector2<u32>, device: &Device, queue: &Queue) -> Texture {
+        Self::multisampled_depth_texture(size, 1, device, queue)
+    }
+
+    /// Same as [Texture::depth_texture], but with `sample_count` samples
+    /// per pixel, e.g. for MSAA.
+    ///
+    /// ⚠️ Multisampled depth textures are render attachments only and
+    /// ⚠️ can't be bound for sampling.
+    pub fn multisampled_depth_texture(
+        size: &Vector2<u32>,
+        sample_count: u32,
+        device: &Device,
+        queue: &Queue,
+    ) -> Texture {
+        let usage = if sample_count > 1 {
+            TextureUsages::RENDER_ATTACHMENT
+        } else {
+            TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING
+        };
+
         Self::from_descriptors(
             &WTextureDescriptor {
                 label: Some("Depth Texture"),
@@ -285,10 +426,10 @@ impl Texture {
                     depth_or_array_layers: 1,
                 },
                 mip_level_count: 1,
-                sample_count: 1,
+                sample_count,
                 dimension: TextureDimension::D2,
                 format: TextureFormat::Depth32Float,
-                usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+                usage,
                 view_formats: &[],
             },
             &TextureViewDescriptor::default(),
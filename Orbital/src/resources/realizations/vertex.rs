@@ -1,6 +1,6 @@
 use std::mem::size_of;
 
-use cgmath::{Vector2, Vector3};
+use cgmath::{Vector2, Vector3, Vector4};
 use wgpu::{VertexAttribute, VertexBufferLayout, VertexFormat, VertexStepMode};
 
 #[derive(Debug, Clone)]
@@ -89,12 +89,67 @@ impl Vertex {
 
 impl From<easy_gltf::model::Vertex> for Vertex {
     fn from(value: easy_gltf::model::Vertex) -> Self {
-        Self::new(
-            value.position,
-            value.normal,
-            // Note: Skipping `w` which defines the strength of the vector.
-            Vector3::new(value.tangent.x, value.tangent.y, value.tangent.z),
-            value.tex_coords,
-        )
+        let tangent = Vector3::new(value.tangent.x, value.tangent.y, value.tangent.z);
+
+        Self {
+            position: value.position,
+            normal: value.normal,
+            tangent,
+            // glTF defines the bitangent as `cross(normal, tangent.xyz) * tangent.w`.
+            // `w` is the handedness of the tangent basis.
+            bitangent: value.normal.cross(tangent) * value.tangent.w,
+            uv: value.tex_coords,
+        }
+    }
+}
+
+/// Skinning information of a single [Vertex].
+///
+/// Kept in a separate vertex buffer, as only skinned [Mesh](super::Mesh)es
+/// need it.
+/// Each [Vertex] is influenced by up to four joints of a
+/// [Skeleton](super::Skeleton).
+/// `joints` are indices into the joints of said
+/// [Skeleton](super::Skeleton) and `weights` their influence.
+/// The weights should add up to one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SkinVertex {
+    pub joints: [u32; 4],
+    pub weights: Vector4<f32>,
+}
+
+impl SkinVertex {
+    pub fn vertex_buffer_layout_descriptor() -> VertexBufferLayout<'static> {
+        VertexBufferLayout {
+            array_stride: size_of::<[u32; 4 + 4]>() as u64,
+            step_mode: VertexStepMode::Vertex,
+            attributes: &[
+                // Locations 5 to 8 are used by the instance
+                VertexAttribute {
+                    offset: 0,
+                    shader_location: 9,
+                    format: VertexFormat::Uint32x4,
+                },
+                VertexAttribute {
+                    offset: size_of::<[u32; 4]>() as u64,
+                    shader_location: 10,
+                    format: VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [
+            self.joints[0].to_le_bytes(),
+            self.joints[1].to_le_bytes(),
+            self.joints[2].to_le_bytes(),
+            self.joints[3].to_le_bytes(),
+            self.weights.x.to_le_bytes(),
+            self.weights.y.to_le_bytes(),
+            self.weights.z.to_le_bytes(),
+            self.weights.w.to_le_bytes(),
+        ]
+        .concat()
     }
 }
@@ -7,6 +7,9 @@ pub struct Timer {
     current_delta_time: f64,
     last_fps: u64,
     last_delta_time: f64,
+    /// If set, every cycle takes exactly this long, regardless of the
+    /// actual time passed.
+    fixed_delta_time: Option<f64>,
 }
 
 impl Timer {
@@ -18,6 +21,18 @@ impl Timer {
             current_delta_time: 0f64,
             last_fps: 0u64,
             last_delta_time: 0f64,
+            fixed_delta_time: None,
+        }
+    }
+
+    /// Makes a [Timer] where every cycle takes exactly `delta_time`
+    /// seconds, regardless of the actual time passed.
+    /// Useful for deterministic, e.g. headless, runs.
+    pub fn fixed(delta_time: f64) -> Self {
+        Self {
+            cycle_delta_time: delta_time,
+            fixed_delta_time: Some(delta_time),
+            ..Self::new()
         }
     }
 
@@ -28,7 +43,7 @@ impl Timer {
 
         // Convert our snapshot into elapsed seconds, increase current delta
         // time and increment cycle count
-        self.cycle_delta_time = elapsed.as_secs_f64();
+        self.cycle_delta_time = self.fixed_delta_time.unwrap_or(elapsed.as_secs_f64());
         self.current_delta_time += self.cycle_delta_time;
         self.current_cycle_count += 1;
 
@@ -0,0 +1,148 @@
+//! Golden image regression tests.
+//!
+//! Set `ORBITAL_UPDATE_GOLDEN_IMAGES` to regenerate the references after
+//! an intended visual change.
+//! Check [orbital::renderer::testing] for more.
+
+use orbital::{
+    app::HeadlessSettings,
+    cgmath::{Deg, InnerSpace, Point3, Quaternion, Rotation3, Vector2, Vector3, Vector4},
+    error::Error,
+    game::{Element, ElementRegistration, Game, GameSettings, World, WorldChange},
+    renderer::{
+        testing::{assert_golden_game, GoldenImageTolerance},
+        StandardRenderer,
+    },
+    resources::{
+        descriptors::{
+            AlphaMode, CameraDescriptor, InstanceDescriptor, Instancing, LightDescriptor,
+            LightType, MaterialDescriptor, MeshDescriptor, ModelDescriptor, TextureDescriptor,
+        },
+        realizations::Vertex,
+    },
+    ulid::Ulid,
+};
+
+/// Unit cube, without any assets involved.
+fn cube_mesh() -> MeshDescriptor {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    for (normal, tangent) in [
+        (Vector3::unit_x(), Vector3::unit_z()),
+        (-Vector3::unit_x(), Vector3::unit_z()),
+        (Vector3::unit_y(), Vector3::unit_x()),
+        (-Vector3::unit_y(), Vector3::unit_x()),
+        (Vector3::unit_z(), Vector3::unit_x()),
+        (-Vector3::unit_z(), Vector3::unit_x()),
+    ] {
+        // Counter-clockwise, when looking at the face
+        let bitangent = normal.cross(tangent);
+        let first = vertices.len() as u32;
+
+        for (u, v) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)] {
+            let position = (normal + tangent * (u * 2.0 - 1.0) + bitangent * (v * 2.0 - 1.0)) * 0.5;
+
+            vertices.push(Vertex::new(position, normal, tangent, Vector2::new(u, v)));
+        }
+
+        indices.extend([first, first + 1, first + 2, first, first + 2, first + 3]);
+    }
+
+    MeshDescriptor { vertices, indices }
+}
+
+fn material(albedo: Vector4<u8>) -> MaterialDescriptor {
+    MaterialDescriptor::PBR {
+        albedo: TextureDescriptor::UniformColor(albedo),
+        metallic: TextureDescriptor::UniformLuma { data: 0 },
+        roughness: TextureDescriptor::UniformLuma { data: 128 },
+        normal: TextureDescriptor::UniformColor(Vector4::new(128, 128, 255, 255)),
+        occlusion: TextureDescriptor::UniformLuma { data: 255 },
+        emissive: TextureDescriptor::UniformColor(Vector4::new(0, 0, 0, 255)),
+        alpha_mode: AlphaMode::Opaque,
+    }
+}
+
+struct Cubes;
+
+impl Element for Cubes {
+    fn on_registration(&mut self, _ulid: &Ulid) -> ElementRegistration {
+        ElementRegistration {
+            models: Some(vec![
+                ModelDescriptor::FromDescriptors(
+                    cube_mesh(),
+                    material(Vector4::new(200, 40, 40, 255)),
+                    Instancing::Multiple(vec![
+                        InstanceDescriptor::default(),
+                        InstanceDescriptor {
+                            position: Vector3::new(0.0, 0.0, -1.75),
+                            rotation: Quaternion::from_axis_angle(Vector3::unit_x(), Deg(45.0)),
+                            ..Default::default()
+                        },
+                    ]),
+                ),
+                ModelDescriptor::FromDescriptors(
+                    cube_mesh(),
+                    material(Vector4::new(40, 200, 40, 255)),
+                    Instancing::Single(InstanceDescriptor {
+                        position: Vector3::new(0.0, 0.0, 1.75),
+                        rotation: Quaternion::from_axis_angle(Vector3::unit_y(), Deg(30.0)),
+                        scale: Vector3::new(1.0, 1.5, 1.0),
+                    }),
+                ),
+            ]),
+            world_changes: Some(vec![WorldChange::SpawnLightOwned(LightDescriptor {
+                light_type: LightType::Directional {
+                    direction: Vector3::new(1.0, -1.0, 0.5).normalize(),
+                },
+                ..Default::default()
+            })]),
+            ..Default::default()
+        }
+    }
+}
+
+struct CubesGame;
+
+impl Game for CubesGame {
+    fn init() -> Self {
+        Self
+    }
+
+    fn on_startup(&mut self, world: &mut World) {
+        world.process_world_change(WorldChange::SpawnCameraAndMakeActive(CameraDescriptor {
+            identifier: "Golden".into(),
+            position: Point3::new(-5.0, 1.5, 0.0),
+            pitch: -0.3,
+            aspect: 4.0 / 3.0,
+            ..Default::default()
+        }));
+        world.process_world_change(WorldChange::SpawnElement(Box::new(Cubes)));
+    }
+}
+
+#[test]
+fn standard_renderer_cubes() {
+    let result = assert_golden_game::<CubesGame, StandardRenderer>(
+        concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/golden/standard_cubes.png"
+        ),
+        GameSettings::default(),
+        HeadlessSettings {
+            size: Vector2::new(320, 240),
+            frame_count: 3,
+            ..Default::default()
+        },
+        GoldenImageTolerance::default(),
+    );
+
+    match result {
+        Ok(_) => (),
+        // Nothing to render on, e.g. a CI runner without any GPU or
+        // software rasterizer
+        Err(Error::NoAdapters) => eprintln!("No adapter found, skipping golden test!"),
+        Err(e) => panic!("Golden image mismatch: {:?}", e),
+    }
+}